@@ -113,7 +113,7 @@ pub struct Args {
 		num_args = 1..,
 		value_hint = ValueHint::CommandString,
 		value_name = "COMMAND",
-		required_unless_present_any = ["completions", "manual", "only_emit_events"],
+		required_unless_present_any = ["completions", "manual", "only_emit_events", "list_watched_paths"],
 	)]
 	pub command: Vec<String>,
 
@@ -121,9 +121,9 @@ pub struct Args {
 	///
 	/// By default, Watchexec watches the current directory.
 	///
-	/// When watching a single file, it's often better to watch the containing directory instead,
-	/// and filter on the filename. Some editors may replace the file with a new one when saving,
-	/// and some platforms may not detect that or further changes.
+	/// When watching a single file, its containing directory is watched instead, and only changes
+	/// to that file are seen. This way, changes are still detected when an editor replaces the
+	/// file with a new one when saving, or when the file is deleted and recreated.
 	///
 	/// Upon starting, Watchexec resolves a "project origin" from the watched paths. See the help
 	/// for '--project-origin' for more information.
@@ -143,7 +143,9 @@ pub struct Args {
 
 	/// Watch a specific directory, non-recursively
 	///
-	/// Unlike '-w', folders watched with this option are not recursed into.
+	/// Unlike '-w', folders watched with this option are not recursed into. Changes to the folder's
+	/// immediate contents are seen, including subfolders being created or removed, but not changes
+	/// within subfolders, even ones created after Watchexec starts.
 	///
 	/// This option can be specified multiple times to watch multiple directories non-recursively.
 	#[arg(
@@ -178,7 +180,9 @@ pub struct Args {
 
 	/// Clear screen before running command
 	///
-	/// If this doesn't completely clear the screen, try '--clear=reset'.
+	/// If this doesn't completely clear the screen, try '--clear=reset'. To clear the screen and
+	/// the terminal's scrollback without a full reset, use '--clear=scrollback'; if the terminal
+	/// doesn't support clearing its scrollback, this only clears the screen.
 	#[arg(
 		short = 'c',
 		long = "clear",
@@ -218,6 +222,21 @@ pub struct Args {
 	)]
 	pub restart: bool,
 
+	/// Run again after the command finishes if changes were dropped while it ran
+	///
+	/// With '--on-busy-update=do-nothing' (the default), events received while the command is
+	/// running are dropped, so changes made during a run are never acted on. With this option,
+	/// filesystem changes received during a run are kept instead, and once the command finishes, if
+	/// there were any, it's run once more with all of them. Duplicate events are merged, and
+	/// changes received during that run are kept for another run in the same way. Other events,
+	/// like signals and keyboard input, are dropped as usual.
+	///
+	/// Unlike 'queue', the next run is given all the changes received during the run, not only the
+	/// last ones, for example in '--emit-events-to'. This has no effect with the other modes:
+	/// 'restart' and 'signal' act on every event immediately, and 'queue' already runs again.
+	#[arg(long, conflicts_with_all = ["restart"])]
+	pub replay_dropped: bool,
+
 	/// Send a signal to the process when it's still running
 	///
 	/// Specify a signal to send to the process when it's still running. This implies
@@ -296,6 +315,21 @@ pub struct Args {
 	#[arg(long = "map-signal", value_name = "SIGNAL:SIGNAL", value_parser = SignalMappingValueParser)]
 	pub signal_map: Vec<SignalMapping>,
 
+	/// Restart the command when Watchexec receives this signal
+	///
+	/// Instead of passing the signal on to the command, Watchexec restarts it as if
+	/// '--on-busy-update=restart' was in effect: the command is stopped using '--stop-signal' and
+	/// '--stop-timeout', then started again. If the command isn't running, it's started. This is
+	/// useful for the common "reload on SIGHUP" daemon idiom, with e.g. '--restart-on-signal HUP'.
+	///
+	/// This takes precedence over '--map-signal' for the same signal. If SIGINT or SIGTERM are
+	/// given, then they no longer quit Watchexec.
+	///
+	/// This option can be specified multiple times to restart on multiple signals. See the long
+	/// documentation for '--stop-signal' for syntax.
+	#[arg(long, value_name = "SIGNAL")]
+	pub restart_on_signal: Vec<Signal>,
+
 	/// Time to wait for new events before taking action
 	///
 	/// When an event is received, Watchexec will wait for up to this amount of time before handling
@@ -329,6 +363,40 @@ pub struct Args {
 	#[arg(long)]
 	pub stdin_quit: bool,
 
+	/// Listen for HTTP requests to trigger a run
+	///
+	/// This starts a minimal HTTP listener on the given address (like "127.0.0.1:8080"). A request
+	/// of 'POST /trigger' which has the '--http-trigger-secret' in an 'X-Watchexec-Secret' header
+	/// is answered with '202 Accepted', and triggers a run as if a file had changed. The request
+	/// body is ignored. Other requests are rejected: '401 Unauthorized' for a missing or wrong
+	/// secret, '405 Method Not Allowed' for other methods on '/trigger', '404 Not Found' for other
+	/// paths, and '400 Bad Request' for anything unparseable. Each connection handles a single
+	/// request.
+	///
+	/// Triggered runs are subject to the usual '--debounce' and '--on-busy-update' behaviour. The
+	/// event has no paths, and its source is 'external'.
+	///
+	/// There is no TLS: bind to a local or otherwise trusted interface, or put a proxy in front.
+	///
+	/// This is only available when Watchexec is built with the 'http-trigger' feature.
+	#[cfg(feature = "http-trigger")]
+	#[arg(long, value_name = "ADDRESS", requires = "http_trigger_secret")]
+	pub http_trigger: Option<std::net::SocketAddr>,
+
+	/// Shared secret required by '--http-trigger'
+	///
+	/// Requests to the '--http-trigger' listener must provide this value in an 'X-Watchexec-Secret'
+	/// header. This can also be given with the WATCHEXEC_HTTP_TRIGGER_SECRET environment variable,
+	/// which avoids exposing it in the process list.
+	#[cfg(feature = "http-trigger")]
+	#[arg(
+		long,
+		value_name = "SECRET",
+		env = "WATCHEXEC_HTTP_TRIGGER_SECRET",
+		hide_env_values = true
+	)]
+	pub http_trigger_secret: Option<String>,
+
 	/// Don't load gitignores
 	///
 	/// Among other VCS exclude files, like for Mercurial, Subversion, Bazaar, DARCS, Fossil. Note
@@ -356,7 +424,7 @@ pub struct Args {
 	///   - Bazaar: .bzrignore at project root.
 	///   - Darcs: _darcs/prefs/boring
 	///   - Fossil: .fossil-settings/ignore-glob
-	///   - Ripgrep/Watchexec/generic: .ignore at project root and child directories.
+	///   - Ripgrep/Watchexec/generic: .ignore and .rgignore at project root and child directories.
 	///
 	/// VCS ignore files (Git, Mercurial, Bazaar, Darcs, Fossil) are only used if the corresponding
 	/// VCS is discovered to be in use for the project/origin. For example, a .bzrignore in a Git
@@ -425,10 +493,29 @@ pub struct Args {
 	)]
 	pub ignore_nothing: bool,
 
+	/// Ignore changes to the command's own outputs while it runs
+	///
+	/// Events for paths under these files or folders are dropped while a command is running, and
+	/// for a short while after it ends, so that a command writing into a watched folder doesn't
+	/// trigger itself. Changes made to these paths outside of runs, like by hand, are still seen.
+	///
+	/// This option can be specified multiple times. Relative paths are resolved against the
+	/// current directory.
+	#[arg(
+		long = "ignore-outputs",
+		help_heading = OPTSET_FILTERING,
+		value_name = "PATH",
+	)]
+	pub ignore_outputs: Vec<PathBuf>,
+
 	/// Wait until first change before running command
 	///
 	/// By default, Watchexec will run the command once immediately. With this option, it will
 	/// instead wait until an event is detected before running the command as normal.
+	///
+	/// Either way, the command is given $WATCHEXEC_FIRST_RUN=1 the first time it's run: at startup,
+	/// or with this option, on the first change. This can be used to do something different then,
+	/// like a full build instead of an incremental one.
 	#[arg(long, short)]
 	pub postpone: bool,
 
@@ -443,6 +530,71 @@ pub struct Args {
 	#[arg(long, value_name = "DURATION")]
 	pub delay_run: Option<TimeSpan>,
 
+	/// Kill the command if it runs for longer than this
+	///
+	/// If the command is still running after this amount of time, Watchexec forcefully stops it
+	/// and reports that it timed out. This counts as the command finishing, including for the
+	/// purposes of '--on-busy-update'. A timed-out run is not retried: the command runs again on
+	/// the next change, as usual.
+	///
+	/// Takes a unit-less value in seconds, or a time span value such as "2min 5s".
+	/// Providing a unit-less value is deprecated and will warn; it will be an error in the future.
+	#[arg(long, value_name = "DURATION")]
+	pub command_timeout: Option<TimeSpan>,
+
+	/// Run the command again when it fails, up to this many times
+	///
+	/// When the command exits unsuccessfully, Watchexec runs it again after '--retry-delay', up to
+	/// this many times, before leaving it failed until the next change. This is useful for flaky
+	/// commands, like tests which depend on the network.
+	///
+	/// Retries are separate from runs triggered by changes. A change which starts the command again
+	/// cancels the retries which are pending or yet to come, and the new run gets its own retries.
+	/// With '--on-busy-update=do-nothing', a change while the command runs also cancels its retries.
+	/// A command which times out (see '--command-timeout') is not retried.
+	#[arg(long, value_name = "N", default_value = "0", hide_default_value = true)]
+	pub retries: u32,
+
+	/// Act on the command exiting with a particular code
+	///
+	/// This lets the command control Watchexec through its exit code. Use CODE:ACTION, where
+	/// ACTION is one of:
+	///
+	/// - 'quit' to quit Watchexec, as with Ctrl-C;
+	/// - 'restart' to run the command again right away;
+	/// - 'reload-filters' to read the filters again, that is the ignore files, filter files, and
+	///   filter programs given as files, so that changes to them take effect.
+	///
+	/// For example, following the sysexits(3) convention, '--on-exit-code 78:reload-filters'
+	/// reloads the filters when the command exits with EX_CONFIG. A code of 0 is a successful exit.
+	///
+	/// This option can be specified multiple times to act on several codes. No codes are mapped by
+	/// default, and mapping the same code more than once is an error. A command which exits with a
+	/// mapped code is not retried (see '--retries'), and a command which times out, or is killed
+	/// by a signal, is not acted on. This has no effect with '--once'.
+	#[arg(
+		long,
+		value_name = "CODE:ACTION",
+		value_parser = ExitCodeMappingValueParser,
+		conflicts_with = "only_emit_events",
+	)]
+	pub on_exit_code: Vec<ExitCodeMapping>,
+
+	/// Wait this long before retrying a failed command
+	///
+	/// See '--retries'. The default is 1 second.
+	///
+	/// Takes a unit-less value in seconds, or a time span value such as "2min 5s".
+	/// Providing a unit-less value is deprecated and will warn; it will be an error in the future.
+	#[arg(
+		long,
+		value_name = "DURATION",
+		default_value = "1s",
+		hide_default_value = true,
+		requires = "retries"
+	)]
+	pub retry_delay: TimeSpan,
+
 	/// Poll for filesystem changes
 	///
 	/// By default, and where available, Watchexec uses the operating system's native file system
@@ -549,13 +701,18 @@ pub struct Args {
 	///
 	/// This option controls where that information is emitted. It defaults to 'none', which doesn't
 	/// emit event information at all. The other options are 'environment' (deprecated), 'stdio',
-	/// 'file', 'json-stdio', and 'json-file'.
+	/// 'file', 'nul-stdio', 'nul-file', 'json-stdio', and 'json-file'.
 	///
 	/// The 'stdio' and 'file' modes are text-based: 'stdio' writes absolute paths to the stdin of
 	/// the command, one per line, each prefixed with `create:`, `remove:`, `rename:`, `modify:`,
 	/// or `other:`, then closes the handle; 'file' writes the same thing to a temporary file, and
 	/// its path is given with the $WATCHEXEC_EVENTS_FILE environment variable.
 	///
+	/// The 'nul-stdio' and 'nul-file' modes are like 'stdio' and 'file', but write only the
+	/// absolute paths, each terminated by a NUL byte, as expected by `xargs -0` and similar tools.
+	/// Paths are written as-is, so this works even when they contain newlines or other special
+	/// characters. Each path appears once, in the order it was first seen in the events.
+	///
 	/// There are also two JSON modes, which are based on JSON objects and can represent the full
 	/// set of events Watchexec handles. Here's an example of a folder being created on Linux:
 	///
@@ -594,7 +751,7 @@ pub struct Args {
 	///       + `simple`, the "simple" event type ('access', 'create', 'modify', 'remove', or 'other').
 	///       + `full`, the "full" event type, which is too complex to fully describe here, but looks like 'General(Precise(Specific))'.
 	///     * 'source', along with:
-	///       + `source`, the source of the event ('filesystem', 'keyboard', 'mouse', 'os', 'time', 'internal').
+	///       + `source`, the source of the event ('filesystem', 'keyboard', 'mouse', 'os', 'time', 'internal', 'external').
 	///     * 'keyboard', along with:
 	///       + `keycode`. Currently only the value 'eof' is supported.
 	///     * 'process', for events caused by processes:
@@ -644,6 +801,17 @@ pub struct Args {
 	)]
 	pub emit_events_to: EmitEvents,
 
+	/// Limit the number of paths given to the command per run
+	///
+	/// Bulk operations like extracting an archive or switching branches can change thousands of
+	/// files at once. With this option, only the first N distinct paths to arrive are given to the
+	/// command, whichever '--emit-events-to' mode is used. The command still runs.
+	///
+	/// When paths were dropped, $WATCHEXEC_PATHS_TRUNCATED is set to '1', and
+	/// $WATCHEXEC_PATHS_COUNT is set to the number of distinct paths before truncation.
+	#[arg(long, help_heading = OPTSET_COMMAND, value_name = "N")]
+	pub max_paths: Option<usize>,
+
 	/// Only emit events to stdout, run no commands.
 	///
 	/// This is a convenience option for using Watchexec as a file watcher, without running any
@@ -651,8 +819,8 @@ pub struct Args {
 	/// spawn a new process for each event.
 	///
 	/// This option requires `--emit-events-to` to be set, and restricts the available modes to
-	/// `stdio` and `json-stdio`, modifying their behaviour to write to stdout instead of the stdin
-	/// of the command.
+	/// `stdio`, `nul-stdio`, and `json-stdio`, modifying their behaviour to write to stdout instead
+	/// of the stdin of the command.
 	#[arg(
 		long,
 		help_heading = OPTSET_OUTPUT,
@@ -666,6 +834,10 @@ pub struct Args {
 	/// setting them for the Watchexec process itself.
 	///
 	/// Use key=value syntax. Multiple variables can be set by repeating the option.
+	///
+	/// The command is always given $WATCHEXEC=1, $WATCHEXEC_BIN set to the path to the Watchexec
+	/// executable, and $WATCHEXEC_RUN_ID set to a random identifier that is unique to each run of
+	/// the command. These cannot be overridden with this option.
 	#[arg(
 		long,
 		short = 'E',
@@ -704,6 +876,22 @@ pub struct Args {
 	)]
 	pub wrap_process: WrapMode,
 
+	/// Run the command in a pseudo-terminal
+	///
+	/// Many programs change their output depending on whether they're running in a terminal, for
+	/// example turning off colours and progress bars when they're not. With this, the command's
+	/// stdout and stderr are a pseudo-terminal, so it behaves as if it was run interactively. Both
+	/// streams are printed together to Watchexec's stdout. The terminal follows the size of the one
+	/// Watchexec is running in, if any.
+	///
+	/// This is only supported on Unix.
+	#[arg(
+		long,
+		help_heading = OPTSET_COMMAND,
+		conflicts_with_all = ["timestamp_prefix", "output_events"],
+	)]
+	pub pty: bool,
+
 	/// Testing only: exit Watchexec after the first run
 	#[arg(short = '1', hide = true)]
 	pub once: bool,
@@ -752,6 +940,24 @@ pub struct Args {
 	)]
 	pub quiet: bool,
 
+	/// Collapse the starting messages of rapidly repeated runs
+	///
+	/// When the command is started again within this amount of time of the previous start, as can
+	/// happen many times in a row with '--on-busy-update=restart', its "[Running: ...]" message is
+	/// not printed. Instead, once the command hasn't been started again for that long, or when it
+	/// finishes, a single "[Running: ... (repeated N times)]" message is printed for all of them.
+	///
+	/// Only identical commands are collapsed: a run of a different command always prints its
+	/// message, and starts a new series.
+	///
+	/// Takes a time span value such as "500ms" or "2s".
+	#[arg(
+		long,
+		help_heading = OPTSET_OUTPUT,
+		value_name = "DURATION",
+	)]
+	pub coalesce_banners: Option<TimeSpan>,
+
 	/// Ring the terminal bell on command completion
 	#[arg(
 		long,
@@ -759,6 +965,52 @@ pub struct Args {
 	)]
 	pub bell: bool,
 
+	/// Prefix each line of the command's output with a timestamp
+	///
+	/// With this, Watchexec captures the command's stdout and stderr, and prints each line back out
+	/// to its own stdout and stderr prefixed with the current local time. The format is given in
+	/// strftime-like syntax (see the chrono crate's documentation for the available specifiers);
+	/// without a value, it defaults to '%Y-%m-%dT%H:%M:%S%.3f%:z', an RFC 3339 timestamp with
+	/// milliseconds.
+	///
+	/// Output is line-buffered, so partial lines are only printed once they're complete or the
+	/// command ends. Lines which aren't valid UTF-8 are printed without a prefix. As the command's
+	/// output goes through a pipe, it won't see a terminal, which may change how it behaves.
+	#[arg(
+		long,
+		help_heading = OPTSET_OUTPUT,
+		num_args = 0..=1,
+		require_equals = true,
+		default_missing_value = "%Y-%m-%dT%H:%M:%S%.3f%:z",
+		value_name = "FORMAT",
+	)]
+	pub timestamp_prefix: Option<String>,
+
+	/// Turn lines the command prints into events
+	///
+	/// With this, lines the command writes to its stdout which start with '@watchexec:' are taken
+	/// out of the output, and the rest of the line (with surrounding whitespace trimmed) is read
+	/// as a path, relative to the command's working directory if not absolute. Each such line
+	/// becomes an event for that path, with the 'external' source, which goes through filtering
+	/// like any other event and can trigger a new run of the command. For example, a command can
+	/// ask for a file to be processed again with:
+	///
+	///   echo "@watchexec: src/generated.rs"
+	///
+	/// To avoid runaway loops, a run of the command can emit at most 100 events: further lines are
+	/// dropped with a warning. A run triggered only by such events is a follow-up of the run which
+	/// emitted them; after 10 follow-ups in a row, events are dropped with a warning until a run is
+	/// triggered by something else, like a file change.
+	///
+	/// As the command's stdout goes through a pipe, it won't see a terminal, which may change how
+	/// it behaves.
+	#[arg(
+		long,
+		help_heading = OPTSET_COMMAND,
+		conflicts_with = "only_emit_events",
+	)]
+	pub output_events: bool,
+
 	/// Set the project origin
 	///
 	/// Watchexec will attempt to discover the project's "origin" (or "root") by searching for a
@@ -814,6 +1066,24 @@ pub struct Args {
 	)]
 	pub filter_patterns: Vec<String>,
 
+	/// Only run the command for changes to files matching a pattern
+	///
+	/// Provide a glob-like pattern, in the same format as '--filter'. Unlike filters, this doesn't
+	/// stop events from being seen: everything watched still reaches Watchexec, and is printed
+	/// with '--print-events', but the command is only run (or restarted, signaled, etc) when at
+	/// least one of the changed files matches a pattern. Multiple patterns can be given by
+	/// repeating the option.
+	///
+	/// The initial run is not affected, as it isn't triggered by a change. With '--postpone', the
+	/// first run waits until a matching file changes. Events which are not from files, such as
+	/// signals, are handled as usual.
+	#[arg(
+		long = "run-on",
+		help_heading = OPTSET_FILTERING,
+		value_name = "PATTERN",
+	)]
+	pub run_on_patterns: Vec<String>,
+
 	/// Files to load filters from
 	///
 	/// Provide a path to a file containing filters, one per line. Empty lines and lines starting
@@ -956,6 +1226,9 @@ pub struct Args {
 	///
 	/// This is a shorthand for '--fs-events create,remove,rename,modify'. Using it alongside the
 	/// '--fs-events' option is non-sensical and not allowed.
+	///
+	/// Events which have a metadata change as well as another kind of change, like a file being
+	/// created with some permissions, are still emitted.
 	#[arg(
 		long = "no-meta",
 		help_heading = OPTSET_FILTERING,
@@ -975,6 +1248,55 @@ pub struct Args {
 	)]
 	pub print_events: bool,
 
+	/// List the watched paths and exit
+	///
+	/// This prints the paths Watchexec would watch, one per line, and exits without running the
+	/// command. That's the paths from '--watch', '--watch-non-recursive', and '--watch-file', or
+	/// the current directory if none are given, as they're given to the file watcher: canonicalised,
+	/// that is absolute and with symlinks resolved, and without duplicates. Paths watched
+	/// non-recursively are followed by '(non-recursive)', single files by '(file)', and paths which
+	/// don't exist by '(missing)'.
+	///
+	/// With '--json', a JSON array is printed instead, with an object for each path with the
+	/// 'path', and the 'recursive', 'file', and 'exists' booleans.
+	#[arg(
+		long,
+		help_heading = OPTSET_DEBUGGING,
+		conflicts_with_all = ["completions", "manual", "only_emit_events"],
+	)]
+	pub list_watched_paths: bool,
+
+	/// Print the list of watched paths as JSON
+	///
+	/// See '--list-watched-paths'.
+	#[arg(
+		long,
+		help_heading = OPTSET_DEBUGGING,
+		requires = "list_watched_paths",
+	)]
+	pub json: bool,
+
+	/// Write a diagnostics report to a file when the file watcher fails
+	///
+	/// When Watchexec stops reacting to changes, this helps find out why, and is useful to attach
+	/// to bug reports. The report is a JSON object with the 'reason' it was written for, the
+	/// 'error' if any, the Watchexec 'version', the effective 'args' (with secrets redacted), the
+	/// 'watched_paths' as with '--list-watched-paths --json', the 'ignore_files' loaded, and the
+	/// 'recent_events' received, up to the last 100, before filtering.
+	///
+	/// It's written when Watchexec stops on a fatal error, and every time the file watcher reports
+	/// a non-fatal error, such as a path which can't be watched, so it describes the latest one. On
+	/// Unix, it can also be written on request by sending Watchexec the USR2 signal: while this
+	/// option is set, that signal isn't passed on to the command. Each report replaces the previous
+	/// one.
+	#[arg(
+		long,
+		help_heading = OPTSET_DEBUGGING,
+		value_hint = ValueHint::FilePath,
+		value_name = "PATH",
+	)]
+	pub diagnostics_file: Option<PathBuf>,
+
 	/// Show the manual page
 	///
 	/// This shows the manual page for Watchexec, if the output is a terminal and the 'man' program
@@ -1010,6 +1332,8 @@ pub enum EmitEvents {
 	Environment,
 	Stdio,
 	File,
+	NulStdio,
+	NulFile,
 	JsonStdio,
 	JsonFile,
 	None,
@@ -1037,6 +1361,7 @@ pub enum ClearMode {
 	#[default]
 	Clear,
 	Reset,
+	Scrollback,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
@@ -1125,6 +1450,72 @@ impl TypedValueParser for SignalMappingValueParser {
 	}
 }
 
+/// What to do when the command exits with a code, see '--on-exit-code'.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitAction {
+	Quit,
+	Restart,
+	ReloadFilters,
+}
+
+impl ExitAction {
+	pub fn parse(action: &str) -> Option<Self> {
+		[Self::Quit, Self::Restart, Self::ReloadFilters]
+			.into_iter()
+			.find(|known| known.as_str() == action)
+	}
+
+	pub const fn as_str(self) -> &'static str {
+		match self {
+			Self::Quit => "quit",
+			Self::Restart => "restart",
+			Self::ReloadFilters => "reload-filters",
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ExitCodeMapping {
+	pub code: i64,
+	pub action: ExitAction,
+}
+
+#[derive(Clone)]
+struct ExitCodeMappingValueParser;
+
+impl TypedValueParser for ExitCodeMappingValueParser {
+	type Value = ExitCodeMapping;
+
+	fn parse_ref(
+		&self,
+		_cmd: &Command,
+		_arg: Option<&Arg>,
+		value: &OsStr,
+	) -> Result<Self::Value, clap::error::Error> {
+		let value = value
+			.to_str()
+			.ok_or_else(|| clap::error::Error::raw(ErrorKind::ValueValidation, "invalid UTF-8"))?;
+		let (code, action) = value
+			.split_once(':')
+			.ok_or_else(|| clap::error::Error::raw(ErrorKind::ValueValidation, "missing ':'"))?;
+
+		let code = code.parse::<i64>().map_err(|err| {
+			clap::error::Error::raw(
+				ErrorKind::ValueValidation,
+				format!("invalid exit code: {err}"),
+			)
+		})?;
+		let action = ExitAction::parse(action).ok_or_else(|| {
+			clap::error::Error::raw(
+				ErrorKind::ValueValidation,
+				format!("unknown action '{action}', expected quit, restart, or reload-filters"),
+			)
+		})?;
+
+		Ok(Self::Value { code, action })
+	}
+}
+
 fn expand_args_up_to_doubledash() -> Result<Vec<OsString>, std::io::Error> {
 	use argfile::Argument;
 	use std::collections::VecDeque;
@@ -1227,16 +1618,39 @@ pub async fn get_args() -> Result<(Args, Option<WorkerGuard>)> {
 	if args.only_emit_events
 		&& !matches!(
 			args.emit_events_to,
-			EmitEvents::JsonStdio | EmitEvents::Stdio
+			EmitEvents::JsonStdio | EmitEvents::Stdio | EmitEvents::NulStdio
 		) {
 		Args::command()
 			.error(
 				ErrorKind::InvalidValue,
-				"only-emit-events requires --emit-events-to=stdio or --emit-events-to=json-stdio",
+				"only-emit-events requires --emit-events-to=stdio, --emit-events-to=nul-stdio, or --emit-events-to=json-stdio",
 			)
 			.exit();
 	}
 
+	if let Some(format) = &args.timestamp_prefix {
+		if !crate::output::is_valid_timestamp_format(format) {
+			Args::command()
+				.error(
+					ErrorKind::InvalidValue,
+					format!("invalid --timestamp-prefix format: {format}"),
+				)
+				.exit();
+		}
+	}
+
+	let mut exit_codes = BTreeSet::new();
+	for ExitCodeMapping { code, .. } in &args.on_exit_code {
+		if !exit_codes.insert(*code) {
+			Args::command()
+				.error(
+					ErrorKind::ArgumentConflict,
+					format!("exit code {code} is given more than one action with --on-exit-code"),
+				)
+				.exit();
+		}
+	}
+
 	if args.stdin_quit && args.watch_file == Some(PathBuf::from("-")) {
 		Args::command()
 			.error(
@@ -1291,7 +1705,13 @@ pub async fn get_args() -> Result<(Args, Option<WorkerGuard>)> {
 					canonicalize(project_origin.join(path)).into_diagnostic()
 				}
 			}
-			.map(WatchedPath::recursive)
+			.map(|path| {
+				if path.is_file() {
+					WatchedPath::file(path)
+				} else {
+					WatchedPath::recursive(path)
+				}
+			})
 		})
 		.chain(take(&mut args.non_recursive_paths).into_iter().map(|path| {
 			{
@@ -1321,6 +1741,15 @@ pub async fn get_args() -> Result<(Args, Option<WorkerGuard>)> {
 	}
 	info!(paths=?args.paths, "effective watched paths");
 
+	let curdir = std::env::current_dir().into_diagnostic()?;
+	args.ignore_outputs = take(&mut args.ignore_outputs)
+		.into_iter()
+		.map(|path| {
+			let path = curdir.join(path);
+			canonicalize(&path).unwrap_or(path)
+		})
+		.collect();
+
 	for (n, prog) in args.filter_programs.iter_mut().enumerate() {
 		if let Some(progpath) = prog.strip_prefix('@') {
 			trace!(?n, path=?progpath, "reading filter program from file");
@@ -5,36 +5,51 @@ use std::{
 	ffi::{OsStr, OsString},
 	fs::File,
 	io::{IsTerminal, Write},
+	mem::take,
 	process::Stdio,
 	sync::{
-		atomic::{AtomicBool, AtomicU8, Ordering},
-		Arc,
+		atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering},
+		Arc, Mutex,
 	},
-	time::Duration,
+	time::{Duration, Instant},
 };
 
 use clearscreen::ClearScreen;
 use miette::{miette, IntoDiagnostic, Report, Result};
-use notify_rust::Notification;
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
-use tokio::{process::Command as TokioCommand, time::sleep};
+use tokio::{
+	process::Command as TokioCommand,
+	sync::{mpsc, oneshot},
+	time::sleep,
+};
 use tracing::{debug, debug_span, error, instrument, trace, trace_span, Instrument};
 use watchexec::{
 	action::ActionHandler,
-	command::{Command, Program, Shell, SpawnOptions},
+	command::{Command, Program, Shell, ShellInput, SpawnOptions},
 	error::RuntimeError,
+	filter::Filterer,
 	job::{CommandState, Job},
-	sources::fs::Watcher,
+	notification::{DesktopNotifier, Notification, Notifier},
+	sources::fs::{FsEventKinds, Watcher},
 	Config, ErrorHook, Id,
 };
-use watchexec_events::{Event, Keyboard, ProcessEnd, Tag};
+use watchexec_events::{Event, Keyboard, Priority, ProcessEnd, Source, Tag};
 use watchexec_signals::Signal;
 
 use crate::{
-	args::{Args, ClearMode, ColourMode, EmitEvents, OnBusyUpdate, SignalMapping, WrapMode},
+	args::{
+		Args, ClearMode, ColourMode, EmitEvents, ExitAction, ExitCodeMapping, FsEvent,
+		OnBusyUpdate, SignalMapping, WrapMode,
+	},
+	output::{OutputCapture, OutputEvents},
 	state::RotatingTempFile,
 };
-use crate::{emits::events_to_simple_format, state::State};
+use crate::{
+	diagnostics::error_chain,
+	emits::{events_to_nul_separated, events_to_simple_format},
+	filterer::{run_on_filterer, WatchexecFilterer},
+	state::State,
+};
 
 #[derive(Clone, Copy, Debug)]
 struct OutputFlags {
@@ -43,12 +58,204 @@ struct OutputFlags {
 	timings: bool,
 	bell: bool,
 	toast: bool,
+	command_timeout: Option<Duration>,
+}
+
+/// Collapses the starting messages of rapidly repeated runs, see '--coalesce-banners'.
+#[derive(Clone, Debug, Default)]
+struct Banners {
+	window: Option<Duration>,
+	series: Arc<Mutex<BannerSeries>>,
+}
+
+#[derive(Debug, Default)]
+struct BannerSeries {
+	command: String,
+	started: Option<Instant>,
+	runs: u64,
+	repeats: usize,
+}
+
+/// Runs the command again when it fails, see '--retries'.
+///
+/// Each run carries its attempt number, and the number of changes seen when it was started by one:
+/// a change which starts the command again cancels the retries of the runs before it.
+#[derive(Clone, Debug, Default)]
+struct Retries {
+	max: u32,
+	delay: Duration,
+	changes: Arc<AtomicU64>,
+	attempt: u32,
+	generation: u64,
+}
+
+impl Retries {
+	fn new(max: u32, delay: Duration) -> Self {
+		Self {
+			max,
+			delay,
+			..Default::default()
+		}
+	}
+
+	/// Record a change which starts the command, cancelling pending retries.
+	fn changed(&self) {
+		self.changes.fetch_add(1, Ordering::SeqCst);
+	}
+
+	/// The retry state for a run started by a change.
+	fn first(&self) -> Self {
+		Self {
+			attempt: 0,
+			generation: self.changes.load(Ordering::SeqCst),
+			..self.clone()
+		}
+	}
+
+	/// Whether no change has started the command since this run was.
+	fn is_current(&self) -> bool {
+		self.changes.load(Ordering::SeqCst) == self.generation
+	}
+
+	/// The retry state for the next attempt, if there are attempts left and it's not cancelled.
+	fn next(&self) -> Option<Self> {
+		(self.attempt < self.max && self.is_current()).then(|| Self {
+			attempt: self.attempt + 1,
+			..self.clone()
+		})
+	}
+}
+
+/// Metadata key for the internal events carrying actions requested by exit codes.
+const EXIT_ACTION_METADATA: &str = "exit-action";
+
+/// Acts on the exit codes of the command, see '--on-exit-code'.
+///
+/// The action is requested with an internal event, so it's carried out by the action handler.
+#[derive(Clone, Debug, Default)]
+struct ExitActions {
+	map: Arc<HashMap<i64, ExitAction>>,
+	events: Option<mpsc::Sender<Event>>,
+}
+
+impl ExitActions {
+	fn new(mappings: &[ExitCodeMapping], events: Option<mpsc::Sender<Event>>) -> Self {
+		Self {
+			map: Arc::new(
+				mappings
+					.iter()
+					.map(|ExitCodeMapping { code, action }| (*code, *action))
+					.collect(),
+			),
+			events,
+		}
+	}
+
+	/// Request the action mapped to how the command ended, returning whether there was one.
+	fn consult(&self, status: Option<ProcessEnd>) -> bool {
+		let code = match status {
+			Some(ProcessEnd::Success) => 0,
+			Some(ProcessEnd::ExitError(code)) => code.get(),
+			_ => return false,
+		};
+		let (Some(action), Some(events)) = (self.map.get(&code), self.events.as_ref()) else {
+			return false;
+		};
+
+		debug!(%code, ?action, "exit code is mapped, requesting action");
+		if let Err(err) = events.try_send(Event {
+			tags: vec![Tag::Source(Source::Internal)],
+			metadata: HashMap::from([(EXIT_ACTION_METADATA.into(), vec![action.as_str().into()])]),
+		}) {
+			debug!(%err, "could not request action for exit code");
+		}
+		true
+	}
+
+	/// The actions requested in these events.
+	fn requested(events: &[Event]) -> impl Iterator<Item = ExitAction> + '_ {
+		events
+			.iter()
+			.filter_map(|event| event.metadata.get(EXIT_ACTION_METADATA))
+			.flatten()
+			.filter_map(|action| ExitAction::parse(action))
+	}
+}
+
+impl Banners {
+	fn new(window: Option<Duration>) -> Self {
+		Self {
+			window,
+			..Default::default()
+		}
+	}
+
+	/// Record a start of the command, returning its run number and whether it's a repeat.
+	fn start(&self, command: &str) -> (u64, bool) {
+		let mut series = self.series.lock().unwrap();
+		let now = Instant::now();
+		let repeat = self.window.is_some_and(|window| {
+			series.command == command
+				&& series
+					.started
+					.is_some_and(|started| now.duration_since(started) < window)
+		});
+
+		series.runs += 1;
+		series.started = Some(now);
+		if repeat {
+			series.repeats += 1;
+		} else {
+			series.command = command.to_owned();
+			series.repeats = 0;
+		}
+
+		(series.runs, repeat)
+	}
+
+	/// Print the collapsed message for the repeats not yet printed, if any.
+	fn flush(&self, outflags: OutputFlags) {
+		let mut series = self.series.lock().unwrap();
+		if series.repeats > 0 {
+			print_banner(
+				outflags,
+				&format!("{} (repeated {} times)", series.command, series.repeats),
+			);
+			series.repeats = 0;
+		}
+	}
+
+	/// Print the collapsed message once the command hasn't been started again for the window.
+	fn flush_later(&self, run: u64, outflags: OutputFlags) {
+		let Some(window) = self.window else {
+			return;
+		};
+
+		let banners = self.clone();
+		tokio::spawn(async move {
+			sleep(window).await;
+			if banners.series.lock().unwrap().runs == run {
+				banners.flush(outflags);
+			}
+		});
+	}
 }
 
-pub fn make_config(args: &Args, state: &State) -> Result<Config> {
+pub async fn make_config(args: &Args, state: &State) -> Result<Config> {
+	let run_on = run_on_filterer(args).await?;
+
 	let _span = debug_span!("args-runtime").entered();
 	let config = Config::default();
-	config.on_error(|err: ErrorHook| {
+	let diagnostics = state.diagnostics.clone();
+	config.on_error(move |err: ErrorHook| {
+		if let (Some(diagnostics), RuntimeError::FsWatcher { .. }) = (&diagnostics, &err.error) {
+			let diagnostics = diagnostics.clone();
+			let error = error_chain(&err.error);
+			tokio::spawn(async move {
+				diagnostics.write("file watcher error", Some(error)).await;
+			});
+		}
+
 		if let RuntimeError::IoError {
 			about: "waiting on process group",
 			..
@@ -68,8 +275,24 @@ pub fn make_config(args: &Args, state: &State) -> Result<Config> {
 	});
 
 	config.pathset(args.paths.clone());
+	config.event_kinds(
+		args.filter_fs_events
+			.iter()
+			.map(|kind| match kind {
+				FsEvent::Access => FsEventKinds::ACCESS,
+				FsEvent::Create => FsEventKinds::CREATE,
+				FsEvent::Remove => FsEventKinds::REMOVE,
+				FsEvent::Rename => FsEventKinds::RENAME,
+				FsEvent::Modify => FsEventKinds::MODIFY,
+				FsEvent::Metadata => FsEventKinds::METADATA,
+			})
+			.collect(),
+	);
+	config.ignore_metadata_only_events(args.filter_fs_meta);
 
 	config.throttle(args.debounce.0);
+	config.max_paths_per_action(args.max_paths);
+	config.ignore_command_outputs(args.ignore_outputs.clone());
 	config.keyboard_events(args.stdin_quit);
 
 	if let Some(interval) = args.poll {
@@ -103,6 +326,9 @@ pub fn make_config(args: &Args, state: &State) -> Result<Config> {
 					ClearMode::Reset => {
 						reset_screen();
 					}
+					ClearMode::Scrollback => {
+						clear_scrollback();
+					}
 				}
 			}
 
@@ -113,6 +339,13 @@ pub fn make_config(args: &Args, state: &State) -> Result<Config> {
 						events_to_simple_format(action.events.as_ref()).unwrap_or_default()
 					);
 				}
+				EmitEvents::NulStdio => {
+					let mut stdout = std::io::stdout();
+					stdout
+						.write_all(&events_to_nul_separated(action.events.as_ref()))
+						.ok();
+					stdout.flush().ok();
+				}
 				EmitEvents::JsonStdio => {
 					for event in action.events.iter().filter(|e| !e.is_empty()) {
 						println!("{}", serde_json::to_string(event).unwrap_or_default());
@@ -136,6 +369,7 @@ pub fn make_config(args: &Args, state: &State) -> Result<Config> {
 
 	let signal = args.signal;
 	let stop_signal = args.stop_signal;
+	let restart_signals: Arc<[Signal]> = args.restart_on_signal.clone().into();
 	let stop_timeout = args.stop_timeout.0;
 
 	let print_events = args.print_events;
@@ -150,7 +384,15 @@ pub fn make_config(args: &Args, state: &State) -> Result<Config> {
 		timings: args.timings,
 		bell: args.bell,
 		toast: args.notify,
+		command_timeout: args.command_timeout.map(|ts| ts.0),
 	};
+	let capture = OutputCapture::new(
+		args.timestamp_prefix.as_deref(),
+		state
+			.output_events
+			.clone()
+			.map(|sender| OutputEvents::new(sender, args.workdir.clone())),
+	);
 
 	let workdir = Arc::new(args.workdir.clone());
 
@@ -167,8 +409,31 @@ pub fn make_config(args: &Args, state: &State) -> Result<Config> {
 		"additional environment variables to add to command"
 	);
 
+	match std::env::current_exe() {
+		Ok(exe) => {
+			add_envs.insert("WATCHEXEC_BIN".into(), exe.into());
+		}
+		Err(err) => {
+			debug!(%err, "could not get path to current executable, not setting WATCHEXEC_BIN");
+		}
+	}
+	add_envs.insert("WATCHEXEC".into(), "1".into());
+
 	let id = Id::default();
 	let command = interpret_command_args(args)?;
+	// the program may only be found with the PATH given to it
+	let path_overridden = args.env.iter().any(|pair| {
+		pair.split_once('=').is_some_and(|(key, _)| {
+			if cfg!(windows) {
+				key.eq_ignore_ascii_case("PATH")
+			} else {
+				key == "PATH"
+			}
+		})
+	});
+	if !path_overridden {
+		command.preflight().into_diagnostic()?;
+	}
 
 	let signal_map: Arc<HashMap<Signal, Option<Signal>>> = Arc::new(
 		args.signal_map
@@ -179,14 +444,32 @@ pub fn make_config(args: &Args, state: &State) -> Result<Config> {
 	);
 
 	let queued = Arc::new(AtomicBool::new(false));
+	let replay_dropped = args.replay_dropped;
+	let dropped: Arc<Mutex<Vec<Event>>> = Arc::default();
+	let banners = Banners::new(args.coalesce_banners.map(|ts| ts.0));
+	let retries = Retries::new(args.retries, args.retry_delay.0);
+	let exit_actions = ExitActions::new(&args.on_exit_code, state.exit_actions.clone());
+	let filterer = config.filterer.clone();
+	let reload_args = Arc::new(args.clone());
 	let quit_again = Arc::new(AtomicU8::new(0));
+	let diagnostics = state.diagnostics.clone();
 
 	config.on_action_async(move |mut action| {
 		let add_envs = add_envs.clone();
+		let banners = banners.clone();
+		let retries = retries.clone();
+		let exit_actions = exit_actions.clone();
+		let capture = capture.clone();
 		let command = command.clone();
+		let diagnostics = diagnostics.clone();
+		let dropped = dropped.clone();
 		let emit_file = emit_file.clone();
+		let filterer = filterer.clone();
 		let queued = queued.clone();
 		let quit_again = quit_again.clone();
+		let reload_args = reload_args.clone();
+		let restart_signals = restart_signals.clone();
+		let run_on = run_on.clone();
 		let signal_map = signal_map.clone();
 		let workdir = workdir.clone();
 		Box::new(
@@ -194,7 +477,12 @@ pub fn make_config(args: &Args, state: &State) -> Result<Config> {
 				trace!(events=?action.events, "handling action");
 
 				let add_envs = add_envs.clone();
+				let banners = banners.clone();
+				let retries = retries.clone();
+				let exit_actions = exit_actions.clone();
+				let capture = capture.clone();
 				let command = command.clone();
+				let dropped = dropped.clone();
 				let emit_file = emit_file.clone();
 				let queued = queued.clone();
 				let quit_again = quit_again.clone();
@@ -203,25 +491,60 @@ pub fn make_config(args: &Args, state: &State) -> Result<Config> {
 
 				trace!("set spawn hook for workdir and environment variables");
 				let job = action.get_or_create_job(id, move || command.clone());
-				let events = action.events.clone();
-				job.set_spawn_hook(move |command, _| {
-					let add_envs = add_envs.clone();
-					let emit_file = emit_file.clone();
-					let events = events.clone();
-
-					if let Some(ref workdir) = workdir.as_ref() {
-						debug!(?workdir, "set command workdir");
-						command.command_mut().current_dir(workdir);
-					}
+				let set_spawn_hook = {
+					let spawn_capture = capture.clone();
+					Arc::new(
+						move |job: &Job,
+						      events: Arc<[Event]>,
+						      paths_truncated: Option<usize>,
+						      first_run: bool| {
+							let add_envs = add_envs.clone();
+							let emit_file = emit_file.clone();
+							let spawn_capture = spawn_capture.clone();
+							let workdir = workdir.clone();
+							job.set_spawn_hook(move |command, _| {
+								let mut add_envs = add_envs.clone();
+								add_envs.insert(
+									"WATCHEXEC_RUN_ID".into(),
+									uuid::Uuid::new_v4().to_string().into(),
+								);
+								if let Some(total) = paths_truncated {
+									add_envs.insert("WATCHEXEC_PATHS_TRUNCATED".into(), "1".into());
+									add_envs.insert(
+										"WATCHEXEC_PATHS_COUNT".into(),
+										total.to_string().into(),
+									);
+								}
+								if first_run {
+									add_envs.insert("WATCHEXEC_FIRST_RUN".into(), "1".into());
+								}
+								let emit_file = emit_file.clone();
+								let events = events.clone();
 
-					emit_events_to_command(
-						command.command_mut(),
-						events,
-						emit_file,
-						emit_events_to,
-						add_envs,
-					);
-				});
+								if let Some(ref workdir) = workdir.as_ref() {
+									debug!(?workdir, "set command workdir");
+									command.command_mut().current_dir(workdir);
+								}
+
+								spawn_capture.setup(command.command_mut(), &events);
+
+								emit_events_to_command(
+									command.command_mut(),
+									events,
+									emit_file,
+									emit_events_to,
+									add_envs,
+								);
+							});
+						},
+					)
+				};
+				set_spawn_hook(
+					&job,
+					action.events.clone(),
+					action.paths_truncated(),
+					action.is_first_run(),
+				);
 
 				let show_events = {
 					let events = action.events.clone();
@@ -235,31 +558,36 @@ pub fn make_config(args: &Args, state: &State) -> Result<Config> {
 					}
 				};
 
-				let clear_screen = {
-					let events = action.events.clone();
-					move || {
-						if let Some(mode) = clear {
-							match mode {
-								ClearMode::Clear => {
-									clearscreen::clear().ok();
-									debug!("cleared screen");
-								}
-								ClearMode::Reset => {
-									reset_screen();
-									debug!("hard-reset screen");
-								}
+				let clear_screen_with = move |events: &[Event]| {
+					if let Some(mode) = clear {
+						match mode {
+							ClearMode::Clear => {
+								clearscreen::clear().ok();
+								debug!("cleared screen");
+							}
+							ClearMode::Reset => {
+								reset_screen();
+								debug!("hard-reset screen");
+							}
+							ClearMode::Scrollback => {
+								clear_scrollback();
+								debug!("cleared screen and scrollback");
 							}
 						}
+					}
 
-						// re-show events after clearing
-						if print_events {
-							trace!("print events to stderr");
-							for (n, event) in events.iter().enumerate() {
-								eprintln!("[EVENT {n}] {event}");
-							}
+					// re-show events after clearing
+					if print_events {
+						trace!("print events to stderr");
+						for (n, event) in events.iter().enumerate() {
+							eprintln!("[EVENT {n}] {event}");
 						}
 					}
 				};
+				let clear_screen = {
+					let events = action.events.clone();
+					move || clear_screen_with(&events)
+				};
 
 				let quit = |mut action: ActionHandler| {
 					match quit_again.fetch_add(1, Ordering::Relaxed) {
@@ -297,7 +625,32 @@ pub fn make_config(args: &Args, state: &State) -> Result<Config> {
 
 					// this blocks the event loop, but also this is a debug feature so i don't care
 					job.start().await;
-					job.to_wait().await;
+					let mut attempt = 0;
+					loop {
+						let output = capture.take_pending();
+						let timed_out = wait_or_timeout(&job, outflags.command_timeout).await;
+						output.finish().await;
+						if timed_out {
+							let banners = banners.clone();
+							job.run(move |context| {
+								end_of_process(context.current, outflags, &banners, true);
+							})
+							.await;
+							break;
+						}
+
+						if attempt >= retries.max
+							|| matches!(end_status(&job).await, None | Some(ProcessEnd::Success))
+						{
+							break;
+						}
+
+						attempt += 1;
+						debug!(%attempt, "command failed, retrying");
+						sleep(retries.delay).await;
+						print_retry(outflags, attempt, retries.max);
+						job.start().await;
+					}
 					return quit(action);
 				}
 
@@ -311,22 +664,71 @@ pub fn make_config(args: &Args, state: &State) -> Result<Config> {
 					return quit(action);
 				}
 
+				let mut restart = false;
+				let requested: Vec<ExitAction> = ExitActions::requested(&action.events).collect();
+				for exit_action in requested {
+					match exit_action {
+						ExitAction::Quit => {
+							debug!("exit code mapped to quit");
+							show_events();
+							return quit(action);
+						}
+						ExitAction::Restart => {
+							debug!("exit code mapped to restart");
+							restart = true;
+						}
+						ExitAction::ReloadFilters => {
+							debug!("exit code mapped to reloading filters");
+							let filterer = filterer.clone();
+							let reload_args = reload_args.clone();
+							// building the filterer isn't Send, so it gets a thread of its own
+							let runtime = tokio::runtime::Handle::current();
+							tokio::task::spawn_blocking(move || {
+								match runtime.block_on(WatchexecFilterer::new(&reload_args)) {
+									Ok(new) => {
+										filterer.replace(new);
+										if !outflags.quiet {
+											eprintln!("[Reloaded filters]");
+										}
+									}
+									Err(err) => {
+										eprintln!("[[Failed to reload filters, keeping the old ones: {err}]]");
+									}
+								}
+							});
+						}
+					}
+				}
+
 				let signals: Vec<Signal> = action.signals().collect();
 				trace!(?signals, "received some signals");
 
 				// if we got a terminate or interrupt signal and they're not mapped, quit
-				if (signals.contains(&Signal::Terminate)
-					&& !signal_map.contains_key(&Signal::Terminate))
-					|| (signals.contains(&Signal::Interrupt)
-						&& !signal_map.contains_key(&Signal::Interrupt))
+				let is_unmapped = |signal: &Signal| {
+					!signal_map.contains_key(signal) && !restart_signals.contains(signal)
+				};
+				if (signals.contains(&Signal::Terminate) && is_unmapped(&Signal::Terminate))
+					|| (signals.contains(&Signal::Interrupt) && is_unmapped(&Signal::Interrupt))
 				{
 					debug!("unmapped terminate or interrupt signal, quit");
 					show_events();
 					return quit(action);
 				}
 
-				// pass all other signals on
+				// pass all other signals on, except for restart signals
 				for signal in signals {
+					if let (Signal::User2, Some(diagnostics)) = (signal, &diagnostics) {
+						debug!("diagnostics report requested by signal, not passing on");
+						diagnostics.write("requested by signal", None).await;
+						continue;
+					}
+
+					if restart_signals.contains(&signal) {
+						debug!(?signal, "restart signal, not passing on");
+						restart = true;
+						continue;
+					}
+
 					match signal_map.get(&signal) {
 						Some(Some(mapped)) => {
 							debug!(?signal, ?mapped, "passing mapped signal");
@@ -342,14 +744,56 @@ pub fn make_config(args: &Args, state: &State) -> Result<Config> {
 					}
 				}
 
-				// only filesystem events below here (or empty synthetic events)
-				if action.paths().next().is_none() && !action.events.iter().any(|e| e.is_empty()) {
-					debug!("no filesystem or synthetic events, skip without doing more");
+				if restart {
+					debug!("restart signal or exit code received, restart the command");
 					show_events();
+					retries.changed();
+					let innerjob = job.clone();
+					job.restart_with_signal(stop_signal.unwrap_or(Signal::Terminate), stop_timeout);
+					job.run(move |context| {
+						clear_screen();
+						setup_process(
+							innerjob.clone(),
+							context.command.clone(),
+							outflags,
+							capture.clone(),
+							banners.clone(),
+							retries.first(),
+							exit_actions.clone(),
+						)
+					});
 					return action;
 				}
 
+				// only filesystem events below here (or empty synthetic or external events)
+				if action.paths().next().is_none()
+					&& !action
+						.events
+						.iter()
+						.any(|e| e.is_empty() || e.is_external())
+				{
+					debug!("no filesystem, synthetic, or external events, skip without doing more");
+					show_events();
+					return action;
+				}
+
+				if let Some(run_on) = run_on.as_ref() {
+					let matched = action.events.iter().any(|event| {
+						if event.paths().next().is_none() {
+							event.is_empty() || event.is_external()
+						} else {
+							run_on.check_event(event, Priority::Normal).unwrap_or(false)
+						}
+					});
+					if !matched {
+						debug!("no changes to paths matching run-on patterns, skip running");
+						show_events();
+						return action;
+					}
+				}
+
 				show_events();
+				retries.changed();
 
 				if let Some(delay) = delay_run {
 					trace!("delaying run by sleeping inside the job");
@@ -363,14 +807,85 @@ pub fn make_config(args: &Args, state: &State) -> Result<Config> {
 				trace!("querying job state via run_async");
 				job.run_async({
 					let job = job.clone();
+					let events = action.events.clone();
 					move |context| {
 						let job = job.clone();
+						let banners = banners.clone();
+						let retries = retries.clone();
+						let exit_actions = exit_actions.clone();
+						let capture = capture.clone();
+						let dropped = dropped.clone();
+						let events = events.clone();
+						let queued = queued.clone();
+						let set_spawn_hook = set_spawn_hook.clone();
 						let is_running = matches!(context.current, CommandState::Running { .. });
 						Box::new(async move {
 							let innerjob = job.clone();
 							if is_running {
 								trace!(?on_busy, "job is running, decide what to do");
 								match on_busy {
+									OnBusyUpdate::DoNothing if replay_dropped => {
+										let mut buffer = dropped.lock().unwrap();
+										for event in
+											events.iter().filter(|e| e.paths().next().is_some())
+										{
+											if !buffer.contains(event) {
+												buffer.push(event.clone());
+											}
+										}
+
+										if buffer.is_empty() {
+											debug!("no changes to replay, do nothing");
+										} else if queued.fetch_or(true, Ordering::SeqCst) {
+											debug!("replay of dropped events is already queued");
+										} else {
+											debug!("queueing replay of dropped events");
+											drop(buffer);
+											tokio::spawn(async move {
+												loop {
+													trace!("waiting for job to finish");
+													job.to_wait().await;
+
+													let events: Arc<[Event]> = {
+														let mut buffer = dropped.lock().unwrap();
+														if buffer.is_empty() {
+															trace!("nothing more to replay");
+															queued.store(false, Ordering::SeqCst);
+															break;
+														}
+														take(&mut *buffer).into()
+													};
+
+													debug!(count=%events.len(), "job finished, replaying dropped events");
+													set_spawn_hook(
+														&job,
+														events.clone(),
+														None,
+														false,
+													);
+													job.start();
+													let innerjob = innerjob.clone();
+													let banners = banners.clone();
+													let retries = retries.clone();
+													let exit_actions = exit_actions.clone();
+													let capture = capture.clone();
+													job.run(move |context| {
+														clear_screen_with(&events);
+														setup_process(
+															innerjob.clone(),
+															context.command.clone(),
+															outflags,
+															capture.clone(),
+															banners.clone(),
+															retries.first(),
+															exit_actions.clone(),
+														)
+													})
+													.await;
+												}
+											});
+										}
+									}
 									OnBusyUpdate::DoNothing => {}
 									OnBusyUpdate::Signal => {
 										job.signal(if cfg!(windows) {
@@ -387,6 +902,10 @@ pub fn make_config(args: &Args, state: &State) -> Result<Config> {
 												innerjob.clone(),
 												context.command.clone(),
 												outflags,
+												capture.clone(),
+												banners.clone(),
+												retries.first(),
+												exit_actions.clone(),
 											)
 										});
 									}
@@ -401,6 +920,10 @@ pub fn make_config(args: &Args, state: &State) -> Result<Config> {
 												innerjob.clone(),
 												context.command.clone(),
 												outflags,
+												capture.clone(),
+												banners.clone(),
+												retries.first(),
+												exit_actions.clone(),
 											)
 										});
 									}
@@ -425,6 +948,10 @@ pub fn make_config(args: &Args, state: &State) -> Result<Config> {
 															innerjob.clone(),
 															context.command.clone(),
 															outflags,
+															capture.clone(),
+															banners.clone(),
+															retries.first(),
+															exit_actions.clone(),
 														)
 													})
 													.await;
@@ -444,6 +971,10 @@ pub fn make_config(args: &Args, state: &State) -> Result<Config> {
 										innerjob.clone(),
 										context.command.clone(),
 										outflags,
+										capture.clone(),
+										banners.clone(),
+										retries.first(),
+										exit_actions.clone(),
 									)
 								});
 							}
@@ -521,11 +1052,13 @@ fn interpret_command_args(args: &Args) -> Result<Arc<Command>> {
 			shell,
 			command: cmd.join(" "),
 			args: Vec::new(),
+			via: ShellInput::Arg,
 		}
 	} else {
 		Program::Exec {
 			prog: cmd.remove(0).into(),
 			args: cmd,
+			argv0: None,
 		}
 	};
 
@@ -534,44 +1067,149 @@ fn interpret_command_args(args: &Args) -> Result<Arc<Command>> {
 		options: SpawnOptions {
 			grouped: matches!(args.wrap_process, WrapMode::Group),
 			session: matches!(args.wrap_process, WrapMode::Session),
+			pty: args.pty,
 			..Default::default()
 		},
 	}))
 }
 
 #[instrument(level = "trace")]
-fn setup_process(job: Job, command: Arc<Command>, outflags: OutputFlags) {
+fn setup_process(
+	job: Job,
+	command: Arc<Command>,
+	outflags: OutputFlags,
+	capture: OutputCapture,
+	banners: Banners,
+	retries: Retries,
+	exit_actions: ExitActions,
+) {
+	let output = capture.take_pending();
 	if outflags.toast {
-		Notification::new()
-			.summary("Watchexec: change detected")
-			.body(&format!("Running {command}"))
-			.show()
-			.map_or_else(
-				|err| {
-					eprintln!("[[Failed to send desktop notification: {err}]]");
-				},
-				drop,
-			);
+		if let Err(err) = DesktopNotifier.notify(&Notification::started(&command)) {
+			eprintln!("[[Failed to send desktop notification: {err}]]");
+		}
 	}
 
 	if !outflags.quiet {
-		let mut stderr = StandardStream::stderr(outflags.colour);
-		stderr.reset().ok();
-		stderr
-			.set_color(ColorSpec::new().set_fg(Some(Color::Green)))
-			.ok();
-		writeln!(&mut stderr, "[Running: {command}]").ok();
-		stderr.reset().ok();
+		let command = command.to_string();
+		let (run, repeat) = banners.start(&command);
+		if repeat {
+			trace!(?run, "repeated run, collapsing its banner");
+			banners.flush_later(run, outflags);
+		} else {
+			print_banner(outflags, &command);
+		}
 	}
 
 	tokio::spawn(async move {
-		job.to_wait().await;
-		job.run(move |context| end_of_process(context.current, outflags));
+		let timed_out = wait_or_timeout(&job, outflags.command_timeout).await;
+		output.finish().await;
+		job.run({
+			let banners = banners.clone();
+			move |context| end_of_process(context.current, outflags, &banners, timed_out)
+		});
+
+		if timed_out {
+			return;
+		}
+		let status = end_status(&job).await;
+		if exit_actions.consult(status) {
+			return;
+		}
+		let Some(next) = retries.next() else {
+			return;
+		};
+		if matches!(status, None | Some(ProcessEnd::Success)) {
+			return;
+		}
+
+		debug!(attempt=%next.attempt, delay=?next.delay, "command failed, retrying");
+		sleep(next.delay).await;
+		if !next.is_current() {
+			debug!("command was started again by a change, cancel retry");
+			return;
+		}
+
+		print_retry(outflags, next.attempt, next.max);
+		job.start();
+		let innerjob = job.clone();
+		job.run(move |context| {
+			setup_process(
+				innerjob,
+				context.command.clone(),
+				outflags,
+				capture,
+				banners,
+				next,
+				exit_actions,
+			);
+		});
 	});
 }
 
+fn print_retry(outflags: OutputFlags, attempt: u32, max: u32) {
+	if outflags.quiet {
+		return;
+	}
+
+	let mut stderr = StandardStream::stderr(outflags.colour);
+	stderr.reset().ok();
+	stderr
+		.set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))
+		.ok();
+	writeln!(
+		&mut stderr,
+		"[Command failed, retrying ({attempt} of {max})]"
+	)
+	.ok();
+	stderr.reset().ok();
+}
+
+/// The status the last run of the job ended with, if it has ended.
+async fn end_status(job: &Job) -> Option<ProcessEnd> {
+	let (status_s, status_r) = oneshot::channel();
+	job.run(move |context| {
+		if let CommandState::Finished { status, .. } = context.current {
+			status_s.send(*status).ok();
+		}
+	})
+	.await;
+	status_r.await.ok()
+}
+
+fn print_banner(outflags: OutputFlags, command: &str) {
+	let mut stderr = StandardStream::stderr(outflags.colour);
+	stderr.reset().ok();
+	stderr
+		.set_color(ColorSpec::new().set_fg(Some(Color::Green)))
+		.ok();
+	writeln!(&mut stderr, "[Running: {command}]").ok();
+	stderr.reset().ok();
+}
+
+/// Wait for the current run of the job to end, stopping it if it outlasts the command timeout.
+///
+/// Returns whether the command timed out.
+async fn wait_or_timeout(job: &Job, command_timeout: Option<Duration>) -> bool {
+	let Some(command_timeout) = command_timeout else {
+		job.to_wait().await;
+		return false;
+	};
+
+	if tokio::time::timeout(command_timeout, job.to_wait())
+		.await
+		.is_ok()
+	{
+		return false;
+	}
+
+	debug!(?command_timeout, "command timed out, stopping it");
+	job.stop().await;
+	true
+}
+
 #[instrument(level = "trace")]
-fn end_of_process(state: &CommandState, outflags: OutputFlags) {
+fn end_of_process(state: &CommandState, outflags: OutputFlags, banners: &Banners, timed_out: bool) {
 	let CommandState::Finished {
 		status,
 		started,
@@ -588,6 +1226,13 @@ fn end_of_process(state: &CommandState, outflags: OutputFlags) {
 		String::new()
 	};
 	let (msg, fg) = match status {
+		_ if timed_out => (
+			format!(
+				"Command timed out after {:?}{timing}",
+				outflags.command_timeout.unwrap_or_default()
+			),
+			Color::Red,
+		),
 		ProcessEnd::ExitError(code) => (format!("Command exited with {code}{timing}"), Color::Red),
 		ProcessEnd::ExitSignal(sig) => {
 			(format!("Command killed by {sig:?}{timing}"), Color::Magenta)
@@ -602,19 +1247,13 @@ fn end_of_process(state: &CommandState, outflags: OutputFlags) {
 	};
 
 	if outflags.toast {
-		Notification::new()
-			.summary("Watchexec: command ended")
-			.body(&msg)
-			.show()
-			.map_or_else(
-				|err| {
-					eprintln!("[[Failed to send desktop notification: {err}]]");
-				},
-				drop,
-			);
+		if let Err(err) = DesktopNotifier.notify(&Notification::ended(&msg)) {
+			eprintln!("[[Failed to send desktop notification: {err}]]");
+		}
 	}
 
 	if !outflags.quiet {
+		banners.flush(outflags);
 		let mut stderr = StandardStream::stderr(outflags.colour);
 		stderr.reset().ok();
 		stderr.set_color(ColorSpec::new().set_fg(Some(fg))).ok();
@@ -663,6 +1302,24 @@ fn emit_events_to_command(
 				error!("Failed to write WATCHEXEC_EVENTS_FILE, continuing without it: {err}");
 			}
 		},
+		EmitEvents::NulStdio => match emits_to_nul_file(&emit_file, &events)
+			.and_then(|path| File::open(path).into_diagnostic())
+		{
+			Ok(file) => {
+				stdin.replace(Stdio::from(file));
+			}
+			Err(err) => {
+				error!("Failed to write events to stdin, continuing without it: {err}");
+			}
+		},
+		EmitEvents::NulFile => match emits_to_nul_file(&emit_file, &events) {
+			Ok(path) => {
+				add_envs.insert("WATCHEXEC_EVENTS_FILE".into(), path.into());
+			}
+			Err(err) => {
+				error!("Failed to write WATCHEXEC_EVENTS_FILE, continuing without it: {err}");
+			}
+		},
 		EmitEvents::JsonStdio => match emits_to_json_file(&emit_file, &events)
 			.and_then(|path| File::open(path).into_diagnostic())
 		{
@@ -706,3 +1363,10 @@ pub(crate) fn reset_screen() {
 		cs.clear().ok();
 	}
 }
+
+pub(crate) fn clear_scrollback() {
+	clearscreen::clear().ok();
+	if let Err(err) = ClearScreen::TerminfoScrollback.clear() {
+		debug!(%err, "terminal doesn't support clearing scrollback, only cleared screen");
+	}
+}
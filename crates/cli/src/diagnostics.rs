@@ -0,0 +1,115 @@
+use std::{
+	collections::VecDeque,
+	error::Error,
+	path::PathBuf,
+	sync::{Arc, Mutex},
+};
+
+use miette::{IntoDiagnostic, Result};
+use serde_json::json;
+use tokio::fs;
+use tracing::{debug, warn};
+use watchexec::Config;
+use watchexec_events::Event;
+
+use crate::args::Args;
+
+/// How many of the most recent events are kept for the report
+const RECENT_EVENTS: usize = 100;
+
+/// Render an error with its sources, like 'outer: inner: innermost'
+pub fn error_chain(err: &dyn Error) -> String {
+	let mut message = err.to_string();
+	let mut source = err.source();
+	while let Some(err) = source {
+		message.push_str(": ");
+		message.push_str(&err.to_string());
+		source = err.source();
+	}
+	message
+}
+
+/// Collects what goes in the '--diagnostics-file' report, and writes it
+#[derive(Clone, Debug)]
+pub struct Diagnostics(Arc<Inner>);
+
+#[derive(Debug)]
+struct Inner {
+	path: PathBuf,
+	args: String,
+	watched_paths: serde_json::Value,
+	ignore_files: Mutex<Vec<PathBuf>>,
+	recent_events: Mutex<VecDeque<Event>>,
+}
+
+impl Diagnostics {
+	pub fn new(path: PathBuf, args: &Args) -> Self {
+		#[allow(unused_mut)] // only changed with the http-trigger feature
+		let mut args = args.clone();
+		#[cfg(feature = "http-trigger")]
+		if args.http_trigger_secret.is_some() {
+			args.http_trigger_secret = Some("(redacted)".into());
+		}
+
+		let config = Config::default();
+		config.pathset(args.paths.clone());
+		let watched_paths = config
+			.watched_paths()
+			.iter()
+			.map(|path| {
+				json!({
+					"path": path.path(),
+					"recursive": path.is_recursive(),
+					"file": path.is_file(),
+					"exists": path.path().exists(),
+				})
+			})
+			.collect();
+
+		Self(Arc::new(Inner {
+			path,
+			args: format!("{args:#?}"),
+			watched_paths,
+			ignore_files: Default::default(),
+			recent_events: Default::default(),
+		}))
+	}
+
+	pub fn set_ignore_files(&self, files: &[PathBuf]) {
+		*self.0.ignore_files.lock().unwrap() = files.to_vec();
+	}
+
+	pub fn record_event(&self, event: Event) {
+		let mut events = self.0.recent_events.lock().unwrap();
+		if events.len() >= RECENT_EVENTS {
+			events.pop_front();
+		}
+		events.push_back(event);
+	}
+
+	/// Write the report, replacing any previous one
+	///
+	/// Failing to write it is only logged, so it doesn't get in the way of the error being reported.
+	pub async fn write(&self, reason: &str, error: Option<String>) {
+		match self.try_write(reason, error).await {
+			Ok(()) => debug!(path=?self.0.path, %reason, "wrote diagnostics report"),
+			Err(err) => warn!(path=?self.0.path, %err, "could not write diagnostics report"),
+		}
+	}
+
+	async fn try_write(&self, reason: &str, error: Option<String>) -> Result<()> {
+		let report = json!({
+			"reason": reason,
+			"error": error,
+			"version": env!("CARGO_PKG_VERSION"),
+			"args": self.0.args,
+			"watched_paths": self.0.watched_paths,
+			"ignore_files": *self.0.ignore_files.lock().unwrap(),
+			"recent_events": *self.0.recent_events.lock().unwrap(),
+		});
+
+		let mut contents = serde_json::to_vec_pretty(&report).into_diagnostic()?;
+		contents.push(b'\n');
+		fs::write(&self.0.path, contents).await.into_diagnostic()
+	}
+}
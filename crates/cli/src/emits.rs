@@ -1,4 +1,4 @@
-use std::{ffi::OsString, fmt::Write, path::PathBuf};
+use std::{collections::HashSet, ffi::OsString, fmt::Write, path::PathBuf};
 
 use miette::{IntoDiagnostic, Result};
 use watchexec::paths::summarise_events_to_env;
@@ -51,6 +51,22 @@ pub fn events_to_simple_format(events: &[Event]) -> Result<String> {
 	Ok(buf)
 }
 
+pub fn events_to_nul_separated(events: &[Event]) -> Vec<u8> {
+	let mut seen = HashSet::new();
+	let mut buf = Vec::new();
+	for path in events
+		.iter()
+		.flat_map(|event| event.paths().map(|(p, _)| p))
+	{
+		if seen.insert(path) {
+			buf.extend_from_slice(path.as_os_str().as_encoded_bytes());
+			buf.push(0);
+		}
+	}
+
+	buf
+}
+
 pub fn emits_to_file(target: &RotatingTempFile, events: &[Event]) -> Result<PathBuf> {
 	target.rotate()?;
 	target.write(events_to_simple_format(events)?.as_bytes())?;
@@ -69,3 +85,9 @@ pub fn emits_to_json_file(target: &RotatingTempFile, events: &[Event]) -> Result
 	}
 	Ok(target.path())
 }
+
+pub fn emits_to_nul_file(target: &RotatingTempFile, events: &[Event]) -> Result<PathBuf> {
+	target.rotate()?;
+	target.write(&events_to_nul_separated(events))?;
+	Ok(target.path())
+}
@@ -1,6 +1,6 @@
 use std::{
 	ffi::OsString,
-	path::{Path, PathBuf, MAIN_SEPARATOR},
+	path::{Path, PathBuf},
 	sync::Arc,
 };
 
@@ -8,62 +8,26 @@ use miette::{IntoDiagnostic, Result};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tracing::{info, trace, trace_span};
 use watchexec::{error::RuntimeError, filter::Filterer};
-use watchexec_events::{
-	filekind::{FileEventKind, ModifyKind},
-	Event, Priority, Tag,
-};
-use watchexec_filterer_globset::GlobsetFilterer;
+use watchexec_events::{Event, Priority};
+use watchexec_filterer_globset::{CombinedFilterer, GlobsetFilterer};
 
-use crate::args::{Args, FsEvent};
+use crate::args::Args;
 
 pub(crate) mod parse;
 mod proglib;
 mod progs;
 mod syncval;
 
-/// A custom filterer that combines the library's Globset filterer and a switch for --no-meta
+/// A custom filterer that combines the library's Globset filterer and filter programs
 #[derive(Debug)]
 pub struct WatchexecFilterer {
-	inner: GlobsetFilterer,
-	fs_events: Vec<FsEvent>,
-	progs: Option<progs::FilterProgs>,
+	inner: CombinedFilterer,
 }
 
 impl Filterer for WatchexecFilterer {
 	#[tracing::instrument(level = "trace", skip(self))]
 	fn check_event(&self, event: &Event, priority: Priority) -> Result<bool, RuntimeError> {
-		for tag in &event.tags {
-			if let Tag::FileEventKind(fek) = tag {
-				let normalised = match fek {
-					FileEventKind::Access(_) => FsEvent::Access,
-					FileEventKind::Modify(ModifyKind::Name(_)) => FsEvent::Rename,
-					FileEventKind::Modify(ModifyKind::Metadata(_)) => FsEvent::Metadata,
-					FileEventKind::Modify(_) => FsEvent::Modify,
-					FileEventKind::Create(_) => FsEvent::Create,
-					FileEventKind::Remove(_) => FsEvent::Remove,
-					_ => continue,
-				};
-
-				trace!(allowed=?self.fs_events, this=?normalised, "check against fs event filter");
-				if !self.fs_events.contains(&normalised) {
-					return Ok(false);
-				}
-			}
-		}
-
-		trace!("check against original event");
-		if !self.inner.check_event(event, priority)? {
-			return Ok(false);
-		}
-
-		if let Some(progs) = &self.progs {
-			trace!("check against program filters");
-			if !progs.check(event)? {
-				return Ok(false);
-			}
-		}
-
-		Ok(true)
+		self.inner.check_event(event, priority)
 	}
 }
 
@@ -80,31 +44,6 @@ impl WatchexecFilterer {
 			crate::dirs::ignores(args, &vcs_types).await?
 		};
 
-		let mut ignores = Vec::new();
-
-		if !args.no_default_ignore {
-			ignores.extend([
-				(format!("**{MAIN_SEPARATOR}.DS_Store"), None),
-				(String::from("watchexec.*.log"), None),
-				(String::from("*.py[co]"), None),
-				(String::from("#*#"), None),
-				(String::from(".#*"), None),
-				(String::from(".*.kate-swp"), None),
-				(String::from(".*.sw?"), None),
-				(String::from(".*.sw?x"), None),
-				(format!("**{MAIN_SEPARATOR}.bzr{MAIN_SEPARATOR}**"), None),
-				(format!("**{MAIN_SEPARATOR}_darcs{MAIN_SEPARATOR}**"), None),
-				(
-					format!("**{MAIN_SEPARATOR}.fossil-settings{MAIN_SEPARATOR}**"),
-					None,
-				),
-				(format!("**{MAIN_SEPARATOR}.git{MAIN_SEPARATOR}**"), None),
-				(format!("**{MAIN_SEPARATOR}.hg{MAIN_SEPARATOR}**"), None),
-				(format!("**{MAIN_SEPARATOR}.pijul{MAIN_SEPARATOR}**"), None),
-				(format!("**{MAIN_SEPARATOR}.svn{MAIN_SEPARATOR}**"), None),
-			]);
-		}
-
 		let whitelist = args
 			.paths
 			.iter()
@@ -121,37 +60,60 @@ impl WatchexecFilterer {
 			filters.extend(read_filter_file(filter_file).await?);
 		}
 
-		ignores.extend(
-			args.ignore_patterns
-				.iter()
-				.map(|f| (f.to_owned(), Some(workdir.clone()))),
-		);
+		let ignores = args
+			.ignore_patterns
+			.iter()
+			.map(|f| (f.to_owned(), Some(workdir.clone())));
 
 		let exts = args
 			.filter_extensions
 			.iter()
 			.map(|e| OsString::from(e.strip_prefix('.').unwrap_or(e)));
 
+		let mut builder = CombinedFilterer::builder(project_origin)
+			.default_ignores(!args.no_default_ignore)
+			.filters(filters)
+			.ignores(ignores)
+			.whitelist(whitelist)
+			.ignore_files(ignore_files)
+			.extensions(exts);
+		if !args.filter_programs_parsed.is_empty() {
+			builder = builder.with_filterer(progs::FilterProgs::new(args)?);
+		}
+
 		info!("initialising Globset filterer");
 		Ok(Arc::new(Self {
-			inner: GlobsetFilterer::new(
-				project_origin,
-				filters,
-				ignores,
-				whitelist,
-				ignore_files,
-				exts,
-			)
-			.await
-			.into_diagnostic()?,
-			fs_events: args.filter_fs_events.clone(),
-			progs: if args.filter_programs_parsed.is_empty() {
-				None
-			} else {
-				Some(progs::FilterProgs::new(args)?)
-			},
+			inner: builder.build().await.into_diagnostic()?,
 		}))
 	}
+
+	/// The paths of the ignore files which were loaded
+	pub fn ignore_files(&self) -> &[PathBuf] {
+		self.inner.ignore_files()
+	}
+}
+
+/// Create a filterer for the '--run-on' patterns, if any
+///
+/// This is not used to filter events, but to decide whether the command should run for them.
+pub async fn run_on_filterer(args: &Args) -> Result<Option<Arc<GlobsetFilterer>>> {
+	if args.run_on_patterns.is_empty() {
+		return Ok(None);
+	}
+
+	let project_origin = args.project_origin.clone().unwrap();
+	let workdir = args.workdir.clone().unwrap();
+	let patterns = args
+		.run_on_patterns
+		.iter()
+		.map(|f| (f.to_owned(), Some(workdir.clone())));
+
+	info!("initialising Globset filterer for run-on patterns");
+	Ok(Some(Arc::new(
+		GlobsetFilterer::new(project_origin, patterns, [], [], [], [])
+			.await
+			.into_diagnostic()?,
+	)))
 }
 
 async fn read_filter_file(path: &Path) -> Result<Vec<(String, Option<PathBuf>)>> {
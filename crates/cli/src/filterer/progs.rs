@@ -7,8 +7,8 @@ use tokio::{
 	task::{block_in_place, spawn_blocking},
 };
 use tracing::{error, trace, warn};
-use watchexec::error::RuntimeError;
-use watchexec_events::Event;
+use watchexec::{error::RuntimeError, filter::Filterer};
+use watchexec_events::{Event, Priority};
 
 use crate::args::Args;
 
@@ -56,11 +56,14 @@ where
 	}
 }
 
-impl FilterProgs {
-	pub fn check(&self, event: &Event) -> Result<bool, RuntimeError> {
+impl Filterer for FilterProgs {
+	fn check_event(&self, event: &Event, _priority: Priority) -> Result<bool, RuntimeError> {
+		trace!("check against program filters");
 		self.channel.call(event.clone())
 	}
+}
 
+impl FilterProgs {
 	pub fn new(args: &Args) -> miette::Result<Self> {
 		let progs = args.filter_programs_parsed.clone();
 		eprintln!(
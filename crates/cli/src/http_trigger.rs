@@ -0,0 +1,227 @@
+use std::{collections::HashMap, io, net::SocketAddr, sync::Arc, time::Duration};
+
+use miette::{IntoDiagnostic, Result, WrapErr};
+use tokio::{
+	io::{AsyncReadExt, AsyncWriteExt},
+	net::{TcpListener, TcpStream},
+	time::timeout,
+};
+use tracing::{debug, info, warn};
+use watchexec::Watchexec;
+use watchexec_events::{metadata::HTTP_TRIGGER, Event, Priority, Source, Tag};
+
+const SECRET_HEADER: &str = "x-watchexec-secret";
+const MAX_HEAD_SIZE: usize = 8 * 1024;
+const MAX_BODY_SIZE: usize = 64 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Status {
+	Accepted,
+	BadRequest,
+	Unauthorized,
+	NotFound,
+	MethodNotAllowed,
+	ServiceUnavailable,
+}
+
+impl Status {
+	const fn code(self) -> u16 {
+		match self {
+			Self::Accepted => 202,
+			Self::BadRequest => 400,
+			Self::Unauthorized => 401,
+			Self::NotFound => 404,
+			Self::MethodNotAllowed => 405,
+			Self::ServiceUnavailable => 503,
+		}
+	}
+
+	const fn reason(self) -> &'static str {
+		match self {
+			Self::Accepted => "Accepted",
+			Self::BadRequest => "Bad Request",
+			Self::Unauthorized => "Unauthorized",
+			Self::NotFound => "Not Found",
+			Self::MethodNotAllowed => "Method Not Allowed",
+			Self::ServiceUnavailable => "Service Unavailable",
+		}
+	}
+}
+
+#[derive(Debug)]
+struct Request {
+	method: String,
+	path: String,
+	secret: Option<String>,
+}
+
+/// Bind the trigger listener and serve it in the background.
+///
+/// Binding happens before returning, so that a bad address is reported at startup.
+pub async fn listen(addr: SocketAddr, secret: String, wx: Arc<Watchexec>) -> Result<()> {
+	let listener = TcpListener::bind(addr)
+		.await
+		.into_diagnostic()
+		.wrap_err_with(|| format!("binding --http-trigger listener to {addr}"))?;
+	info!(addr=?listener.local_addr().ok(), "http trigger listening");
+
+	let secret = Arc::new(secret);
+	tokio::spawn(async move {
+		loop {
+			match listener.accept().await {
+				Ok((stream, peer)) => {
+					let secret = secret.clone();
+					let wx = wx.clone();
+					tokio::spawn(async move {
+						if let Err(err) = handle(stream, peer, &secret, &wx).await {
+							debug!(%peer, %err, "http trigger connection error");
+						}
+					});
+				}
+				Err(err) => warn!(%err, "http trigger: failed to accept connection"),
+			}
+		}
+	});
+
+	Ok(())
+}
+
+async fn handle(
+	mut stream: TcpStream,
+	peer: SocketAddr,
+	secret: &str,
+	wx: &Watchexec,
+) -> io::Result<()> {
+	let mut status = match timeout(READ_TIMEOUT, read_request(&mut stream)).await {
+		Ok(Ok(Some(request))) => route(&request, secret),
+		Ok(Ok(None)) | Err(_) => Status::BadRequest,
+		Ok(Err(err)) => return Err(err),
+	};
+	debug!(%peer, ?status, "http trigger request");
+
+	if status == Status::Accepted {
+		let event = Event {
+			tags: vec![Tag::Source(Source::External)],
+			metadata: HashMap::from([(HTTP_TRIGGER.into(), vec![peer.to_string()])]),
+		};
+		if let Err(err) = wx.send_event(event, Priority::Normal).await {
+			warn!(%err, "http trigger: failed to send event");
+			status = Status::ServiceUnavailable;
+		}
+	}
+
+	let body = format!("{}\n", status.reason());
+	let allow = if status == Status::MethodNotAllowed {
+		"Allow: POST\r\n"
+	} else {
+		""
+	};
+	let response = format!(
+		"HTTP/1.1 {} {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n{allow}\r\n{body}",
+		status.code(),
+		status.reason(),
+		body.len(),
+	);
+	stream.write_all(response.as_bytes()).await?;
+	stream.shutdown().await
+}
+
+fn route(request: &Request, secret: &str) -> Status {
+	if request.path != "/trigger" {
+		Status::NotFound
+	} else if request.method != "POST" {
+		Status::MethodNotAllowed
+	} else if !request
+		.secret
+		.as_deref()
+		.is_some_and(|given| secret_matches(given.as_bytes(), secret.as_bytes()))
+	{
+		Status::Unauthorized
+	} else {
+		Status::Accepted
+	}
+}
+
+// constant-time for equal lengths, so the secret can't be guessed byte by byte
+fn secret_matches(given: &[u8], secret: &[u8]) -> bool {
+	given.len() == secret.len()
+		&& given
+			.iter()
+			.zip(secret)
+			.fold(0, |acc, (a, b)| acc | (a ^ b))
+			== 0
+}
+
+/// Read and parse a request head, then discard its body.
+///
+/// Returns `None` if the request is malformed or too large.
+async fn read_request(stream: &mut TcpStream) -> io::Result<Option<Request>> {
+	let mut buf = Vec::with_capacity(1024);
+	let mut chunk = [0; 1024];
+	let end = loop {
+		if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+			break pos;
+		}
+		if buf.len() > MAX_HEAD_SIZE {
+			return Ok(None);
+		}
+		let n = stream.read(&mut chunk).await?;
+		if n == 0 {
+			return Ok(None);
+		}
+		buf.extend_from_slice(&chunk[..n]);
+	};
+
+	let Ok(head) = std::str::from_utf8(&buf[..end]) else {
+		return Ok(None);
+	};
+
+	let mut lines = head.split("\r\n");
+	let mut request_line = lines.next().unwrap_or_default().split(' ');
+	let (Some(method), Some(target), Some(version), None) = (
+		request_line.next(),
+		request_line.next(),
+		request_line.next(),
+		request_line.next(),
+	) else {
+		return Ok(None);
+	};
+	if !version.starts_with("HTTP/1.") {
+		return Ok(None);
+	}
+
+	let mut secret = None;
+	let mut content_length: usize = 0;
+	for line in lines {
+		let Some((name, value)) = line.split_once(':') else {
+			return Ok(None);
+		};
+		let value = value.trim();
+		if name.eq_ignore_ascii_case(SECRET_HEADER) {
+			secret = Some(value.to_owned());
+		} else if name.eq_ignore_ascii_case("content-length") {
+			let Ok(length) = value.parse() else {
+				return Ok(None);
+			};
+			content_length = length;
+		}
+	}
+
+	// read the body out, so closing the connection doesn't reset it before the client has seen
+	// the response
+	let remaining = content_length
+		.saturating_sub(buf.len() - (end + 4))
+		.min(MAX_BODY_SIZE);
+	tokio::io::copy(
+		&mut (&mut *stream).take(remaining as u64),
+		&mut tokio::io::sink(),
+	)
+	.await?;
+
+	Ok(Some(Request {
+		method: method.to_owned(),
+		path: target.split('?').next().unwrap_or_default().to_owned(),
+		secret,
+	}))
+}
@@ -1,45 +1,130 @@
 #![deny(rust_2018_idioms)]
 #![allow(clippy::missing_const_for_fn, clippy::future_not_send)]
 
-use std::{io::Write, process::Stdio};
+use std::{io::Write, process::Stdio, sync::Arc};
 
 use args::{Args, ShellCompletion};
 use clap::CommandFactory;
 use clap_complete::{Generator, Shell};
 use clap_mangen::Man;
+use futures::StreamExt;
 use is_terminal::IsTerminal;
 use miette::{IntoDiagnostic, Result};
-use tokio::{io::AsyncWriteExt, process::Command};
+use tokio::{io::AsyncWriteExt, process::Command, sync::mpsc};
 use tracing::{debug, info};
-use watchexec::Watchexec;
+use watchexec::{
+	config::{Config, Validation},
+	error::CriticalError,
+	Watchexec,
+};
 use watchexec_events::{Event, Priority};
 
-use crate::filterer::WatchexecFilterer;
+use crate::{
+	diagnostics::{error_chain, Diagnostics},
+	filterer::WatchexecFilterer,
+};
 
 pub mod args;
 mod config;
+mod diagnostics;
 mod dirs;
 mod emits;
 mod filterer;
+#[cfg(feature = "http-trigger")]
+mod http_trigger;
+mod output;
 mod state;
 
 async fn run_watchexec(args: Args) -> Result<()> {
 	info!(version=%env!("CARGO_PKG_VERSION"), "constructing Watchexec from CLI");
 
-	let state = state::State::default();
-	let config = config::make_config(&args, &state)?;
-	config.filterer(WatchexecFilterer::new(&args).await?);
+	let mut state = state::State::default();
+	let mut output_events = None;
+	if args.output_events {
+		let (events_s, events_r) = mpsc::channel(64);
+		state.output_events = Some(events_s);
+		output_events = Some(events_r);
+	}
+	let mut exit_actions = None;
+	if !args.on_exit_code.is_empty() {
+		let (events_s, events_r) = mpsc::channel(8);
+		state.exit_actions = Some(events_s);
+		exit_actions = Some(events_r);
+	}
+	if let Some(path) = &args.diagnostics_file {
+		state.diagnostics = Some(Diagnostics::new(path.clone(), &args));
+	}
+
+	let config = config::make_config(&args, &state).await?;
+	let filterer = WatchexecFilterer::new(&args).await?;
+	if let Some(diagnostics) = &state.diagnostics {
+		diagnostics.set_ignore_files(filterer.ignore_files());
+	}
+	config.filterer(filterer);
+
+	// an empty pathset is how --watch /dev/null disables file watching
+	config
+		.validate_with(Validation {
+			allow_empty_pathset: true,
+			..Default::default()
+		})
+		.map_err(|errors| CriticalError::InvalidConfig { errors })?;
 
 	info!("initialising Watchexec runtime");
-	let wx = Watchexec::with_config(config)?;
+	let wx = Arc::new(Watchexec::with_config(config)?);
 
 	if !args.postpone {
 		debug!("kicking off with empty event");
 		wx.send_event(Event::default(), Priority::Urgent).await?;
 	}
 
+	if let Some(mut events) = output_events {
+		let wx = wx.clone();
+		tokio::spawn(async move {
+			while let Some(event) = events.recv().await {
+				if let Err(err) = wx.send_event(event, Priority::Normal).await {
+					debug!(%err, "could not send output event, stopping");
+					break;
+				}
+			}
+		});
+	}
+
+	if let Some(mut events) = exit_actions {
+		let wx = wx.clone();
+		tokio::spawn(async move {
+			while let Some(event) = events.recv().await {
+				if let Err(err) = wx.send_event(event, Priority::Urgent).await {
+					debug!(%err, "could not send exit code action, stopping");
+					break;
+				}
+			}
+		});
+	}
+
+	if let Some(diagnostics) = state.diagnostics.clone() {
+		let mut events = Box::pin(wx.raw_events());
+		tokio::spawn(async move {
+			while let Some(event) = events.next().await {
+				diagnostics.record_event(event);
+			}
+		});
+	}
+
+	#[cfg(feature = "http-trigger")]
+	if let Some(addr) = args.http_trigger {
+		let secret = args.http_trigger_secret.clone().unwrap_or_default();
+		http_trigger::listen(addr, secret, wx.clone()).await?;
+	}
+
 	info!("running main loop");
-	wx.main().await.into_diagnostic()??;
+	let result = wx.main().await.into_diagnostic()?;
+	if let (Err(err), Some(diagnostics)) = (&result, &state.diagnostics) {
+		diagnostics
+			.write("fatal error", Some(error_chain(err)))
+			.await;
+	}
+	result?;
 
 	if matches!(args.screen_clear, Some(args::ClearMode::Reset)) {
 		config::reset_screen();
@@ -94,6 +179,47 @@ async fn run_manpage(_args: Args) -> Result<()> {
 	Ok(())
 }
 
+#[allow(clippy::unused_async)]
+async fn run_list_watched_paths(args: Args) -> Result<()> {
+	info!(version=%env!("CARGO_PKG_VERSION"), "listing watched paths");
+
+	let config = Config::default();
+	config.pathset(args.paths.clone());
+	let watched = config.watched_paths();
+
+	let mut stdout = std::io::stdout().lock();
+	if args.json {
+		let list: Vec<_> = watched
+			.iter()
+			.map(|path| {
+				serde_json::json!({
+					"path": path.path(),
+					"recursive": path.is_recursive(),
+					"file": path.is_file(),
+					"exists": path.path().exists(),
+				})
+			})
+			.collect();
+		serde_json::to_writer(&mut stdout, &list).into_diagnostic()?;
+		writeln!(stdout).into_diagnostic()?;
+	} else {
+		for path in watched {
+			write!(stdout, "{}", path.path().display()).into_diagnostic()?;
+			if path.is_file() {
+				write!(stdout, " (file)").into_diagnostic()?;
+			} else if !path.is_recursive() {
+				write!(stdout, " (non-recursive)").into_diagnostic()?;
+			}
+			if !path.path().exists() {
+				write!(stdout, " (missing)").into_diagnostic()?;
+			}
+			writeln!(stdout).into_diagnostic()?;
+		}
+	}
+
+	Ok(())
+}
+
 #[allow(clippy::unused_async)]
 async fn run_completions(shell: ShellCompletion) -> Result<()> {
 	fn generate(generator: impl Generator) {
@@ -122,6 +248,8 @@ pub async fn run() -> Result<()> {
 		run_manpage(args).await
 	} else if let Some(shell) = args.completions {
 		run_completions(shell).await
+	} else if args.list_watched_paths {
+		run_list_watched_paths(args).await
 	} else {
 		run_watchexec(args).await
 	}
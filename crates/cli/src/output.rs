@@ -0,0 +1,268 @@
+use std::{
+	collections::HashMap,
+	io::{BufRead, BufReader, Read, Write},
+	mem::take,
+	path::PathBuf,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+
+use chrono::{
+	format::{Item, StrftimeItems},
+	Local,
+};
+use tokio::{
+	process::Command as TokioCommand,
+	sync::mpsc,
+	task::{spawn_blocking, JoinHandle},
+	time::timeout,
+};
+use tracing::{debug, error, trace, warn};
+use watchexec_events::{Event, Source, Tag};
+
+/// Longest chunk of output we'll buffer while looking for the end of a line.
+///
+/// Past this, the chunk is written out as-is and the rest of the line follows without a prefix.
+const MAX_LINE: u64 = 64 * 1024;
+
+/// How long to wait for output to be written out after the command ends.
+///
+/// Pipes stay open as long as any process holds them, including background processes spawned by
+/// the command, so this bounds how long the end of a run can be held up.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Lines of output starting with this are turned into events, see `--output-events`.
+const OUTPUT_EVENT_PREFIX: &[u8] = b"@watchexec:";
+
+/// Metadata key marking events from `--output-events`, with the follow-up depth as value.
+const OUTPUT_EVENT_METADATA: &str = "output-event";
+
+/// Most events a single run of the command can emit.
+const MAX_OUTPUT_EVENTS_PER_RUN: usize = 100;
+
+/// Most follow-up runs in a row triggered only by output events.
+const MAX_OUTPUT_EVENT_DEPTH: usize = 10;
+
+/// Returns whether a `--timestamp-prefix` format is valid.
+pub fn is_valid_timestamp_format(format: &str) -> bool {
+	!StrftimeItems::new(format).any(|item| matches!(item, Item::Error))
+}
+
+/// Captures the command's stdout and stderr to annotate them before printing them out.
+#[derive(Clone, Debug, Default)]
+pub struct OutputCapture {
+	timestamp_format: Option<Arc<str>>,
+	events: Option<OutputEvents>,
+	pending: Arc<Mutex<Vec<JoinHandle<()>>>>,
+}
+
+/// Where to send events from the command's output, see `--output-events`.
+#[derive(Clone, Debug)]
+pub struct OutputEvents {
+	sender: mpsc::Sender<Event>,
+	workdir: PathBuf,
+}
+
+impl OutputEvents {
+	pub fn new(sender: mpsc::Sender<Event>, workdir: Option<PathBuf>) -> Self {
+		Self {
+			sender,
+			workdir: workdir
+				.or_else(|| std::env::current_dir().ok())
+				.unwrap_or_default(),
+		}
+	}
+}
+
+impl OutputCapture {
+	pub fn new(timestamp_format: Option<&str>, events: Option<OutputEvents>) -> Self {
+		Self {
+			timestamp_format: timestamp_format.map(Arc::from),
+			events,
+			..Default::default()
+		}
+	}
+
+	/// Redirect the command's stdout and stderr through pipes, and start printing from them.
+	///
+	/// The events are those which triggered the run, to keep track of follow-up runs. Does nothing
+	/// if no capture options are set.
+	pub fn setup(&self, command: &mut TokioCommand, triggers: &[Event]) {
+		if self.timestamp_format.is_none() && self.events.is_none() {
+			return;
+		}
+
+		// stderr is only captured for timestamps
+		let stderr = self.timestamp_format.as_ref().map(|_| os_pipe::pipe());
+		let (stdout, stderr) = match (os_pipe::pipe(), stderr.transpose()) {
+			(Ok(stdout), Ok(stderr)) => (stdout, stderr),
+			(Err(err), _) | (_, Err(err)) => {
+				error!("Failed to capture command output, continuing without it: {err}");
+				return;
+			}
+		};
+
+		debug!("set command stdout to capture pipe");
+		command.stdout(stdout.1);
+		let events = self.events.clone().map(|events| {
+			let depth = follow_up_depth(triggers);
+			trace!(?depth, "capturing output events");
+			(events, depth)
+		});
+
+		let mut pending = self.pending.lock().unwrap();
+		pending.push(pump(
+			stdout.0,
+			std::io::stdout(),
+			self.timestamp_format.clone(),
+			events,
+		));
+		if let (Some(stderr), Some(format)) = (stderr, self.timestamp_format.clone()) {
+			debug!("set command stderr to capture pipe");
+			command.stderr(stderr.1);
+			pending.push(pump(stderr.0, std::io::stderr(), Some(format), None));
+		}
+	}
+
+	/// Take the printing tasks started since the last call.
+	///
+	/// Call this right after the command has started to get the tasks for that run.
+	pub fn take_pending(&self) -> CapturedOutput {
+		CapturedOutput(take(&mut *self.pending.lock().unwrap()))
+	}
+}
+
+/// The printing tasks for a single run of the command.
+#[derive(Debug, Default)]
+pub struct CapturedOutput(Vec<JoinHandle<()>>);
+
+impl CapturedOutput {
+	/// Wait for all the output to be printed, up to a timeout.
+	pub async fn finish(self) {
+		for task in self.0 {
+			if timeout(DRAIN_TIMEOUT, task).await.is_err() {
+				debug!("command output is still open after the command ended, not waiting");
+			}
+		}
+	}
+}
+
+fn pump(
+	reader: impl Read + Send + 'static,
+	writer: impl Write + Send + 'static,
+	format: Option<Arc<str>>,
+	events: Option<(OutputEvents, usize)>,
+) -> JoinHandle<()> {
+	spawn_blocking(move || {
+		if let Err(err) = copy_lines(reader, writer, format.as_deref(), events) {
+			debug!(%err, "error while printing command output");
+		}
+		trace!("done printing command output");
+	})
+}
+
+/// Copy from the reader to the writer, prefixing each line with a timestamp if a format is given.
+///
+/// Lines that aren't valid UTF-8 are passed through without a prefix. A last line without a
+/// trailing newline is prefixed as normal.
+///
+/// If events are given, lines starting with `@watchexec:` are sent as events instead.
+fn copy_lines(
+	reader: impl Read,
+	mut writer: impl Write,
+	format: Option<&str>,
+	events: Option<(OutputEvents, usize)>,
+) -> std::io::Result<()> {
+	let mut reader = BufReader::new(reader);
+	let mut line = Vec::new();
+	let mut at_line_start = true;
+	let mut sent = 0;
+
+	loop {
+		line.clear();
+		if (&mut reader).take(MAX_LINE).read_until(b'\n', &mut line)? == 0 {
+			return Ok(());
+		}
+
+		if let (true, Some((events, depth))) = (at_line_start, &events) {
+			if let Some(path) = line.strip_prefix(OUTPUT_EVENT_PREFIX) {
+				// a line that's too long ends without a newline, the rest is dropped with it
+				at_line_start = line.ends_with(b"\n");
+				if *depth >= MAX_OUTPUT_EVENT_DEPTH {
+					warn!(
+						"too many follow-up runs from output events in a row, dropping event (max {MAX_OUTPUT_EVENT_DEPTH})"
+					);
+				} else if sent >= MAX_OUTPUT_EVENTS_PER_RUN {
+					warn!(
+						"too many output events from this run, dropping event (max {MAX_OUTPUT_EVENTS_PER_RUN})"
+					);
+				} else if let Some(event) = output_event(events, path, depth + 1) {
+					sent += 1;
+					trace!(?event, "sending output event");
+					if events.sender.blocking_send(event).is_err() {
+						debug!("output event channel closed");
+					}
+				}
+				continue;
+			}
+		}
+
+		if let (true, Some(format)) = (at_line_start, format) {
+			if std::str::from_utf8(&line).is_ok() {
+				write!(writer, "{} ", Local::now().format(format))?;
+			}
+		}
+
+		writer.write_all(&line)?;
+		writer.flush()?;
+		at_line_start = line.ends_with(b"\n");
+	}
+}
+
+/// Make an event from the rest of an output event line.
+///
+/// Returns `None` if there's no path.
+fn output_event(events: &OutputEvents, path: &[u8], depth: usize) -> Option<Event> {
+	let path = path.trim_ascii();
+	if path.is_empty() {
+		return None;
+	}
+
+	#[cfg(unix)]
+	let path = {
+		use std::os::unix::ffi::OsStrExt;
+		std::ffi::OsStr::from_bytes(path)
+	};
+	#[cfg(not(unix))]
+	let path = String::from_utf8_lossy(path).into_owned();
+
+	Some(Event {
+		tags: vec![
+			Tag::Source(Source::External),
+			Tag::Path {
+				path: events.workdir.join(path),
+				file_type: None,
+			},
+		],
+		metadata: HashMap::from([(OUTPUT_EVENT_METADATA.into(), vec![depth.to_string()])]),
+	})
+}
+
+/// How many follow-up runs in a row led to a run with these triggering events.
+///
+/// A run is a follow-up if it was triggered only by output events.
+fn follow_up_depth(triggers: &[Event]) -> usize {
+	let mut depth = 0;
+	for event in triggers {
+		match event
+			.metadata
+			.get(OUTPUT_EVENT_METADATA)
+			.and_then(|values| values.first())
+			.and_then(|value| value.parse().ok())
+		{
+			Some(event_depth) => depth = depth.max(event_depth),
+			None => return 0,
+		}
+	}
+	depth
+}
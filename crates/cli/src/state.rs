@@ -7,10 +7,17 @@ use std::{
 
 use miette::{IntoDiagnostic, Result};
 use tempfile::NamedTempFile;
+use tokio::sync::mpsc;
+use watchexec_events::Event;
+
+use crate::diagnostics::Diagnostics;
 
 #[derive(Clone, Debug, Default)]
 pub struct State {
 	pub emit_file: RotatingTempFile,
+	pub output_events: Option<mpsc::Sender<Event>>,
+	pub exit_actions: Option<mpsc::Sender<Event>>,
+	pub diagnostics: Option<Diagnostics>,
 }
 
 #[derive(Clone, Debug, Default)]
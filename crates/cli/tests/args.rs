@@ -0,0 +1,14 @@
+use clap::Parser;
+use watchexec_cli::args::{Args, ClearMode};
+
+#[test]
+fn clear_scrollback() {
+	let args = Args::try_parse_from(["watchexec", "--clear=scrollback", "--", "echo"]).unwrap();
+	assert!(matches!(args.screen_clear, Some(ClearMode::Scrollback)));
+}
+
+#[test]
+fn clear_default() {
+	let args = Args::try_parse_from(["watchexec", "--clear", "--", "echo"]).unwrap();
+	assert!(matches!(args.screen_clear, Some(ClearMode::Clear)));
+}
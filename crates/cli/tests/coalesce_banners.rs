@@ -0,0 +1,77 @@
+#[cfg(unix)]
+use std::{process::Stdio, time::Duration};
+
+#[cfg(unix)]
+use miette::{IntoDiagnostic, Result};
+#[cfg(unix)]
+use tokio::io::AsyncReadExt;
+
+#[cfg(unix)]
+#[tokio::test]
+async fn rapid_restarts_print_one_banner() -> Result<()> {
+	let wexec_bin = std::env::var("TEST_WATCHEXEC_BIN").unwrap_or(
+		option_env!("CARGO_BIN_EXE_watchexec")
+			.map(std::string::ToString::to_string)
+			.unwrap_or("watchexec".into()),
+	);
+
+	let watched = tempfile::tempdir().into_diagnostic()?;
+
+	let mut child = tokio::process::Command::new(wexec_bin)
+		.args([
+			"--postpone",
+			"--restart",
+			"--color=never",
+			"--debounce=50ms",
+			"--coalesce-banners=1s",
+			"--watch",
+		])
+		.arg(watched.path())
+		.args(["--", "sleep 3"])
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.stderr(Stdio::piped())
+		.kill_on_drop(true)
+		.spawn()
+		.into_diagnostic()?;
+
+	// give the watcher time to start
+	tokio::time::sleep(Duration::from_millis(500)).await;
+	for n in 0..5 {
+		std::fs::write(watched.path().join(format!("file{n}")), "content").into_diagnostic()?;
+		tokio::time::sleep(Duration::from_millis(200)).await;
+	}
+
+	// let the window pass so the collapsed banner is printed
+	tokio::time::sleep(Duration::from_millis(1500)).await;
+	child.start_kill().into_diagnostic()?;
+
+	let mut stderr = String::new();
+	child
+		.stderr
+		.take()
+		.unwrap()
+		.read_to_string(&mut stderr)
+		.await
+		.into_diagnostic()?;
+	child.wait().await.into_diagnostic()?;
+
+	let banners: Vec<&str> = stderr
+		.lines()
+		.filter(|line| line.starts_with("[Running:"))
+		.collect();
+	assert_eq!(
+		banners,
+		["[Running: sleep 3]", banners[1]],
+		"should print the first banner and one collapsed banner: {stderr}"
+	);
+
+	let repeats: usize = banners[1]
+		.strip_prefix("[Running: sleep 3 (repeated ")
+		.and_then(|rest| rest.strip_suffix(" times)]"))
+		.and_then(|count| count.parse().ok())
+		.expect("collapsed banner should have a count");
+	assert!(repeats >= 2, "should collapse the restarts: {stderr}");
+
+	Ok(())
+}
@@ -0,0 +1,118 @@
+#[cfg(unix)]
+use std::{path::Path, process::Stdio, time::Duration};
+
+#[cfg(unix)]
+use miette::{IntoDiagnostic, Result};
+#[cfg(unix)]
+use serde_json::Value;
+
+#[cfg(unix)]
+fn spawn_watchexec(
+	watched: &[&Path],
+	report: &Path,
+	extra: &[&str],
+) -> Result<tokio::process::Child> {
+	let wexec_bin = std::env::var("TEST_WATCHEXEC_BIN").unwrap_or(
+		option_env!("CARGO_BIN_EXE_watchexec")
+			.map(std::string::ToString::to_string)
+			.unwrap_or("watchexec".into()),
+	);
+
+	let mut command = tokio::process::Command::new(wexec_bin);
+	command
+		.args(["--quiet", "--postpone", "--diagnostics-file"])
+		.arg(report)
+		.args(extra);
+	for path in watched {
+		command.arg("--watch").arg(path);
+	}
+	command
+		.args(["--", "true"])
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.stderr(Stdio::null())
+		.kill_on_drop(true)
+		.spawn()
+		.into_diagnostic()
+}
+
+#[cfg(unix)]
+async fn read_report(report: &Path, reason: &str) -> Result<Value> {
+	tokio::time::timeout(Duration::from_secs(10), async {
+		loop {
+			if let Some(value) = std::fs::read(report)
+				.ok()
+				.and_then(|bytes| serde_json::from_slice::<Value>(&bytes).ok())
+				.filter(|value| value["reason"] == reason)
+			{
+				return value;
+			}
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.into_diagnostic()
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn watcher_error_writes_report() -> Result<()> {
+	let root = tempfile::tempdir().into_diagnostic()?;
+	let root_path = dunce::canonicalize(root.path()).into_diagnostic()?;
+	let watched = root_path.join("watched");
+	std::fs::create_dir(&watched).into_diagnostic()?;
+	let report = root_path.join("report.json");
+
+	// the poll watcher reports an error when it can't scan a watched path anymore
+	let mut child = spawn_watchexec(&[&watched], &report, &["--poll", "100ms"])?;
+
+	// give the watcher time to start, then take the watched directory away from under it
+	tokio::time::sleep(Duration::from_millis(500)).await;
+	std::fs::remove_dir(&watched).into_diagnostic()?;
+
+	let value = read_report(&report, "file watcher error").await?;
+	child.kill().await.into_diagnostic()?;
+
+	let error = value["error"].as_str().expect("error should be a string");
+	assert!(
+		error.contains(&watched.display().to_string()),
+		"error should be about the removed path: {error}"
+	);
+
+	let watched_paths: Vec<&str> = value["watched_paths"]
+		.as_array()
+		.expect("watched paths should be an array")
+		.iter()
+		.filter_map(|path| path["path"].as_str())
+		.collect();
+	assert!(watched_paths.contains(&watched.to_str().unwrap()));
+	assert!(value["ignore_files"].is_array());
+	assert!(value["recent_events"].is_array());
+
+	Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn usr2_writes_report() -> Result<()> {
+	let root = tempfile::tempdir().into_diagnostic()?;
+	let root_path = dunce::canonicalize(root.path()).into_diagnostic()?;
+	let report = root_path.join("report.json");
+
+	let mut child = spawn_watchexec(&[&root_path], &report, &[])?;
+	let pid = child.id().expect("watchexec should be running");
+
+	// give the signal listener time to start
+	tokio::time::sleep(Duration::from_millis(500)).await;
+	let status = std::process::Command::new("kill")
+		.args(["-USR2", &pid.to_string()])
+		.status()
+		.into_diagnostic()?;
+	assert!(status.success(), "failed to send SIGUSR2");
+
+	let value = read_report(&report, "requested by signal").await?;
+	child.kill().await.into_diagnostic()?;
+	assert_eq!(value["error"], Value::Null);
+
+	Ok(())
+}
@@ -0,0 +1,147 @@
+#[cfg(unix)]
+use std::{process::Stdio, time::Duration};
+
+#[cfg(unix)]
+use miette::{IntoDiagnostic, Result};
+
+#[cfg(unix)]
+#[tokio::test]
+async fn invocation_context_env() -> Result<()> {
+	let wexec_bin = std::env::var("TEST_WATCHEXEC_BIN").unwrap_or(
+		option_env!("CARGO_BIN_EXE_watchexec")
+			.map(std::string::ToString::to_string)
+			.unwrap_or("watchexec".into()),
+	);
+
+	let watched = tempfile::tempdir().into_diagnostic()?;
+	let output = tempfile::tempdir().into_diagnostic()?;
+	let env_file = output.path().join("env");
+
+	let mut child = tokio::process::Command::new(wexec_bin)
+		.args(["--quiet", "--debounce=10ms", "-E", "WATCHEXEC=0", "--watch"])
+		.arg(watched.path())
+		.arg("--")
+		.arg(format!(
+			"env >> '{}'; echo >> '{}'",
+			env_file.display(),
+			env_file.display()
+		))
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.kill_on_drop(true)
+		.spawn()
+		.into_diagnostic()?;
+
+	let read_runs = || {
+		std::fs::read_to_string(&env_file)
+			.unwrap_or_default()
+			.split("\n\n")
+			.filter(|run| run.contains("WATCHEXEC_RUN_ID="))
+			.map(ToOwned::to_owned)
+			.collect::<Vec<_>>()
+	};
+
+	let runs = tokio::time::timeout(Duration::from_secs(10), async {
+		let mut triggered = false;
+		loop {
+			let runs = read_runs();
+			if runs.len() >= 2 {
+				return runs;
+			}
+			if runs.len() == 1 && !triggered {
+				std::fs::write(watched.path().join("file"), "change").unwrap();
+				triggered = true;
+			}
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.into_diagnostic()?;
+	child.kill().await.into_diagnostic()?;
+
+	let run_ids: Vec<&str> = runs
+		.iter()
+		.map(|run| {
+			assert!(
+				run.lines().any(|line| line == "WATCHEXEC=1"),
+				"missing WATCHEXEC=1 in {run:?}"
+			);
+			assert!(
+				run.lines().any(|line| line.starts_with("WATCHEXEC_BIN=/")),
+				"missing WATCHEXEC_BIN in {run:?}"
+			);
+			run.lines()
+				.find_map(|line| line.strip_prefix("WATCHEXEC_RUN_ID="))
+				.unwrap()
+		})
+		.collect();
+	assert_ne!(run_ids[0], run_ids[1], "run ID should change across runs");
+
+	assert!(
+		runs[0].lines().any(|line| line == "WATCHEXEC_FIRST_RUN=1"),
+		"startup run should be the first run: {:?}",
+		runs[0]
+	);
+	assert!(
+		!runs[1].contains("WATCHEXEC_FIRST_RUN="),
+		"later runs shouldn't be the first run: {:?}",
+		runs[1]
+	);
+
+	Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn first_run_with_postpone() -> Result<()> {
+	let wexec_bin = std::env::var("TEST_WATCHEXEC_BIN").unwrap_or(
+		option_env!("CARGO_BIN_EXE_watchexec")
+			.map(std::string::ToString::to_string)
+			.unwrap_or("watchexec".into()),
+	);
+
+	let watched = tempfile::tempdir().into_diagnostic()?;
+	let output = tempfile::tempdir().into_diagnostic()?;
+	let log = output.path().join("log");
+
+	let mut child = tokio::process::Command::new(wexec_bin)
+		.args(["--quiet", "--postpone", "--debounce=10ms", "--watch"])
+		.arg(watched.path())
+		.arg("--")
+		.arg(format!(
+			"echo \"first=${{WATCHEXEC_FIRST_RUN:-0}}\" >> '{}'",
+			log.display()
+		))
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.kill_on_drop(true)
+		.spawn()
+		.into_diagnostic()?;
+
+	let runs = tokio::time::timeout(Duration::from_secs(10), async {
+		let mut n = 0;
+		loop {
+			let runs = std::fs::read_to_string(&log).unwrap_or_default();
+			if runs.lines().count() >= 2 {
+				return runs;
+			}
+
+			// keep changing until runs happen, as the watcher may not be ready at first
+			n += 1;
+			std::fs::write(watched.path().join("file"), n.to_string()).unwrap();
+			tokio::time::sleep(Duration::from_millis(200)).await;
+		}
+	})
+	.await
+	.into_diagnostic()?;
+	child.kill().await.into_diagnostic()?;
+
+	let runs: Vec<&str> = runs.lines().collect();
+	assert_eq!(
+		runs[..2],
+		["first=1", "first=0"],
+		"the first change should be the first run"
+	);
+
+	Ok(())
+}
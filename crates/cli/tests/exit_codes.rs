@@ -0,0 +1,81 @@
+#[cfg(unix)]
+use std::{process::Stdio, time::Duration};
+
+#[cfg(unix)]
+use miette::{IntoDiagnostic, Result};
+
+/// Run a command exiting with `code` once, with code 78 mapped to reloading filters.
+#[cfg(unix)]
+async fn stderr_after_exit(code: u8) -> Result<String> {
+	let wexec_bin = std::env::var("TEST_WATCHEXEC_BIN").unwrap_or(
+		option_env!("CARGO_BIN_EXE_watchexec")
+			.map(std::string::ToString::to_string)
+			.unwrap_or("watchexec".into()),
+	);
+
+	let watched = tempfile::tempdir().into_diagnostic()?;
+	let output = tempfile::tempdir().into_diagnostic()?;
+	let runs_file = output.path().join("runs");
+
+	let mut child = tokio::process::Command::new(wexec_bin)
+		.args([
+			"--color=never",
+			"--on-exit-code=78:reload-filters",
+			"--watch",
+		])
+		.arg(watched.path())
+		.arg("--")
+		.arg(format!(
+			"echo run >> '{}'; exit {code}",
+			runs_file.display()
+		))
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.stderr(Stdio::piped())
+		.kill_on_drop(true)
+		.spawn()
+		.into_diagnostic()?;
+
+	tokio::time::timeout(Duration::from_secs(10), async {
+		while !runs_file.exists() {
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.into_diagnostic()?;
+
+	// give the action time to happen
+	tokio::time::sleep(Duration::from_millis(1000)).await;
+	child.start_kill().into_diagnostic()?;
+	let output = child.wait_with_output().await.into_diagnostic()?;
+
+	String::from_utf8(output.stderr).into_diagnostic()
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn mapped_exit_code_reloads_filters() -> Result<()> {
+	let stderr = stderr_after_exit(78).await?;
+	assert!(
+		stderr.contains("[Reloaded filters]"),
+		"should reload filters: {stderr:?}"
+	);
+
+	Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn other_exit_code_does_nothing() -> Result<()> {
+	let stderr = stderr_after_exit(1).await?;
+	assert!(
+		stderr.contains("[Command exited with 1]"),
+		"should report the exit: {stderr:?}"
+	);
+	assert!(
+		!stderr.contains("[Reloaded filters]"),
+		"should not reload filters: {stderr:?}"
+	);
+
+	Ok(())
+}
@@ -0,0 +1,121 @@
+#[cfg(all(unix, feature = "http-trigger"))]
+use std::{
+	io::{Read, Write},
+	net::{SocketAddr, TcpListener, TcpStream},
+	process::Stdio,
+	time::Duration,
+};
+
+#[cfg(all(unix, feature = "http-trigger"))]
+use miette::{IntoDiagnostic, Result};
+
+#[cfg(all(unix, feature = "http-trigger"))]
+fn request(addr: SocketAddr, method: &str, path: &str, secret: Option<&str>) -> Result<String> {
+	let mut stream = TcpStream::connect(addr).into_diagnostic()?;
+	let secret = secret
+		.map(|s| format!("X-Watchexec-Secret: {s}\r\n"))
+		.unwrap_or_default();
+	write!(
+		stream,
+		"{method} {path} HTTP/1.1\r\nHost: localhost\r\n{secret}Content-Length: 4\r\n\r\nbody"
+	)
+	.into_diagnostic()?;
+
+	let mut response = String::new();
+	stream.read_to_string(&mut response).into_diagnostic()?;
+	Ok(response
+		.lines()
+		.next()
+		.unwrap_or_default()
+		.trim()
+		.to_owned())
+}
+
+#[cfg(all(unix, feature = "http-trigger"))]
+#[tokio::test]
+async fn post_with_secret_triggers_run() -> Result<()> {
+	let wexec_bin = std::env::var("TEST_WATCHEXEC_BIN").unwrap_or(
+		option_env!("CARGO_BIN_EXE_watchexec")
+			.map(std::string::ToString::to_string)
+			.unwrap_or("watchexec".into()),
+	);
+
+	let addr = TcpListener::bind("127.0.0.1:0")
+		.into_diagnostic()?
+		.local_addr()
+		.into_diagnostic()?;
+
+	let watched = tempfile::tempdir().into_diagnostic()?;
+	let output = tempfile::tempdir().into_diagnostic()?;
+	let runs_file = output.path().join("runs");
+
+	let mut child = tokio::process::Command::new(wexec_bin)
+		.args(["--quiet", "--postpone", "--debounce=10ms", "--http-trigger"])
+		.arg(addr.to_string())
+		.args(["--http-trigger-secret", "s3cret", "--watch"])
+		.arg(watched.path())
+		.arg("--")
+		.arg(format!("echo run >> '{}'", runs_file.display()))
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.kill_on_drop(true)
+		.spawn()
+		.into_diagnostic()?;
+
+	let count_runs = || {
+		std::fs::read_to_string(&runs_file)
+			.unwrap_or_default()
+			.lines()
+			.count()
+	};
+
+	// wait for the listener to come up
+	tokio::time::timeout(Duration::from_secs(10), async {
+		while TcpStream::connect(addr).is_err() {
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.into_diagnostic()?;
+
+	assert_eq!(
+		request(addr, "POST", "/trigger", None)?,
+		"HTTP/1.1 401 Unauthorized"
+	);
+	assert_eq!(
+		request(addr, "POST", "/trigger", Some("wrong"))?,
+		"HTTP/1.1 401 Unauthorized"
+	);
+	assert_eq!(
+		request(addr, "GET", "/trigger", Some("s3cret"))?,
+		"HTTP/1.1 405 Method Not Allowed"
+	);
+	assert_eq!(
+		request(addr, "POST", "/elsewhere", Some("s3cret"))?,
+		"HTTP/1.1 404 Not Found"
+	);
+
+	tokio::time::sleep(Duration::from_millis(300)).await;
+	assert_eq!(
+		count_runs(),
+		0,
+		"rejected requests should not trigger a run"
+	);
+
+	assert_eq!(
+		request(addr, "POST", "/trigger", Some("s3cret"))?,
+		"HTTP/1.1 202 Accepted"
+	);
+
+	tokio::time::timeout(Duration::from_secs(10), async {
+		while count_runs() == 0 {
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.into_diagnostic()?;
+	child.kill().await.into_diagnostic()?;
+
+	assert_eq!(count_runs(), 1, "accepted request should trigger one run");
+	Ok(())
+}
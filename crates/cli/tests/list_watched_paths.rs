@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use miette::{IntoDiagnostic, Result};
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn lists_canonical_paths_and_flags_missing() -> Result<()> {
+	let wexec_bin = std::env::var("TEST_WATCHEXEC_BIN").unwrap_or(
+		option_env!("CARGO_BIN_EXE_watchexec")
+			.map(std::string::ToString::to_string)
+			.unwrap_or("watchexec".into()),
+	);
+
+	let root = tempfile::tempdir().into_diagnostic()?;
+	let root_path = dunce::canonicalize(root.path()).into_diagnostic()?;
+	let src = root_path.join("src");
+	let docs = root_path.join("docs");
+	std::fs::create_dir(&src).into_diagnostic()?;
+	std::fs::create_dir(&docs).into_diagnostic()?;
+	let missing = root_path.join("missing");
+
+	let output = tokio::time::timeout(
+		Duration::from_secs(30),
+		tokio::process::Command::new(wexec_bin)
+			.args(["--list-watched-paths", "--json", "--watch"])
+			.arg(src.join("..").join("src"))
+			.arg("--watch")
+			.arg(&missing)
+			.arg("--watch-non-recursive")
+			.arg(&docs)
+			.kill_on_drop(true)
+			.output(),
+	)
+	.await
+	.into_diagnostic()?
+	.into_diagnostic()?;
+
+	assert!(
+		output.status.success(),
+		"watchexec should succeed: {}",
+		String::from_utf8_lossy(&output.stderr)
+	);
+
+	let listed: Value = serde_json::from_slice(&output.stdout).into_diagnostic()?;
+	let mut listed = listed.as_array().expect("a JSON array").clone();
+	listed.sort_by_key(|path| path["path"].as_str().map(ToString::to_string));
+	assert_eq!(
+		listed,
+		vec![
+			json!({ "path": docs, "recursive": false, "file": false, "exists": true }),
+			json!({ "path": missing, "recursive": true, "file": false, "exists": false }),
+			json!({ "path": src, "recursive": true, "file": false, "exists": true }),
+		]
+	);
+
+	Ok(())
+}
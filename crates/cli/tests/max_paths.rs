@@ -0,0 +1,83 @@
+#[cfg(unix)]
+use std::{collections::HashSet, process::Stdio, time::Duration};
+
+#[cfg(unix)]
+use miette::{IntoDiagnostic, Result};
+
+#[cfg(unix)]
+#[tokio::test]
+async fn truncated_paths_are_marked() -> Result<()> {
+	let wexec_bin = std::env::var("TEST_WATCHEXEC_BIN").unwrap_or(
+		option_env!("CARGO_BIN_EXE_watchexec")
+			.map(std::string::ToString::to_string)
+			.unwrap_or("watchexec".into()),
+	);
+
+	let watched = tempfile::tempdir().into_diagnostic()?;
+	let output = tempfile::tempdir().into_diagnostic()?;
+	let env_file = output.path().join("env");
+
+	let mut child = tokio::process::Command::new(wexec_bin)
+		.args([
+			"--quiet",
+			"--postpone",
+			"--debounce=500ms",
+			"--max-paths=2",
+			"--emit-events-to=environment",
+			"--watch",
+		])
+		.arg(watched.path())
+		.arg("--")
+		.arg(format!("env > '{}'", env_file.display()))
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.kill_on_drop(true)
+		.spawn()
+		.into_diagnostic()?;
+
+	// give the watcher time to start
+	tokio::time::sleep(Duration::from_millis(500)).await;
+	for n in 0..5 {
+		std::fs::write(watched.path().join(format!("file{n}")), "content").into_diagnostic()?;
+	}
+
+	let env = tokio::time::timeout(Duration::from_secs(10), async {
+		loop {
+			let env = std::fs::read_to_string(&env_file).unwrap_or_default();
+			if env.contains("WATCHEXEC_PATHS_COUNT=") {
+				return env;
+			}
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.into_diagnostic()?;
+	child.kill().await.into_diagnostic()?;
+
+	let vars: Vec<(&str, &str)> = env.lines().filter_map(|l| l.split_once('=')).collect();
+	assert!(
+		vars.contains(&("WATCHEXEC_PATHS_TRUNCATED", "1")),
+		"marker should be set: {env}"
+	);
+
+	let count: usize = vars
+		.iter()
+		.find(|(k, _)| *k == "WATCHEXEC_PATHS_COUNT")
+		.and_then(|(_, v)| v.parse().ok())
+		.expect("count should be a number");
+	assert!(count >= 5, "count should include dropped paths: {count}");
+
+	let paths: HashSet<&str> = vars
+		.iter()
+		.filter(|(k, _)| {
+			k.starts_with("WATCHEXEC_") && k.ends_with("_PATH") && *k != "WATCHEXEC_COMMON_PATH"
+		})
+		.flat_map(|(_, v)| v.split(':'))
+		.collect();
+	assert!(
+		!paths.is_empty() && paths.len() <= 2,
+		"at most 2 paths should be given: {paths:?}"
+	);
+
+	Ok(())
+}
@@ -0,0 +1,71 @@
+#[cfg(unix)]
+use std::{process::Stdio, time::Duration};
+
+#[cfg(unix)]
+use miette::{IntoDiagnostic, Result};
+
+#[cfg(unix)]
+#[tokio::test]
+async fn paths_with_newlines_survive_nul_stdio() -> Result<()> {
+	let wexec_bin = std::env::var("TEST_WATCHEXEC_BIN").unwrap_or(
+		option_env!("CARGO_BIN_EXE_watchexec")
+			.map(std::string::ToString::to_string)
+			.unwrap_or("watchexec".into()),
+	);
+
+	let watched = tempfile::tempdir().into_diagnostic()?;
+	let output = tempfile::tempdir().into_diagnostic()?;
+	let out_file = output.path().join("paths");
+	let done_file = output.path().join("done");
+
+	let mut child = tokio::process::Command::new(wexec_bin)
+		.args([
+			"--quiet",
+			"--postpone",
+			"--debounce=500ms",
+			"--emit-events-to=nul-stdio",
+			"--watch",
+		])
+		.arg(watched.path())
+		.arg("--")
+		.arg(format!(
+			"cat > '{}'; touch '{}'",
+			out_file.display(),
+			done_file.display()
+		))
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.kill_on_drop(true)
+		.spawn()
+		.into_diagnostic()?;
+
+	// give the watcher time to start
+	tokio::time::sleep(Duration::from_millis(500)).await;
+	let weird = watched.path().join("two\nlines:and;separators");
+	std::fs::write(weird, "content").into_diagnostic()?;
+
+	tokio::time::timeout(Duration::from_secs(10), async {
+		while !done_file.exists() {
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.into_diagnostic()?;
+	child.kill().await.into_diagnostic()?;
+
+	let stream = std::fs::read(&out_file).into_diagnostic()?;
+	assert_eq!(stream.last(), Some(&0), "each path should end with a NUL");
+
+	let paths: Vec<&[u8]> = stream[..stream.len() - 1].split(|b| *b == 0).collect();
+	assert_eq!(
+		paths
+			.iter()
+			.filter(|path| path.ends_with(b"/two\nlines:and;separators"))
+			.count(),
+		1,
+		"path should appear once, with its newline intact: {:?}",
+		String::from_utf8_lossy(&stream)
+	);
+
+	Ok(())
+}
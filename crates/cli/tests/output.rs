@@ -0,0 +1,39 @@
+#[cfg(unix)]
+use miette::{IntoDiagnostic, Result};
+
+#[cfg(unix)]
+#[tokio::test]
+async fn timestamp_prefix_multiline() -> Result<()> {
+	let wexec_bin = std::env::var("TEST_WATCHEXEC_BIN").unwrap_or(
+		option_env!("CARGO_BIN_EXE_watchexec")
+			.map(std::string::ToString::to_string)
+			.unwrap_or("watchexec".into()),
+	);
+
+	let output = tokio::process::Command::new(wexec_bin)
+		.args([
+			"-1",
+			"--quiet",
+			"--timestamp-prefix=<%Y>",
+			"--",
+			r"printf 'one\ntwo\nthree'",
+		])
+		.output()
+		.await
+		.into_diagnostic()?;
+	assert!(output.status.success(), "watchexec failed: {output:?}");
+
+	let stdout = String::from_utf8(output.stdout).into_diagnostic()?;
+	let lines: Vec<&str> = stdout.lines().collect();
+	assert_eq!(lines.len(), 3, "unexpected output: {stdout:?}");
+	for (line, expected) in lines.iter().zip(["one", "two", "three"]) {
+		let (prefix, rest) = line.split_once(' ').expect("line should have a prefix");
+		assert!(
+			prefix.starts_with('<') && prefix.ends_with('>'),
+			"unexpected prefix: {prefix:?}"
+		);
+		assert_eq!(rest, expected);
+	}
+
+	Ok(())
+}
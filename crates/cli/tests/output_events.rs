@@ -0,0 +1,78 @@
+#[cfg(unix)]
+use std::{path::Path, process::Stdio, time::Duration};
+
+#[cfg(unix)]
+use miette::{IntoDiagnostic, Result};
+
+#[cfg(unix)]
+async fn count_runs(log: &Path, settle: Duration) -> usize {
+	let mut runs = 0;
+	loop {
+		tokio::time::sleep(settle).await;
+		let now = std::fs::read_to_string(log)
+			.unwrap_or_default()
+			.lines()
+			.count();
+		if now == runs {
+			return runs;
+		}
+		runs = now;
+	}
+}
+
+#[cfg(unix)]
+async fn run_with_output_events(script: &str) -> Result<(usize, String)> {
+	let wexec_bin = std::env::var("TEST_WATCHEXEC_BIN").unwrap_or(
+		option_env!("CARGO_BIN_EXE_watchexec")
+			.map(std::string::ToString::to_string)
+			.unwrap_or("watchexec".into()),
+	);
+
+	let watched = tempfile::tempdir().into_diagnostic()?;
+	let output = tempfile::tempdir().into_diagnostic()?;
+	let log = output.path().join("log");
+
+	let mut child = tokio::process::Command::new(wexec_bin)
+		.args(["--output-events", "--watch"])
+		.arg(watched.path())
+		.arg("--workdir")
+		.arg(output.path())
+		.arg("--")
+		.arg(script)
+		.stdin(Stdio::null())
+		.stdout(Stdio::piped())
+		.kill_on_drop(true)
+		.spawn()
+		.into_diagnostic()?;
+
+	let runs = count_runs(&log, Duration::from_secs(2)).await;
+	child.start_kill().into_diagnostic()?;
+	let stdout = child.wait_with_output().await.into_diagnostic()?.stdout;
+	Ok((runs, String::from_utf8_lossy(&stdout).into_owned()))
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn emitted_path_triggers_another_run() -> Result<()> {
+	let (runs, stdout) = run_with_output_events(
+		"echo run >> log; if [ ! -e marker ]; then touch marker; echo '@watchexec: again.txt'; fi; echo done",
+	)
+	.await?;
+
+	assert_eq!(runs, 2, "output event should trigger exactly one more run");
+	assert!(
+		!stdout.contains("@watchexec:"),
+		"event lines should be taken out of the output: {stdout:?}"
+	);
+	assert!(stdout.contains("done"), "other output should be printed");
+	Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn follow_up_runs_are_capped() -> Result<()> {
+	let (runs, _) = run_with_output_events("echo run >> log; echo '@watchexec: again.txt'").await?;
+
+	assert_eq!(runs, 11, "initial run and 10 follow-ups");
+	Ok(())
+}
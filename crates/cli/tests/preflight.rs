@@ -0,0 +1,42 @@
+#[cfg(unix)]
+use std::time::Duration;
+
+#[cfg(unix)]
+use miette::{IntoDiagnostic, Result};
+
+#[cfg(unix)]
+#[tokio::test]
+async fn missing_program_fails_at_startup() -> Result<()> {
+	let wexec_bin = std::env::var("TEST_WATCHEXEC_BIN").unwrap_or(
+		option_env!("CARGO_BIN_EXE_watchexec")
+			.map(std::string::ToString::to_string)
+			.unwrap_or("watchexec".into()),
+	);
+
+	let output = tokio::time::timeout(
+		Duration::from_secs(30),
+		tokio::process::Command::new(wexec_bin)
+			.args([
+				"-1",
+				"--color=never",
+				"--shell=none",
+				"--",
+				"watchexec-does-not-exist",
+			])
+			.kill_on_drop(true)
+			.output(),
+	)
+	.await
+	.into_diagnostic()?
+	.into_diagnostic()?;
+
+	assert!(!output.status.success(), "watchexec should fail");
+
+	let stderr = String::from_utf8(output.stderr).into_diagnostic()?;
+	assert!(
+		stderr.contains("program not found: watchexec-does-not-exist"),
+		"unexpected output: {stderr:?}"
+	);
+
+	Ok(())
+}
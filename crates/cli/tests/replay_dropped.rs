@@ -0,0 +1,95 @@
+#[cfg(unix)]
+use std::{path::Path, process::Stdio, time::Duration};
+
+#[cfg(unix)]
+use miette::{IntoDiagnostic, Result};
+
+#[cfg(unix)]
+fn spawn_watchexec(watched: &Path, runs_file: &Path) -> Result<tokio::process::Child> {
+	let wexec_bin = std::env::var("TEST_WATCHEXEC_BIN").unwrap_or(
+		option_env!("CARGO_BIN_EXE_watchexec")
+			.map(std::string::ToString::to_string)
+			.unwrap_or("watchexec".into()),
+	);
+
+	tokio::process::Command::new(wexec_bin)
+		.args([
+			"--quiet",
+			"--postpone",
+			"--debounce=100ms",
+			"--replay-dropped",
+			"--watch",
+		])
+		.arg(watched)
+		.arg("--")
+		.arg(format!("echo run >> '{}'; sleep 1", runs_file.display()))
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.kill_on_drop(true)
+		.spawn()
+		.into_diagnostic()
+}
+
+#[cfg(unix)]
+async fn wait_for_runs(runs_file: &Path, count: usize) -> Result<()> {
+	tokio::time::timeout(Duration::from_secs(10), async {
+		while std::fs::read_to_string(runs_file)
+			.unwrap_or_default()
+			.lines()
+			.count() < count
+		{
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.into_diagnostic()
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn change_during_run_runs_once_more() -> Result<()> {
+	let watched = tempfile::tempdir().into_diagnostic()?;
+	let output = tempfile::tempdir().into_diagnostic()?;
+	let runs_file = output.path().join("runs");
+	let mut child = spawn_watchexec(watched.path(), &runs_file)?;
+
+	// give the watcher time to start
+	tokio::time::sleep(Duration::from_millis(500)).await;
+	std::fs::write(watched.path().join("first.txt"), "content").into_diagnostic()?;
+	wait_for_runs(&runs_file, 1).await?;
+
+	// several changes while the command runs
+	std::fs::write(watched.path().join("second.txt"), "content").into_diagnostic()?;
+	tokio::time::sleep(Duration::from_millis(200)).await;
+	std::fs::write(watched.path().join("third.txt"), "content").into_diagnostic()?;
+
+	wait_for_runs(&runs_file, 2).await?;
+	tokio::time::sleep(Duration::from_millis(2500)).await;
+	child.kill().await.into_diagnostic()?;
+
+	let runs = std::fs::read_to_string(&runs_file).into_diagnostic()?;
+	assert_eq!(runs.lines().count(), 2, "should run once more: {runs}");
+
+	Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn no_change_during_run_runs_once() -> Result<()> {
+	let watched = tempfile::tempdir().into_diagnostic()?;
+	let output = tempfile::tempdir().into_diagnostic()?;
+	let runs_file = output.path().join("runs");
+	let mut child = spawn_watchexec(watched.path(), &runs_file)?;
+
+	tokio::time::sleep(Duration::from_millis(500)).await;
+	std::fs::write(watched.path().join("first.txt"), "content").into_diagnostic()?;
+	wait_for_runs(&runs_file, 1).await?;
+
+	tokio::time::sleep(Duration::from_millis(2500)).await;
+	child.kill().await.into_diagnostic()?;
+
+	let runs = std::fs::read_to_string(&runs_file).into_diagnostic()?;
+	assert_eq!(runs.lines().count(), 1, "should not run again: {runs}");
+
+	Ok(())
+}
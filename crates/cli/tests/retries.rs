@@ -0,0 +1,123 @@
+#[cfg(unix)]
+use std::{path::Path, process::Stdio, time::Duration};
+
+#[cfg(unix)]
+use miette::{IntoDiagnostic, Result};
+
+#[cfg(unix)]
+fn watchexec_bin() -> String {
+	std::env::var("TEST_WATCHEXEC_BIN").unwrap_or(
+		option_env!("CARGO_BIN_EXE_watchexec")
+			.map(std::string::ToString::to_string)
+			.unwrap_or("watchexec".into()),
+	)
+}
+
+/// A command which fails the first two times it runs, and then succeeds.
+#[cfg(unix)]
+fn flaky_command(runs_file: &Path) -> String {
+	format!(
+		"echo run >> '{runs}'; test $(wc -l < '{runs}') -ge 3",
+		runs = runs_file.display()
+	)
+}
+
+#[cfg(unix)]
+fn count_runs(runs_file: &Path) -> usize {
+	std::fs::read_to_string(runs_file)
+		.unwrap_or_default()
+		.lines()
+		.count()
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn retries_until_success_once() -> Result<()> {
+	let output = tempfile::tempdir().into_diagnostic()?;
+	let runs_file = output.path().join("runs");
+
+	let output = tokio::time::timeout(
+		Duration::from_secs(30),
+		tokio::process::Command::new(watchexec_bin())
+			.args([
+				"-1",
+				"--color=never",
+				"--retries=3",
+				"--retry-delay=100ms",
+				"--",
+			])
+			.arg(flaky_command(&runs_file))
+			.stdin(Stdio::null())
+			.kill_on_drop(true)
+			.output(),
+	)
+	.await
+	.into_diagnostic()?
+	.into_diagnostic()?;
+
+	assert_eq!(count_runs(&runs_file), 3, "should run until it succeeds");
+	let stderr = String::from_utf8(output.stderr).into_diagnostic()?;
+	assert!(
+		stderr.contains("[Command failed, retrying (2 of 3)]"),
+		"unexpected output: {stderr:?}"
+	);
+	assert!(
+		!stderr.contains("(3 of 3)"),
+		"should not retry after success: {stderr:?}"
+	);
+
+	Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn retries_until_success_on_change() -> Result<()> {
+	let watched = tempfile::tempdir().into_diagnostic()?;
+	let output = tempfile::tempdir().into_diagnostic()?;
+	let runs_file = output.path().join("runs");
+
+	let mut child = tokio::process::Command::new(watchexec_bin())
+		.args([
+			"--postpone",
+			"--color=never",
+			"--debounce=100ms",
+			"--retries=5",
+			"--retry-delay=100ms",
+			"--watch",
+		])
+		.arg(watched.path())
+		.arg("--")
+		.arg(flaky_command(&runs_file))
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.stderr(Stdio::piped())
+		.kill_on_drop(true)
+		.spawn()
+		.into_diagnostic()?;
+
+	// give the watcher time to start
+	tokio::time::sleep(Duration::from_millis(500)).await;
+	std::fs::write(watched.path().join("file.txt"), "content").into_diagnostic()?;
+
+	tokio::time::timeout(Duration::from_secs(10), async {
+		while count_runs(&runs_file) < 3 {
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.into_diagnostic()?;
+
+	// no more retries once it has succeeded
+	tokio::time::sleep(Duration::from_millis(1000)).await;
+	child.start_kill().into_diagnostic()?;
+	let output = child.wait_with_output().await.into_diagnostic()?;
+
+	assert_eq!(count_runs(&runs_file), 3, "should stop retrying on success");
+	let stderr = String::from_utf8(output.stderr).into_diagnostic()?;
+	assert!(
+		stderr.contains("[Command was successful]"),
+		"should report success: {stderr:?}"
+	);
+
+	Ok(())
+}
@@ -0,0 +1,65 @@
+#[cfg(unix)]
+use std::{process::Stdio, time::Duration};
+
+#[cfg(unix)]
+use miette::{IntoDiagnostic, Result};
+
+#[cfg(unix)]
+#[tokio::test]
+async fn only_matching_changes_run() -> Result<()> {
+	let wexec_bin = std::env::var("TEST_WATCHEXEC_BIN").unwrap_or(
+		option_env!("CARGO_BIN_EXE_watchexec")
+			.map(std::string::ToString::to_string)
+			.unwrap_or("watchexec".into()),
+	);
+
+	let watched = tempfile::tempdir().into_diagnostic()?;
+	let output = tempfile::tempdir().into_diagnostic()?;
+	let runs_file = output.path().join("runs");
+
+	let mut child = tokio::process::Command::new(wexec_bin)
+		.args([
+			"--quiet",
+			"--postpone",
+			"--debounce=100ms",
+			"--run-on=*.rs",
+			"--watch",
+		])
+		.arg(watched.path())
+		.arg("--workdir")
+		.arg(watched.path())
+		.arg("--")
+		.arg(format!("echo run >> '{}'", runs_file.display()))
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.kill_on_drop(true)
+		.spawn()
+		.into_diagnostic()?;
+
+	// give the watcher time to start
+	tokio::time::sleep(Duration::from_millis(500)).await;
+	std::fs::write(watched.path().join("notes.txt"), "content").into_diagnostic()?;
+	tokio::time::sleep(Duration::from_millis(1000)).await;
+	assert!(
+		!runs_file.exists(),
+		"a change to a non-matching file should not run the command"
+	);
+
+	std::fs::write(watched.path().join("main.rs"), "fn main() {}").into_diagnostic()?;
+	let runs = tokio::time::timeout(Duration::from_secs(10), async {
+		loop {
+			let runs = std::fs::read_to_string(&runs_file).unwrap_or_default();
+			if !runs.is_empty() {
+				return runs;
+			}
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.into_diagnostic()?;
+	child.kill().await.into_diagnostic()?;
+
+	assert_eq!(runs.lines().count(), 1, "should run once: {runs}");
+
+	Ok(())
+}
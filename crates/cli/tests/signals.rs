@@ -0,0 +1,77 @@
+#[cfg(unix)]
+use std::{process::Stdio, time::Duration};
+
+#[cfg(unix)]
+use miette::{IntoDiagnostic, Result};
+
+#[cfg(unix)]
+#[tokio::test]
+async fn restart_on_sighup() -> Result<()> {
+	let wexec_bin = std::env::var("TEST_WATCHEXEC_BIN").unwrap_or(
+		option_env!("CARGO_BIN_EXE_watchexec")
+			.map(std::string::ToString::to_string)
+			.unwrap_or("watchexec".into()),
+	);
+
+	let watched = tempfile::tempdir().into_diagnostic()?;
+	let output = tempfile::tempdir().into_diagnostic()?;
+	let starts_file = output.path().join("starts");
+
+	let mut child = tokio::process::Command::new(wexec_bin)
+		.args([
+			"--quiet",
+			"--restart-on-signal",
+			"HUP",
+			"--stop-timeout=1s",
+			"--watch",
+		])
+		.arg(watched.path())
+		.arg("--")
+		.arg(format!(
+			"echo start >> '{}'; sleep 30",
+			starts_file.display()
+		))
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.kill_on_drop(true)
+		.spawn()
+		.into_diagnostic()?;
+	let pid = child.id().expect("watchexec should be running");
+
+	let count_starts = || {
+		std::fs::read_to_string(&starts_file)
+			.unwrap_or_default()
+			.lines()
+			.count()
+	};
+
+	tokio::time::timeout(Duration::from_secs(10), async {
+		while count_starts() < 1 {
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.into_diagnostic()?;
+
+	let status = std::process::Command::new("kill")
+		.args(["-HUP", &pid.to_string()])
+		.status()
+		.into_diagnostic()?;
+	assert!(status.success(), "failed to send SIGHUP");
+
+	tokio::time::timeout(Duration::from_secs(10), async {
+		while count_starts() < 2 {
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.into_diagnostic()?;
+
+	assert!(
+		child.try_wait().into_diagnostic()?.is_none(),
+		"watchexec should still be running after SIGHUP"
+	);
+	child.kill().await.into_diagnostic()?;
+
+	Ok(())
+}
@@ -0,0 +1,46 @@
+#[cfg(unix)]
+use std::time::{Duration, Instant};
+
+#[cfg(unix)]
+use miette::{IntoDiagnostic, Result};
+
+#[cfg(unix)]
+#[tokio::test]
+async fn command_timeout_kills() -> Result<()> {
+	let wexec_bin = std::env::var("TEST_WATCHEXEC_BIN").unwrap_or(
+		option_env!("CARGO_BIN_EXE_watchexec")
+			.map(std::string::ToString::to_string)
+			.unwrap_or("watchexec".into()),
+	);
+
+	let started = Instant::now();
+	let output = tokio::time::timeout(
+		Duration::from_secs(30),
+		tokio::process::Command::new(wexec_bin)
+			.args([
+				"-1",
+				"--color=never",
+				"--command-timeout=1s",
+				"--",
+				"sleep 60",
+			])
+			.kill_on_drop(true)
+			.output(),
+	)
+	.await
+	.into_diagnostic()?
+	.into_diagnostic()?;
+
+	assert!(
+		started.elapsed() < Duration::from_secs(10),
+		"command should have been killed well before it ended"
+	);
+
+	let stderr = String::from_utf8(output.stderr).into_diagnostic()?;
+	assert!(
+		stderr.contains("[Command timed out after 1s]"),
+		"unexpected output: {stderr:?}"
+	);
+
+	Ok(())
+}
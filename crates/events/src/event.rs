@@ -2,6 +2,7 @@ use std::{
 	collections::HashMap,
 	fmt,
 	path::{Path, PathBuf},
+	time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use watchexec_signals::Signal;
@@ -9,7 +10,10 @@ use watchexec_signals::Signal;
 #[cfg(feature = "serde")]
 use crate::serde_formats::{SerdeEvent, SerdeTag};
 
-use crate::{filekind::FileEventKind, FileType, Keyboard, ProcessEnd};
+use crate::{
+	filekind::{FileEventKind, ModifyKind},
+	metadata, FileType, Keyboard, ProcessEnd,
+};
 
 /// An event, as far as watchexec cares about.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -20,6 +24,8 @@ pub struct Event {
 	pub tags: Vec<Tag>,
 
 	/// Arbitrary other information, cannot be used for filtering.
+	///
+	/// Some keys are well-known, see the [`metadata`](crate::metadata) module.
 	pub metadata: HashMap<String, Vec<String>>,
 }
 
@@ -104,6 +110,9 @@ pub enum Source {
 
 	/// Event is internal to Watchexec.
 	Internal,
+
+	/// Event comes from outside Watchexec, such as a remote trigger.
+	External,
 }
 
 impl fmt::Display for Source {
@@ -118,6 +127,7 @@ impl fmt::Display for Source {
 				Self::Os => "os",
 				Self::Time => "time",
 				Self::Internal => "internal",
+				Self::External => "external",
 			}
 		)
 	}
@@ -173,12 +183,86 @@ impl Event {
 			.any(|tag| matches!(tag, Tag::Source(Source::Internal)))
 	}
 
+	/// Returns true if the event has an External source tag.
+	#[must_use]
+	pub fn is_external(&self) -> bool {
+		self.tags
+			.iter()
+			.any(|tag| matches!(tag, Tag::Source(Source::External)))
+	}
+
 	/// Returns true if the event has no tags.
 	#[must_use]
 	pub fn is_empty(&self) -> bool {
 		self.tags.is_empty()
 	}
 
+	/// Return the first value of a metadata key.
+	#[must_use]
+	pub fn metadata_str(&self, key: &str) -> Option<&str> {
+		self.metadata
+			.get(key)
+			.and_then(|values| values.first())
+			.map(String::as_str)
+	}
+
+	/// Return the first value of a metadata key, parsed as an unsigned integer.
+	///
+	/// Returns `None` if the key is missing or its value isn't a decimal integer which fits.
+	#[must_use]
+	pub fn metadata_u64(&self, key: &str) -> Option<u64> {
+		self.metadata_str(key)?.parse().ok()
+	}
+
+	/// Return the first value of a metadata key, parsed as an unsigned 32-bit integer.
+	///
+	/// Returns `None` if the key is missing or its value isn't a decimal integer which fits.
+	#[must_use]
+	pub fn metadata_u32(&self, key: &str) -> Option<u32> {
+		self.metadata_str(key)?.parse().ok()
+	}
+
+	/// Set a metadata key to a single value, replacing any existing values.
+	///
+	/// Integers are written in decimal, which is what the typed accessors expect.
+	pub fn set_metadata(&mut self, key: impl Into<String>, value: impl ToString) {
+		self.metadata.insert(key.into(), vec![value.to_string()]);
+	}
+
+	/// The size of the file the event is about, from the [`FILE_SIZE`](metadata::FILE_SIZE) key.
+	#[must_use]
+	pub fn file_size(&self) -> Option<u64> {
+		self.metadata_u64(metadata::FILE_SIZE)
+	}
+
+	/// When the event happened, from the [`TIMESTAMP`](metadata::TIMESTAMP) key.
+	#[must_use]
+	pub fn timestamp(&self) -> Option<SystemTime> {
+		UNIX_EPOCH.checked_add(Duration::from_millis(
+			self.metadata_u64(metadata::TIMESTAMP)?,
+		))
+	}
+
+	/// The process ID of whatever caused the event, from the
+	/// [`TRIGGERING_PID`](metadata::TRIGGERING_PID) key.
+	#[must_use]
+	pub fn triggering_pid(&self) -> Option<u32> {
+		self.metadata_u32(metadata::TRIGGERING_PID)
+	}
+
+	/// The user ID of the process which changed a file, from the [`UID`](metadata::UID) key.
+	#[must_use]
+	pub fn uid(&self) -> Option<u32> {
+		self.metadata_u32(metadata::UID)
+	}
+
+	/// The kind of watcher which produced a filesystem event, from the
+	/// [`NOTIFY_BACKEND`](metadata::NOTIFY_BACKEND) key.
+	#[must_use]
+	pub fn watcher_backend(&self) -> Option<&str> {
+		self.metadata_str(metadata::NOTIFY_BACKEND)
+	}
+
 	/// Return all paths in the event's tags.
 	pub fn paths(&self) -> impl Iterator<Item = (&Path, Option<&FileType>)> {
 		self.tags.iter().filter_map(|p| match p {
@@ -187,6 +271,52 @@ impl Event {
 		})
 	}
 
+	/// Return all paths in the event's tags, if the event has a create kind.
+	///
+	/// See [`paths_of_kind()`](Self::paths_of_kind) for how paths and kinds are matched up.
+	pub fn created_paths(&self) -> impl Iterator<Item = (&Path, Option<&FileType>)> {
+		self.paths_of_kind(|kind| matches!(kind, FileEventKind::Create(_)))
+	}
+
+	/// Return all paths in the event's tags, if the event has a modify kind other than a rename.
+	///
+	/// See [`paths_of_kind()`](Self::paths_of_kind) for how paths and kinds are matched up.
+	pub fn modified_paths(&self) -> impl Iterator<Item = (&Path, Option<&FileType>)> {
+		self.paths_of_kind(
+			|kind| matches!(kind, FileEventKind::Modify(modify) if !matches!(modify, ModifyKind::Name(_))),
+		)
+	}
+
+	/// Return all paths in the event's tags, if the event has a remove kind.
+	///
+	/// See [`paths_of_kind()`](Self::paths_of_kind) for how paths and kinds are matched up.
+	pub fn removed_paths(&self) -> impl Iterator<Item = (&Path, Option<&FileType>)> {
+		self.paths_of_kind(|kind| matches!(kind, FileEventKind::Remove(_)))
+	}
+
+	/// Return all paths in the event's tags, if the event has a rename kind.
+	///
+	/// See [`paths_of_kind()`](Self::paths_of_kind) for how paths and kinds are matched up.
+	pub fn renamed_paths(&self) -> impl Iterator<Item = (&Path, Option<&FileType>)> {
+		self.paths_of_kind(|kind| matches!(kind, FileEventKind::Modify(ModifyKind::Name(_))))
+	}
+
+	/// Return all paths in the event's tags, if the event has a kind matching the predicate.
+	///
+	/// Tags don't associate paths with kinds individually, so this returns either all paths of
+	/// the event, if any of its kind tags match, or none. Paths of events without any kind tag are
+	/// never returned.
+	pub fn paths_of_kind(
+		&self,
+		predicate: impl Fn(&FileEventKind) -> bool,
+	) -> impl Iterator<Item = (&Path, Option<&FileType>)> {
+		let matches = self
+			.tags
+			.iter()
+			.any(|tag| matches!(tag, Tag::FileEventKind(kind) if predicate(kind)));
+		self.paths().filter(move |_| matches)
+	}
+
 	/// Return all signals in the event's tags.
 	pub fn signals(&self) -> impl Iterator<Item = Signal> + '_ {
 		self.tags.iter().filter_map(|p| match p {
@@ -204,6 +334,34 @@ impl Event {
 	}
 }
 
+/// The longest path all paths of the events have in common.
+///
+/// This is computed by components, so `/foo/bar` and `/foo/baz` have `/foo` in common, not
+/// `/foo/ba`. A single path (or the same path several times) is its own common ancestor.
+///
+/// Returns `None` if no event has a path, or if paths have nothing in common, like relative and
+/// absolute paths, or paths on different drives on Windows.
+#[must_use]
+pub fn common_ancestor(events: &[Event]) -> Option<PathBuf> {
+	let mut paths = events.iter().flat_map(Event::paths).map(|(path, _)| path);
+	let mut common: Vec<_> = paths.next()?.components().collect();
+
+	for path in paths {
+		let shared = path
+			.components()
+			.zip(&common)
+			.take_while(|(component, other)| component == *other)
+			.count();
+		common.truncate(shared);
+	}
+
+	if common.is_empty() {
+		None
+	} else {
+		Some(common.into_iter().collect())
+	}
+}
+
 impl fmt::Display for Event {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		write!(f, "Event")?;
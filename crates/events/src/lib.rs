@@ -12,6 +12,8 @@ pub use keyboard::*;
 #[doc(inline)]
 pub use process::*;
 
+pub mod metadata;
+
 mod event;
 mod fs;
 mod keyboard;
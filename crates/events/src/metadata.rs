@@ -0,0 +1,56 @@
+//! Well-known keys of [`Event::metadata`](crate::Event::metadata).
+//!
+//! Metadata values are strings, so these are stored in a fixed text format, which is what the
+//! typed accessors on [`Event`](crate::Event) read and
+//! [`Event::set_metadata()`](crate::Event::set_metadata) writes: integers in decimal, with a single
+//! value per key.
+
+/// The size of the file the event is about, in bytes.
+///
+/// Read with [`Event::file_size()`](crate::Event::file_size).
+pub const FILE_SIZE: &str = "file-size";
+
+/// When the event happened, in milliseconds since the Unix epoch.
+///
+/// Read with [`Event::timestamp()`](crate::Event::timestamp).
+pub const TIMESTAMP: &str = "timestamp";
+
+/// The process ID of whatever caused the event, if that's known but not as a [`Tag::Process`].
+///
+/// Read with [`Event::triggering_pid()`](crate::Event::triggering_pid).
+///
+/// [`Tag::Process`]: crate::Tag::Process
+pub const TRIGGERING_PID: &str = "triggering-pid";
+
+/// The user ID of the process which changed a file.
+///
+/// Read with [`Event::uid()`](crate::Event::uid).
+pub const UID: &str = "uid";
+
+/// Backend-specific information about a file event, from Notify.
+pub const FILE_EVENT_INFO: &str = "file-event-info";
+
+/// The name of the Notify backend which produced a file event.
+///
+/// Read with [`Event::watcher_backend()`](crate::Event::watcher_backend). This is set by
+/// Watchexec on events from its filesystem watcher, to one of:
+///
+/// - `inotify` on Linux and Android,
+/// - `fsevent` on macOS,
+/// - `kqueue` on the BSDs and iOS,
+/// - `windows` on Windows, for `ReadDirectoryChangesW`,
+/// - `poll` for the poll watcher, whether it was chosen or is the fallback on other platforms.
+///
+/// It's absent from all other events, including filesystem events made by Watchexec itself rather
+/// than by a watcher, like those about watched paths being lost and restored.
+pub const NOTIFY_BACKEND: &str = "notify-backend";
+
+/// The state of a watched path, on events made by Watchexec about its filesystem watcher.
+///
+/// This is `lost` when a watched path is deleted, and `restored` when it reappears and is watched
+/// again. It's `ready` on the internal event sent once the watcher is set up, see the `ready_event`
+/// option of Watchexec's config.
+pub const WATCH: &str = "watch";
+
+/// The address of the client which asked for an event through Watchexec's HTTP trigger.
+pub const HTTP_TRIGGER: &str = "http-trigger";
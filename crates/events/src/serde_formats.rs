@@ -4,7 +4,7 @@ use std::{
 	path::PathBuf,
 };
 
-use serde::{Deserialize, Serialize};
+use serde::{de::Error as _, Deserialize, Serialize};
 use watchexec_signals::Signal;
 
 use crate::{
@@ -360,3 +360,59 @@ impl From<SerdeEvent> for Event {
 		}
 	}
 }
+
+/// An event record with its schema version, see [`Event::to_json_versioned()`].
+#[derive(Debug, Serialize, Deserialize)]
+struct VersionedEvent {
+	// legacy records have no version
+	#[serde(default)]
+	v: u32,
+
+	#[serde(flatten)]
+	event: Event,
+}
+
+impl Event {
+	/// The version of the JSON schema of events written by [`to_json_versioned()`](Self::to_json_versioned()).
+	///
+	/// This is incremented whenever the format changes in a way which readers would need to know
+	/// about. Version 1 is the same as the unversioned format, plus the `v` field.
+	pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+	/// Serialise the event as a single line of JSON, with its schema version.
+	///
+	/// This is the same as the plain JSON serialisation, with an additional `v` field holding
+	/// [`JSON_SCHEMA_VERSION`](Self::JSON_SCHEMA_VERSION), so consumers can detect format changes.
+	///
+	/// # Errors
+	///
+	/// This errors if serialisation fails, which shouldn't happen for events.
+	pub fn to_json_versioned(&self) -> Result<String, serde_json::Error> {
+		serde_json::to_string(&VersionedEvent {
+			v: Self::JSON_SCHEMA_VERSION,
+			event: self.clone(),
+		})
+	}
+
+	/// Read an event from a line of JSON, versioned or not.
+	///
+	/// This reads records written by [`to_json_versioned()`](Self::to_json_versioned()), and legacy
+	/// records without a `v` field (as written by the plain JSON serialisation), which are treated
+	/// as version 0.
+	///
+	/// # Errors
+	///
+	/// This errors if the JSON is invalid or isn't an event, or if the record is from a schema
+	/// version newer than [`JSON_SCHEMA_VERSION`](Self::JSON_SCHEMA_VERSION).
+	pub fn from_json_versioned(line: &str) -> Result<Self, serde_json::Error> {
+		let VersionedEvent { v, event } = serde_json::from_str(line)?;
+		if v > Self::JSON_SCHEMA_VERSION {
+			return Err(serde_json::Error::custom(format!(
+				"unsupported event schema version {v} (supported up to {})",
+				Self::JSON_SCHEMA_VERSION
+			)));
+		}
+
+		Ok(event)
+	}
+}
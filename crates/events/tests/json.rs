@@ -131,6 +131,7 @@ fn sources() {
 				Tag::Source(Source::Os),
 				Tag::Source(Source::Time),
 				Tag::Source(Source::Internal),
+				Tag::Source(Source::External),
 			],
 			metadata: Default::default(),
 		},
@@ -253,3 +254,53 @@ fn paths() {
 
 	assert_eq!(parse_file("tests/snapshots/paths.json"), paths);
 }
+
+#[test]
+fn versioned_roundtrip() {
+	let event = Event {
+		tags: vec![
+			Tag::Path {
+				path: "/foo/bar".into(),
+				file_type: Some(FileType::File),
+			},
+			Tag::FileEventKind(EventKind::Create(CreateKind::File)),
+			Tag::Source(Source::Filesystem),
+		],
+		metadata: [("notify-backend".to_string(), vec!["inotify".to_string()])].into(),
+	};
+
+	let line = event.to_json_versioned().unwrap();
+	assert!(!line.contains('\n'), "should be a single line: {line}");
+
+	let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+	assert_eq!(value["v"], Event::JSON_SCHEMA_VERSION);
+	assert!(value["tags"].is_array());
+
+	assert_eq!(Event::from_json_versioned(&line).unwrap(), event);
+}
+
+#[test]
+fn versioned_reads_legacy() {
+	let event = Event {
+		tags: vec![Tag::Source(Source::Internal)],
+		metadata: Default::default(),
+	};
+
+	let legacy = serde_json::to_string(&event).unwrap();
+	assert!(!legacy.contains("\"v\""));
+	assert_eq!(Event::from_json_versioned(&legacy).unwrap(), event);
+
+	assert_eq!(
+		Event::from_json_versioned(r#"{"tags":[{"kind":"source","source":"internal"}]}"#).unwrap(),
+		event
+	);
+}
+
+#[test]
+fn versioned_rejects_future() {
+	let line = format!(
+		r#"{{"v":{},"tags":[{{"kind":"source","source":"internal"}}]}}"#,
+		Event::JSON_SCHEMA_VERSION + 1
+	);
+	assert!(Event::from_json_versioned(&line).is_err());
+}
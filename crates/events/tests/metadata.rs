@@ -0,0 +1,50 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use watchexec_events::{metadata, Event};
+
+#[test]
+fn typed_accessors() {
+	let mut event = Event::default();
+	event.set_metadata(metadata::FILE_SIZE, 1234_u64);
+	event.set_metadata(metadata::TIMESTAMP, 1_700_000_000_000_u64);
+	event.set_metadata(metadata::TRIGGERING_PID, 42_u32);
+
+	assert_eq!(event.metadata_str(metadata::FILE_SIZE), Some("1234"));
+	assert_eq!(event.file_size(), Some(1234));
+	assert_eq!(
+		event.timestamp(),
+		Some(UNIX_EPOCH + Duration::from_millis(1_700_000_000_000))
+	);
+	assert_eq!(event.triggering_pid(), Some(42));
+	assert_eq!(event.uid(), None);
+}
+
+#[test]
+fn malformed_values() {
+	let mut event = Event::default();
+	event.set_metadata(metadata::FILE_SIZE, "big");
+	event.set_metadata(metadata::TRIGGERING_PID, u64::MAX);
+	event.set_metadata(metadata::UID, -1);
+	event
+		.metadata
+		.insert(metadata::TIMESTAMP.into(), Vec::new());
+
+	assert_eq!(event.file_size(), None);
+	assert_eq!(event.triggering_pid(), None);
+	assert_eq!(event.uid(), None);
+	assert_eq!(event.timestamp(), None);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+	let mut event = Event::default();
+	event.set_metadata(metadata::FILE_SIZE, 1234_u64);
+
+	let json = serde_json::to_string(&event).unwrap();
+	assert_eq!(json, r#"{"metadata":{"file-size":["1234"]}}"#);
+
+	let parsed: Event = serde_json::from_str(&json).unwrap();
+	assert_eq!(parsed, event);
+	assert_eq!(parsed.file_size(), Some(1234));
+}
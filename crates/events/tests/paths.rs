@@ -0,0 +1,129 @@
+use std::path::{Path, PathBuf};
+
+use watchexec_events::{
+	common_ancestor,
+	filekind::{CreateKind, DataChange, FileEventKind, ModifyKind, RemoveKind, RenameMode},
+	Event, FileType, Source, Tag,
+};
+
+fn event(kind: Option<FileEventKind>, paths: &[&str]) -> Event {
+	let mut tags = vec![Tag::Source(Source::Filesystem)];
+	tags.extend(kind.map(Tag::FileEventKind));
+	tags.extend(paths.iter().map(|path| Tag::Path {
+		path: path.into(),
+		file_type: Some(FileType::File),
+	}));
+	Event {
+		tags,
+		metadata: Default::default(),
+	}
+}
+
+fn collect<'e, I>(events: &'e [Event], f: impl Fn(&'e Event) -> I) -> Vec<&'e Path>
+where
+	I: Iterator<Item = (&'e Path, Option<&'e FileType>)>,
+{
+	events
+		.iter()
+		.flat_map(|event| f(event).map(|(path, _)| path))
+		.collect()
+}
+
+#[test]
+fn paths_by_kind() {
+	let events = [
+		event(Some(FileEventKind::Create(CreateKind::File)), &["/new"]),
+		event(
+			Some(FileEventKind::Modify(ModifyKind::Data(DataChange::Content))),
+			&["/changed"],
+		),
+		event(Some(FileEventKind::Remove(RemoveKind::File)), &["/gone"]),
+		event(
+			Some(FileEventKind::Modify(ModifyKind::Name(RenameMode::Both))),
+			&["/from", "/to"],
+		),
+		event(None, &["/unknown"]),
+	];
+
+	assert_eq!(
+		collect(&events, Event::paths),
+		["/new", "/changed", "/gone", "/from", "/to", "/unknown"].map(Path::new)
+	);
+	assert_eq!(collect(&events, Event::created_paths), [Path::new("/new")]);
+	assert_eq!(
+		collect(&events, Event::modified_paths),
+		[Path::new("/changed")]
+	);
+	assert_eq!(collect(&events, Event::removed_paths), [Path::new("/gone")]);
+	assert_eq!(
+		collect(&events, Event::renamed_paths),
+		["/from", "/to"].map(Path::new)
+	);
+}
+
+#[test]
+fn paths_of_event_with_several_kinds() {
+	let mut mixed = event(Some(FileEventKind::Create(CreateKind::Any)), &["/a", "/b"]);
+	mixed
+		.tags
+		.push(Tag::FileEventKind(FileEventKind::Remove(RemoveKind::Any)));
+
+	let created: Vec<_> = mixed.created_paths().map(|(path, _)| path).collect();
+	let removed: Vec<_> = mixed.removed_paths().map(|(path, _)| path).collect();
+	assert_eq!(created, ["/a", "/b"].map(Path::new));
+	assert_eq!(removed, ["/a", "/b"].map(Path::new));
+	assert_eq!(mixed.modified_paths().count(), 0);
+	assert_eq!(mixed.renamed_paths().count(), 0);
+}
+
+#[test]
+fn common_ancestor_of_shared_prefix() {
+	let events = [
+		event(None, &["/project/src/main.rs"]),
+		event(None, &["/project/src/lib/mod.rs", "/project/src/lib.rs"]),
+	];
+	assert_eq!(
+		common_ancestor(&events),
+		Some(PathBuf::from("/project/src"))
+	);
+
+	// by components, not characters
+	let events = [event(None, &["/project/foo", "/project/foobar"])];
+	assert_eq!(common_ancestor(&events), Some(PathBuf::from("/project")));
+}
+
+#[test]
+fn common_ancestor_of_single_path() {
+	let events = [event(None, &["/project/src/main.rs"])];
+	assert_eq!(
+		common_ancestor(&events),
+		Some(PathBuf::from("/project/src/main.rs"))
+	);
+}
+
+#[test]
+fn common_ancestor_of_divergent_roots() {
+	let events = [
+		event(None, &["/project/main.rs"]),
+		event(None, &["relative/main.rs"]),
+	];
+	assert_eq!(common_ancestor(&events), None);
+
+	#[cfg(unix)]
+	assert_eq!(
+		common_ancestor(&[event(None, &["/one/a", "/two/b"])]),
+		Some(PathBuf::from("/"))
+	);
+}
+
+#[test]
+fn common_ancestor_without_paths() {
+	assert_eq!(common_ancestor(&[]), None);
+	assert_eq!(
+		common_ancestor(&[Event {
+			tags: vec![Tag::Source(Source::Keyboard)],
+			metadata: Default::default(),
+		}]),
+		None
+	);
+}
@@ -0,0 +1,306 @@
+//! A Watchexec filterer for the git status of paths.
+//!
+//! This filterer is meant to be used as a backing filterer inside a more complex or complete
+//! filterer, and not as a standalone filterer.
+//!
+//! It reads the git index of a repository to find out whether paths are tracked, modified,
+//! untracked, or ignored, and passes those with one of a configured set of statuses. The index is
+//! read once and only read again when it changes on disk, so checking an event doesn't call git or
+//! read the whole index. Whether a tracked file is modified is decided by comparing its metadata
+//! to the index, and if that's not conclusive, by hashing its content.
+
+#![doc(html_favicon_url = "https://watchexec.github.io/logo:watchexec.svg")]
+#![doc(html_logo_url = "https://watchexec.github.io/logo:watchexec.svg")]
+#![warn(clippy::unwrap_used, missing_docs)]
+#![deny(rust_2018_idioms)]
+
+use std::{
+	fmt, fs,
+	path::{Path, PathBuf},
+	sync::Mutex,
+	time::SystemTime,
+};
+
+use gix_index::entry::{stat, Stat};
+use ignore::Match;
+use ignore_files::{IgnoreFile, IgnoreFilter};
+use miette::Diagnostic;
+use thiserror::Error;
+use tracing::{debug, trace, trace_span, warn};
+use watchexec::{error::RuntimeError, filter::Filterer};
+use watchexec_events::{Event, FileType, Priority};
+
+/// The status of a path in a git repository.
+///
+/// A path can have more than one status: a modified file is also tracked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GitStatus {
+	/// The path is in the index, or for a folder, something under it is.
+	Tracked,
+
+	/// The path is tracked, and differs in the worktree from the index, or has been deleted.
+	Modified,
+
+	/// The path isn't tracked, and isn't ignored.
+	Untracked,
+
+	/// The path isn't tracked, and is ignored by the ignore files of the repository.
+	Ignored,
+}
+
+/// Errors emitted by the [`GitStatusFilterer`].
+#[derive(Debug, Error, Diagnostic)]
+#[non_exhaustive]
+pub enum Error {
+	/// Error received when the origin isn't the top of a git worktree.
+	#[error("not a git worktree: {0:?}")]
+	NotAWorktree(PathBuf),
+
+	/// Error received when the git index cannot be read.
+	#[error("cannot read git index '{path:?}': {err}")]
+	Index {
+		/// The path to the index.
+		path: PathBuf,
+
+		/// The underlying error.
+		#[source]
+		err: gix_index::file::init::Error,
+	},
+
+	/// Error received when the ignore files cannot be loaded.
+	#[error(transparent)]
+	#[diagnostic(transparent)]
+	IgnoreFiles(#[from] ignore_files::Error),
+}
+
+/// A Watchexec [`Filterer`] implementation for the git status of paths.
+///
+/// Paths outside the worktree, and paths inside the git folder, always pass, as they have no
+/// status. Events with several paths pass if any of their paths pass. Non-path events pass.
+pub struct GitStatusFilterer {
+	worktree: PathBuf,
+	index_path: PathBuf,
+	statuses: Vec<GitStatus>,
+	ignores: IgnoreFilter,
+	index: Mutex<CachedIndex>,
+}
+
+impl fmt::Debug for GitStatusFilterer {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("GitStatusFilterer")
+			.field("worktree", &self.worktree)
+			.field("index_path", &self.index_path)
+			.field("statuses", &self.statuses)
+			.field("ignores", &self.ignores)
+			.finish_non_exhaustive()
+	}
+}
+
+/// The index as last read, and what the file looked like then.
+struct CachedIndex {
+	file: gix_index::File,
+	version: Option<(SystemTime, u64)>,
+}
+
+impl GitStatusFilterer {
+	/// Create a new `GitStatusFilterer` for the worktree at `origin`.
+	///
+	/// Paths pass if they have any of the `statuses`. The `ignore_files` are used to tell ignored
+	/// paths from untracked paths: these should be the git ignore files of the repository, such as
+	/// found by [`ignore_files::from_origin()`] for [`ProjectType::Git`].
+	///
+	/// The origin must contain the `.git` folder, or a `.git` file pointing to the git folder, as
+	/// in linked worktrees. A repository without an index yet is treated as having nothing
+	/// tracked.
+	///
+	/// [`ProjectType::Git`]: https://docs.rs/project-origins/latest/project_origins/enum.ProjectType.html#variant.Git
+	pub async fn new(
+		origin: impl AsRef<Path>,
+		statuses: impl IntoIterator<Item = GitStatus>,
+		ignore_files: &[IgnoreFile],
+	) -> Result<Self, Error> {
+		let origin = origin.as_ref();
+		let worktree =
+			dunce::canonicalize(origin).map_err(|_| Error::NotAWorktree(origin.to_owned()))?;
+		let index_path = git_dir(&worktree)?.join("index");
+		let index = load_index(&index_path)?;
+		let ignores = IgnoreFilter::new(&worktree, ignore_files).await?;
+
+		let statuses = statuses.into_iter().collect();
+		debug!(
+			?worktree,
+			?index_path,
+			?statuses,
+			"git status filterer ready"
+		);
+		Ok(Self {
+			worktree,
+			index_path,
+			statuses,
+			ignores,
+			index: Mutex::new(index),
+		})
+	}
+
+	/// Find the statuses of a path.
+	///
+	/// Returns `None` for paths outside the worktree or inside the git folder. The index is read
+	/// again first if it has changed since it was last read.
+	pub fn status(&self, path: &Path, is_dir: bool) -> Option<Vec<GitStatus>> {
+		let path = dunce::simplified(path);
+		let relative = path.strip_prefix(&self.worktree).ok()?;
+		if relative.starts_with(".git") {
+			return None;
+		}
+
+		let mut index = self.index.lock().expect("git index lock poisoned");
+		self.refresh(&mut index);
+		let index = &index.file;
+
+		let key = gix_path::to_unix_separators_on_windows(gix_path::into_bstr(relative));
+		if let Some(entry) = index.entry_by_path(&key) {
+			return Some(if is_modified(index, entry, path) {
+				vec![GitStatus::Tracked, GitStatus::Modified]
+			} else {
+				vec![GitStatus::Tracked]
+			});
+		}
+
+		if is_dir && !relative.as_os_str().is_empty() {
+			let mut prefix = key.into_owned();
+			prefix.push(b'/');
+			if index.prefixed_entries(prefix.as_ref()).is_some() {
+				return Some(vec![GitStatus::Tracked]);
+			}
+		}
+
+		Some(match self.ignores.match_path(path, is_dir) {
+			Match::Ignore(_) => vec![GitStatus::Ignored],
+			Match::None | Match::Whitelist(_) => vec![GitStatus::Untracked],
+		})
+	}
+
+	/// Read the index again if it has changed, keeping the previous one if that fails.
+	fn refresh(&self, index: &mut CachedIndex) {
+		if index_version(&self.index_path) == index.version {
+			return;
+		}
+
+		match load_index(&self.index_path) {
+			Ok(new) => {
+				debug!(path=?self.index_path, "git index changed, read it again");
+				*index = new;
+			}
+			Err(err) => warn!(%err, "cannot read git index again, keeping the previous one"),
+		}
+	}
+}
+
+impl Filterer for GitStatusFilterer {
+	/// Filter an event.
+	///
+	/// This implementation never errors. It ignores event priority.
+	fn check_event(&self, event: &Event, _priority: Priority) -> Result<bool, RuntimeError> {
+		let _span = trace_span!("filterer_check").entered();
+
+		let mut paths = event.paths().peekable();
+		if paths.peek().is_none() {
+			trace!("non-path event (pass)");
+			return Ok(true);
+		}
+
+		Ok(paths.any(|(path, file_type)| {
+			let _span = trace_span!("path", ?path).entered();
+			let is_dir = file_type.map_or(false, |t| matches!(t, FileType::Dir));
+			match self.status(path, is_dir) {
+				None => {
+					trace!("not in the worktree (pass)");
+					true
+				}
+				Some(statuses) => {
+					let pass = statuses.iter().any(|s| self.statuses.contains(s));
+					trace!(?statuses, ?pass, "git status");
+					pass
+				}
+			}
+		}))
+	}
+}
+
+/// Find the git folder of a worktree, following a `.git` file if needed.
+fn git_dir(worktree: &Path) -> Result<PathBuf, Error> {
+	let dot_git = worktree.join(".git");
+	if dot_git.is_dir() {
+		return Ok(dot_git);
+	}
+
+	fs::read_to_string(&dot_git)
+		.ok()
+		.and_then(|content| {
+			content
+				.lines()
+				.find_map(|line| line.strip_prefix("gitdir:"))
+				.map(|dir| worktree.join(dir.trim()))
+		})
+		.ok_or_else(|| Error::NotAWorktree(worktree.to_owned()))
+}
+
+fn index_version(path: &Path) -> Option<(SystemTime, u64)> {
+	let meta = fs::metadata(path).ok()?;
+	Some((meta.modified().ok()?, meta.len()))
+}
+
+fn load_index(path: &Path) -> Result<CachedIndex, Error> {
+	// read the version first so a change while reading is picked up next time
+	let version = index_version(path);
+	let file = gix_index::File::at_or_default(
+		path,
+		gix_hash::Kind::Sha1,
+		false,
+		gix_index::decode::Options::default(),
+	)
+	.map_err(|err| Error::Index {
+		path: path.to_owned(),
+		err,
+	})?;
+
+	trace!(?path, entries=%file.entries().len(), "read git index");
+	Ok(CachedIndex { file, version })
+}
+
+/// Whether a tracked file differs from the index.
+fn is_modified(index: &gix_index::File, entry: &gix_index::Entry, path: &Path) -> bool {
+	let Ok(meta) = gix_index::fs::Metadata::from_path_no_follow(path) else {
+		trace!("deleted");
+		return true;
+	};
+
+	if entry
+		.mode
+		.change_to_match_fs(&meta, true, cfg!(unix))
+		.is_some()
+	{
+		trace!("type or executable bit changed");
+		return true;
+	}
+
+	if entry.mode.is_submodule() {
+		return false;
+	}
+
+	let options = stat::Options::default();
+	if let Ok(stat) = Stat::from_fs(&meta) {
+		if entry.stat.matches(&stat, options) && !entry.stat.is_racy(index.timestamp(), options) {
+			return false;
+		}
+	}
+
+	let content = if meta.is_symlink() {
+		fs::read_link(path).map(|target| gix_path::into_bstr(target).into_owned().into())
+	} else {
+		fs::read(path)
+	};
+	content.map_or(true, |data| {
+		gix_object::compute_hash(gix_hash::Kind::Sha1, gix_object::Kind::Blob, &data) != entry.id
+	})
+}
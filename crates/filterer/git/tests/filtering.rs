@@ -0,0 +1,152 @@
+use std::{
+	path::{Path, PathBuf},
+	process::Command,
+};
+
+use ignore_files::IgnoreFile;
+use tempfile::TempDir;
+use watchexec::filter::Filterer;
+use watchexec_events::{Event, FileType, Priority, Tag};
+use watchexec_filterer_git::{GitStatus, GitStatusFilterer};
+
+struct Repo {
+	dir: TempDir,
+	root: PathBuf,
+}
+
+impl Repo {
+	fn new() -> Self {
+		let dir = tempfile::tempdir().unwrap();
+		let root = dunce::canonicalize(dir.path()).unwrap();
+		let repo = Self { dir, root };
+		repo.git(&["init", "--quiet"]);
+		repo.write(".gitignore", "ignored.txt\n");
+		repo.write("tracked.txt", "tracked");
+		repo.write("sub/tracked.txt", "tracked");
+		repo.git(&["add", ".gitignore", "tracked.txt", "sub/tracked.txt"]);
+		repo.write("untracked.txt", "untracked");
+		repo.write("ignored.txt", "ignored");
+		repo
+	}
+
+	fn git(&self, args: &[&str]) {
+		let status = Command::new("git")
+			.args(args)
+			.current_dir(&self.root)
+			.status()
+			.unwrap();
+		assert!(status.success(), "git {args:?} failed");
+	}
+
+	fn write(&self, path: &str, content: &str) {
+		let path = self.root.join(path);
+		std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+		std::fs::write(path, content).unwrap();
+	}
+
+	async fn filterer(&self, statuses: &[GitStatus]) -> GitStatusFilterer {
+		let ignore_files = [IgnoreFile {
+			path: self.root.join(".gitignore"),
+			applies_in: Some(self.root.clone()),
+			applies_to: None,
+		}];
+		GitStatusFilterer::new(self.dir.path(), statuses.iter().copied(), &ignore_files)
+			.await
+			.unwrap()
+	}
+}
+
+trait PathHarness: Filterer {
+	fn path_pass(&self, path: &Path, file_type: Option<FileType>, pass: bool) {
+		let event = Event {
+			tags: vec![Tag::Path {
+				path: path.into(),
+				file_type,
+			}],
+			metadata: Default::default(),
+		};
+
+		assert_eq!(
+			self.check_event(&event, Priority::Normal).unwrap(),
+			pass,
+			"{path:?} (expected {})",
+			if pass { "pass" } else { "fail" }
+		);
+	}
+}
+
+impl PathHarness for GitStatusFilterer {}
+
+#[tokio::test]
+async fn untracked_fail_tracked_pass() {
+	let repo = Repo::new();
+	let filterer = repo.filterer(&[GitStatus::Tracked]).await;
+
+	filterer.path_pass(&repo.root.join("tracked.txt"), Some(FileType::File), true);
+	filterer.path_pass(&repo.root.join("sub/tracked.txt"), None, true);
+	filterer.path_pass(&repo.root.join("sub"), Some(FileType::Dir), true);
+	filterer.path_pass(
+		&repo.root.join("untracked.txt"),
+		Some(FileType::File),
+		false,
+	);
+	filterer.path_pass(&repo.root.join("ignored.txt"), Some(FileType::File), false);
+}
+
+#[tokio::test]
+async fn untracked_and_ignored_differ() {
+	let repo = Repo::new();
+	let untracked = repo.filterer(&[GitStatus::Untracked]).await;
+	let ignored = repo.filterer(&[GitStatus::Ignored]).await;
+
+	untracked.path_pass(&repo.root.join("untracked.txt"), None, true);
+	untracked.path_pass(&repo.root.join("ignored.txt"), None, false);
+	untracked.path_pass(&repo.root.join("tracked.txt"), None, false);
+
+	ignored.path_pass(&repo.root.join("untracked.txt"), None, false);
+	ignored.path_pass(&repo.root.join("ignored.txt"), None, true);
+	ignored.path_pass(&repo.root.join("tracked.txt"), None, false);
+}
+
+#[tokio::test]
+async fn modified_and_deleted() {
+	let repo = Repo::new();
+	let filterer = repo.filterer(&[GitStatus::Modified]).await;
+
+	filterer.path_pass(&repo.root.join("tracked.txt"), None, false);
+	repo.write("tracked.txt", "changed content");
+	filterer.path_pass(&repo.root.join("tracked.txt"), None, true);
+
+	filterer.path_pass(&repo.root.join("sub/tracked.txt"), None, false);
+	std::fs::remove_file(repo.root.join("sub/tracked.txt")).unwrap();
+	filterer.path_pass(&repo.root.join("sub/tracked.txt"), None, true);
+}
+
+#[tokio::test]
+async fn index_changes_are_picked_up() {
+	let repo = Repo::new();
+	let filterer = repo.filterer(&[GitStatus::Tracked]).await;
+
+	filterer.path_pass(&repo.root.join("untracked.txt"), None, false);
+	repo.git(&["add", "untracked.txt"]);
+	filterer.path_pass(&repo.root.join("untracked.txt"), None, true);
+}
+
+#[tokio::test]
+async fn outside_paths_pass() {
+	let repo = Repo::new();
+	let filterer = repo.filterer(&[GitStatus::Tracked]).await;
+
+	filterer.path_pass(Path::new("/elsewhere/file.txt"), None, true);
+	filterer.path_pass(&repo.root.join(".git/index"), None, true);
+}
+
+#[tokio::test]
+async fn non_path_events_pass() {
+	let repo = Repo::new();
+	let filterer = repo.filterer(&[GitStatus::Tracked]).await;
+
+	assert!(filterer
+		.check_event(&Event::default(), Priority::Normal)
+		.unwrap());
+}
@@ -0,0 +1,246 @@
+use std::{
+	ffi::OsString,
+	fmt,
+	path::{PathBuf, MAIN_SEPARATOR},
+};
+
+use ignore_files::{Error, IgnoreFile};
+use tracing::{debug, trace, trace_span};
+use watchexec::{error::RuntimeError, filter::Filterer};
+use watchexec_events::{Event, Priority};
+
+use crate::GlobsetFilterer;
+
+/// A filterer which puts together ignore files, globs, extensions, and other filterers.
+///
+/// This is how the Watchexec CLI filters events, for use in other programs. It's made with a
+/// [`CombinedFiltererBuilder`], obtained from [`CombinedFilterer::builder()`].
+///
+/// Events are checked in this order, and checking stops at the first rejection:
+///
+/// 1. paths in the whitelist pass everything else;
+/// 2. the ignore files;
+/// 3. the ignore globs, including the [default ignores](CombinedFiltererBuilder::default_ignores);
+/// 4. the filter globs and extensions, where a path passes if it matches either;
+/// 5. the extra filterers, in the order they were added.
+///
+/// Steps 1 to 4 are a [`GlobsetFilterer`]: see there for the details of how paths are matched.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use watchexec_filterer_globset::CombinedFilterer;
+/// # async fn example() -> Result<(), ignore_files::Error> {
+/// let filterer = CombinedFilterer::builder("/code/project")
+///     .default_ignores(true)
+///     .ignores([("target/**".into(), None)])
+///     .extensions(["rs".into(), "toml".into()])
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct CombinedFilterer {
+	globset: GlobsetFilterer,
+	extra: Vec<Box<dyn Filterer>>,
+	ignore_files: Vec<PathBuf>,
+}
+
+impl fmt::Debug for CombinedFilterer {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("CombinedFilterer")
+			.field("globset", &self.globset)
+			.field("extra", &self.extra)
+			.finish_non_exhaustive()
+	}
+}
+
+impl CombinedFilterer {
+	/// Start building a filterer for a project origin.
+	///
+	/// The origin is where globs without a path apply from, see [`GlobsetFilterer::new()`].
+	pub fn builder(origin: impl Into<PathBuf>) -> CombinedFiltererBuilder {
+		CombinedFiltererBuilder {
+			origin: origin.into(),
+			default_ignores: false,
+			filters: Vec::new(),
+			ignores: Vec::new(),
+			whitelist: Vec::new(),
+			ignore_files: Vec::new(),
+			extensions: Vec::new(),
+			extra: Vec::new(),
+		}
+	}
+
+	/// The paths of the ignore files which were loaded.
+	#[must_use]
+	pub fn ignore_files(&self) -> &[PathBuf] {
+		&self.ignore_files
+	}
+}
+
+impl Filterer for CombinedFilterer {
+	fn check_event(&self, event: &Event, priority: Priority) -> Result<bool, RuntimeError> {
+		let _span = trace_span!("combined_filterer_check").entered();
+
+		trace!("checking globset filterer");
+		if !self.globset.check_event(event, priority)? {
+			return Ok(false);
+		}
+
+		for (n, filterer) in self.extra.iter().enumerate() {
+			trace!(%n, "checking extra filterer");
+			if !filterer.check_event(event, priority)? {
+				return Ok(false);
+			}
+		}
+
+		Ok(true)
+	}
+}
+
+/// Builder for a [`CombinedFilterer`].
+///
+/// Methods which take lists add to what was given before. Globs are given as a tuple of the
+/// pattern and an optional path of the folder the pattern applies in, with `None` for patterns
+/// which apply everywhere; see [`GlobsetFilterer::new()`].
+#[must_use]
+pub struct CombinedFiltererBuilder {
+	origin: PathBuf,
+	default_ignores: bool,
+	filters: Vec<(String, Option<PathBuf>)>,
+	ignores: Vec<(String, Option<PathBuf>)>,
+	whitelist: Vec<PathBuf>,
+	ignore_files: Vec<IgnoreFile>,
+	extensions: Vec<OsString>,
+	extra: Vec<Box<dyn Filterer>>,
+}
+
+impl fmt::Debug for CombinedFiltererBuilder {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("CombinedFiltererBuilder")
+			.field("origin", &self.origin)
+			.field("default_ignores", &self.default_ignores)
+			.field("filters", &self.filters)
+			.field("ignores", &self.ignores)
+			.field("whitelist", &self.whitelist)
+			.field("ignore_files", &self.ignore_files)
+			.field("extensions", &self.extensions)
+			.field("extra", &self.extra)
+			.finish()
+	}
+}
+
+impl CombinedFiltererBuilder {
+	/// Whether to ignore editor swap and backup files, and VCS directories.
+	///
+	/// These are the [`DEFAULT_IGNORES`], which the Watchexec CLI ignores unless given
+	/// `--no-default-ignore`. Default is false.
+	pub fn default_ignores(mut self, enable: bool) -> Self {
+		self.default_ignores = enable;
+		self
+	}
+
+	/// Add globs which paths must match to pass.
+	///
+	/// If there are no filter globs nor extensions, all paths which aren't ignored pass.
+	pub fn filters(mut self, filters: impl IntoIterator<Item = (String, Option<PathBuf>)>) -> Self {
+		self.filters.extend(filters);
+		self
+	}
+
+	/// Add globs which paths must not match to pass.
+	pub fn ignores(mut self, ignores: impl IntoIterator<Item = (String, Option<PathBuf>)>) -> Self {
+		self.ignores.extend(ignores);
+		self
+	}
+
+	/// Add paths which pass regardless of ignores and filters.
+	///
+	/// These are absolute paths to files, matched exactly. The extra filterers still apply.
+	pub fn whitelist(mut self, paths: impl IntoIterator<Item = PathBuf>) -> Self {
+		self.whitelist.extend(paths);
+		self
+	}
+
+	/// Add ignore files to load.
+	pub fn ignore_files(mut self, files: impl IntoIterator<Item = IgnoreFile>) -> Self {
+		self.ignore_files.extend(files);
+		self
+	}
+
+	/// Add file extensions, without the leading dot, which paths may have to pass.
+	pub fn extensions(mut self, extensions: impl IntoIterator<Item = OsString>) -> Self {
+		self.extensions.extend(extensions);
+		self
+	}
+
+	/// Add a filterer to check events with after the globs, such as one for filter programs.
+	pub fn with_filterer(mut self, filterer: impl Filterer + 'static) -> Self {
+		self.extra.push(Box::new(filterer));
+		self
+	}
+
+	/// Load the ignore files, compile the globs, and make the filterer.
+	#[allow(clippy::future_not_send)]
+	pub async fn build(self) -> Result<CombinedFilterer, Error> {
+		let mut ignores = Vec::new();
+		if self.default_ignores {
+			ignores.extend(DEFAULT_IGNORES.iter().map(|glob| {
+				(
+					glob.replace('/', &MAIN_SEPARATOR.to_string()),
+					None::<PathBuf>,
+				)
+			}));
+		}
+		ignores.extend(self.ignores);
+
+		let ignore_files = self
+			.ignore_files
+			.iter()
+			.map(|file| file.path.clone())
+			.collect();
+
+		debug!(
+			origin=?self.origin,
+			default_ignores=%self.default_ignores,
+			extra=%self.extra.len(),
+			"building combined filterer"
+		);
+		Ok(CombinedFilterer {
+			globset: GlobsetFilterer::new(
+				self.origin,
+				self.filters,
+				ignores,
+				self.whitelist,
+				self.ignore_files,
+				self.extensions,
+			)
+			.await?,
+			extra: self.extra,
+			ignore_files,
+		})
+	}
+}
+
+/// Globs for editor swap and backup files, and VCS directories, ignored by default in Watchexec.
+///
+/// These are written with `/` as the separator, which is replaced by the platform's separator
+/// when they're used by [`CombinedFiltererBuilder::default_ignores()`].
+pub const DEFAULT_IGNORES: &[&str] = &[
+	"**/.DS_Store",
+	"watchexec.*.log",
+	"*.py[co]",
+	"#*#",
+	".#*",
+	".*.kate-swp",
+	".*.sw?",
+	".*.sw?x",
+	"**/.bzr/**",
+	"**/_darcs/**",
+	"**/.fossil-settings/**",
+	"**/.git/**",
+	"**/.hg/**",
+	"**/.pijul/**",
+	"**/.svn/**",
+];
@@ -9,8 +9,8 @@
 #![deny(rust_2018_idioms)]
 
 use std::{
-	ffi::OsString,
-	fmt,
+	ffi::{OsStr, OsString},
+	fmt, fs,
 	path::{Path, PathBuf},
 };
 
@@ -21,6 +21,11 @@ use watchexec::{error::RuntimeError, filter::Filterer};
 use watchexec_events::{Event, FileType, Priority};
 use watchexec_filterer_ignore::IgnoreFilterer;
 
+#[doc(inline)]
+pub use combined::{CombinedFilterer, CombinedFiltererBuilder, DEFAULT_IGNORES};
+
+mod combined;
+
 /// A simple filterer in the style of the watchexec v1.17 filter.
 #[cfg_attr(feature = "full_debug", derive(Debug))]
 pub struct GlobsetFilterer {
@@ -31,6 +36,7 @@ pub struct GlobsetFilterer {
 	whitelist: Vec<PathBuf>,
 	ignore_files: IgnoreFilterer,
 	extensions: Vec<OsString>,
+	case_insensitive: bool,
 }
 
 #[cfg(not(feature = "full_debug"))]
@@ -42,6 +48,7 @@ impl fmt::Debug for GlobsetFilterer {
 			.field("ignores", &"ignore::gitignore::Gitignore{...}")
 			.field("ignore_files", &self.ignore_files)
 			.field("extensions", &self.extensions)
+			.field("case_insensitive", &self.case_insensitive)
 			.finish()
 	}
 }
@@ -62,6 +69,9 @@ impl GlobsetFilterer {
 	/// The extensions list is used to filter files by extension.
 	///
 	/// Non-path events are always passed.
+	///
+	/// Paths are matched case-insensitively if the filesystem of the origin is case-insensitive,
+	/// see [`is_case_insensitive()`] and [`new_with_case()`](GlobsetFilterer::new_with_case).
 	#[allow(clippy::future_not_send)]
 	pub async fn new(
 		origin: impl AsRef<Path>,
@@ -70,10 +80,45 @@ impl GlobsetFilterer {
 		whitelist: impl IntoIterator<Item = PathBuf>,
 		ignore_files: impl IntoIterator<Item = IgnoreFile>,
 		extensions: impl IntoIterator<Item = OsString>,
+	) -> Result<Self, Error> {
+		let origin = origin.as_ref();
+		Self::new_with_case(
+			origin,
+			filters,
+			ignores,
+			whitelist,
+			ignore_files,
+			extensions,
+			is_case_insensitive(origin),
+		)
+		.await
+	}
+
+	/// Create a new `GlobsetFilterer`, choosing whether paths are matched case-insensitively.
+	///
+	/// This is the same as [`new()`](GlobsetFilterer::new), but doesn't detect case sensitivity
+	/// from the filesystem of the origin. When `case_insensitive` is true, the filter and ignore
+	/// globs, the extensions, and the whitelist are matched without regard to case, so an ignore
+	/// of `Build/` also ignores `build/file`. Only ASCII letters are folded for the extensions
+	/// and the whitelist.
+	///
+	/// This doesn't apply to the ignore files, which are matched case-sensitively like git does
+	/// by default (without `core.ignoreCase`).
+	#[allow(clippy::future_not_send)]
+	pub async fn new_with_case(
+		origin: impl AsRef<Path>,
+		filters: impl IntoIterator<Item = (String, Option<PathBuf>)>,
+		ignores: impl IntoIterator<Item = (String, Option<PathBuf>)>,
+		whitelist: impl IntoIterator<Item = PathBuf>,
+		ignore_files: impl IntoIterator<Item = IgnoreFile>,
+		extensions: impl IntoIterator<Item = OsString>,
+		case_insensitive: bool,
 	) -> Result<Self, Error> {
 		let origin = origin.as_ref();
 		let mut filters_builder = GitignoreBuilder::new(origin);
 		let mut ignores_builder = GitignoreBuilder::new(origin);
+		filters_builder.case_insensitive(case_insensitive).ok();
+		ignores_builder.case_insensitive(case_insensitive).ok();
 
 		for (filter, in_path) in filters {
 			trace!(filter=?&filter, "add filter to globset filterer");
@@ -96,6 +141,62 @@ impl GlobsetFilterer {
 			.build()
 			.map_err(|err| Error::Glob { file: None, err })?;
 
+		Self::build(
+			origin,
+			filters,
+			ignores,
+			whitelist,
+			ignore_files,
+			extensions,
+			case_insensitive,
+		)
+		.await
+	}
+
+	/// Create a new `GlobsetFilterer` from already-compiled filter and ignore matchers.
+	///
+	/// This is the same as [`new()`](GlobsetFilterer::new), but takes the filters and ignores as
+	/// [`Gitignore`] matchers instead of lists of globs, which avoids compiling them again when
+	/// they're already available, such as when building many filterers from the same globs.
+	///
+	/// The filterer takes ownership of the matchers. To share matchers between filterers, clone
+	/// them: cloning a `Gitignore` does not recompile its globs. For the same behaviour as `new()`,
+	/// the matchers should be built with a [`GitignoreBuilder`] rooted at the `origin`.
+	///
+	/// The case sensitivity of the matchers is whatever they were built with. The extensions and
+	/// the whitelist are matched case-insensitively if the filesystem of the origin is.
+	#[allow(clippy::future_not_send)]
+	pub async fn from_compiled(
+		origin: impl AsRef<Path>,
+		filters: Gitignore,
+		ignores: Gitignore,
+		whitelist: impl IntoIterator<Item = PathBuf>,
+		ignore_files: impl IntoIterator<Item = IgnoreFile>,
+		extensions: impl IntoIterator<Item = OsString>,
+	) -> Result<Self, Error> {
+		let origin = origin.as_ref();
+		Self::build(
+			origin,
+			filters,
+			ignores,
+			whitelist,
+			ignore_files,
+			extensions,
+			is_case_insensitive(origin),
+		)
+		.await
+	}
+
+	#[allow(clippy::future_not_send)]
+	async fn build(
+		origin: &Path,
+		filters: Gitignore,
+		ignores: Gitignore,
+		whitelist: impl IntoIterator<Item = PathBuf>,
+		ignore_files: impl IntoIterator<Item = IgnoreFile>,
+		extensions: impl IntoIterator<Item = OsString>,
+		case_insensitive: bool,
+	) -> Result<Self, Error> {
 		let extensions: Vec<OsString> = extensions.into_iter().collect();
 
 		let mut ignore_files =
@@ -113,6 +214,7 @@ impl GlobsetFilterer {
 			num_in_ignore_files=?ignore_files.0.num_ignores(),
 			num_neg_ignores=%ignores.num_whitelists(),
 			num_extensions=%extensions.len(),
+			?case_insensitive,
 		"globset filterer built");
 
 		Ok(Self {
@@ -122,8 +224,67 @@ impl GlobsetFilterer {
 			whitelist,
 			ignore_files,
 			extensions,
+			case_insensitive,
 		})
 	}
+
+	fn same(&self, a: &OsStr, b: &OsStr) -> bool {
+		if self.case_insensitive {
+			a.eq_ignore_ascii_case(b)
+		} else {
+			a == b
+		}
+	}
+}
+
+/// Detects whether the filesystem a path is on is case-insensitive.
+///
+/// This looks for the path (or its closest ancestor which has letters in its name) under a name
+/// with the case of its letters swapped: if that's the same file, the filesystem is taken to be
+/// case-insensitive. If the path doesn't exist or has no letters at all, this returns false.
+#[must_use]
+pub fn is_case_insensitive(path: &Path) -> bool {
+	let Ok(path) = fs::canonicalize(path) else {
+		return false;
+	};
+
+	for ancestor in path.ancestors() {
+		let Some(name) = ancestor.file_name().and_then(OsStr::to_str) else {
+			continue;
+		};
+
+		let swapped: String = name
+			.chars()
+			.map(|c| {
+				if c.is_ascii_lowercase() {
+					c.to_ascii_uppercase()
+				} else {
+					c.to_ascii_lowercase()
+				}
+			})
+			.collect();
+		if swapped == name {
+			continue;
+		}
+
+		return is_same_file(ancestor, &ancestor.with_file_name(swapped));
+	}
+
+	false
+}
+
+#[cfg(unix)]
+fn is_same_file(a: &Path, b: &Path) -> bool {
+	use std::os::unix::fs::MetadataExt;
+	match (fs::metadata(a), fs::metadata(b)) {
+		(Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+		_ => false,
+	}
+}
+
+#[cfg(not(unix))]
+fn is_same_file(_a: &Path, b: &Path) -> bool {
+	b.exists()
 }
 
 impl Filterer for GlobsetFilterer {
@@ -137,10 +298,11 @@ impl Filterer for GlobsetFilterer {
 			trace!("checking internal whitelist");
 			// Ideally check path equality backwards for better perf
 			// There could be long matching prefixes so we will exit late
-			if event
-				.paths()
-				.any(|(p, _)| self.whitelist.iter().any(|w| w == p))
-			{
+			if event.paths().any(|(p, _)| {
+				self.whitelist
+					.iter()
+					.any(|w| self.same(w.as_os_str(), p.as_os_str()))
+			}) {
 				trace!("internal whitelist filterer matched (success)");
 				return Ok(true);
 			}
@@ -212,7 +374,7 @@ impl Filterer for GlobsetFilterer {
 					}
 
 					if let Some(ext) = path.extension() {
-						if self.extensions.iter().any(|e| e == ext) {
+						if self.extensions.iter().any(|e| self.same(e, ext)) {
 							trace!("allowed by extension filter");
 							return true;
 						}
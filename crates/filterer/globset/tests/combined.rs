@@ -0,0 +1,151 @@
+#[allow(dead_code)] // only the harness is used here
+mod helpers;
+use helpers::globset::{filt, PathHarness, Priority};
+use std::{
+	io::Write,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+};
+
+use ignore_files::IgnoreFile;
+use watchexec::{error::RuntimeError, filter::Filterer};
+use watchexec_events::Event;
+use watchexec_filterer_globset::CombinedFilterer;
+
+async fn origin() -> std::path::PathBuf {
+	tokio::fs::canonicalize(".").await.unwrap()
+}
+
+#[derive(Debug, Default)]
+struct Counting(AtomicUsize);
+
+impl Filterer for Counting {
+	fn check_event(&self, _event: &Event, _priority: Priority) -> Result<bool, RuntimeError> {
+		self.0.fetch_add(1, Ordering::SeqCst);
+		Ok(true)
+	}
+}
+
+#[derive(Debug)]
+struct Reject;
+
+impl Filterer for Reject {
+	fn check_event(&self, _event: &Event, _priority: Priority) -> Result<bool, RuntimeError> {
+		Ok(false)
+	}
+}
+
+#[tokio::test]
+async fn default_ignores() {
+	let filterer = CombinedFilterer::builder(origin().await)
+		.default_ignores(true)
+		.build()
+		.await
+		.unwrap();
+
+	filterer.file_does_pass("src/main.rs");
+	filterer.file_doesnt_pass(".git/index");
+	filterer.file_doesnt_pass("sub/.hg/store");
+	filterer.file_doesnt_pass(".main.rs.swp");
+	filterer.file_doesnt_pass("module.pyc");
+	filterer.file_doesnt_pass("watchexec.2024-10-14.log");
+
+	let filterer = CombinedFilterer::builder(origin().await)
+		.build()
+		.await
+		.unwrap();
+	filterer.file_does_pass(".git/index");
+}
+
+#[tokio::test]
+async fn same_as_globset_alone() {
+	let filterer = CombinedFilterer::builder(origin().await)
+		.filters([("*.rs".into(), None), ("Cargo.toml".into(), None)])
+		.ignores([("target/**".into(), None)])
+		.build()
+		.await
+		.unwrap();
+	let globset = filt(&["*.rs", "Cargo.toml"], &["target/**"], &[], &[], &[]).await;
+
+	for path in [
+		"src/main.rs",
+		"Cargo.toml",
+		"Cargo.lock",
+		"target/debug/build.rs",
+	] {
+		let path = origin().await.join(path);
+		assert_eq!(
+			filterer.check_path(path.clone(), None).unwrap(),
+			globset.check_path(path.clone(), None).unwrap(),
+			"{path:?}"
+		);
+	}
+}
+
+#[tokio::test]
+async fn ignores_before_filters() {
+	// like `watchexec -e rs -i 'target/**'`
+	let filterer = CombinedFilterer::builder(origin().await)
+		.ignores([("target/**".into(), None)])
+		.extensions(["rs".into()])
+		.build()
+		.await
+		.unwrap();
+
+	filterer.file_does_pass("src/lib.rs");
+	filterer.file_doesnt_pass("target/build/out.rs");
+	filterer.file_doesnt_pass("Cargo.toml");
+}
+
+#[tokio::test]
+async fn ignore_files_then_filters() {
+	// like `watchexec -f '*.txt'` in a project with a .gitignore
+	let mut ignore_file = tempfile::NamedTempFile::new().unwrap();
+	ignore_file.write_all(b"secret.txt\n").unwrap();
+
+	let filterer = CombinedFilterer::builder(origin().await)
+		.filters([("*.txt".into(), None)])
+		.ignore_files([IgnoreFile {
+			path: ignore_file.path().into(),
+			applies_in: None,
+			applies_to: None,
+		}])
+		.build()
+		.await
+		.unwrap();
+
+	assert_eq!(filterer.ignore_files(), [ignore_file.path().to_owned()]);
+	filterer.file_does_pass("notes.txt");
+	filterer.file_doesnt_pass("secret.txt");
+	filterer.file_doesnt_pass("notes.md");
+}
+
+#[tokio::test]
+async fn extra_filterers_run_last() {
+	let counting = Arc::new(Counting::default());
+	let filterer = CombinedFilterer::builder(origin().await)
+		.ignores([("*.log".into(), None)])
+		.with_filterer(counting.clone())
+		.build()
+		.await
+		.unwrap();
+
+	filterer.file_doesnt_pass("debug.log");
+	assert_eq!(
+		counting.0.load(Ordering::SeqCst),
+		0,
+		"extra filterers should not see ignored events"
+	);
+
+	filterer.file_does_pass("main.rs");
+	assert_eq!(counting.0.load(Ordering::SeqCst), 1);
+
+	let filterer = CombinedFilterer::builder(origin().await)
+		.with_filterer(Reject)
+		.build()
+		.await
+		.unwrap();
+	filterer.file_doesnt_pass("main.rs");
+}
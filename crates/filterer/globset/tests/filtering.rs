@@ -719,3 +719,151 @@ async fn whitelist_overrides_ignore_files_nested() {
 	filterer.file_doesnt_pass("prunes/nested/target");
 	filterer.dir_doesnt_pass("prunes/nested/target");
 }
+
+#[tokio::test]
+async fn from_compiled_matches_from_strings() {
+	use ignore::gitignore::GitignoreBuilder;
+	use watchexec_events::FileType;
+	use watchexec_filterer_globset::GlobsetFilterer;
+
+	let origin = tokio::fs::canonicalize(".").await.unwrap();
+	let compile = |globs: &[&str]| {
+		let mut builder = GitignoreBuilder::new(&origin);
+		for glob in globs {
+			builder.add_line(None, glob).unwrap();
+		}
+		builder.build().unwrap()
+	};
+
+	let filters = ["*.rs", "Cargo.toml", "!main.rs"];
+	let ignores = ["target/", "*.bak.rs"];
+
+	let from_strings = filt(&filters, &ignores, &[], &["toml", "rs"], &[]).await;
+	let from_compiled = GlobsetFilterer::from_compiled(
+		&origin,
+		compile(&filters),
+		compile(&ignores),
+		Vec::new(),
+		Vec::new(),
+		["toml", "rs"].map(Into::into),
+	)
+	.await
+	.unwrap();
+
+	let origin = &origin;
+	for path in [
+		"lib.rs",
+		"src/lib.rs",
+		"main.rs",
+		"Cargo.toml",
+		"Cargo.json",
+		"backup.bak.rs",
+		"target",
+		"target/lib.rs",
+		"README.md",
+	] {
+		for file_type in [Some(FileType::File), Some(FileType::Dir), None] {
+			assert_eq!(
+				from_compiled
+					.check_path(origin.join(path), file_type)
+					.unwrap(),
+				from_strings
+					.check_path(origin.join(path), file_type)
+					.unwrap(),
+				"{path:?} ({file_type:?})"
+			);
+		}
+	}
+
+	from_compiled.file_does_pass("src/lib.rs");
+	from_compiled.file_doesnt_pass("README.md");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn non_utf8_filenames() {
+	use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+	use watchexec_events::FileType;
+
+	let origin = std::fs::canonicalize(".").unwrap();
+	let path = |bytes: &[u8]| origin.join(OsStr::from_bytes(bytes));
+
+	let filterer = filt(&["caf*.rs"], &[], &[], &[], &[]).await;
+	assert!(filterer
+		.check_path(path(b"caf\xe9.rs"), Some(FileType::File))
+		.unwrap());
+	assert!(!filterer
+		.check_path(path(b"caf\xe9.txt"), Some(FileType::File))
+		.unwrap());
+
+	let filterer = filt(&[], &["*.bak"], &[], &[], &[]).await;
+	assert!(!filterer
+		.check_path(path(b"\xff\xfe.bak"), Some(FileType::File))
+		.unwrap());
+	assert!(filterer
+		.check_path(path(b"\xff\xfe.rs"), Some(FileType::File))
+		.unwrap());
+
+	let filterer = filt(&[], &[], &[], &["rs"], &[]).await;
+	assert!(filterer
+		.check_path(path(b"sub\xe9/lib.rs"), Some(FileType::File))
+		.unwrap());
+	assert!(!filterer
+		.check_path(path(b"sub\xe9/lib.md"), Some(FileType::File))
+		.unwrap());
+}
+
+#[tokio::test]
+async fn case_insensitive_paths() {
+	use watchexec_filterer_globset::GlobsetFilterer;
+
+	async fn filt_case(
+		filters: &[&str],
+		ignores: &[&str],
+		extensions: &[&str],
+		case_insensitive: bool,
+	) -> Result<GlobsetFilterer, ignore_files::Error> {
+		GlobsetFilterer::new_with_case(
+			tokio::fs::canonicalize(".").await.unwrap(),
+			filters.iter().map(|s| ((*s).to_string(), None)),
+			ignores.iter().map(|s| ((*s).to_string(), None)),
+			Vec::new(),
+			Vec::new(),
+			extensions.iter().map(Into::into),
+			case_insensitive,
+		)
+		.await
+	}
+
+	let ignores = ["Build/", "Build/**"];
+	let insensitive = filt_case(&[], &ignores, &[], true).await.unwrap();
+	insensitive.dir_doesnt_pass("build");
+	insensitive.file_doesnt_pass("build/file");
+	insensitive.file_doesnt_pass("BUILD/file");
+	insensitive.file_does_pass("builds/file");
+
+	let sensitive = filt_case(&[], &ignores, &[], false).await.unwrap();
+	sensitive.dir_does_pass("build");
+	sensitive.file_does_pass("build/file");
+	sensitive.file_doesnt_pass("Build/file");
+
+	let filters = ["*.toml", "src/**"];
+	let insensitive = filt_case(&filters, &[], &[], true).await.unwrap();
+	insensitive.file_does_pass("Cargo.TOML");
+	insensitive.file_does_pass("SRC/thing.txt");
+	insensitive.file_doesnt_pass("Cargo.lock");
+
+	let sensitive = filt_case(&filters, &[], &[], false).await.unwrap();
+	sensitive.file_does_pass("Cargo.toml");
+	sensitive.file_doesnt_pass("Cargo.TOML");
+	sensitive.file_doesnt_pass("SRC/thing.txt");
+
+	let insensitive = filt_case(&[], &[], &["RS"], true).await.unwrap();
+	insensitive.file_does_pass("lib.rs");
+	insensitive.file_does_pass("lib.Rs");
+	insensitive.file_doesnt_pass("lib.md");
+
+	let sensitive = filt_case(&[], &[], &["RS"], false).await.unwrap();
+	sensitive.file_does_pass("lib.RS");
+	sensitive.file_doesnt_pass("lib.rs");
+}
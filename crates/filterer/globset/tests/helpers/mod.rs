@@ -6,7 +6,7 @@ use std::{
 use ignore_files::IgnoreFile;
 use watchexec::{error::RuntimeError, filter::Filterer};
 use watchexec_events::{Event, FileType, Priority, Tag};
-use watchexec_filterer_globset::GlobsetFilterer;
+use watchexec_filterer_globset::{CombinedFilterer, GlobsetFilterer};
 use watchexec_filterer_ignore::IgnoreFilterer;
 
 pub mod globset {
@@ -82,6 +82,7 @@ pub trait PathHarness: Filterer {
 	}
 }
 
+impl PathHarness for CombinedFilterer {}
 impl PathHarness for GlobsetFilterer {}
 impl PathHarness for IgnoreFilterer {}
 
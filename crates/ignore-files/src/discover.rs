@@ -10,7 +10,7 @@ use gix_config::{path::interpolate::Context as InterpolateContext, File, Path as
 use miette::{bail, Result};
 use normalize_path::NormalizePath;
 use project_origins::ProjectType;
-use tokio::fs::{canonicalize, metadata, read_dir};
+use tokio::fs::{canonicalize, metadata, read_dir, read_to_string};
 use tracing::{trace, trace_span};
 
 use crate::{IgnoreFile, IgnoreFilter};
@@ -115,9 +115,10 @@ impl From<&Path> for IgnoreFilesFromOriginArgs {
 /// This considers:
 /// - Git ignore files (`.gitignore`)
 /// - Mercurial ignore files (`.hgignore`)
-/// - Tool-generic `.ignore` files
+/// - Tool-generic `.ignore` and `.rgignore` files
 /// - `.git/info/exclude` files in the `path` directory only
 /// - Git configurable project ignore files (with `core.excludesFile` in `.git/config`)
+/// - Mercurial configurable project ignore files (with `ui.ignore` in `.hg/hgrc`)
 ///
 /// Importantly, this should be called from the origin of the project, not a subfolder. This
 /// function will not discover the project origin, and will not traverse parent directories. Use the
@@ -137,6 +138,21 @@ impl From<&Path> for IgnoreFilesFromOriginArgs {
 /// This is the only case in which the `applies_in` field is None from this function. When such is
 /// received the global Git ignore files found by [`from_environment()`] **should be ignored**.
 ///
+/// ## Precedence
+///
+/// As with the [`ignore`] crate and ripgrep, ignore files in deeper directories override those in
+/// shallower ones, and within a directory, `.rgignore` overrides `.ignore`, which overrides VCS
+/// ignore files like `.gitignore`. The files are returned in that order, from lowest precedence
+/// to highest, so adding them to an [`IgnoreFilter`](crate::IgnoreFilter) in order gives that
+/// result. The generic files aren't tied to any VCS: their `applies_to` is `None`.
+///
+/// ## Mercurial config
+///
+/// The `ui.ignore` and `ui.ignore.*` settings in the project's `.hg/hgrc` are ignore files which
+/// apply to the whole project, in addition to `.hgignore` files. As in Mercurial, relative paths
+/// are resolved from the origin, and `~` and environment variables are expanded. These are
+/// returned with `applies_in` set to the origin.
+///
 /// ## Async
 ///
 /// This future is not `Send` due to [`gix_config`] internals.
@@ -200,6 +216,17 @@ pub async fn from_origin(
 		},
 	}
 
+	for path in hgrc_ignores(&[origin.join(".hg/hgrc")], Some(origin), &mut errors).await {
+		discover_file(
+			&mut ignore_files,
+			&mut errors,
+			Some(origin.clone()),
+			Some(ProjectType::Mercurial),
+			path,
+		)
+		.await;
+	}
+
 	discover_file(
 		&mut ignore_files,
 		&mut errors,
@@ -244,13 +271,16 @@ pub async fn from_origin(
 					Visit::Done => break,
 					Visit::Skip => continue,
 					Visit::Find(dir) => {
-						// Attempt to find a .ignore file in the directory
+						// Files found later in a directory take precedence over earlier ones, so
+						// tool-generic files go last, with .rgignore over .ignore, as in ripgrep.
+
+						// Attempt to find a .gitignore file in the directory
 						if discover_file(
 							&mut ignore_files,
 							&mut errors,
 							Some(dir.clone()),
-							None,
-							dir.join(".ignore"),
+							Some(ProjectType::Git),
+							dir.join(".gitignore"),
 						)
 						.await
 						{
@@ -258,13 +288,13 @@ pub async fn from_origin(
 								.await;
 						}
 
-						// Attempt to find a .gitignore file in the directory
+						// Attempt to find a .hgignore file in the directory
 						if discover_file(
 							&mut ignore_files,
 							&mut errors,
 							Some(dir.clone()),
-							Some(ProjectType::Git),
-							dir.join(".gitignore"),
+							Some(ProjectType::Mercurial),
+							dir.join(".hgignore"),
 						)
 						.await
 						{
@@ -272,13 +302,27 @@ pub async fn from_origin(
 								.await;
 						}
 
-						// Attempt to find a .hgignore file in the directory
+						// Attempt to find a .ignore file in the directory
 						if discover_file(
 							&mut ignore_files,
 							&mut errors,
 							Some(dir.clone()),
-							Some(ProjectType::Mercurial),
-							dir.join(".hgignore"),
+							None,
+							dir.join(".ignore"),
+						)
+						.await
+						{
+							dirs.add_last_file_to_filter(&ignore_files, &mut errors)
+								.await;
+						}
+
+						// Attempt to find a .rgignore file in the directory
+						if discover_file(
+							&mut ignore_files,
+							&mut errors,
+							Some(dir.clone()),
+							None,
+							dir.join(".rgignore"),
 						)
 						.await
 						{
@@ -305,8 +349,22 @@ pub async fn from_origin(
 /// This considers:
 /// - User-specific git ignore files (e.g. `~/.gitignore`)
 /// - Git configurable ignore files (e.g. with `core.excludesFile` in system or user config)
+/// - Mercurial configurable ignore files (with `ui.ignore` in system or user config)
+/// - User-specific Bazaar and Breezy ignore files (e.g. `~/.bazaar/ignore`)
 /// - `$XDG_CONFIG_HOME/{appname}/ignore`, as well as other locations (APPDATA on Windows…)
 ///
+/// Mercurial config files are found as Mercurial does: from the `HGRCPATH` environment variable if
+/// it's set (files, or folders of `*.rc` files), otherwise from the system config in
+/// `/etc/mercurial` and the user config in `~/.hgrc` and `$XDG_CONFIG_HOME/hg/hgrc` (or
+/// `%USERPROFILE%\.hgrc` and `%USERPROFILE%\Mercurial.ini` on Windows). All the `ui.ignore` and
+/// `ui.ignore.*` settings are used, with later files overriding earlier ones for the same setting.
+/// Relative paths are resolved from the folder of the config file they're set in.
+///
+/// For Bazaar, the first ignore file found is used, looking at the Breezy config folder
+/// (`$BRZ_HOME`, `$XDG_CONFIG_HOME/breezy`, `%APPDATA%\breezy`, or `~/.config/breezy`), then
+/// the Bazaar config folder (`%APPDATA%\bazaar\2.0`, `$XDG_CONFIG_HOME/bazaar`,
+/// `$BZR_HOME/.bazaar`, or `~/.bazaar`).
+///
 /// All errors (permissions, etc) are collected and returned alongside the ignore files: you may
 /// want to show them to the user while still using whatever ignores were successfully found. Errors
 /// from files not being found are silently ignored (the files are just not returned).
@@ -379,12 +437,41 @@ pub async fn from_environment(appname: Option<&str>) -> (Vec<IgnoreFile>, Vec<Er
 		}
 	}
 
-	let mut bzrs = Vec::with_capacity(5);
+	for path in hgrc_ignores(&hgrc_paths().await, None, &mut errors).await {
+		discover_file(
+			&mut files,
+			&mut errors,
+			None,
+			Some(ProjectType::Mercurial),
+			path,
+		)
+		.await;
+	}
+
+	let mut bzrs = Vec::with_capacity(8);
+	if let Ok(home) = env::var("BRZ_HOME") {
+		bzrs.push(Path::new(&home).join("ignore"));
+	}
+	if let Ok(home) = env::var("XDG_CONFIG_HOME") {
+		bzrs.push(Path::new(&home).join("breezy/ignore"));
+	}
 	if let Ok(home) = env::var("APPDATA") {
-		bzrs.push(Path::new(&home).join("Bazzar/2.0/ignore"));
+		bzrs.push(Path::new(&home).join("breezy/ignore"));
 	}
 	if let Ok(home) = env::var("HOME") {
-		bzrs.push(Path::new(&home).join(".bazarr/ignore"));
+		bzrs.push(Path::new(&home).join(".config/breezy/ignore"));
+	}
+	if let Ok(home) = env::var("APPDATA") {
+		bzrs.push(Path::new(&home).join("bazaar/2.0/ignore"));
+	}
+	if let Ok(home) = env::var("XDG_CONFIG_HOME") {
+		bzrs.push(Path::new(&home).join("bazaar/ignore"));
+	}
+	if let Ok(home) = env::var("BZR_HOME") {
+		bzrs.push(Path::new(&home).join(".bazaar/ignore"));
+	}
+	if let Ok(home) = env::var("HOME") {
+		bzrs.push(Path::new(&home).join(".bazaar/ignore"));
 	}
 
 	for path in bzrs {
@@ -463,6 +550,174 @@ pub async fn discover_file(
 	}
 }
 
+/// The Mercurial system and user config files, in the order Mercurial reads them.
+async fn hgrc_paths() -> Vec<PathBuf> {
+	let mut paths = Vec::new();
+	if let Some(list) = env::var_os("HGRCPATH") {
+		for entry in env::split_paths(&list) {
+			if entry.is_dir() {
+				paths.extend(rc_files_in(&entry).await);
+			} else {
+				paths.push(entry);
+			}
+		}
+		return paths;
+	}
+
+	if cfg!(windows) {
+		if let Ok(home) = env::var("PROGRAMDATA") {
+			paths.push(Path::new(&home).join("Mercurial/hgrc"));
+		}
+		for var in ["HOME", "USERPROFILE"] {
+			if let Ok(home) = env::var(var) {
+				paths.push(Path::new(&home).join(".hgrc"));
+				paths.push(Path::new(&home).join("Mercurial.ini"));
+			}
+		}
+	} else {
+		paths.push(PathBuf::from("/etc/mercurial/hgrc"));
+		paths.extend(rc_files_in(Path::new("/etc/mercurial/hgrc.d")).await);
+		if let Ok(home) = env::var("HOME") {
+			paths.push(Path::new(&home).join(".hgrc"));
+		}
+		if let Ok(config) = env::var("XDG_CONFIG_HOME") {
+			paths.push(Path::new(&config).join("hg/hgrc"));
+		} else if let Ok(home) = env::var("HOME") {
+			paths.push(Path::new(&home).join(".config/hg/hgrc"));
+		}
+	}
+
+	paths
+}
+
+/// The `*.rc` files in a folder, in name order.
+async fn rc_files_in(dir: &Path) -> Vec<PathBuf> {
+	let mut files = Vec::new();
+	if let Ok(mut entries) = read_dir(dir).await {
+		while let Ok(Some(entry)) = entries.next_entry().await {
+			let path = entry.path();
+			if path.extension().map_or(false, |ext| ext == "rc") {
+				files.push(path);
+			}
+		}
+	}
+	files.sort();
+	files
+}
+
+/// Read the ignore files set with `ui.ignore` and `ui.ignore.*` in Mercurial config files.
+///
+/// Files are read in order, and later settings override earlier ones with the same name, as in
+/// Mercurial. `%unset` is supported, but `%include` isn't followed. Relative paths are resolved
+/// from `root` if given, otherwise from the folder of the config file.
+async fn hgrc_ignores(
+	config_files: &[PathBuf],
+	root: Option<&Path>,
+	errors: &mut Vec<Error>,
+) -> Vec<PathBuf> {
+	let mut settings: Vec<(String, PathBuf)> = Vec::new();
+	for config in config_files {
+		let content = match read_to_string(config).await {
+			Ok(content) => content,
+			Err(err) if err.kind() == ErrorKind::NotFound => continue,
+			Err(err) => {
+				errors.push(err);
+				continue;
+			}
+		};
+
+		let base = root
+			.or_else(|| config.parent())
+			.unwrap_or_else(|| Path::new(""));
+		let mut section = "";
+		for line in content.lines() {
+			// continuation lines and blank lines
+			if line.starts_with(char::is_whitespace) || line.trim().is_empty() {
+				continue;
+			}
+
+			let line = line.trim_end();
+			if line.starts_with('#') || line.starts_with(';') {
+				continue;
+			}
+
+			if let Some(rest) = line.strip_prefix('[') {
+				if let Some(end) = rest.find(']') {
+					section = rest[..end].trim();
+				}
+				continue;
+			}
+
+			if section != "ui" {
+				continue;
+			}
+
+			if let Some(key) = line.strip_prefix("%unset") {
+				let key = key.trim();
+				settings.retain(|(name, _)| name != key);
+				continue;
+			}
+
+			let (key, value) = match line.split_once('=') {
+				Some((key, value)) => (key.trim(), value),
+				None => continue,
+			};
+			if key != "ignore" && !key.starts_with("ignore.") {
+				continue;
+			}
+
+			settings.retain(|(name, _)| name != key);
+			let value = value.trim();
+			if !value.is_empty() {
+				trace!(?config, %key, %value, "found mercurial ignore setting");
+				settings.push((key.to_owned(), base.join(expand_hg_path(value))));
+			}
+		}
+	}
+
+	settings.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Expand environment variables and a leading `~` in a path from a Mercurial config file.
+fn expand_hg_path(value: &str) -> PathBuf {
+	let mut expanded = String::with_capacity(value.len());
+	let mut rest = value;
+	while let Some(start) = rest.find('$') {
+		expanded.push_str(&rest[..start]);
+		let after = &rest[start + 1..];
+		let (name, remainder) = if let Some(braced) = after.strip_prefix('{') {
+			match braced.find('}') {
+				Some(end) => (&braced[..end], &braced[end + 1..]),
+				None => ("", after),
+			}
+		} else {
+			let end = after
+				.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+				.unwrap_or(after.len());
+			(&after[..end], &after[end..])
+		};
+
+		match env::var(name) {
+			Ok(var) if !name.is_empty() => expanded.push_str(&var),
+			// unknown variables are left as they are
+			_ => expanded.push_str(&rest[start..rest.len() - remainder.len()]),
+		}
+		rest = remainder;
+	}
+	expanded.push_str(rest);
+
+	if let Some(path) = expanded
+		.strip_prefix("~/")
+		.or_else(|| expanded.strip_prefix("~\\"))
+	{
+		if let Ok(home) = env::var("HOME").or_else(|_| env::var("USERPROFILE")) {
+			return Path::new(&home).join(path);
+		}
+	}
+
+	PathBuf::from(expanded)
+}
+
 async fn find_file(path: PathBuf) -> Result<Option<PathBuf>, Error> {
 	match metadata(&path).await {
 		Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
@@ -1,7 +1,7 @@
 use std::fmt;
 use std::path::{Path, PathBuf};
 
-use futures::stream::{FuturesUnordered, StreamExt};
+use futures::stream::{FuturesOrdered, StreamExt};
 use ignore::{
 	gitignore::{Gitignore, GitignoreBuilder, Glob},
 	Match,
@@ -76,6 +76,7 @@ impl IgnoreFilter {
 		let _span = trace_span!("build_filterer", ?origin);
 
 		trace!(files=%files.len(), "loading file contents");
+		// kept in order, as later files take precedence
 		let (files_contents, errors): (Vec<_>, Vec<_>) = files
 			.iter()
 			.map(|file| async move {
@@ -88,7 +89,7 @@ impl IgnoreFilter {
 					})?;
 				Ok((file.clone(), content))
 			})
-			.collect::<FuturesUnordered<_>>()
+			.collect::<FuturesOrdered<_>>()
 			.collect::<Vec<_>>()
 			.await
 			.into_iter()
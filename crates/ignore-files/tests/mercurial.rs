@@ -0,0 +1,62 @@
+use std::path::PathBuf;
+
+use ignore_files::{from_environment, from_origin, IgnoreFile};
+use project_origins::ProjectType;
+
+mod helpers;
+
+use helpers::ignore_tests::*;
+
+fn fixture(path: &str) -> PathBuf {
+	dunce::canonicalize(".").unwrap().join("tests").join(path)
+}
+
+fn mercurial_files(files: &[IgnoreFile]) -> Vec<PathBuf> {
+	files
+		.iter()
+		.filter(|file| file.applies_to == Some(ProjectType::Mercurial))
+		.map(|file| file.path.clone())
+		.collect()
+}
+
+#[tokio::test]
+async fn project_hgignore_and_hgrc() {
+	let origin = fixture("hg");
+	let (files, errors) = from_origin(origin.as_path()).await;
+	assert!(errors.is_empty(), "{errors:?}");
+
+	let mut found = mercurial_files(&files);
+	found.sort();
+	assert_eq!(
+		found,
+		vec![origin.join(".hgignore"), origin.join("local-ignore")],
+		"should find .hgignore and ui.ignore, but not unset or other sections"
+	);
+	assert!(files
+		.iter()
+		.filter(|file| file.applies_to == Some(ProjectType::Mercurial))
+		.all(|file| file.applies_in.as_deref() == Some(origin.as_path())));
+
+	let filter = filt("hg", &files).await;
+	filter.agnostic_fail("hg/main.rs.orig");
+	filter.agnostic_fail("hg/nested/file.orig");
+	filter.dir_doesnt_pass("hg/scratch");
+	filter.agnostic_pass("hg/main.rs");
+}
+
+#[tokio::test]
+async fn global_hgrc() {
+	std::env::set_var("HGRCPATH", fixture("hg-global/hgrc"));
+	let (files, _) = from_environment(None).await;
+
+	let global = IgnoreFile {
+		applies_to: Some(ProjectType::Mercurial),
+		..file("hg-global/global-ignore").applies_globally()
+	};
+	assert_eq!(mercurial_files(&files), vec![global.path.clone()]);
+	assert!(files.contains(&global), "should apply globally");
+
+	let filter = filt("hg", &files).await;
+	filter.agnostic_fail("hg/main.rs.rej");
+	filter.agnostic_pass("hg/main.rs");
+}
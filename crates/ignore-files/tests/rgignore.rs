@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+use ignore_files::from_origin;
+
+mod helpers;
+
+use helpers::ignore_tests::*;
+
+fn fixture(path: &str) -> PathBuf {
+	dunce::canonicalize(".").unwrap().join("tests").join(path)
+}
+
+#[tokio::test]
+async fn finds_ignore_and_rgignore() {
+	let origin = fixture("rg");
+	let (files, errors) = from_origin(origin.as_path()).await;
+	assert!(errors.is_empty(), "{errors:?}");
+
+	let found: Vec<PathBuf> = files.iter().map(|file| file.path.clone()).collect();
+	assert_eq!(
+		found,
+		vec![
+			origin.join(".ignore"),
+			origin.join(".rgignore"),
+			origin.join("nested/.ignore"),
+		],
+		"should find generic ignore files from lowest to highest precedence"
+	);
+	assert!(files.iter().all(|file| file.applies_to.is_none()));
+}
+
+#[tokio::test]
+async fn ignore_entries_filter() {
+	let (files, _) = from_origin(fixture("rg").as_path()).await;
+	let filter = filt("rg", &files).await;
+
+	filter.agnostic_fail("rg/build.tmp");
+	filter.agnostic_fail("rg/debug.log");
+	filter.agnostic_pass("rg/main.rs");
+}
+
+#[tokio::test]
+async fn generic_ignore_applies_globally() {
+	let filter = filt("", &[file("rg/.ignore").applies_globally()]).await;
+
+	filter.agnostic_fail("rg/build.tmp");
+	filter.agnostic_fail("tree/debug.log");
+}
+
+#[tokio::test]
+async fn local_files_take_precedence() {
+	let (files, _) = from_origin(fixture("rg").as_path()).await;
+	let filter = filt("rg", &files).await;
+
+	// .rgignore overrides .ignore in the same directory
+	filter.agnostic_pass("rg/keep.log");
+
+	// a deeper .ignore overrides shallower files
+	filter.agnostic_pass("rg/nested/scratch.tmp");
+	filter.agnostic_fail("rg/nested/debug.log");
+}
@@ -29,6 +29,7 @@ async fn main() -> Result<()> {
 						format!("Current time: {:?}", Instant::now()),
 						"Press Ctrl+C to quit".into(),
 					],
+					argv0: None,
 				},
 				options: Default::default(),
 			}));
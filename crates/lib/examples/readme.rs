@@ -5,7 +5,7 @@ use std::{
 
 use miette::{IntoDiagnostic, Result};
 use watchexec::{
-	command::{Command, Program, Shell},
+	command::{Command, Program, Shell, ShellInput},
 	job::CommandState,
 	Watchexec,
 };
@@ -35,6 +35,7 @@ async fn main() -> Result<()> {
 					"
 					.into(),
 					args: Vec::new(),
+					via: ShellInput::Arg,
 				},
 				options: Default::default(),
 			}));
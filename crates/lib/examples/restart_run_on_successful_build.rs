@@ -26,6 +26,7 @@ async fn main() -> Result<()> {
 					program: Program::Exec {
 						prog: "cargo".into(),
 						args: vec!["build".into()],
+						argv0: None,
 					},
 					options: Default::default(),
 				})
@@ -36,6 +37,7 @@ async fn main() -> Result<()> {
 					program: Program::Exec {
 						prog: "cargo".into(),
 						args: vec!["run".into()],
+						argv0: None,
 					},
 					options: SpawnOptions {
 						grouped: true,
@@ -1,15 +1,32 @@
 //! Processor responsible for receiving events, filtering them, and scheduling actions in response.
 
+#[doc(inline)]
+pub use concurrency::ConcurrencyPolicy;
+#[doc(inline)]
+pub use executor::ActionTask;
 #[doc(inline)]
 pub use handler::Handler as ActionHandler;
 #[doc(inline)]
-pub use quit::QuitManner;
+pub use last_run::LastRun;
+#[doc(inline)]
+pub use quit::{QuitManner, QuitReturn};
 #[doc(inline)]
 pub use r#return::ActionReturn;
 #[doc(inline)]
+pub use spawn::{PostSpawnReturn, PreSpawnReturn, SpawnContext};
+#[doc(inline)]
+pub use throttle::{AdaptiveThrottle, ThrottleMode};
+#[doc(inline)]
 pub use worker::worker;
+pub(crate) use worker::worker_publishing_jobs;
 
+mod concurrency;
+mod executor;
 mod handler;
+mod last_run;
 mod quit;
 mod r#return;
+mod spawn;
+mod throttle;
+mod throttle_state;
 mod worker;
@@ -0,0 +1,179 @@
+use std::{
+	collections::VecDeque,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use tokio::sync::oneshot;
+use tracing::trace;
+
+use crate::{
+	action::{AdaptiveThrottle, LastRun},
+	changeable::Changeable,
+	Config,
+};
+
+/// What to do when starting a command would go over the limit of concurrent jobs.
+///
+/// The limit is set with
+/// [`Config::max_concurrent_jobs()`](crate::Config::max_concurrent_jobs()); this sets what happens
+/// to the commands which would go over it.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ConcurrencyPolicy {
+	/// Wait for a running command to end before starting.
+	///
+	/// Waiting commands start in the order they were started in, as running commands end. While a
+	/// command waits, its job doesn't process other controls, so stopping it takes effect once it
+	/// has started.
+	///
+	/// This is the default.
+	#[default]
+	Queue,
+
+	/// Don't start the command.
+	///
+	/// The start is cancelled as if the pre-spawn hook had returned an error: the job's error
+	/// handler is called, but the Watchexec error hook isn't.
+	Skip,
+}
+
+/// The running commands, and those waiting to start under the limit of concurrent jobs.
+///
+/// Every command takes a slot while it's spawned and running, whether there's a limit or not, so
+/// this also knows whether commands are running, see [`Config::ignore_command_outputs`],
+/// [`Config::ignore_events_during_run`], and [`Config::idle_timeout`], how long they ran for, see
+/// [`Config::adaptive_throttle`], and how the last one ended, see [`LastRun`].
+#[derive(Debug)]
+pub(crate) struct JobSlots {
+	max: Changeable<Option<usize>>,
+	policy: Changeable<ConcurrencyPolicy>,
+	state: Mutex<SlotsState>,
+}
+
+#[derive(Debug, Default)]
+struct SlotsState {
+	running: usize,
+	queue: VecDeque<oneshot::Sender<()>>,
+	last_ended: Option<Instant>,
+	recent_runtimes: VecDeque<Duration>,
+	last_run: Option<LastRun>,
+}
+
+impl JobSlots {
+	pub fn new(config: &Config) -> Self {
+		Self {
+			max: config.max_concurrent_jobs.clone(),
+			policy: config.concurrency_policy.clone(),
+			state: Mutex::default(),
+		}
+	}
+
+	/// The current limit and policy, if there's a limit.
+	pub fn limit(&self) -> Option<(usize, ConcurrencyPolicy)> {
+		self.max.get().map(|max| (max.max(1), self.policy.get()))
+	}
+
+	/// Take a slot without any limit.
+	pub fn take(self: &Arc<Self>) -> JobSlot {
+		let mut state = self.state.lock().expect("job slots lock poisoned");
+		state.running += 1;
+		trace!(running=%state.running, "took job slot");
+		JobSlot(self.clone(), Instant::now())
+	}
+
+	/// Whether a command is running, or one ended less than `window` ago.
+	pub fn busy_within(&self, window: Duration) -> bool {
+		let state = self.state.lock().expect("job slots lock poisoned");
+		state.running > 0 || state.last_ended.map_or(false, |at| at.elapsed() < window)
+	}
+
+	/// When things went idle, if no command is running: `since` or when the last command ended,
+	/// whichever is later.
+	pub fn idle_since(&self, since: Instant) -> Option<Instant> {
+		let state = self.state.lock().expect("job slots lock poisoned");
+		(state.running == 0).then(|| state.last_ended.map_or(since, |at| at.max(since)))
+	}
+
+	/// The average runtime of the last commands to end, if any have.
+	pub fn average_runtime(&self) -> Option<Duration> {
+		let state = self.state.lock().expect("job slots lock poisoned");
+		let count = u32::try_from(state.recent_runtimes.len()).ok()?;
+		(count > 0).then(|| state.recent_runtimes.iter().sum::<Duration>() / count)
+	}
+
+	/// Record how a command ended.
+	pub fn record(&self, run: LastRun) {
+		let mut state = self.state.lock().expect("job slots lock poisoned");
+		trace!(?run, "recorded command end");
+		state.last_run = Some(run);
+	}
+
+	/// How the last command to end went, if any has.
+	pub fn last_run(&self) -> Option<LastRun> {
+		self.state.lock().expect("job slots lock poisoned").last_run
+	}
+
+	/// Take a slot if there's one free under `max`.
+	pub fn try_acquire(self: &Arc<Self>, max: usize) -> Option<JobSlot> {
+		let mut state = self.state.lock().expect("job slots lock poisoned");
+		if state.running < max {
+			state.running += 1;
+			trace!(running=%state.running, %max, "took job slot");
+			Some(JobSlot(self.clone(), Instant::now()))
+		} else {
+			None
+		}
+	}
+
+	/// Take a slot, waiting in line for one to be freed if there are none free under `max`.
+	pub async fn acquire(self: &Arc<Self>, max: usize) -> JobSlot {
+		let waiting = {
+			let mut state = self.state.lock().expect("job slots lock poisoned");
+			if state.running < max && state.queue.is_empty() {
+				state.running += 1;
+				trace!(running=%state.running, %max, "took job slot");
+				return JobSlot(self.clone(), Instant::now());
+			}
+
+			let (s, r) = oneshot::channel();
+			state.queue.push_back(s);
+			trace!(waiting=%state.queue.len(), %max, "waiting for a job slot");
+			r
+		};
+
+		// the sender is only dropped once the slot has been handed over
+		waiting.await.ok();
+		JobSlot(self.clone(), Instant::now())
+	}
+
+	fn release(&self, started: Instant) {
+		let mut state = self.state.lock().expect("job slots lock poisoned");
+		let now = Instant::now();
+		state.last_ended = Some(now);
+		if state.recent_runtimes.len() >= AdaptiveThrottle::RECENT_RUNS {
+			state.recent_runtimes.pop_front();
+		}
+		state.recent_runtimes.push_back(now - started);
+		while let Some(next) = state.queue.pop_front() {
+			if next.send(()).is_ok() {
+				trace!("handed job slot over to next in line");
+				return;
+			}
+		}
+
+		state.running -= 1;
+		trace!(running=%state.running, "freed job slot");
+	}
+}
+
+/// A slot for a running command, which is freed when dropped.
+///
+/// This also holds when the slot was taken, to know how long the command ran for.
+#[derive(Debug)]
+pub(crate) struct JobSlot(Arc<JobSlots>, Instant);
+
+impl Drop for JobSlot {
+	fn drop(&mut self) {
+		self.0.release(self.1);
+	}
+}
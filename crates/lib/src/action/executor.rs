@@ -0,0 +1,6 @@
+use std::{future::Future, pin::Pin};
+
+/// A task run by an action handler with [`run_task()`](super::ActionHandler::run_task()).
+///
+/// This is what the [action executor](crate::Config::action_executor) is given to spawn.
+pub type ActionTask = Pin<Box<dyn Future<Output = ()> + Send>>;
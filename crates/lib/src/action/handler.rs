@@ -1,15 +1,33 @@
-use std::{collections::HashMap, path::Path, sync::Arc, time::Duration};
-use tokio::task::JoinHandle;
-use watchexec_events::{Event, FileType, ProcessEnd};
+use std::{
+	collections::HashMap,
+	future::Future,
+	panic::AssertUnwindSafe,
+	path::{Component, Path, PathBuf},
+	pin::Pin,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+
+use futures::FutureExt;
+use tokio::{
+	sync::{mpsc, oneshot},
+	task::JoinHandle,
+};
+use tracing::debug;
+use watchexec_events::{common_ancestor, Event, FileType, ProcessEnd, Source, Tag};
 use watchexec_signals::Signal;
 use watchexec_supervisor::{
 	command::Command,
-	job::{start_job, Job},
+	job::{start_job, CommandState, Job},
 };
 
-use crate::id::Id;
+use crate::{changeable::ChangeableFn, error::RuntimeError, id::Id, Config};
 
-use super::QuitManner;
+use super::{
+	concurrency::{JobSlot, JobSlots},
+	ActionTask, ConcurrencyPolicy, LastRun, PostSpawnReturn, PreSpawnReturn, QuitManner,
+	SpawnContext,
+};
 
 /// The environment given to the action handler.
 ///
@@ -54,16 +72,42 @@ pub struct Handler {
 	pub events: Arc<[Event]>,
 	extant: HashMap<Id, Job>,
 	pub(crate) new: HashMap<Id, (Job, JoinHandle<()>)>,
+	pub(crate) tasks: Vec<JoinHandle<()>>,
 	pub(crate) quit: Option<QuitManner>,
+	paths_truncated: Option<usize>,
+	first_run: bool,
+	last_run: Option<LastRun>,
+	errors: mpsc::Sender<RuntimeError>,
+	pre_spawn: ChangeableFn<SpawnContext, PreSpawnReturn>,
+	post_spawn: ChangeableFn<SpawnContext, PostSpawnReturn>,
+	executor: ChangeableFn<ActionTask, JoinHandle<()>>,
+	slots: Arc<JobSlots>,
 }
 
 impl Handler {
-	pub(crate) fn new(events: Arc<[Event]>, jobs: HashMap<Id, Job>) -> Self {
+	pub(crate) fn new(
+		events: Arc<[Event]>,
+		jobs: HashMap<Id, Job>,
+		paths_truncated: Option<usize>,
+		first_run: bool,
+		errors: mpsc::Sender<RuntimeError>,
+		slots: Arc<JobSlots>,
+		config: &Config,
+	) -> Self {
 		Self {
 			events,
 			extant: jobs,
 			new: HashMap::new(),
+			tasks: Vec::new(),
 			quit: None,
+			paths_truncated,
+			first_run,
+			last_run: slots.last_run(),
+			errors,
+			pre_spawn: config.pre_spawn_handler.clone(),
+			post_spawn: config.post_spawn_handler.clone(),
+			executor: config.action_executor.clone(),
+			slots,
 		}
 	}
 
@@ -73,18 +117,100 @@ impl Handler {
 	/// `Action` (and thus in the Watchexec instance, when the action handler returns).
 	pub fn create_job(&mut self, command: Arc<Command>) -> (Id, Job) {
 		let id = Id::default();
-		let (job, task) = start_job(command);
-		self.new.insert(id, (job.clone(), task));
+		let job = self.create_job_with_id(id, command);
 		(id, job)
 	}
 
 	// exposing this is dangerous as it allows duplicate IDs which may leak jobs
 	fn create_job_with_id(&mut self, id: Id, command: Arc<Command>) -> Job {
 		let (job, task) = start_job(command);
+		self.set_spawn_hooks(id, &job);
 		self.new.insert(id, (job.clone(), task));
 		job
 	}
 
+	fn set_spawn_hooks(&self, id: Id, job: &Job) {
+		let pre_spawn = self.pre_spawn.clone();
+		let errors = self.errors.clone();
+		let slots = self.slots.clone();
+		// the slot of the command being spawned or running, see JobSlots
+		let held: Arc<Mutex<Option<JobSlot>>> = Arc::default();
+		let handle = job.clone();
+		job.set_pre_spawn_hook(move |context| {
+			let hook = pre_spawn.call(SpawnContext {
+				id,
+				command: context.command.clone(),
+				pid: None,
+			});
+			let errors = errors.clone();
+			let slots = slots.clone();
+			let limit = slots.limit();
+			let held = held.clone();
+			let job = handle.clone();
+			Box::new(async move {
+				// a previous command of this job was stopped, or failed to spawn
+				held.lock().expect("job slot lock poisoned").take();
+
+				if let Err(err) = Pin::from(hook).await {
+					let err = err.to_string();
+					errors
+						.send(RuntimeError::Handler {
+							ctx: "pre-spawn hook",
+							err: err.clone(),
+						})
+						.await
+						.ok();
+					return Err(std::io::Error::new(std::io::ErrorKind::Other, err));
+				}
+
+				let slot = match limit {
+					None => slots.take(),
+					Some((max, ConcurrencyPolicy::Queue)) => slots.acquire(max).await,
+					Some((max, ConcurrencyPolicy::Skip)) => {
+						if let Some(slot) = slots.try_acquire(max) {
+							slot
+						} else {
+							debug!(?id, %max, "too many jobs running, not starting");
+							return Err(std::io::Error::new(
+								std::io::ErrorKind::Other,
+								format!("already {max} jobs running, not starting"),
+							));
+						}
+					}
+				};
+				*held.lock().expect("job slot lock poisoned") = Some(slot);
+
+				// this runs after the spawn, to hold the slot until the command ends
+				job.run({
+					let job = job.clone();
+					move |context| {
+						let slot = held.lock().expect("job slot lock poisoned").take();
+						if let (Some(slot), true) = (slot, context.current.is_running()) {
+							tokio::spawn(async move {
+								job.to_wait().await;
+								if let Some(run) = finished_run(id, &job).await {
+									slots.record(run);
+								}
+								drop(slot);
+							});
+						}
+					}
+				});
+
+				Ok(())
+			})
+		});
+
+		let post_spawn = self.post_spawn.clone();
+		job.set_post_spawn_hook(move |context, pid| {
+			post_spawn.call(SpawnContext {
+				id,
+				command: context.command.clone(),
+				pid,
+			})
+		});
+	}
+
 	/// Get an existing job or create a new one given an Id.
 	///
 	/// This starts the [`Job`] immediately if one with the Id doesn't exist, and stores a copy of
@@ -111,6 +237,40 @@ impl Handler {
 		self.extant.iter().map(|(id, job)| (*id, job.clone()))
 	}
 
+	/// Run a future as a task tracked by Watchexec.
+	///
+	/// This is for when the reaction to events is some Rust code rather than (or as well as) a
+	/// command. The task is spawned immediately (with the
+	/// [action executor](crate::Config::action_executor), which may delay its start), and is
+	/// tracked by the Watchexec instance once the action handler returns:
+	///
+	/// - on a graceful quit (`quit_gracefully()`), Watchexec waits for all tasks to complete, after
+	///   jobs have been stopped, before its main task returns;
+	/// - on an immediate quit (`quit()`), or if Watchexec stops for any other reason, tasks which
+	///   are still running are cancelled: they're dropped at their next `.await` point.
+	///
+	/// If the task panics, the panic is caught and reported to the error hook as a
+	/// [`RuntimeError::Handler`].
+	pub fn run_task(&mut self, task: impl Future<Output = ()> + Send + 'static) {
+		let errors = self.errors.clone();
+		self.tasks.push(self.executor.call(Box::pin(async move {
+			if let Err(panic) = AssertUnwindSafe(task).catch_unwind().await {
+				let err = panic
+					.downcast_ref::<&str>()
+					.map(|msg| (*msg).to_owned())
+					.or_else(|| panic.downcast_ref::<String>().cloned())
+					.unwrap_or_else(|| "task panicked".into());
+				errors
+					.send(RuntimeError::Handler {
+						ctx: "running action task",
+						err,
+					})
+					.await
+					.ok();
+			}
+		})));
+	}
+
 	/// Shut down the Watchexec instance immediately.
 	///
 	/// This will kill and drop all jobs without waiting on processes, then quit.
@@ -145,6 +305,34 @@ impl Handler {
 		self.events.iter().flat_map(Event::signals)
 	}
 
+	/// Whether the event set is made only of signals.
+	///
+	/// This is `true` when there's at least one event, and every event carries a signal: the
+	/// action was triggered by signals arriving, and nothing else. Any other event, like a
+	/// filesystem change or keyboard input, makes it `false`, so a set with both signals and
+	/// filesystem events is neither signal-only nor without [filesystem
+	/// events](Self::has_filesystem_events).
+	#[must_use]
+	pub fn is_signal_only(&self) -> bool {
+		!self.events.is_empty()
+			&& self
+				.events
+				.iter()
+				.all(|event| event.signals().next().is_some())
+	}
+
+	/// Whether the event set has any filesystem events.
+	///
+	/// These are events from the filesystem [source](Source::Filesystem), or events with paths.
+	/// This says nothing about whether there are other events as well; see
+	/// [`is_signal_only()`](Self::is_signal_only) for the other side.
+	#[must_use]
+	pub fn has_filesystem_events(&self) -> bool {
+		self.events.iter().any(|event| {
+			event.tags.contains(&Tag::Source(Source::Filesystem)) || event.paths().next().is_some()
+		})
+	}
+
 	/// Convenience to get all paths in the event set.
 	///
 	/// An action contains a set of events, and some of those events might relate to watched
@@ -155,8 +343,114 @@ impl Handler {
 		self.events.iter().flat_map(Event::paths)
 	}
 
+	/// The longest path all paths in the event set have in common.
+	///
+	/// See [`watchexec_events::common_ancestor()`] for details; this is `None` if no event has a
+	/// path.
+	#[must_use]
+	pub fn common_path(&self) -> Option<PathBuf> {
+		common_ancestor(&self.events)
+	}
+
+	/// Group the events in the set by the directory their paths are in, cut at some depth.
+	///
+	/// The key for a path is its parent directory, truncated to `depth` components below the root
+	/// (or below the start, for relative paths): with a depth of 2, `/a/b/c/file` and `/a/b/file`
+	/// both go under `/a/b`, while `/a/file` goes under `/a`. Events with paths in several
+	/// directories appear in each of their groups, once per group, and events without paths don't
+	/// appear at all. Within a group, events are in the order they were received.
+	///
+	/// This doesn't change which events are given to the action, it's only a different view of
+	/// [`events`](Self::events).
+	#[must_use]
+	pub fn events_by_dir(&self, depth: usize) -> HashMap<PathBuf, Vec<&Event>> {
+		let mut groups: HashMap<PathBuf, Vec<&Event>> = HashMap::new();
+		for event in self.events.iter() {
+			let mut dirs: Vec<PathBuf> = event
+				.paths()
+				.map(|(path, _)| dir_at_depth(path, depth))
+				.collect();
+			dirs.sort();
+			dirs.dedup();
+			for dir in dirs {
+				groups.entry(dir).or_default().push(event);
+			}
+		}
+
+		groups
+	}
+
+	/// The number of distinct paths before truncation, if paths were truncated.
+	///
+	/// This is `Some` only when [`Config::max_paths_per_action`] is set and was exceeded, in
+	/// which case [`paths()`](Self::paths) returns fewer paths than this.
+	#[must_use]
+	pub fn paths_truncated(&self) -> Option<usize> {
+		self.paths_truncated
+	}
+
+	/// Whether this is the first action since Watchexec started.
+	///
+	/// This is only `true` for the very first call of the action handler. If an event is sent at
+	/// startup to kick things off (like the CLI does unless `--postpone` is given), that's the first
+	/// action; otherwise it's the first one triggered by real events. This lets handlers do
+	/// something different the first time, like a full build instead of an incremental one.
+	#[must_use]
+	pub fn is_first_run(&self) -> bool {
+		self.first_run
+	}
+
+	/// How the last command to end went, before this action.
+	///
+	/// This is the status and duration of the command which ended most recently, across all jobs,
+	/// as of when the action handler was called. It's `None` until a command has ended, including
+	/// for the first action. Commands which are stopped count as ending, with the status they were
+	/// stopped with, but commands which fail to spawn don't.
+	#[must_use]
+	pub fn last_run(&self) -> Option<LastRun> {
+		self.last_run
+	}
+
 	/// Convenience to get all process completions in the event set.
 	pub fn completions(&self) -> impl Iterator<Item = Option<ProcessEnd>> + '_ {
 		self.events.iter().flat_map(Event::completions)
 	}
 }
+
+/// How the command of a job which has just ended went.
+async fn finished_run(id: Id, job: &Job) -> Option<LastRun> {
+	let (run_s, run_r) = oneshot::channel();
+	job.run(move |context| {
+		if let CommandState::Finished {
+			status,
+			started,
+			finished,
+		} = context.current
+		{
+			run_s
+				.send(LastRun {
+					id,
+					status: *status,
+					duration: finished.saturating_duration_since(*started),
+				})
+				.ok();
+		}
+	})
+	.await;
+	run_r.await.ok()
+}
+
+fn dir_at_depth(path: &Path, depth: usize) -> PathBuf {
+	let mut dir = PathBuf::new();
+	let mut level = 0;
+	for component in path.parent().unwrap_or(path).components() {
+		if !matches!(component, Component::Prefix(_) | Component::RootDir) {
+			if level == depth {
+				break;
+			}
+			level += 1;
+		}
+		dir.push(component);
+	}
+	dir
+}
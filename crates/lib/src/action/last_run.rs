@@ -0,0 +1,20 @@
+use std::time::Duration;
+
+use watchexec_events::ProcessEnd;
+
+use crate::id::Id;
+
+/// How the last command to end went, see
+/// [`ActionHandler::last_run()`](super::ActionHandler::last_run()).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LastRun {
+	/// The ID of the job the command ran in.
+	pub id: Id,
+
+	/// How the command ended.
+	pub status: ProcessEnd,
+
+	/// How long the command ran for, from when it was spawned to when its status was collected.
+	pub duration: Duration,
+}
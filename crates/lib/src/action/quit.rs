@@ -1,4 +1,5 @@
-use std::time::Duration;
+use std::{future::Future, time::Duration};
+
 use watchexec_signals::Signal;
 
 /// How the Watchexec instance should quit.
@@ -15,3 +16,6 @@ pub enum QuitManner {
 		grace: Duration,
 	},
 }
+
+/// The return type of the quit hook.
+pub type QuitReturn = Box<dyn Future<Output = ()> + Send + Sync>;
@@ -9,6 +9,8 @@ use super::ActionHandler;
 /// action handler via [`Config::on_action`](crate::Config::on_action) and
 /// [`Config::on_action_async`](crate::Config::on_action_async) as that takes care of wrapping the
 /// return type from the specialised signature on these methods.
+// the sync variant is the common case, and it's moved once per action: not worth boxing
+#[allow(clippy::large_enum_variant)]
 pub enum ActionReturn {
 	/// The action handler is synchronous and here's its return value.
 	Sync(ActionHandler),
@@ -0,0 +1,34 @@
+use std::{error::Error, future::Future, sync::Arc};
+
+use watchexec_supervisor::command::Command;
+
+use crate::id::Id;
+
+/// Context given to the spawn hooks.
+///
+/// See [`Config::on_pre_spawn()`](crate::Config::on_pre_spawn()) and
+/// [`Config::on_post_spawn()`](crate::Config::on_post_spawn()).
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct SpawnContext {
+	/// The ID of the job the command is spawned for.
+	pub id: Id,
+
+	/// The command being spawned.
+	pub command: Arc<Command>,
+
+	/// The process ID of the spawned command.
+	///
+	/// This is always `None` in the pre-spawn hook. In the post-spawn hook, it may still be `None`
+	/// if the process has already exited and been reaped.
+	pub pid: Option<u32>,
+}
+
+/// The return type of the pre-spawn hook.
+///
+/// If the future resolves to an error, the command isn't spawned.
+pub type PreSpawnReturn =
+	Box<dyn Future<Output = Result<(), Box<dyn Error + Send + Sync>>> + Send + Sync>;
+
+/// The return type of the post-spawn hook.
+pub type PostSpawnReturn = Box<dyn Future<Output = ()> + Send + Sync>;
@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+/// How events are batched by the throttle.
+///
+/// The throttle (or "debounce") duration is set with
+/// [`Config::throttle()`](crate::Config::throttle()); this sets which edge of the throttle window
+/// actions are run on.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ThrottleMode {
+	/// Collect events for the throttle duration after the first one, then run an action with all
+	/// of them.
+	///
+	/// This is the default.
+	#[default]
+	Trailing,
+
+	/// Run an action immediately on the first event, then drop all events for the throttle
+	/// duration after that.
+	Leading,
+
+	/// Run an action immediately on the first event, then collect events for the throttle duration
+	/// after that, and if there were any, run another action with all of them at the end.
+	Both,
+}
+
+/// Bounds for a throttle which adapts to how long commands take to run.
+///
+/// When this is set with [`Config::adaptive_throttle()`](crate::Config::adaptive_throttle()), the
+/// throttle duration is the average runtime of the last few commands to end (the last
+/// [`RECENT_RUNS`](Self::RECENT_RUNS), across all jobs), bounded by [`min`](Self::min) and
+/// [`max`](Self::max). Until a command has ended, the configured throttle is used, within the same
+/// bounds. Fast commands then run again quickly after changes, and slow commands collect more
+/// changes into each run instead of being restarted over and over.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AdaptiveThrottle {
+	/// The shortest the throttle can be.
+	pub min: Duration,
+
+	/// The longest the throttle can be.
+	///
+	/// If this is less than `min`, it wins.
+	pub max: Duration,
+}
+
+impl AdaptiveThrottle {
+	/// How many of the last commands to end are averaged.
+	pub const RECENT_RUNS: usize = 5;
+
+	/// The throttle duration for an average command runtime, within the bounds.
+	#[must_use]
+	pub fn window(&self, average: Duration) -> Duration {
+		average.max(self.min).min(self.max)
+	}
+}
+
+impl Default for AdaptiveThrottle {
+	/// From 50 milliseconds (the default throttle) to 10 seconds.
+	fn default() -> Self {
+		Self {
+			min: Duration::from_millis(50),
+			max: Duration::from_secs(10),
+		}
+	}
+}
@@ -0,0 +1,59 @@
+use std::{
+	io::{Error, ErrorKind, Result},
+	path::{Path, PathBuf},
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use tokio::fs;
+use tracing::{debug, trace};
+
+/// Read the time of the last action from the state file, see
+/// [`Config::throttle_state_file`](crate::Config::throttle_state_file).
+///
+/// A missing or unreadable file is treated as no state.
+pub async fn read(path: &Path) -> Option<SystemTime> {
+	let content = match fs::read_to_string(path).await {
+		Ok(content) => content,
+		Err(err) => {
+			trace!(?path, %err, "no throttle state");
+			return None;
+		}
+	};
+
+	let Ok(millis) = content.trim().parse::<u64>() else {
+		debug!(?path, ?content, "throttle state is malformed, ignoring");
+		return None;
+	};
+
+	UNIX_EPOCH.checked_add(Duration::from_millis(millis))
+}
+
+/// Write the time of an action to the state file.
+///
+/// This writes to a temporary file next to it, then renames that over the state file, so it's
+/// never seen half-written, even if Watchexec is stopped midway.
+pub async fn write(path: &Path, at: SystemTime) -> Result<()> {
+	let millis = at
+		.duration_since(UNIX_EPOCH)
+		.map_err(|err| Error::new(ErrorKind::InvalidData, err))?
+		.as_millis();
+
+	let mut temp = path.as_os_str().to_owned();
+	temp.push(format!(".{}.tmp", std::process::id()));
+	let temp = PathBuf::from(temp);
+
+	fs::write(&temp, format!("{millis}\n")).await?;
+	if let Err(err) = fs::rename(&temp, path).await {
+		fs::remove_file(&temp).await.ok();
+		return Err(err);
+	}
+	Ok(())
+}
+
+/// How long to wait from now for a throttle window to have passed since `last`.
+///
+/// Returns `None` if it has already passed, or if `last` is in the future: the state is stale.
+pub fn remaining(last: SystemTime, window: Duration) -> Option<Duration> {
+	let elapsed = SystemTime::now().duration_since(last).ok()?;
+	window.checked_sub(elapsed).filter(|wait| !wait.is_zero())
+}
@@ -1,23 +1,35 @@
 use std::{
-	collections::HashMap,
+	collections::{hash_map::DefaultHasher, BTreeSet, HashMap},
+	hash::{Hash, Hasher},
 	mem::take,
+	path::{Path, PathBuf},
+	pin::Pin,
 	sync::Arc,
-	time::{Duration, Instant},
+	time::{Duration, Instant, SystemTime},
 };
 
 use async_priority_channel as priority;
-use tokio::{sync::mpsc, time::timeout};
-use tracing::{debug, trace};
-use watchexec_events::{Event, Priority};
+use tokio::{
+	sync::{broadcast, mpsc, watch},
+	task::spawn_blocking,
+	time::{sleep, timeout},
+};
+use tracing::{debug, trace, warn};
+use watchexec_events::{
+	filekind::{FileEventKind, ModifyKind},
+	Event, Priority, Source, Tag,
+};
+use watchexec_signals::Signal;
 use watchexec_supervisor::job::Job;
 
-use super::{handler::Handler, quit::QuitManner};
+use super::{concurrency::JobSlots, handler::Handler, quit::QuitManner, throttle_state};
 use crate::{
-	action::ActionReturn,
+	action::{ActionReturn, ThrottleMode},
 	error::{CriticalError, RuntimeError},
 	filter::Filterer,
 	id::Id,
 	late_join_set::LateJoinSet,
+	sources::fs::{symbolic_path, PathCanonicalization},
 	Config,
 };
 
@@ -30,59 +42,179 @@ pub async fn worker(
 	config: Arc<Config>,
 	errors: mpsc::Sender<RuntimeError>,
 	events: priority::Receiver<Event, Priority>,
+) -> Result<(), CriticalError> {
+	let (published, _) = watch::channel(HashMap::new());
+	let (raw_events, _) = broadcast::channel(1);
+	worker_publishing_jobs(config, errors, events, published, raw_events).await
+}
+
+/// The main worker, publishing the current set of jobs after every action, and every event as it
+/// comes in.
+///
+/// This is what [`Watchexec::jobs()`](crate::Watchexec::jobs) and
+/// [`Watchexec::raw_events()`](crate::Watchexec::raw_events) read from.
+pub(crate) async fn worker_publishing_jobs(
+	config: Arc<Config>,
+	errors: mpsc::Sender<RuntimeError>,
+	events: priority::Receiver<Event, Priority>,
+	published: watch::Sender<HashMap<Id, Job>>,
+	raw_events: broadcast::Sender<Event>,
 ) -> Result<(), CriticalError> {
 	let mut jobtasks = LateJoinSet::default();
+	let mut usertasks = LateJoinSet::default();
 	let mut jobs = HashMap::<Id, Job>::new();
+	let slots = Arc::new(JobSlots::new(&config));
+	let mut leading = None;
+	// the paths of the last action that ran, and when, see Config::dedup_runs_by_paths
+	let mut last_run: Option<(u64, Instant)> = None;
+	// events outside of the trigger paths, held until an action is triggered, see
+	// Config::trigger_paths
+	let mut held: Vec<Event> = Vec::new();
+	let mut triggers = TriggerPaths::default();
+	let mut first_run = true;
+	// whether jobs and tasks were already stopped, as the event channel may also close, e.g. on a
+	// CriticalError::Exit
+	let mut quitted = false;
+	// when the last event passed the filters, see Config::idle_timeout
+	let mut active = Instant::now();
+	// when the last action ran before a restart, see Config::throttle_state_file
+	let mut resumed = match config.throttle_state_file.get() {
+		Some(path) => throttle_state::read(&path).await,
+		None => None,
+	};
 
 	while let Some(mut set) = throttle_collect(
 		config.clone(),
 		events.clone(),
 		errors.clone(),
-		Instant::now(),
+		&slots,
+		&raw_events,
+		&mut leading,
+		&mut active,
+		first_run,
 	)
 	.await?
 	{
+		if set.is_empty() {
+			debug!(timeout=?config.idle_timeout.get(), "idle for too long, quitting");
+			quit(
+				QuitManner::Graceful {
+					signal: Signal::Terminate,
+					grace: Duration::ZERO,
+				},
+				&mut jobs,
+				&published,
+				&mut jobtasks,
+				&mut usertasks,
+			)
+			.await;
+			quitted = true;
+			break;
+		}
+
+		if !hold_untriggered(triggers.get(&config).await, &mut set, &mut held) {
+			trace!("no events left to act on");
+			continue;
+		}
+
+		let paths_truncated = config
+			.max_paths_per_action
+			.get()
+			.and_then(|max| truncate_paths(&mut set, max));
+		if let Some(total) = paths_truncated {
+			debug!(?total, "truncated paths in action");
+		}
+
+		if let Some(window) = config.dedup_runs_by_paths.get() {
+			let hash = paths_hash(&set);
+			if let (Some(hash), Some((last, ran))) = (hash, last_run) {
+				if hash == last && ran.elapsed() < window {
+					debug!(?window, "same paths as the last action, skipping");
+					continue;
+				}
+			}
+			last_run = hash.map(|hash| (hash, Instant::now()));
+		}
+
+		if config.sort_events.get() {
+			sort_events(&mut set);
+		}
+
+		if let Some(last) = resumed.take() {
+			match throttle_state::remaining(last, throttle_window(&config, &slots)) {
+				Some(_) if set.iter().any(|event| event.signals().next().is_some()) => {
+					debug!("first action has signals, not delaying it");
+				}
+				Some(wait) => {
+					debug!(
+						?wait,
+						"last action before restart was recent, delaying first action"
+					);
+					sleep(wait).await;
+				}
+				None => {
+					trace!("throttle state is stale, ignoring");
+				}
+			}
+		}
+
+		if let Some(path) = config.throttle_state_file.get() {
+			if let Err(err) = throttle_state::write(&path, SystemTime::now()).await {
+				errors
+					.send(RuntimeError::IoError {
+						about: "writing throttle state file",
+						err,
+					})
+					.await?;
+			}
+		}
+
 		let events: Arc<[Event]> = Arc::from(take(&mut set).into_boxed_slice());
 
 		trace!("preparing action handler");
-		let action = Handler::new(events.clone(), jobs.clone());
+		let action = Handler::new(
+			events.clone(),
+			jobs.clone(),
+			paths_truncated,
+			first_run,
+			errors.clone(),
+			slots.clone(),
+			&config,
+		);
+		first_run = false;
 
 		debug!("running action handler");
+		let watchdog = config
+			.action_timeout
+			.get()
+			.and_then(|limit| action_watchdog(&config, limit));
 		let action = match config.action_handler.call(action) {
 			ActionReturn::Sync(action) => action,
 			ActionReturn::Async(action) => Box::into_pin(action).await,
 		};
+		// disconnecting stops the watchdog
+		drop(watchdog);
 
 		debug!("take control of new tasks");
+		let new_jobs = !action.new.is_empty();
 		for (id, (job, task)) in action.new {
 			trace!(?id, "taking control of new task");
 			jobtasks.insert(task);
 			jobs.insert(id, job);
 		}
+		if new_jobs {
+			published.send_replace(jobs.clone());
+		}
+
+		for task in action.tasks {
+			trace!("taking control of action task");
+			usertasks.insert(task);
+		}
 
 		if let Some(manner) = action.quit {
-			debug!(?manner, "quitting worker");
-			match manner {
-				QuitManner::Abort => break,
-				QuitManner::Graceful { signal, grace } => {
-					debug!(?signal, ?grace, "quitting worker gracefully");
-					let mut tasks = LateJoinSet::default();
-					for (id, job) in jobs.drain() {
-						trace!(?id, "quitting job");
-						tasks.spawn(async move {
-							job.stop_with_signal(signal, grace);
-							job.delete().await;
-						});
-					}
-					// TODO: spawn to process actions, and allow events to come in while
-					//       waiting for graceful shutdown, e.g. a second Ctrl-C to hasten
-					debug!("waiting for graceful shutdown tasks");
-					tasks.join_all().await;
-					debug!("waiting for job tasks to end");
-					jobtasks.join_all().await;
-					break;
-				}
-			}
+			quit(manner, &mut jobs, &published, &mut jobtasks, &mut usertasks).await;
+			quitted = true;
+			break;
 		}
 
 		let gc: Vec<Id> = jobs
@@ -101,33 +233,469 @@ pub async fn worker(
 			for id in gc {
 				jobs.remove(&id);
 			}
+			published.send_replace(jobs.clone());
 		}
 
 		debug!("action handler finished");
 	}
 
+	if !quitted {
+		debug!("event channel closed, quitting");
+		quit(
+			QuitManner::Abort,
+			&mut jobs,
+			&published,
+			&mut jobtasks,
+			&mut usertasks,
+		)
+		.await;
+	}
+
+	let quit_timeout = config.quit_timeout.get();
+	debug!(?quit_timeout, "running quit hook");
+	let hook = Pin::from(config.quit_handler.call(()));
+	if timeout(quit_timeout, hook).await.is_err() {
+		warn!(?quit_timeout, "quit hook timed out, dropping it");
+	}
+
 	debug!("action worker finished");
 	Ok(())
 }
 
+/// Stop or kill all jobs and tasks, for the worker to quit.
+async fn quit(
+	manner: QuitManner,
+	jobs: &mut HashMap<Id, Job>,
+	published: &watch::Sender<HashMap<Id, Job>>,
+	jobtasks: &mut LateJoinSet,
+	usertasks: &mut LateJoinSet,
+) {
+	debug!(?manner, "quitting worker");
+	match manner {
+		QuitManner::Abort => {
+			debug!("killing jobs and tasks");
+			jobs.clear();
+			published.send_replace(HashMap::new());
+			jobtasks.abort_all();
+			usertasks.abort_all();
+			jobtasks.join_all().await;
+			usertasks.join_all().await;
+		}
+		QuitManner::Graceful { signal, grace } => {
+			debug!(?signal, ?grace, "quitting worker gracefully");
+			let mut tasks = LateJoinSet::default();
+			for (id, job) in jobs.drain() {
+				trace!(?id, "quitting job");
+				tasks.spawn(async move {
+					if job.command().options.detached {
+						trace!(?id, "job is detached, leaving it running");
+						job.detach().await;
+						return;
+					}
+
+					job.stop_with_signal(signal, grace);
+					job.delete().await;
+				});
+			}
+			published.send_replace(HashMap::new());
+			// TODO: spawn to process actions, and allow events to come in while
+			//       waiting for graceful shutdown, e.g. a second Ctrl-C to hasten
+			debug!("waiting for graceful shutdown tasks");
+			tasks.join_all().await;
+			debug!("waiting for job tasks to end");
+			jobtasks.join_all().await;
+			debug!("waiting for action tasks to end");
+			usertasks.join_all().await;
+		}
+	}
+}
+
+/// How long until the idle timeout runs out, if it's set.
+///
+/// While a command is running, this is the whole timeout, so it's checked again after that long.
+/// See [`Config::idle_timeout`].
+fn idle_left(config: &Config, slots: &JobSlots, active: Instant) -> Option<Duration> {
+	let timeout = config.idle_timeout.get()?;
+	Some(
+		slots
+			.idle_since(active)
+			.map_or(timeout, |since| timeout.saturating_sub(since.elapsed())),
+	)
+}
+
+/// Drop paths beyond the first `max` distinct paths, in order of arrival.
+///
+/// Events left without any path are dropped. Returns the number of distinct paths there were
+/// before truncation, if any were dropped.
+fn truncate_paths(set: &mut Vec<Event>, max: usize) -> Option<usize> {
+	let mut order: HashMap<PathBuf, usize> = HashMap::new();
+	set.retain_mut(|event| {
+		let mut had_paths = false;
+		let mut has_paths = false;
+		event.tags.retain(|tag| {
+			let Tag::Path { path, .. } = tag else {
+				return true;
+			};
+
+			had_paths = true;
+			let next = order.len();
+			let keep = *order.entry(path.clone()).or_insert(next) < max;
+			has_paths |= keep;
+			keep
+		});
+		has_paths || !had_paths
+	});
+
+	let total = order.len();
+	if total > max {
+		Some(total)
+	} else {
+		None
+	}
+}
+
+/// Watch an action handler, calling the action timeout hook if it's still running past the limit.
+///
+/// This runs on its own thread, so a sync handler blocking the runtime doesn't keep it from firing.
+/// The watchdog stops when the returned sender is dropped. See [`Config::action_timeout`].
+fn action_watchdog(config: &Config, limit: Duration) -> Option<std::sync::mpsc::Sender<()>> {
+	use std::sync::mpsc::{channel, RecvTimeoutError};
+
+	let config = config.clone();
+	let (done_s, done_r) = channel::<()>();
+	std::thread::Builder::new()
+		.name("watchexec-action-watchdog".into())
+		.spawn(move || {
+			if let Err(RecvTimeoutError::Timeout) = done_r.recv_timeout(limit) {
+				warn!(?limit, "action handler is overdue, events are queueing up");
+				config.action_timeout_handler.call(limit);
+			}
+		})
+		.map_err(|err| warn!(%err, "could not start the action watchdog"))
+		.ok()?;
+	Some(done_s)
+}
+
+/// Sort events by their first path, then their first filesystem event kind, see
+/// [`Config::sort_events`].
+///
+/// The sort is stable, so equal events keep their order of arrival.
+fn sort_events(set: &mut [Event]) {
+	set.sort_by_cached_key(|event| {
+		let path = event.paths().next().map(|(path, _)| path.to_owned());
+		let kind = event.tags.iter().find_map(|tag| match tag {
+			Tag::FileEventKind(kind) => Some(kind_order(kind)),
+			_ => None,
+		});
+		(path, kind)
+	});
+}
+
+/// Order filesystem event kinds, as they aren't ordered themselves.
+///
+/// Kinds go in the order they're declared in, then by their subkinds, the same way.
+fn kind_order(kind: &FileEventKind) -> (u8, u8, u8) {
+	use watchexec_events::filekind::{
+		AccessKind, AccessMode, CreateKind, DataChange, MetadataKind, RemoveKind, RenameMode,
+	};
+
+	let access_mode = |mode: &AccessMode| match mode {
+		AccessMode::Any => 0,
+		AccessMode::Execute => 1,
+		AccessMode::Read => 2,
+		AccessMode::Write => 3,
+		AccessMode::Other => 4,
+	};
+
+	match kind {
+		FileEventKind::Any => (0, 0, 0),
+		FileEventKind::Access(access) => match access {
+			AccessKind::Any => (1, 0, 0),
+			AccessKind::Read => (1, 1, 0),
+			AccessKind::Open(mode) => (1, 2, access_mode(mode)),
+			AccessKind::Close(mode) => (1, 3, access_mode(mode)),
+			AccessKind::Other => (1, 4, 0),
+		},
+		FileEventKind::Create(create) => match create {
+			CreateKind::Any => (2, 0, 0),
+			CreateKind::File => (2, 1, 0),
+			CreateKind::Folder => (2, 2, 0),
+			CreateKind::Other => (2, 3, 0),
+		},
+		FileEventKind::Modify(modify) => match modify {
+			ModifyKind::Any => (3, 0, 0),
+			ModifyKind::Data(data) => (
+				3,
+				1,
+				match data {
+					DataChange::Any => 0,
+					DataChange::Size => 1,
+					DataChange::Content => 2,
+					DataChange::Other => 3,
+				},
+			),
+			ModifyKind::Metadata(meta) => (
+				3,
+				2,
+				match meta {
+					MetadataKind::Any => 0,
+					MetadataKind::AccessTime => 1,
+					MetadataKind::WriteTime => 2,
+					MetadataKind::Permissions => 3,
+					MetadataKind::Ownership => 4,
+					MetadataKind::Extended => 5,
+					MetadataKind::Other => 6,
+				},
+			),
+			ModifyKind::Name(rename) => (
+				3,
+				3,
+				match rename {
+					RenameMode::Any => 0,
+					RenameMode::To => 1,
+					RenameMode::From => 2,
+					RenameMode::Both => 3,
+					RenameMode::Other => 4,
+				},
+			),
+			ModifyKind::Other => (3, 4, 0),
+		},
+		FileEventKind::Remove(remove) => match remove {
+			RemoveKind::Any => (4, 0, 0),
+			RemoveKind::File => (4, 1, 0),
+			RemoveKind::Folder => (4, 2, 0),
+			RemoveKind::Other => (4, 3, 0),
+		},
+		FileEventKind::Other => (5, 0, 0),
+	}
+}
+
+/// Hash the set of distinct paths in the events, regardless of order.
+///
+/// Returns `None` if any event has no path.
+fn paths_hash(set: &[Event]) -> Option<u64> {
+	let mut paths: BTreeSet<&Path> = BTreeSet::new();
+	for event in set {
+		let mut had_paths = false;
+		for (path, _) in event.paths() {
+			had_paths = true;
+			paths.insert(path);
+		}
+		if !had_paths {
+			return None;
+		}
+	}
+
+	let mut hasher = DefaultHasher::new();
+	paths.hash(&mut hasher);
+	Some(hasher.finish())
+}
+
+/// Hold back events outside of the trigger paths, and give them back once an action is triggered.
+///
+/// Returns whether there are events left to run an action for. See [`Config::trigger_paths`].
+fn hold_untriggered(triggers: &[PathBuf], set: &mut Vec<Event>, held: &mut Vec<Event>) -> bool {
+	if triggers.is_empty() {
+		// trigger paths may have been unset while events were held
+		set.splice(0..0, take(held));
+		return true;
+	}
+
+	let triggered = set.iter().any(|event| {
+		event
+			.paths()
+			.any(|(path, _)| triggers.iter().any(|trigger| path.starts_with(trigger)))
+	});
+	if triggered {
+		debug!(held = held.len(), "action triggered, with held events");
+		set.splice(0..0, take(held));
+		return true;
+	}
+
+	let (untriggered, pathless): (Vec<Event>, Vec<Event>) = take(set)
+		.into_iter()
+		.partition(|event| event.paths().next().is_some());
+	if !untriggered.is_empty() {
+		debug!(
+			events = untriggered.len(),
+			"no events under trigger paths, holding"
+		);
+		for event in untriggered {
+			hold(held, event);
+		}
+	}
+	*set = pathless;
+	!set.is_empty()
+}
+
+/// The trigger paths in the form event paths are in, see [`Config::trigger_paths`].
+///
+/// Resolving them touches the filesystem, so it's only done again when the configured paths or
+/// the path canonicalisation change.
+#[derive(Debug, Default)]
+struct TriggerPaths {
+	given: Vec<PathBuf>,
+	symbolic: bool,
+	resolved: Vec<PathBuf>,
+}
+
+impl TriggerPaths {
+	async fn get(&mut self, config: &Config) -> &[PathBuf] {
+		let given = config.trigger_paths.get();
+		let symbolic = config.path_canonicalization.get() == PathCanonicalization::Symbolic;
+		if given != self.given || symbolic != self.symbolic {
+			let paths = given.clone();
+			self.resolved = spawn_blocking(move || resolve_trigger_paths(paths, symbolic))
+				.await
+				.unwrap_or_else(|err| {
+					debug!(%err, "could not resolve trigger paths, using them as given");
+					given.clone()
+				});
+			trace!(triggers=?self.resolved, "resolved trigger paths");
+			self.given = given;
+			self.symbolic = symbolic;
+		}
+
+		&self.resolved
+	}
+}
+
+fn resolve_trigger_paths(paths: Vec<PathBuf>, symbolic: bool) -> Vec<PathBuf> {
+	paths
+		.into_iter()
+		.map(|path| {
+			if symbolic {
+				symbolic_path(&path)
+			} else {
+				dunce::canonicalize(&path).unwrap_or(path)
+			}
+		})
+		.collect()
+}
+
+/// The most events held back by [`Config::trigger_paths`] until an action is triggered.
+const MAX_HELD_EVENTS: usize = 1024;
+
+/// Hold an event until an action is triggered.
+///
+/// Only the latest event for the same paths is kept, and past [`MAX_HELD_EVENTS`], the oldest are
+/// dropped, so the held events stay bounded when changes never reach the trigger paths.
+fn hold(held: &mut Vec<Event>, event: Event) {
+	let paths = |event: &Event| -> BTreeSet<PathBuf> {
+		event.paths().map(|(path, _)| path.to_owned()).collect()
+	};
+	let key = paths(&event);
+	held.retain(|other| paths(other) != key);
+	held.push(event);
+
+	if held.len() > MAX_HELD_EVENTS {
+		let dropped = held.len() - MAX_HELD_EVENTS;
+		trace!(dropped, "too many held events, dropping the oldest");
+		held.drain(..dropped);
+	}
+}
+
+/// Whether all the paths of the event are command outputs, while commands run or settle.
+///
+/// See [`Config::ignore_command_outputs`].
+fn is_command_output(config: &Config, slots: &JobSlots, event: &Event) -> bool {
+	let outputs = config.ignore_command_outputs.get();
+	if outputs.is_empty() {
+		return false;
+	}
+
+	let mut paths = event.paths().peekable();
+	paths.peek().is_some()
+		&& paths.all(|(path, _)| outputs.iter().any(|output| path.starts_with(output)))
+		&& slots.busy_within(config.command_outputs_settle.get())
+}
+
+/// Whether the event is from the filesystem, while commands run or settle.
+///
+/// See [`Config::ignore_events_during_run`].
+fn is_during_run(config: &Config, slots: &JobSlots, event: &Event) -> bool {
+	config.ignore_events_during_run.get()
+		&& event.tags.contains(&Tag::Source(Source::Filesystem))
+		&& slots.busy_within(config.command_outputs_settle.get())
+}
+
+/// Whether the event has file event kinds, and they're all metadata modifications.
+///
+/// See [`Config::ignore_metadata_only_events`].
+fn is_metadata_only(event: &Event) -> bool {
+	let mut kinds = event
+		.tags
+		.iter()
+		.filter_map(|tag| match tag {
+			Tag::FileEventKind(kind) => Some(kind),
+			_ => None,
+		})
+		.peekable();
+	kinds.peek().is_some()
+		&& kinds.all(|kind| matches!(kind, FileEventKind::Modify(ModifyKind::Metadata(_))))
+}
+
+/// The throttle duration, adapted to how long recent commands ran for if configured.
+///
+/// See [`Config::adaptive_throttle`].
+fn throttle_window(config: &Config, slots: &JobSlots) -> Duration {
+	let throttle = config.throttle.get();
+	match config.adaptive_throttle.get() {
+		None => throttle,
+		Some(adaptive) => adaptive.window(slots.average_runtime().unwrap_or(throttle)),
+	}
+}
+
+/// Collect events until the throttle says an action should run.
+///
+/// `leading` is the time of the last action run on the leading edge of the throttle window, for
+/// the leading and both throttle modes; it's updated when an action should run on a leading edge.
+///
+/// Before the `first_run`, the [initial throttle](Config::initial_throttle) is used if set, on the
+/// trailing edge whatever the throttle mode.
+///
+/// `active` is the time the last event passed the filters, and is updated as they do. If the
+/// [idle timeout](Config::idle_timeout) runs out while waiting for the first event of a set, an
+/// empty set is returned.
+#[allow(clippy::too_many_arguments)]
 pub async fn throttle_collect(
 	config: Arc<Config>,
 	events: priority::Receiver<Event, Priority>,
 	errors: mpsc::Sender<RuntimeError>,
-	mut last: Instant,
+	slots: &JobSlots,
+	raw_events: &broadcast::Sender<Event>,
+	leading: &mut Option<Instant>,
+	active: &mut Instant,
+	first_run: bool,
 ) -> Result<Option<Vec<Event>>, CriticalError> {
 	if events.is_closed() {
 		trace!("events channel closed, stopping");
 		return Ok(None);
 	}
 
+	let initial = config.initial_throttle.get().filter(|_| first_run);
+	let window = || initial.unwrap_or_else(|| throttle_window(&config, slots));
+
+	let mut last = Instant::now();
 	let mut set: Vec<Event> = vec![];
 	loop {
 		let maxtime = if set.is_empty() {
-			trace!("nothing in set, waiting forever for next event");
-			Duration::from_secs(u64::MAX)
+			match idle_left(&config, slots, *active) {
+				Some(left) if left.is_zero() => {
+					trace!("nothing in set and idle timeout ran out, stopping");
+					return Ok(Some(set));
+				}
+				Some(left) => {
+					trace!(?left, "nothing in set, waiting for next event until idle");
+					left
+				}
+				None => {
+					trace!("nothing in set, waiting forever for next event");
+					Duration::from_secs(u64::MAX)
+				}
+			}
 		} else {
-			config.throttle.get().saturating_sub(last.elapsed())
+			window().saturating_sub(last.elapsed())
 		};
 
 		if maxtime.is_zero() {
@@ -154,11 +722,23 @@ pub async fn throttle_collect(
 				Ok(Err(_empty)) => return Ok(None),
 				Ok(Ok((event, priority))) => {
 					trace!(?event, ?priority, "got event");
+					if raw_events.receiver_count() > 0 {
+						raw_events.send(event.clone()).ok();
+					}
 
 					if priority == Priority::Urgent {
 						trace!("urgent event, by-passing filters");
 					} else if event.is_empty() {
 						trace!("empty event, by-passing filters");
+					} else if is_command_output(&config, slots, &event) {
+						trace!("event is for command outputs while commands run, dropping");
+						continue;
+					} else if is_during_run(&config, slots, &event) {
+						trace!("filesystem event while commands run, dropping");
+						continue;
+					} else if config.ignore_metadata_only_events.get() && is_metadata_only(&event) {
+						trace!("event only changes metadata, dropping");
+						continue;
 					} else {
 						let filtered = config.filterer.check_event(&event, priority);
 						match filtered {
@@ -176,6 +756,37 @@ pub async fn throttle_collect(
 							}
 						}
 					}
+					*active = Instant::now();
+
+					let mode = if initial.is_some() {
+						ThrottleMode::Trailing
+					} else {
+						config.throttle_mode.get()
+					};
+					if set.is_empty()
+						&& priority != Priority::Urgent
+						&& mode != ThrottleMode::Trailing
+					{
+						match *leading {
+							Some(at) if at.elapsed() < window() => {
+								if mode == ThrottleMode::Leading {
+									trace!("within leading throttle window, dropping event");
+									continue;
+								}
+
+								trace!("within leading throttle window, collecting event");
+								last = at;
+								set.push(event);
+								continue;
+							}
+							_ => {
+								trace!("event is on the leading edge, by-passing throttle");
+								*leading = Some(Instant::now());
+								set.push(event);
+								return Ok(Some(set));
+							}
+						}
+					}
 
 					if set.is_empty() {
 						trace!("event is the first, resetting throttle window");
@@ -188,7 +799,7 @@ pub async fn throttle_collect(
 						trace!("urgent event, by-passing throttle");
 					} else {
 						let elapsed = last.elapsed();
-						if elapsed < config.throttle.get() {
+						if elapsed < window() {
 							trace!(?elapsed, "still within throttle window, cycling");
 							continue;
 						}
@@ -70,28 +70,47 @@ impl<T> fmt::Debug for Changeable<T> {
 ///
 /// As this is for Watchexec, only `Fn`s with a single argument and return value are supported
 /// here; it's simple enough to make your own if you want more.
-pub struct ChangeableFn<T, U>(Changeable<Arc<dyn (Fn(T) -> U) + Send + Sync>>);
+///
+/// Alongside the fn, this tracks whether it was ever replaced from its default.
+#[allow(clippy::type_complexity)]
+pub struct ChangeableFn<T, U>(Changeable<(Arc<dyn (Fn(T) -> U) + Send + Sync>, bool)>);
 impl<T, U> ChangeableFn<T, U>
 where
 	T: Send,
 	U: Send,
 {
 	pub(crate) fn new(f: impl (Fn(T) -> U) + Send + Sync + 'static) -> Self {
-		Self(Changeable::new(Arc::new(f)))
+		Self(Changeable::new((Arc::new(f), false)))
 	}
 
 	/// Replace the fn with a new one.
 	///
 	/// Panics if the lock was poisoned.
 	pub fn replace(&self, new: impl (Fn(T) -> U) + Send + Sync + 'static) {
-		self.0.replace(Arc::new(new));
+		self.0.replace((Arc::new(new), true));
 	}
 
 	/// Call the fn.
 	///
 	/// Panics if the lock was poisoned.
 	pub fn call(&self, data: T) -> U {
-		(self.0.get())(data)
+		(self.0.get().0)(data)
+	}
+
+	/// Copy into a new `ChangeableFn` holding the same fn, but which can be replaced independently.
+	pub(crate) fn snapshot(&self) -> Self {
+		Self(Changeable::new(self.0.get()))
+	}
+
+	/// Whether the fn was ever replaced, i.e. isn't the default.
+	pub(crate) fn is_set(&self) -> bool {
+		self.0.get().1
+	}
+
+	/// Whether both hold the same fn instance.
+	pub(crate) fn same_as(&self, other: &Self) -> bool {
+		// compare data pointers only, vtable pointers aren't guaranteed unique
+		Arc::as_ptr(&self.0.get().0).cast::<()>() == Arc::as_ptr(&other.0.get().0).cast::<()>()
 	}
 }
 
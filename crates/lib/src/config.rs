@@ -1,16 +1,39 @@
 //! Configuration and builders for [`crate::Watchexec`].
 
-use std::{future::Future, pin::pin, sync::Arc, time::Duration};
+use std::{
+	fmt,
+	future::Future,
+	path::PathBuf,
+	pin::pin,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
 
-use tokio::sync::Notify;
-use tracing::{debug, trace};
+use tokio::{
+	sync::{watch, Notify},
+	task::JoinHandle,
+};
+use tracing::{debug, trace, warn};
 
 use crate::{
-	action::{ActionHandler, ActionReturn},
+	action::{
+		ActionHandler, ActionReturn, ActionTask, AdaptiveThrottle, ConcurrencyPolicy,
+		PostSpawnReturn, PreSpawnReturn, QuitReturn, SpawnContext, ThrottleMode,
+	},
 	changeable::{Changeable, ChangeableFn},
+	error::ConfigError,
 	filter::{ChangeableFilterer, Filterer},
-	sources::fs::{WatchedPath, Watcher},
-	ErrorHook,
+	sources::{
+		fs::{
+			dedupe_pathset, symbolic_path, AncestorWatch, FdExhaustion, FsEventKinds,
+			PathCanonicalization, WatchLimit, WatchedPath, Watcher,
+		},
+		origins::OriginScan,
+	},
+	ErrorHook, ErrorRateLimit,
 };
 
 /// Configuration for [`Watchexec`][crate::Watchexec].
@@ -34,6 +57,12 @@ pub struct Config {
 	/// should read the configuration again.
 	pub(crate) change_signal: Arc<Notify>,
 
+	/// Counts the change signals, so that changes can be told apart.
+	pub(crate) generation: Arc<AtomicU64>,
+
+	/// The last generation of the config the filesystem worker has applied.
+	pub(crate) applied: Arc<watch::Sender<u64>>,
+
 	/// The main handler to define: what to do when an action is triggered.
 	///
 	/// This handler is called with the [`Action`] environment, look at its doc for more detail.
@@ -106,15 +135,290 @@ pub struct Config {
 	/// have that ability.
 	pub error_handler: ChangeableFn<ErrorHook, ()>,
 
+	/// Limit how often identical errors are given to the error handler.
+	///
+	/// A persistently failing source, like a watched network mount which went away, can otherwise
+	/// call the error handler many times per second with the same error. When this is set, repeated
+	/// identical errors are coalesced, and the handler is told how many were dropped with
+	/// [`ErrorHook::suppressed`]. See [`ErrorRateLimit`] for details.
+	///
+	/// Default is `None`, which gives every error to the handler.
+	pub error_rate_limit: Changeable<Option<ErrorRateLimit>>,
+
+	/// Pre-spawn hook.
+	///
+	/// This is called before every command spawn, in every job, and the spawn waits for the future
+	/// it returns. If that resolves to an error, the command is not spawned, and the error is given
+	/// to the error hook as a [`RuntimeError::Handler`](crate::error::RuntimeError::Handler). The
+	/// default hook does nothing.
+	pub pre_spawn_handler: ChangeableFn<SpawnContext, PreSpawnReturn>,
+
+	/// Post-spawn hook.
+	///
+	/// This is called after every command spawn, in every job, with the process ID of the new
+	/// process. The job waits for the future it returns before handling further control. The
+	/// default hook does nothing.
+	pub post_spawn_handler: ChangeableFn<SpawnContext, PostSpawnReturn>,
+
+	/// Quit hook.
+	///
+	/// This is called once when Watchexec quits, whether that's from the action handler asking for
+	/// it (with [`quit()`](crate::action::ActionHandler::quit) or
+	/// [`quit_gracefully()`](crate::action::ActionHandler::quit_gracefully)) or from a
+	/// [`CriticalError::Exit`](crate::error::CriticalError::Exit). It's called after all jobs have
+	/// been stopped (or killed, for an abrupt quit), and the main task doesn't resolve until the
+	/// future it returns does, or until [`quit_timeout`](Self::quit_timeout) elapses, whichever
+	/// comes first. This makes it a good place for final cleanup.
+	///
+	/// It is not called if Watchexec stops because of any other critical error. The default hook
+	/// does nothing.
+	pub quit_handler: ChangeableFn<(), QuitReturn>,
+
+	/// Action timeout hook.
+	///
+	/// This is called when an action handler hasn't returned within the
+	/// [`action_timeout`](Self::action_timeout), with that timeout, once per overdue action. It's
+	/// called from a separate thread, while the action handler is still running: the handler isn't
+	/// cancelled. The default hook does nothing; a warning is logged regardless.
+	///
+	/// Like the error hook, it should return quickly.
+	pub action_timeout_handler: ChangeableFn<Duration, ()>,
+
+	/// Action task executor.
+	///
+	/// This spawns the tasks that action handlers start with
+	/// [`ActionHandler::run_task()`](crate::action::ActionHandler::run_task()), and returns a
+	/// handle Watchexec uses to track them. Replacing it lets tasks be bounded or run elsewhere,
+	/// like through a semaphore-limited spawner, or on a dedicated runtime. Tasks are given to the
+	/// executor in the order they're run, as action handlers themselves are called one after the
+	/// other; when they actually start is up to the executor.
+	///
+	/// The default is [`tokio::spawn`].
+	pub action_executor: ChangeableFn<ActionTask, JoinHandle<()>>,
+
 	/// The set of filesystem paths to be watched.
 	///
 	/// If this is non-empty, the filesystem event source is started and configured to provide
 	/// events for these paths. If it becomes empty, the filesystem event source is shut down.
 	pub pathset: Changeable<Vec<WatchedPath>>,
 
+	/// Collapse overlapping paths in the pathset.
+	///
+	/// When this is true, recursive watches of directories within another recursive watch are
+	/// dropped, keeping only the top-most, as its watch already covers them. Paths are compared
+	/// once canonicalised, so `.` and `./src` overlap; paths which don't exist are compared as
+	/// given, once normalised. Watches which are the same once canonicalised are only kept once,
+	/// the first time they appear. What's dropped is logged at debug level.
+	///
+	/// Non-recursive and file watches are never collapsed into others, even within a recursive
+	/// watch, and don't collapse others, as they don't cover subdirectories.
+	///
+	/// Default is false.
+	pub dedupe_pathset: Changeable<bool>,
+
+	/// Paths under which changes trigger actions, within the watched [`pathset`](Config::pathset).
+	///
+	/// When this is non-empty, the pathset is only watched for context: an action handler is
+	/// called for filesystem events once one of them is under a trigger path (or is a trigger path
+	/// itself). Events outside of the trigger paths are held back until then, and are given to the
+	/// handler along with the events which triggered it, so it still sees everything that changed
+	/// since the last action (e.g. to describe it in environment variables). Events without paths,
+	/// like signals and keyboard input, aren't held back: the handler is called for them, without
+	/// the held events.
+	///
+	/// This applies after the [filterer](Config::filterer): events which are filtered out are
+	/// neither held nor trigger. [Raw events](crate::Watchexec::raw_events) are published as they
+	/// come in, regardless of trigger paths. Held events are counted towards
+	/// [`max_paths_per_action`](Config::max_paths_per_action) in the action they're given to.
+	///
+	/// So that changes which never reach the trigger paths don't pile up, only the latest held
+	/// event for the same paths is kept, and at most 1024 events are held: past that, the oldest
+	/// are dropped.
+	///
+	/// Paths are compared once canonicalised, like the pathset, so they may be given relative to
+	/// the current directory. With [`PathCanonicalization::Symbolic`], they're compared once made
+	/// absolute instead, keeping symlinks, like event paths are then reported. Trigger paths
+	/// outside the pathset never see changes.
+	///
+	/// Default is empty, which makes every change trigger.
+	pub trigger_paths: Changeable<Vec<PathBuf>>,
+
+	/// Watch project origins found under a base path, looking for them again periodically.
+	///
+	/// When this is set, the base path and its subdirectories, up to a maximum depth and skipping
+	/// hidden directories, are checked with [`project_origins::is_origin`] at the scan's interval.
+	/// Origins which are found are added to the [`pathset`](Config::pathset) (recursively), and
+	/// origins which were added this way but aren't found anymore are removed from it. Other paths
+	/// in the pathset are left alone, so this can be combined with setting it directly.
+	///
+	/// Setting this to `None` removes the origins which were added.
+	///
+	/// Default is `None`.
+	pub origin_scan: Changeable<Option<OriginScan>>,
+
 	/// The kind of filesystem watcher to be used.
 	pub file_watcher: Changeable<Watcher>,
 
+	/// What to do when the filesystem watcher runs out of file descriptors.
+	///
+	/// This mostly concerns kqueue, the native watcher on BSDs, which needs a file descriptor for
+	/// every watched file and directory. See [`FdExhaustion`] for the options.
+	///
+	/// Default is to report the error.
+	pub fd_exhaustion: Changeable<FdExhaustion>,
+
+	/// The maximum number of paths the filesystem watcher may watch.
+	///
+	/// This protects against accidentally watching huge trees, like `/` or a `node_modules`. Paths
+	/// are counted as the watcher uses them: a recursive watch counts its directory and every
+	/// directory within it, while a non-recursive or file watch counts as one. Paths from the
+	/// pathset are added in order until the next would go over the limit; that one and all the
+	/// remaining ones are then not watched, and [`watch_limit`](Self::watch_limit) says what
+	/// happens. Paths are counted when they're added, so trees growing afterwards aren't
+	/// accounted for.
+	///
+	/// Default is no limit.
+	pub max_watched_paths: Changeable<Option<usize>>,
+
+	/// What to do when the [watched paths limit](Self::max_watched_paths) is reached.
+	///
+	/// Default is to report it to the error hook as a warning.
+	pub watch_limit: Changeable<WatchLimit>,
+
+	/// Whether to follow watched paths which are symlinks.
+	///
+	/// If this is true, watched paths which are symlinks are resolved to their final target, which
+	/// is then watched instead (recursively or not, as the watched path specifies). Events are then
+	/// reported with the target's paths. Symlinks which are broken or which loop are skipped with a
+	/// warning, and tried again on the next config change.
+	///
+	/// If this is false, watched paths are given to the filesystem watcher as-is. Some watchers
+	/// (like inotify on Linux) follow symlinks themselves, while others (like FSEvents on macOS)
+	/// will only watch the link itself.
+	///
+	/// Symlinks found _within_ watched directories are left to the filesystem watcher either way.
+	///
+	/// Default is false.
+	pub follow_symlinks: Changeable<bool>,
+
+	/// Whether watched paths and event paths have their symlinks resolved.
+	///
+	/// Watching through a symlink, with [`PathCanonicalization::Canonical`], events may be reported
+	/// with the paths the symlink resolves to, depending on the watcher and on
+	/// [`follow_symlinks`](Self::follow_symlinks). With [`PathCanonicalization::Symbolic`], they're
+	/// reported under the watched path as it was given instead.
+	///
+	/// Filterers see event paths in that form, so globs or origins which were canonicalised (as
+	/// the Watchexec CLI does) may not match symbolic event paths: give them in the same form as
+	/// the pathset. [Trigger paths](Self::trigger_paths) are compared in the same form as well.
+	///
+	/// Default is [`PathCanonicalization::Canonical`].
+	pub path_canonicalization: Changeable<PathCanonicalization>,
+
+	/// The kinds of filesystem events to let through.
+	///
+	/// Filesystem events of other kinds are dropped as soon as they're received, before filtering.
+	/// Events which don't have a known kind are always let through, and events from other sources
+	/// are unaffected.
+	///
+	/// Default is all kinds.
+	pub event_kinds: Changeable<FsEventKinds>,
+
+	/// Whether to drop events which only describe changes to metadata.
+	///
+	/// Permission, ownership, and timestamp changes, like those from `chmod` or from backup tools,
+	/// usually don't call for a new run. When this is true, events whose file event kinds are all
+	/// [metadata modifications](watchexec_events::filekind::ModifyKind::Metadata) are dropped as
+	/// they're received, before filtering. Events which also have another kind, like a creation
+	/// reported together with a permission change, are kept, as are events without a file event
+	/// kind and [urgent](watchexec_events::Priority::Urgent) events.
+	///
+	/// Unlike leaving [`FsEventKinds::METADATA`] out of [`event_kinds`](Self::event_kinds), this
+	/// applies to events from any source, including those sent to
+	/// [`Watchexec::send_event()`](crate::Watchexec::send_event()).
+	///
+	/// Default is false.
+	pub ignore_metadata_only_events: Changeable<bool>,
+
+	/// Whether to pair up rename events.
+	///
+	/// Some watchers report a rename as two events, one for the source path and one for the
+	/// destination. When this is enabled, these are paired up and emitted as a single event, with a
+	/// `Modify(Name(Both))` kind and both paths, source first. Pairing uses the watcher's event
+	/// tracker (such as the inotify cookie) where available, and adjacency otherwise.
+	///
+	/// The source event is held back for a short time (100ms) while waiting for its pair: if that
+	/// doesn't arrive, the source event is emitted alone. A destination event without a source is
+	/// emitted alone immediately.
+	///
+	/// Default is false.
+	pub coalesce_renames: Changeable<bool>,
+
+	/// How often to check that watched paths still exist.
+	///
+	/// When a watched path is deleted, most watchers silently stop watching it, and won't pick it
+	/// up again if it's recreated. When this is set, the watched paths are checked at this interval:
+	/// if one has disappeared, it's dropped from the watcher and an event is emitted with a
+	/// `Remove` kind, the configured path, and a `watch: lost` metadata entry. Lost paths are then
+	/// checked at the same interval, and when one reappears it's watched again and an event is
+	/// emitted with a `Create` kind and a `watch: restored` metadata entry.
+	///
+	/// Checks are cheap (a single stat per path), so there's no backoff: the interval is fixed.
+	/// Events created or modified in a path between its reappearance and the next check are missed.
+	///
+	/// Default is `None`, which disables the check.
+	pub watch_check_interval: Changeable<Option<Duration>>,
+
+	/// Follow watched paths which are deleted through their nearest existing ancestor.
+	///
+	/// Editors and build tools often delete and recreate files and directories, and most watchers
+	/// stop watching a path when it's deleted. When this is set, a watched path which disappears is
+	/// dropped from the watcher, and the nearest directory above it which still exists, up to the
+	/// [maximum depth](AncestorWatch::max_depth), is watched instead (non-recursively). When the
+	/// path reappears, it's watched again and the ancestor is dropped. If no ancestor within reach
+	/// exists, it's looked for again with a [backoff](AncestorWatch::backoff).
+	///
+	/// This is transparent: events for the ancestors themselves and for other paths in them are
+	/// dropped, except for the watched paths, so the recreation of a path is seen as it would be
+	/// if it were still watched. Changes made within a recreated directory before it's watched again
+	/// are missed, though this is usually a very short window.
+	///
+	/// Disappearance is noticed from the watcher's own events, and also at the
+	/// [`watch_check_interval`](Self::watch_check_interval) if that's set. Unlike with the check
+	/// alone, no event reports that a path was lost or restored, unless the check is also set.
+	///
+	/// Default is `None`, which doesn't follow deleted paths.
+	pub watch_ancestors: Changeable<Option<AncestorWatch>>,
+
+	/// Emit an event once the filesystem watcher is set up.
+	///
+	/// When this is true, an event is emitted the first time the filesystem event source has
+	/// applied the pathset, that is, once all paths are being watched and changes to them will be
+	/// seen. If the pathset is empty at that point, the event is emitted right away. This lets
+	/// applications tell the first run of an action, with everything in place, from later ones.
+	///
+	/// The event has an `Internal` source tag, no path, and a `watch: ready` metadata entry. It's
+	/// emitted at most once per Watchexec instance, and not at all if this is only enabled after
+	/// the watcher is set up.
+	///
+	/// Default is false.
+	pub ready_event: Changeable<bool>,
+
+	/// Attribute filesystem events to the processes which caused them.
+	///
+	/// When this is true, filesystem events for changes to file contents are tagged with the
+	/// `Process` (pid) which made the change, and given a `uid` metadata entry with the user id
+	/// of that process, if it could be read.
+	///
+	/// This is best-effort. It's only implemented on Linux, with fanotify, which requires the
+	/// `CAP_SYS_ADMIN` capability (in practice, running as root). Elsewhere, or when fanotify isn't
+	/// available, events go without, a warning is logged, and this is set back to false.
+	/// Creations, deletions, and renames aren't attributed, and as fanotify sees a whole mount,
+	/// this adds some overhead for every write on the mounts of the watched paths.
+	///
+	/// Default is false.
+	pub process_origins: Changeable<bool>,
+
 	/// Watch stdin and emit events when input comes in over the keyboard.
 	///
 	/// If this is true, the keyboard event source is started and configured to report when input
@@ -133,8 +437,196 @@ pub struct Config {
 	/// Default is 50ms.
 	pub throttle: Changeable<Duration>,
 
+	/// The throttle to use until the first action.
+	///
+	/// A flurry of events often comes in at startup, from setting up or from a build which just
+	/// finished. When this is set, events are collected for this long, from the first one, before
+	/// the first action runs, always on the trailing edge whatever the
+	/// [`throttle_mode`](Self::throttle_mode). Once the first action has run, the
+	/// [`throttle`](Self::throttle) and throttle mode apply as usual. The transition is at the
+	/// first action, not after a fixed time: if no events come in for a while after starting, the
+	/// first batch still waits for this window. Urgent events aren't throttled either way.
+	///
+	/// Default is `None`, which uses the normal throttle from the start.
+	pub initial_throttle: Changeable<Option<Duration>>,
+
+	/// Which edge of the throttle window actions are run on.
+	///
+	/// See [`ThrottleMode`] for details. Default is trailing, as described for `throttle`.
+	pub throttle_mode: Changeable<ThrottleMode>,
+
+	/// Adapt the throttle to how long commands take to run.
+	///
+	/// When this is set, the throttle is the average runtime of the last commands to end, within
+	/// the bounds given, instead of the fixed [`throttle`](Self::throttle). See
+	/// [`AdaptiveThrottle`] for details. This applies to all throttle modes.
+	///
+	/// Default is `None`, which uses the fixed throttle.
+	pub adaptive_throttle: Changeable<Option<AdaptiveThrottle>>,
+
+	/// A file to keep the time of the last action in, across restarts of Watchexec.
+	///
+	/// When Watchexec is itself restarted, by a supervisor or a service manager, it doesn't know
+	/// when the last action ran, and could run an action immediately, even though one just ran.
+	/// When this is set, the time of each action is written to this file, and at startup, if the
+	/// last action recorded there ran less than a throttle window ago (the
+	/// [`throttle`](Self::throttle) or the [`adaptive_throttle`](Self::adaptive_throttle)), the
+	/// first action is delayed until that window has passed. Events coming in meanwhile are queued
+	/// for the next action.
+	///
+	/// An action with signal events, like an interrupt, isn't delayed. A recorded time older than
+	/// a throttle window, or in the future, is stale and ignored, as is a missing or unreadable
+	/// file. Failing to write the file is reported to the error handler, and doesn't stop actions.
+	///
+	/// The file holds the time as the number of milliseconds since the Unix epoch, in decimal,
+	/// followed by a newline. Its directory must exist, and be writable: the file is replaced
+	/// atomically, by writing a temporary file next to it and renaming it over.
+	///
+	/// Default is `None`, which doesn't keep the time of actions.
+	pub throttle_state_file: Changeable<Option<PathBuf>>,
+
+	/// The maximum number of distinct paths given to an action.
+	///
+	/// Bulk operations like extracting an archive can produce thousands of paths in a single
+	/// action. When this is set and an action collects more distinct paths than this, only the
+	/// first paths to arrive are kept, up to this number, and the rest are dropped from the events.
+	/// Events which are left without any path are dropped entirely. The action still runs, and
+	/// [`ActionHandler::paths_truncated()`](crate::action::ActionHandler::paths_truncated) returns
+	/// the number of distinct paths there were before truncation.
+	///
+	/// Default is `None`, which doesn't limit paths.
+	pub max_paths_per_action: Changeable<Option<usize>>,
+
+	/// Skip actions for the same paths as the last action, within this window.
+	///
+	/// Some tools report the same file repeatedly for a single change (for example, editors which
+	/// save atomically), and running the same action again is wasteful. When this is set, the set
+	/// of distinct paths in each action's events is remembered, and if an action would run with
+	/// exactly the same set of paths within this duration of the last action that ran, it's
+	/// skipped entirely: the action handler isn't called. Order and event kinds don't matter.
+	///
+	/// The window starts when an action runs, and isn't extended by skipped actions, so a steady
+	/// stream of the same paths still runs an action at most once per window. Actions with events
+	/// that have no path (like signals or keyboard events) always run. This applies after
+	/// [`max_paths_per_action`](Config::max_paths_per_action) truncation.
+	///
+	/// This differs from the throttle, which batches events into actions, in that it compares
+	/// consecutive actions.
+	///
+	/// Default is `None`, which never skips actions.
+	pub dedup_runs_by_paths: Changeable<Option<Duration>>,
+
+	/// Sort the events of each action, so they're given in the same order across runs and platforms.
+	///
+	/// Events otherwise come in the order they arrive in, which depends on timing and on the
+	/// watcher. When this is true, the events of each action are sorted by their first path, then
+	/// by their first filesystem event kind; events without a path come first, and events which
+	/// compare equal keep their order of arrival. Sorting happens just before the action handler is
+	/// called: after filtering, throttling, [`max_paths_per_action`](Config::max_paths_per_action)
+	/// truncation (which still keeps the first paths to arrive), and
+	/// [`dedup_runs_by_paths`](Config::dedup_runs_by_paths).
+	///
+	/// This costs a sort of each action's events, with a key computed once per event, which is
+	/// negligible for usual actions but may show for actions with many thousands of events.
+	///
+	/// Default is false.
+	pub sort_events: Changeable<bool>,
+
+	/// The maximum number of commands running at once, across all jobs.
+	///
+	/// This is checked when a command is about to be spawned, after the pre-spawn hook: if as many
+	/// commands are already running, the [`concurrency_policy`](Self::concurrency_policy) decides
+	/// whether it waits or isn't started. A command counts against the limit from when it's
+	/// spawned until it ends, so a restart frees the slot of the old command when it's stopped and
+	/// takes one again for the new command. A limit of zero is treated as one.
+	///
+	/// Changes to the limit apply to the next commands to start.
+	///
+	/// Default is `None`, which doesn't limit commands.
+	pub max_concurrent_jobs: Changeable<Option<usize>>,
+
+	/// What to do with commands which would go over the limit of concurrent jobs.
+	///
+	/// See [`ConcurrencyPolicy`] for details. Default is to queue them.
+	pub concurrency_policy: Changeable<ConcurrencyPolicy>,
+
+	/// Paths written to by the commands, whose events are dropped while commands run.
+	///
+	/// A command which writes into a watched folder triggers itself again once it's done, and so on
+	/// in a loop. When this is set, events whose paths are all under these paths are dropped while
+	/// any command is running, and for the [settling window](Self::command_outputs_settle) after
+	/// the last one ends, as events can arrive some time after the writes that caused them. Events
+	/// with other paths aren't dropped, even if they also have paths under these. Outside of runs
+	/// and their settling window, events for these paths are delivered as usual.
+	///
+	/// Paths should be absolute, as event paths are. Default is empty.
+	pub ignore_command_outputs: Changeable<Vec<PathBuf>>,
+
+	/// Drop all filesystem events while commands run.
+	///
+	/// This is the blunt version of [`ignore_command_outputs`](Self::ignore_command_outputs), for
+	/// when the commands are assumed to cause every change that happens while they run: events
+	/// from the filesystem are dropped while any command is running, and for the
+	/// [settling window](Self::command_outputs_settle) after the last one ends. Only changes made
+	/// after that trigger actions.
+	///
+	/// As the events are dropped before they get to an action handler, they don't get to queue or
+	/// restart a command either: with this, a busy command is never restarted or queued again
+	/// because of a filesystem change, whatever the action handler does with events. Events from
+	/// other sources, like signals and keyboard input, aren't dropped, nor are urgent events.
+	///
+	/// Default is false.
+	pub ignore_events_during_run: Changeable<bool>,
+
+	/// How long after commands end to keep dropping events for their outputs.
+	///
+	/// See [`ignore_command_outputs`](Self::ignore_command_outputs) and
+	/// [`ignore_events_during_run`](Self::ignore_events_during_run). Default is 500 milliseconds.
+	pub command_outputs_settle: Changeable<Duration>,
+
+	/// How long an action handler can run before it's considered stuck.
+	///
+	/// When an action handler (sync or async) hasn't returned by then, a warning is logged and the
+	/// [`action_timeout_handler`](Self::action_timeout_handler) is called, so a stall can be
+	/// noticed. The handler is left to run: it's never interrupted. While it runs, new events
+	/// queue up in the event channel as usual, and are processed once it returns.
+	///
+	/// Action handlers are watched from a thread of their own, so this works even for a sync
+	/// handler which blocks the runtime, such as on a current-thread runtime. That's one more
+	/// thread for the duration of each action.
+	///
+	/// Default is `None`, which doesn't watch action handlers.
+	pub action_timeout: Changeable<Option<Duration>>,
+
+	/// How long to wait for the quit hook to complete.
+	///
+	/// If the future returned by the [`quit_handler`](Self::quit_handler) hasn't resolved by then,
+	/// it's dropped with a warning, and Watchexec finishes quitting.
+	///
+	/// Default is 5 seconds.
+	pub quit_timeout: Changeable<Duration>,
+
+	/// How long to wait without activity before quitting.
+	///
+	/// When this is set, Watchexec quits gracefully once it has gone that long without any event
+	/// passing the filters and without any command running. The timer starts when Watchexec does,
+	/// and is reset by each event which passes the filters, and when each command ends. While a
+	/// command is running, the timer is suspended, however long the command runs for. Events
+	/// dropped by the filterer (or by other settings, like
+	/// [`ignore_command_outputs`](Self::ignore_command_outputs)) don't count as activity.
+	///
+	/// Quitting is done as with a graceful quit from an action handler: jobs are deleted, action
+	/// tasks are waited on, and the [`quit_handler`](Self::quit_handler) is called. No action
+	/// handler is run for it.
+	///
+	/// Default is `None`, which never quits on inactivity.
+	pub idle_timeout: Changeable<Option<Duration>>,
+
 	/// The filterer implementation to use when filtering events.
 	///
+	/// It can be replaced at any time: checks already running complete against the filterer they
+	/// started with, and later ones use the new one. See [`ChangeableFilterer`] for details.
+	///
 	/// The default is a no-op, which will always pass every event.
 	pub filterer: ChangeableFilterer,
 
@@ -159,12 +651,52 @@ impl Default for Config {
 	fn default() -> Self {
 		Self {
 			change_signal: Default::default(),
+			generation: Default::default(),
+			applied: Arc::new(watch::channel(0).0),
 			action_handler: ChangeableFn::new(ActionReturn::Sync),
 			error_handler: Default::default(),
+			error_rate_limit: Default::default(),
+			pre_spawn_handler: ChangeableFn::new(|_| -> PreSpawnReturn {
+				Box::new(async { Ok(()) })
+			}),
+			post_spawn_handler: ChangeableFn::new(|_| -> PostSpawnReturn { Box::new(async {}) }),
+			quit_handler: ChangeableFn::new(|()| -> QuitReturn { Box::new(async {}) }),
+			action_timeout_handler: Default::default(),
+			action_executor: ChangeableFn::new(tokio::spawn),
 			pathset: Default::default(),
+			dedupe_pathset: Default::default(),
+			trigger_paths: Default::default(),
+			origin_scan: Default::default(),
 			file_watcher: Default::default(),
+			fd_exhaustion: Default::default(),
+			max_watched_paths: Default::default(),
+			watch_limit: Default::default(),
+			follow_symlinks: Default::default(),
+			path_canonicalization: Default::default(),
+			event_kinds: Default::default(),
+			ignore_metadata_only_events: Default::default(),
+			coalesce_renames: Default::default(),
+			watch_check_interval: Default::default(),
+			watch_ancestors: Default::default(),
+			ready_event: Default::default(),
+			process_origins: Default::default(),
 			keyboard_events: Default::default(),
 			throttle: Changeable::new(Duration::from_millis(50)),
+			initial_throttle: Default::default(),
+			throttle_mode: Default::default(),
+			adaptive_throttle: Default::default(),
+			throttle_state_file: Default::default(),
+			max_paths_per_action: Default::default(),
+			dedup_runs_by_paths: Default::default(),
+			sort_events: Default::default(),
+			max_concurrent_jobs: Default::default(),
+			concurrency_policy: Default::default(),
+			ignore_command_outputs: Default::default(),
+			ignore_events_during_run: Default::default(),
+			command_outputs_settle: Changeable::new(Duration::from_millis(500)),
+			action_timeout: Default::default(),
+			quit_timeout: Changeable::new(Duration::from_secs(5)),
+			idle_timeout: Default::default(),
 			filterer: Default::default(),
 			error_channel_size: 64,
 			event_channel_size: 4096,
@@ -178,17 +710,36 @@ impl Config {
 	/// This is called automatically by all other methods here, so most of the time calling this
 	/// isn't needed, but it can be useful for some advanced uses.
 	pub fn signal_change(&self) -> &Self {
+		self.generation.fetch_add(1, Ordering::SeqCst);
 		self.change_signal.notify_waiters();
 		self
 	}
 
+	/// The current generation of the config, which goes up with every change signal.
+	pub(crate) fn generation(&self) -> u64 {
+		self.generation.load(Ordering::SeqCst)
+	}
+
+	/// Record that the filesystem worker has applied the config as of a generation.
+	pub(crate) fn mark_applied(&self, generation: u64) {
+		trace!(?generation, "config applied");
+		self.applied.send_if_modified(|applied| {
+			if *applied < generation {
+				*applied = generation;
+				true
+			} else {
+				false
+			}
+		});
+	}
+
 	/// Watch the config for a change, but run once first.
 	///
 	/// This returns a Stream where the first value is available immediately, and then every
 	/// subsequent one is from a change signal for this Config.
 	#[must_use]
 	pub(crate) fn watch(&self) -> ConfigWatched {
-		ConfigWatched::new(self.change_signal.clone())
+		ConfigWatched::new(self.change_signal.clone(), self.generation.clone())
 	}
 
 	/// Set the pathset to be watched.
@@ -203,6 +754,76 @@ impl Config {
 		self.signal_change()
 	}
 
+	/// The pathset as it's watched, with paths canonicalised.
+	///
+	/// Paths which exist are made absolute, with symlinks and `.` and `..` components resolved.
+	/// With [`PathCanonicalization::Symbolic`], symlinks are kept instead, and paths are made
+	/// absolute whether they exist or not. Paths which don't exist are otherwise kept as they are:
+	/// check with [`WatchedPath::path()`]`.exists()`. Paths which resolve to the same watch are
+	/// only listed once, the first time they appear; the order of the pathset is otherwise kept.
+	///
+	/// This reflects the pathset at the time it's called, including paths added by the
+	/// [`origin_scan`](Self::origin_scan) so far, and without paths collapsed by
+	/// [`dedupe_pathset`](Self::dedupe_pathset).
+	#[must_use]
+	pub fn watched_paths(&self) -> Vec<WatchedPath> {
+		let symbolic = self.path_canonicalization.get() == PathCanonicalization::Symbolic;
+		let mut watched: Vec<WatchedPath> = Vec::new();
+		for path in self.pathset.get() {
+			let path = if symbolic {
+				WatchedPath {
+					path: symbolic_path(&path.path),
+					..path
+				}
+			} else {
+				match dunce::canonicalize(&path.path) {
+					Ok(canonical) => WatchedPath {
+						path: canonical,
+						..path
+					},
+					Err(_) => path,
+				}
+			};
+
+			if !watched.contains(&path) {
+				watched.push(path);
+			}
+		}
+
+		if self.dedupe_pathset.get() {
+			dedupe_pathset(watched)
+		} else {
+			watched
+		}
+	}
+
+	/// Collapse overlapping paths in the pathset, see [`dedupe_pathset`](Self::dedupe_pathset).
+	pub fn dedupe_pathset(&self, enable: bool) -> &Self {
+		debug!(?enable, "Config: dedupe pathset");
+		self.dedupe_pathset.replace(enable);
+		self.signal_change()
+	}
+
+	/// Set the paths under which changes trigger actions, see
+	/// [`trigger_paths`](Self::trigger_paths).
+	pub fn trigger_paths<I, P>(&self, paths: I) -> &Self
+	where
+		I: IntoIterator<Item = P>,
+		P: Into<PathBuf>,
+	{
+		let paths: Vec<PathBuf> = paths.into_iter().map(Into::into).collect();
+		debug!(?paths, "Config: trigger paths");
+		self.trigger_paths.replace(paths);
+		self.signal_change()
+	}
+
+	/// Watch project origins found under a base path, see [`OriginScan`].
+	pub fn origin_scan(&self, scan: Option<OriginScan>) -> &Self {
+		debug!(?scan, "Config: origin scan");
+		self.origin_scan.replace(scan);
+		self.signal_change()
+	}
+
 	/// Set the file watcher type to use.
 	pub fn file_watcher(&self, watcher: Watcher) -> &Self {
 		debug!(?watcher, "Config: file watcher");
@@ -210,6 +831,94 @@ impl Config {
 		self.signal_change()
 	}
 
+	/// Set what to do when the filesystem watcher runs out of file descriptors.
+	pub fn fd_exhaustion(&self, strategy: FdExhaustion) -> &Self {
+		debug!(?strategy, "Config: fd exhaustion");
+		self.fd_exhaustion.replace(strategy);
+		self.signal_change()
+	}
+
+	/// Set the maximum number of paths the filesystem watcher may watch.
+	///
+	/// See [`max_watched_paths`](Self::max_watched_paths) for how paths are counted.
+	pub fn max_watched_paths(&self, max: Option<usize>) -> &Self {
+		debug!(?max, "Config: max watched paths");
+		self.max_watched_paths.replace(max);
+		self.signal_change()
+	}
+
+	/// Set what to do when the watched paths limit is reached.
+	pub fn watch_limit(&self, limit: WatchLimit) -> &Self {
+		debug!(?limit, "Config: watch limit");
+		self.watch_limit.replace(limit);
+		self.signal_change()
+	}
+
+	/// Set whether to follow watched paths which are symlinks.
+	pub fn follow_symlinks(&self, follow: bool) -> &Self {
+		debug!(?follow, "Config: follow symlinks");
+		self.follow_symlinks.replace(follow);
+		self.signal_change()
+	}
+
+	/// Set whether watched paths and event paths have their symlinks resolved, see
+	/// [`path_canonicalization`](Self::path_canonicalization).
+	pub fn path_canonicalization(&self, mode: PathCanonicalization) -> &Self {
+		debug!(?mode, "Config: path canonicalization");
+		self.path_canonicalization.replace(mode);
+		self.signal_change()
+	}
+
+	/// Set the kinds of filesystem events to let through.
+	pub fn event_kinds(&self, kinds: FsEventKinds) -> &Self {
+		debug!(?kinds, "Config: event kinds");
+		self.event_kinds.replace(kinds);
+		self.signal_change()
+	}
+
+	/// Drop events which only describe changes to metadata, see
+	/// [`ignore_metadata_only_events`](Self::ignore_metadata_only_events).
+	pub fn ignore_metadata_only_events(&self, enable: bool) -> &Self {
+		debug!(?enable, "Config: ignore metadata-only events");
+		self.ignore_metadata_only_events.replace(enable);
+		self.signal_change()
+	}
+
+	/// Pair up rename events into single events.
+	pub fn coalesce_renames(&self, enable: bool) -> &Self {
+		debug!(?enable, "Config: coalesce renames");
+		self.coalesce_renames.replace(enable);
+		self.signal_change()
+	}
+
+	/// Check watched paths at this interval, and re-watch them if they reappear after deletion.
+	pub fn watch_check_interval(&self, interval: Option<Duration>) -> &Self {
+		debug!(?interval, "Config: watch check interval");
+		self.watch_check_interval.replace(interval);
+		self.signal_change()
+	}
+
+	/// Follow deleted watched paths through their ancestors, to watch them again when recreated.
+	pub fn watch_ancestors(&self, follow: Option<AncestorWatch>) -> &Self {
+		debug!(?follow, "Config: watch ancestors");
+		self.watch_ancestors.replace(follow);
+		self.signal_change()
+	}
+
+	/// Emit an event once the filesystem watcher is set up.
+	pub fn ready_event(&self, enable: bool) -> &Self {
+		debug!(?enable, "Config: ready event");
+		self.ready_event.replace(enable);
+		self.signal_change()
+	}
+
+	/// Attribute filesystem events to the processes which caused them, where possible.
+	pub fn process_origins(&self, enable: bool) -> &Self {
+		debug!(?enable, "Config: process origins");
+		self.process_origins.replace(enable);
+		self.signal_change()
+	}
+
 	/// Enable keyboard/stdin event source.
 	pub fn keyboard_events(&self, enable: bool) -> &Self {
 		debug!(?enable, "Config: keyboard");
@@ -225,6 +934,118 @@ impl Config {
 		self.signal_change()
 	}
 
+	/// Set the throttle to use until the first action.
+	///
+	/// See [`initial_throttle`](Self::initial_throttle).
+	pub fn initial_throttle(&self, throttle: Option<Duration>) -> &Self {
+		debug!(?throttle, "Config: initial throttle");
+		self.initial_throttle.replace(throttle);
+		self.signal_change()
+	}
+
+	/// Set which edge of the throttle window actions are run on.
+	pub fn throttle_mode(&self, mode: ThrottleMode) -> &Self {
+		debug!(?mode, "Config: throttle mode");
+		self.throttle_mode.replace(mode);
+		self.signal_change()
+	}
+
+	/// Adapt the throttle to how long commands take to run, within bounds.
+	pub fn adaptive_throttle(&self, bounds: Option<AdaptiveThrottle>) -> &Self {
+		debug!(?bounds, "Config: adaptive throttle");
+		self.adaptive_throttle.replace(bounds);
+		self.signal_change()
+	}
+
+	/// Set a file to keep the time of the last action in, across restarts.
+	pub fn throttle_state_file(&self, path: Option<PathBuf>) -> &Self {
+		debug!(?path, "Config: throttle state file");
+		self.throttle_state_file.replace(path);
+		self.signal_change()
+	}
+
+	/// Set the maximum number of distinct paths given to an action.
+	pub fn max_paths_per_action(&self, max: Option<usize>) -> &Self {
+		debug!(?max, "Config: max paths per action");
+		self.max_paths_per_action.replace(max);
+		self.signal_change()
+	}
+
+	/// Skip actions for the same paths as the last action, within a window.
+	pub fn dedup_runs_by_paths(&self, window: Option<Duration>) -> &Self {
+		debug!(?window, "Config: dedup runs by paths");
+		self.dedup_runs_by_paths.replace(window);
+		self.signal_change()
+	}
+
+	/// Sort the events of each action by path then kind, see [`sort_events`](Self::sort_events).
+	pub fn sort_events(&self, enable: bool) -> &Self {
+		debug!(?enable, "Config: sort events");
+		self.sort_events.replace(enable);
+		self.signal_change()
+	}
+
+	/// Set the maximum number of commands running at once, across all jobs.
+	pub fn max_concurrent_jobs(&self, max: Option<usize>) -> &Self {
+		debug!(?max, "Config: max concurrent jobs");
+		self.max_concurrent_jobs.replace(max);
+		self.signal_change()
+	}
+
+	/// Set what to do with commands which would go over the limit of concurrent jobs.
+	pub fn concurrency_policy(&self, policy: ConcurrencyPolicy) -> &Self {
+		debug!(?policy, "Config: concurrency policy");
+		self.concurrency_policy.replace(policy);
+		self.signal_change()
+	}
+
+	/// Set paths written to by the commands, whose events are dropped while commands run.
+	pub fn ignore_command_outputs<I, P>(&self, paths: I) -> &Self
+	where
+		I: IntoIterator<Item = P>,
+		P: Into<PathBuf>,
+	{
+		let paths: Vec<PathBuf> = paths.into_iter().map(Into::into).collect();
+		debug!(?paths, "Config: ignore command outputs");
+		self.ignore_command_outputs.replace(paths);
+		self.signal_change()
+	}
+
+	/// Drop all filesystem events while commands run.
+	pub fn ignore_events_during_run(&self, enable: bool) -> &Self {
+		debug!(?enable, "Config: ignore events during run");
+		self.ignore_events_during_run.replace(enable);
+		self.signal_change()
+	}
+
+	/// Set how long after commands end to keep dropping events for their outputs, or all events.
+	pub fn command_outputs_settle(&self, window: Duration) -> &Self {
+		debug!(?window, "Config: command outputs settle");
+		self.command_outputs_settle.replace(window);
+		self.signal_change()
+	}
+
+	/// Set how long an action handler can run before it's considered stuck.
+	pub fn action_timeout(&self, timeout: Option<Duration>) -> &Self {
+		debug!(?timeout, "Config: action timeout");
+		self.action_timeout.replace(timeout);
+		self.signal_change()
+	}
+
+	/// Set how long to wait for the quit hook to complete.
+	pub fn quit_timeout(&self, timeout: Duration) -> &Self {
+		debug!(?timeout, "Config: quit timeout");
+		self.quit_timeout.replace(timeout);
+		self.signal_change()
+	}
+
+	/// Set how long to wait without activity before quitting.
+	pub fn idle_timeout(&self, timeout: Option<Duration>) -> &Self {
+		debug!(?timeout, "Config: idle timeout");
+		self.idle_timeout.replace(timeout);
+		self.signal_change()
+	}
+
 	/// Set the filterer implementation to use.
 	pub fn filterer(&self, filterer: impl Filterer + Send + Sync + 'static) -> &Self {
 		debug!(?filterer, "Config: filterer");
@@ -239,6 +1060,20 @@ impl Config {
 		self.signal_change()
 	}
 
+	/// Limit how often identical errors are given to the error handler.
+	///
+	/// At most `max_per_interval` identical errors are given to the handler as they come within
+	/// each `interval`, and the rest are coalesced. See [`ErrorRateLimit`] for details.
+	pub fn error_rate_limit(&self, max_per_interval: usize, interval: Duration) -> &Self {
+		let limit = ErrorRateLimit {
+			max_per_interval,
+			interval,
+		};
+		debug!(?limit, "Config: error rate limit");
+		self.error_rate_limit.replace(Some(limit));
+		self.signal_change()
+	}
+
 	/// Set the action handler.
 	pub fn on_action(
 		&self,
@@ -263,38 +1098,439 @@ impl Config {
 			.replace(move |action| ActionReturn::Async(handler(action)));
 		self.signal_change()
 	}
+
+	/// Set the pre-spawn hook.
+	///
+	/// This is awaited before every command is spawned, for all jobs. Return an error from the
+	/// future to cancel that spawn.
+	pub fn on_pre_spawn(
+		&self,
+		handler: impl (Fn(SpawnContext) -> PreSpawnReturn) + Send + Sync + 'static,
+	) -> &Self {
+		debug!("Config: on_pre_spawn");
+		self.pre_spawn_handler.replace(handler);
+		self.signal_change()
+	}
+
+	/// Set the post-spawn hook.
+	///
+	/// This is awaited after every command is spawned, for all jobs.
+	pub fn on_post_spawn(
+		&self,
+		handler: impl (Fn(SpawnContext) -> PostSpawnReturn) + Send + Sync + 'static,
+	) -> &Self {
+		debug!("Config: on_post_spawn");
+		self.post_spawn_handler.replace(handler);
+		self.signal_change()
+	}
+
+	/// Set the quit hook.
+	///
+	/// This is awaited once when Watchexec quits, after jobs are stopped.
+	pub fn on_quit(&self, handler: impl (Fn() -> QuitReturn) + Send + Sync + 'static) -> &Self {
+		debug!("Config: on_quit");
+		self.quit_handler.replace(move |()| handler());
+		self.signal_change()
+	}
+
+	/// Set the action timeout hook.
+	///
+	/// This is called with the [action timeout](Self::action_timeout) when an action handler
+	/// hasn't returned within it.
+	pub fn on_action_timeout(&self, handler: impl Fn(Duration) + Send + Sync + 'static) -> &Self {
+		debug!("Config: on_action_timeout");
+		self.action_timeout_handler.replace(handler);
+		self.signal_change()
+	}
+
+	/// Set the action task executor.
+	///
+	/// See [`action_executor`](Self::action_executor).
+	pub fn action_executor(
+		&self,
+		executor: impl (Fn(ActionTask) -> JoinHandle<()>) + Send + Sync + 'static,
+	) -> &Self {
+		debug!("Config: action_executor");
+		self.action_executor.replace(executor);
+		self.signal_change()
+	}
+
+	/// Check the configuration for mistakes before running.
+	///
+	/// Misconfigurations otherwise only show up as runtime errors, or as Watchexec silently doing
+	/// nothing. This checks with the default [`Validation`], and returns all hard errors at once;
+	/// wrap them in [`CriticalError::InvalidConfig`](crate::error::CriticalError::InvalidConfig) to
+	/// report them together. Warnings are logged instead.
+	///
+	/// This isn't called by Watchexec itself: call it before [`main()`](crate::Watchexec::main).
+	pub fn validate(&self) -> Result<(), Vec<ConfigError>> {
+		self.validate_with(Validation::default())
+	}
+
+	/// Check the configuration for mistakes before running, with custom strictness.
+	///
+	/// See [`validate()`](Self::validate) and [`Validation`].
+	pub fn validate_with(&self, validation: Validation) -> Result<(), Vec<ConfigError>> {
+		let mut errors = Vec::new();
+
+		if !self.action_handler.is_set() {
+			errors.push(ConfigError::NoActionHandler);
+		}
+
+		let pathset = self.pathset.get();
+		if pathset.is_empty() && !validation.allow_empty_pathset {
+			errors.push(ConfigError::EmptyPathset);
+		}
+
+		for path in pathset {
+			if path.path.exists() {
+				continue;
+			}
+
+			if validation.missing_paths_are_errors {
+				errors.push(ConfigError::MissingPath { path: path.path });
+			} else {
+				warn!(path=?path.path, "Config: watched path does not exist");
+			}
+		}
+
+		if !self.filterer.is_set() {
+			if validation.require_filterer {
+				errors.push(ConfigError::NoFilterer);
+			} else {
+				warn!("Config: no filterer, all events will pass");
+			}
+		}
+
+		if errors.is_empty() {
+			Ok(())
+		} else {
+			Err(errors)
+		}
+	}
+
+	/// Make a copy of this config which doesn't share its values.
+	///
+	/// Cloning a `Config` shares all its values, such that changes to a clone apply to the
+	/// original. This instead copies every value into a new, independent config, which can be
+	/// kept around to compare against later with [`diff()`](Self::diff). The handlers and the
+	/// filterer are the same instances as in this config, until replaced in either.
+	///
+	/// The copy has its own change signal, and is not connected to any Watchexec instance.
+	#[must_use]
+	pub fn snapshot(&self) -> Self {
+		Self {
+			change_signal: Default::default(),
+			generation: Default::default(),
+			applied: Arc::new(watch::channel(0).0),
+			action_handler: self.action_handler.snapshot(),
+			error_handler: self.error_handler.snapshot(),
+			error_rate_limit: Changeable::new(self.error_rate_limit.get()),
+			pre_spawn_handler: self.pre_spawn_handler.snapshot(),
+			post_spawn_handler: self.post_spawn_handler.snapshot(),
+			quit_handler: self.quit_handler.snapshot(),
+			action_timeout_handler: self.action_timeout_handler.snapshot(),
+			action_executor: self.action_executor.snapshot(),
+			pathset: Changeable::new(self.pathset.get()),
+			dedupe_pathset: Changeable::new(self.dedupe_pathset.get()),
+			trigger_paths: Changeable::new(self.trigger_paths.get()),
+			origin_scan: Changeable::new(self.origin_scan.get()),
+			file_watcher: Changeable::new(self.file_watcher.get()),
+			fd_exhaustion: Changeable::new(self.fd_exhaustion.get()),
+			max_watched_paths: Changeable::new(self.max_watched_paths.get()),
+			watch_limit: Changeable::new(self.watch_limit.get()),
+			follow_symlinks: Changeable::new(self.follow_symlinks.get()),
+			path_canonicalization: Changeable::new(self.path_canonicalization.get()),
+			event_kinds: Changeable::new(self.event_kinds.get()),
+			ignore_metadata_only_events: Changeable::new(self.ignore_metadata_only_events.get()),
+			coalesce_renames: Changeable::new(self.coalesce_renames.get()),
+			watch_check_interval: Changeable::new(self.watch_check_interval.get()),
+			watch_ancestors: Changeable::new(self.watch_ancestors.get()),
+			ready_event: Changeable::new(self.ready_event.get()),
+			process_origins: Changeable::new(self.process_origins.get()),
+			keyboard_events: Changeable::new(self.keyboard_events.get()),
+			throttle: Changeable::new(self.throttle.get()),
+			initial_throttle: Changeable::new(self.initial_throttle.get()),
+			throttle_mode: Changeable::new(self.throttle_mode.get()),
+			adaptive_throttle: Changeable::new(self.adaptive_throttle.get()),
+			throttle_state_file: Changeable::new(self.throttle_state_file.get()),
+			max_paths_per_action: Changeable::new(self.max_paths_per_action.get()),
+			dedup_runs_by_paths: Changeable::new(self.dedup_runs_by_paths.get()),
+			sort_events: Changeable::new(self.sort_events.get()),
+			max_concurrent_jobs: Changeable::new(self.max_concurrent_jobs.get()),
+			concurrency_policy: Changeable::new(self.concurrency_policy.get()),
+			ignore_command_outputs: Changeable::new(self.ignore_command_outputs.get()),
+			ignore_events_during_run: Changeable::new(self.ignore_events_during_run.get()),
+			command_outputs_settle: Changeable::new(self.command_outputs_settle.get()),
+			action_timeout: Changeable::new(self.action_timeout.get()),
+			quit_timeout: Changeable::new(self.quit_timeout.get()),
+			idle_timeout: Changeable::new(self.idle_timeout.get()),
+			filterer: self.filterer.snapshot(),
+			error_channel_size: self.error_channel_size,
+			event_channel_size: self.event_channel_size,
+		}
+	}
+
+	/// Describe what changed from this config to the `other` one.
+	///
+	/// Values are compared by equality. The handlers and the filterer can't be compared that way,
+	/// so they're compared by identity instead: they're reported as replaced unless both configs
+	/// hold the same instance. This is the case for a config and its [`snapshot()`](Self::snapshot)
+	/// until either is changed, but not for two configs created separately.
+	///
+	/// This is meant for reporting, like logging what changed before and after reconfiguring.
+	#[must_use]
+	pub fn diff(&self, other: &Self) -> ConfigDiff {
+		let mut diff = ConfigDiff::default();
+
+		macro_rules! replaced {
+			($($field:ident),+) => {$(
+				if !self.$field.same_as(&other.$field) {
+					diff.fields.push(FieldDiff {
+						field: stringify!($field),
+						values: None,
+					});
+				}
+			)+};
+		}
+
+		macro_rules! changed {
+			($($field:ident),+) => {$(
+				let (old, new) = (self.$field.get(), other.$field.get());
+				if old != new {
+					diff.fields.push(FieldDiff {
+						field: stringify!($field),
+						values: Some((format!("{old:?}"), format!("{new:?}"))),
+					});
+				}
+			)+};
+		}
+
+		replaced!(
+			action_handler,
+			error_handler,
+			pre_spawn_handler,
+			post_spawn_handler,
+			quit_handler,
+			action_timeout_handler,
+			action_executor
+		);
+
+		let (old_pathset, new_pathset) = (self.pathset.get(), other.pathset.get());
+		diff.pathset_added = new_pathset
+			.iter()
+			.filter(|path| !old_pathset.contains(path))
+			.cloned()
+			.collect();
+		diff.pathset_removed = old_pathset
+			.iter()
+			.filter(|path| !new_pathset.contains(path))
+			.cloned()
+			.collect();
+
+		changed!(
+			error_rate_limit,
+			dedupe_pathset,
+			trigger_paths,
+			origin_scan,
+			file_watcher,
+			fd_exhaustion,
+			max_watched_paths,
+			watch_limit,
+			follow_symlinks,
+			path_canonicalization,
+			event_kinds,
+			ignore_metadata_only_events,
+			coalesce_renames,
+			watch_check_interval,
+			watch_ancestors,
+			ready_event,
+			process_origins,
+			keyboard_events,
+			throttle,
+			initial_throttle,
+			throttle_mode,
+			adaptive_throttle,
+			throttle_state_file,
+			max_paths_per_action,
+			dedup_runs_by_paths,
+			sort_events,
+			max_concurrent_jobs,
+			concurrency_policy,
+			ignore_command_outputs,
+			ignore_events_during_run,
+			command_outputs_settle,
+			action_timeout,
+			quit_timeout,
+			idle_timeout
+		);
+		replaced!(filterer);
+
+		for (field, old, new) in [
+			(
+				"error_channel_size",
+				self.error_channel_size,
+				other.error_channel_size,
+			),
+			(
+				"event_channel_size",
+				self.event_channel_size,
+				other.event_channel_size,
+			),
+		] {
+			if old != new {
+				diff.fields.push(FieldDiff {
+					field,
+					values: Some((old.to_string(), new.to_string())),
+				});
+			}
+		}
+
+		diff
+	}
+}
+
+/// The differences between two [`Config`]s, as obtained with [`Config::diff()`].
+///
+/// The `Display` implementation writes one change per line, like:
+///
+/// ```text
+/// pathset: + /new/path
+/// pathset: - /old/path (non-recursive)
+/// throttle: 50ms -> 100ms
+/// filterer: replaced
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ConfigDiff {
+	/// Paths in the new pathset which weren't in the old one.
+	pub pathset_added: Vec<WatchedPath>,
+
+	/// Paths in the old pathset which aren't in the new one.
+	pub pathset_removed: Vec<WatchedPath>,
+
+	/// Other fields which changed, in the order they're declared in [`Config`].
+	pub fields: Vec<FieldDiff>,
+}
+
+impl ConfigDiff {
+	/// Whether nothing changed.
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.pathset_added.is_empty() && self.pathset_removed.is_empty() && self.fields.is_empty()
+	}
+}
+
+impl fmt::Display for ConfigDiff {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for (sign, path) in self
+			.pathset_added
+			.iter()
+			.map(|path| ('+', path))
+			.chain(self.pathset_removed.iter().map(|path| ('-', path)))
+		{
+			write!(f, "pathset: {sign} {}", path.path.display())?;
+			if path.file {
+				write!(f, " (file)")?;
+			} else if !path.recursive {
+				write!(f, " (non-recursive)")?;
+			}
+			writeln!(f)?;
+		}
+
+		for field in &self.fields {
+			writeln!(f, "{field}")?;
+		}
+
+		Ok(())
+	}
+}
+
+/// A single changed field in a [`ConfigDiff`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FieldDiff {
+	/// The name of the field in [`Config`].
+	pub field: &'static str,
+
+	/// The `Debug` representations of the old and new values.
+	///
+	/// This is `None` for the handlers and the filterer, which can only be replaced.
+	pub values: Option<(String, String)>,
+}
+
+impl fmt::Display for FieldDiff {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.values {
+			Some((old, new)) => write!(f, "{}: {old} -> {new}", self.field),
+			None => write!(f, "{}: replaced", self.field),
+		}
+	}
 }
 
 #[derive(Debug)]
 pub(crate) struct ConfigWatched {
 	first_run: bool,
 	notify: Arc<Notify>,
+	generation: Arc<AtomicU64>,
+	seen: u64,
 }
 
 impl ConfigWatched {
-	fn new(notify: Arc<Notify>) -> Self {
+	fn new(notify: Arc<Notify>, generation: Arc<AtomicU64>) -> Self {
 		let notified = notify.notified();
 		pin!(notified).as_mut().enable();
 
 		Self {
 			first_run: true,
 			notify,
+			generation,
+			seen: 0,
 		}
 	}
 
-	pub async fn next(&mut self) {
+	/// Wait for a change, and return the generation of the config as of that change.
+	///
+	/// Changes made since the last call returned are seen even if they were signaled while nothing
+	/// was waiting, so this resolves immediately for those.
+	pub async fn next(&mut self) -> u64 {
 		let notified = self.notify.notified();
 		let mut notified = pin!(notified);
 		notified.as_mut().enable();
 
+		let current = self.generation.load(Ordering::SeqCst);
 		if self.first_run {
 			trace!("ConfigWatched: first run");
 			self.first_run = false;
+		} else if current != self.seen {
+			trace!(seen=?self.seen, ?current, "ConfigWatched: changed since last seen");
 		} else {
 			trace!(?notified, "ConfigWatched: waiting for change");
-			// there's a bit of a gotcha where any config changes made after a Notified resolves
-			// but before a new one is issued will not be caught. not sure how to fix that yet.
 			notified.await;
 		}
+
+		self.seen = self.generation.load(Ordering::SeqCst);
+		self.seen
 	}
 }
+
+/// How strictly [`Config::validate_with()`] checks a configuration.
+///
+/// With the default, these are hard errors:
+/// - no action handler was set;
+/// - the pathset is empty.
+///
+/// And these are only warnings, logged but not returned:
+/// - a watched path doesn't exist (it may be created later);
+/// - no filterer was set (every event will pass).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Validation {
+	/// Whether an empty pathset is fine, like when only other event sources are used.
+	pub allow_empty_pathset: bool,
+
+	/// Whether watched paths which don't exist are errors instead of warnings.
+	pub missing_paths_are_errors: bool,
+
+	/// Whether not setting a filterer is an error instead of a warning.
+	pub require_filterer: bool,
+}
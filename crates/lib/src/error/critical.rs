@@ -3,7 +3,7 @@ use thiserror::Error;
 use tokio::{sync::mpsc, task::JoinError};
 use watchexec_events::{Event, Priority};
 
-use super::{FsWatcherError, RuntimeError};
+use super::{ConfigError, FsWatcherError, RuntimeError};
 use crate::sources::fs::Watcher;
 
 /// Errors which are not recoverable and stop watchexec execution.
@@ -69,4 +69,12 @@ pub enum CriticalError {
 		#[source]
 		err: FsWatcherError,
 	},
+
+	/// Error returned when the configuration doesn't pass [validation](crate::Config::validate).
+	#[error("invalid configuration")]
+	InvalidConfig {
+		/// Every problem found.
+		#[related]
+		errors: Vec<ConfigError>,
+	},
 }
@@ -51,6 +51,40 @@ pub enum FsWatcherError {
 		err: notify::Error,
 	},
 
+	/// A path couldn't be watched because the process ran out of file descriptors, and is polled
+	/// instead.
+	///
+	/// This is only emitted with [`FdExhaustion::Poll`](crate::sources::fs::FdExhaustion::Poll),
+	/// and can be treated as a warning: the path is still watched.
+	#[error("too many open files while adding {path:?}, polling it instead")]
+	#[diagnostic(help(
+		"raise your `nofile` limit (see `ulimit -n`), or watch fewer or smaller directories"
+	))]
+	PollFallback {
+		/// The path that is polled.
+		path: PathBuf,
+
+		/// The underlying error.
+		#[source]
+		err: notify::Error,
+	},
+
+	/// A path wasn't watched because it would go over the
+	/// [watched paths limit](crate::Config::max_watched_paths).
+	///
+	/// The remaining paths in the pathset aren't watched either. With
+	/// [`WatchLimit::Warn`](crate::sources::fs::WatchLimit::Warn), this can be treated as a warning:
+	/// the paths before it are still watched.
+	#[error("not watching {path:?} and after: would watch more than {limit} paths")]
+	#[diagnostic(help("watch fewer or smaller directories, or raise the limit"))]
+	PathLimit {
+		/// The first path that isn't watched.
+		path: PathBuf,
+
+		/// The limit.
+		limit: usize,
+	},
+
 	/// Error received when removing from the pathset for the filesystem watcher fails.
 	#[error("while removing {path:?}")]
 	PathRemove {
@@ -71,3 +105,43 @@ pub enum KeyboardWatcherError {
 	#[error("failed to shut down stdin watcher")]
 	StdinShutdown,
 }
+
+/// Problems found when [validating](crate::Config::validate) a configuration.
+///
+/// These are only the hard errors; see [`Validation`](crate::config::Validation) for what is
+/// merely warned about.
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum ConfigError {
+	/// No action handler was set, so Watchexec would never do anything, not even quit.
+	#[error("no action handler")]
+	#[diagnostic(help("set one with `Config::on_action()`, or use `Watchexec::new()`"))]
+	NoActionHandler,
+
+	/// The pathset is empty, so no filesystem events will be received.
+	#[error("no paths to watch")]
+	#[diagnostic(help(
+		"set paths with `Config::pathset()`, or allow an empty pathset if that's intended"
+	))]
+	EmptyPathset,
+
+	/// A path in the pathset doesn't exist.
+	///
+	/// This is only an error when the [`missing_paths_are_errors`] validation is enabled.
+	///
+	/// [`missing_paths_are_errors`]: crate::config::Validation::missing_paths_are_errors
+	#[error("watched path does not exist: {path:?}")]
+	MissingPath {
+		/// The path that doesn't exist.
+		path: PathBuf,
+	},
+
+	/// No filterer was set, so every event will pass.
+	///
+	/// This is only an error when the [`require_filterer`] validation is enabled.
+	///
+	/// [`require_filterer`]: crate::config::Validation::require_filterer
+	#[error("no filterer")]
+	#[diagnostic(help("set one with `Config::filterer()`"))]
+	NoFilterer,
+}
@@ -0,0 +1,130 @@
+use std::{
+	collections::HashMap,
+	error::Error,
+	mem::{discriminant, Discriminant},
+	time::Duration,
+};
+
+use tokio::time::Instant;
+
+use crate::error::RuntimeError;
+
+/// How often identical runtime errors can reach the error handler.
+///
+/// Within each `interval`, the first `max_per_interval` errors which are identical are given to the
+/// [error handler](crate::Config::on_error) as they come. Further identical errors are held back
+/// until the end of the interval: then the last of them is given to the handler, with
+/// [`ErrorHook::suppressed`](crate::ErrorHook::suppressed) set to how many more were dropped. The
+/// next identical error after that starts a new interval.
+///
+/// Two errors are identical when they're the same variant of [`RuntimeError`] and have the same
+/// message, along with the messages of their sources: for example, two
+/// [`FsWatcher`](RuntimeError::FsWatcher) errors are identical if they're from the same watcher
+/// kind, have the same [`FsWatcherError`](crate::error::FsWatcherError) variant and paths, and the
+/// same underlying I/O error. The [`Exit`](RuntimeError::Exit) pseudo-error is never limited.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ErrorRateLimit {
+	/// How many identical errors are given to the handler within an interval.
+	///
+	/// If this is zero, it's treated as one.
+	pub max_per_interval: usize,
+
+	/// How long an interval is, from the first error in it.
+	pub interval: Duration,
+}
+
+/// What makes errors identical for rate limiting.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct ErrorKey {
+	variant: Discriminant<RuntimeError>,
+	message: String,
+}
+
+impl ErrorKey {
+	fn of(err: &RuntimeError) -> Self {
+		let mut message = err.to_string();
+		let mut source = err.source();
+		while let Some(err) = source {
+			message.push_str(": ");
+			message.push_str(&err.to_string());
+			source = err.source();
+		}
+
+		Self {
+			variant: discriminant(err),
+			message,
+		}
+	}
+}
+
+#[derive(Debug)]
+struct Interval {
+	ends: Instant,
+	passed: usize,
+	suppressed: usize,
+	last: Option<RuntimeError>,
+}
+
+/// Keeps track of identical errors within their intervals.
+#[derive(Debug, Default)]
+pub(crate) struct ErrorLimiter {
+	intervals: HashMap<ErrorKey, Interval>,
+}
+
+impl ErrorLimiter {
+	/// Decide whether an error goes to the handler now, or is held back.
+	///
+	/// Returns the error if it should be given to the handler now.
+	pub fn admit(
+		&mut self,
+		err: RuntimeError,
+		limit: Option<ErrorRateLimit>,
+		now: Instant,
+	) -> Option<RuntimeError> {
+		let Some(limit) = limit else {
+			return Some(err);
+		};
+
+		let interval = self
+			.intervals
+			.entry(ErrorKey::of(&err))
+			.or_insert_with(|| Interval {
+				ends: now + limit.interval,
+				passed: 0,
+				suppressed: 0,
+				last: None,
+			});
+
+		if interval.passed < limit.max_per_interval.max(1) {
+			interval.passed += 1;
+			Some(err)
+		} else {
+			interval.suppressed += 1;
+			interval.last = Some(err);
+			None
+		}
+	}
+
+	/// When the earliest interval ends, if any.
+	pub fn next_end(&self) -> Option<Instant> {
+		self.intervals.values().map(|interval| interval.ends).min()
+	}
+
+	/// End intervals which are over, or all of them if `all` is true.
+	///
+	/// Returns the last error held back in each, with how many more identical errors were dropped.
+	pub fn end_intervals(&mut self, now: Instant, all: bool) -> Vec<(RuntimeError, usize)> {
+		let mut held = Vec::new();
+		self.intervals.retain(|_, interval| {
+			if !all && interval.ends > now {
+				return true;
+			}
+
+			if let Some(err) = interval.last.take() {
+				held.push((err, interval.suppressed - 1));
+			}
+			false
+		});
+		held
+	}
+}
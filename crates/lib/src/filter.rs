@@ -1,8 +1,12 @@
 //! The `Filterer` trait for event filtering.
 
-use std::{fmt, sync::Arc};
+use std::{
+	fmt,
+	path::{Path, PathBuf},
+	sync::Arc,
+};
 
-use watchexec_events::{Event, Priority};
+use watchexec_events::{Event, Priority, Tag};
 
 use crate::{changeable::Changeable, error::RuntimeError};
 
@@ -33,22 +37,213 @@ impl<T: Filterer> Filterer for Arc<T> {
 	}
 }
 
+/// A `Filterer` which passes events only if all of its filterers pass them.
+///
+/// Filterers are checked in order, and checking stops at the first one which rejects the event or
+/// returns an error: that error is returned, and later filterers aren't called. With no filterers,
+/// every event passes.
+#[derive(Debug, Default)]
+pub struct AndFilterer(pub Vec<Box<dyn Filterer>>);
+
+impl Filterer for AndFilterer {
+	fn check_event(&self, event: &Event, priority: Priority) -> Result<bool, RuntimeError> {
+		for filterer in &self.0 {
+			if !filterer.check_event(event, priority)? {
+				return Ok(false);
+			}
+		}
+
+		Ok(true)
+	}
+}
+
+/// A `Filterer` which passes events if any of its filterers pass them.
+///
+/// Filterers are checked in order, and checking stops at the first one which passes the event or
+/// returns an error: that error is returned, and later filterers aren't called. With no filterers,
+/// no event passes.
+#[derive(Debug, Default)]
+pub struct OrFilterer(pub Vec<Box<dyn Filterer>>);
+
+impl Filterer for OrFilterer {
+	fn check_event(&self, event: &Event, priority: Priority) -> Result<bool, RuntimeError> {
+		for filterer in &self.0 {
+			if filterer.check_event(event, priority)? {
+				return Ok(true);
+			}
+		}
+
+		Ok(false)
+	}
+}
+
+/// A `Filterer` which checks paths with the filterer of the origin they're in.
+///
+/// This is for watching several projects at once, each with their own filtering (like their own
+/// ignore files). Each origin is a path prefix with a filterer: paths are checked with the
+/// filterer of the most specific (deepest) origin they're in, and paths outside of all origins,
+/// as well as events without paths, are checked with the default filterer.
+///
+/// An event whose paths are in different origins is split: each filterer is given the event with
+/// only the paths it's responsible for (and all the other tags), and the event passes if any of
+/// them pass it. Errors are returned as soon as they happen.
+///
+/// # Examples
+///
+/// ```
+/// # use watchexec::filter::MultiOriginFilterer;
+/// let filterer = MultiOriginFilterer::new(())
+///     .with_origin("/code/frontend", ())
+///     .with_origin("/code/backend", ());
+/// ```
+#[derive(Debug)]
+pub struct MultiOriginFilterer {
+	/// Sorted deepest first, so the first match is the most specific.
+	origins: Vec<(PathBuf, Box<dyn Filterer>)>,
+	default: Box<dyn Filterer>,
+}
+
+impl MultiOriginFilterer {
+	/// Create with a default filterer, for paths outside all origins and events without paths.
+	pub fn new(default: impl Filterer + 'static) -> Self {
+		Self {
+			origins: Vec::new(),
+			default: Box::new(default),
+		}
+	}
+
+	/// Add an origin with its filterer, replacing any filterer already set for that origin.
+	#[must_use]
+	pub fn with_origin(
+		mut self,
+		origin: impl Into<PathBuf>,
+		filterer: impl Filterer + 'static,
+	) -> Self {
+		let origin = origin.into();
+		self.origins.retain(|(existing, _)| existing != &origin);
+		self.origins.push((origin, Box::new(filterer)));
+		self.origins
+			.sort_by_key(|(origin, _)| std::cmp::Reverse(origin.components().count()));
+		self
+	}
+
+	/// The filterer which is responsible for a path.
+	pub fn filterer_for_path(&self, path: &Path) -> &dyn Filterer {
+		self.filterer(self.origin_of(path))
+	}
+
+	fn filterer(&self, origin: Option<usize>) -> &dyn Filterer {
+		origin.map_or(self.default.as_ref(), |n| self.origins[n].1.as_ref())
+	}
+
+	fn origin_of(&self, path: &Path) -> Option<usize> {
+		self.origins
+			.iter()
+			.position(|(origin, _)| path.starts_with(origin))
+	}
+}
+
+impl Filterer for MultiOriginFilterer {
+	fn check_event(&self, event: &Event, priority: Priority) -> Result<bool, RuntimeError> {
+		let mut groups: Vec<(Option<usize>, Vec<Tag>)> = Vec::new();
+		for (path, _) in event.paths() {
+			let origin = self.origin_of(path);
+			if !groups.iter().any(|(existing, _)| *existing == origin) {
+				groups.push((origin, Vec::new()));
+			}
+		}
+
+		match groups.len() {
+			0 => self.default.check_event(event, priority),
+			1 => self.filterer(groups[0].0).check_event(event, priority),
+			_ => {
+				for tag in &event.tags {
+					if let Tag::Path { path, .. } = tag {
+						let origin = self.origin_of(path);
+						for (existing, tags) in &mut groups {
+							if *existing == origin {
+								tags.push(tag.clone());
+							}
+						}
+					} else {
+						for (_, tags) in &mut groups {
+							tags.push(tag.clone());
+						}
+					}
+				}
+
+				for (origin, tags) in groups {
+					let split = Event {
+						tags,
+						metadata: event.metadata.clone(),
+					};
+					if self.filterer(origin).check_event(&split, priority)? {
+						return Ok(true);
+					}
+				}
+
+				Ok(false)
+			}
+		}
+	}
+}
+
 /// A shareable `Filterer` that doesn't hold a lock when it is called.
 ///
 /// This is a specialisation of [`Changeable`] for `Filterer`.
-pub struct ChangeableFilterer(Changeable<Arc<dyn Filterer>>);
+///
+/// Replacing the filterer is safe while events are being checked: each
+/// [`check_event()`](Filterer::check_event) call takes the current filterer (a cheap `Arc` clone,
+/// under a short read lock) and runs entirely against it. A check that's in flight when the
+/// filterer is replaced completes with the old filterer, which is kept alive until then; it never
+/// sees a mix of both. Checks which start after [`replace()`](Self::replace) returns see the new
+/// filterer: the lock's release and acquire make everything the new filterer was built with
+/// visible to them. Checks racing with a replacement see either one, whole.
+///
+/// To make several checks against the same filterer regardless of replacements, take it once
+/// with [`current()`](Self::current).
+///
+/// Alongside the filterer, this tracks whether it was ever replaced from the default no-op.
+pub struct ChangeableFilterer(Changeable<(Arc<dyn Filterer>, bool)>);
 impl ChangeableFilterer {
 	/// Replace the filterer with a new one.
 	///
 	/// Panics if the lock was poisoned.
 	pub fn replace(&self, new: impl Filterer + Send + Sync + 'static) {
-		self.0.replace(Arc::new(new));
+		self.0.replace((Arc::new(new), true));
+	}
+
+	/// Get the current filterer.
+	///
+	/// This is a snapshot: replacing the filterer afterwards doesn't affect it.
+	///
+	/// Panics if the lock was poisoned.
+	#[must_use]
+	pub fn current(&self) -> Arc<dyn Filterer> {
+		self.0.get().0
+	}
+
+	/// Copy into a new `ChangeableFilterer` holding the same filterer, but which can be replaced
+	/// independently.
+	pub(crate) fn snapshot(&self) -> Self {
+		Self(Changeable::new(self.0.get()))
+	}
+
+	/// Whether the filterer was ever replaced, i.e. isn't the default no-op.
+	pub(crate) fn is_set(&self) -> bool {
+		self.0.get().1
+	}
+
+	/// Whether both hold the same filterer instance.
+	pub(crate) fn same_as(&self, other: &Self) -> bool {
+		// compare data pointers only, vtable pointers aren't guaranteed unique
+		Arc::as_ptr(&self.0.get().0).cast::<()>() == Arc::as_ptr(&other.0.get().0).cast::<()>()
 	}
 }
 
 impl Filterer for ChangeableFilterer {
 	fn check_event(&self, event: &Event, priority: Priority) -> Result<bool, RuntimeError> {
-		Arc::as_ref(&self.0.get()).check_event(event, priority)
+		self.current().check_event(event, priority)
 	}
 }
 
@@ -61,14 +256,14 @@ impl Clone for ChangeableFilterer {
 
 impl Default for ChangeableFilterer {
 	fn default() -> Self {
-		Self(Changeable::new(Arc::new(())))
+		Self(Changeable::new((Arc::new(()), false)))
 	}
 }
 
 impl fmt::Debug for ChangeableFilterer {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		f.debug_struct("ChangeableFilterer")
-			.field("filterer", &format!("{:?}", self.0.get()))
+			.field("filterer", &format!("{:?}", self.0.get().0))
 			.finish_non_exhaustive()
 	}
 }
@@ -59,6 +59,7 @@
 pub mod action;
 pub mod error;
 pub mod filter;
+pub mod notification;
 pub mod paths;
 pub mod sources;
 
@@ -66,6 +67,7 @@ pub mod sources;
 pub mod changeable;
 pub mod config;
 
+mod error_rate_limit;
 mod id;
 mod late_join_set;
 mod watched_path;
@@ -73,6 +75,7 @@ mod watchexec;
 
 #[doc(inline)]
 pub use crate::{
+	error_rate_limit::ErrorRateLimit,
 	id::Id,
 	watched_path::WatchedPath,
 	watchexec::{ErrorHook, Watchexec},
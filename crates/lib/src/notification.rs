@@ -0,0 +1,136 @@
+//! Notifications about commands, like desktop notifications.
+//!
+//! A [`Notifier`] shows [`Notification`]s. With the `desktop-notifications` feature, the
+//! [`DesktopNotifier`] shows them as desktop notifications; other notifiers can be written for
+//! other channels, or to record them. [`notify_on_outcome()`] notifies when a job's command ends,
+//! and is meant to be run from action handlers with
+//! [`ActionHandler::run_task()`](crate::action::ActionHandler::run_task()):
+//!
+//! ```no_run
+//! # use std::sync::Arc;
+//! # use watchexec::{command::{Command, Program}, notification::{notify_on_outcome, Notification}, Watchexec};
+//! # let command = Arc::new(Command { program: Program::Exec { prog: "make".into(), args: Vec::new(), argv0: None }, options: Default::default() });
+//! let notifier = |notification: &Notification| {
+//!     eprintln!("{}: {}", notification.summary, notification.body);
+//!     Ok(())
+//! };
+//! let wx = Watchexec::new(move |mut action| {
+//!     let (_, job) = action.create_job(command.clone());
+//!     job.start();
+//!     action.run_task(notify_on_outcome(job, notifier));
+//!     action
+//! });
+//! ```
+
+use std::error::Error;
+
+use tokio::sync::oneshot;
+use tracing::{debug, warn};
+use watchexec_events::ProcessEnd;
+use watchexec_supervisor::{
+	command::Command,
+	job::{CommandState, Job},
+};
+
+/// A notification about a command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Notification {
+	/// A short summary, used as the title of desktop notifications.
+	pub summary: String,
+
+	/// The text of the notification.
+	pub body: String,
+}
+
+impl Notification {
+	/// A notification that a command is starting because of a change.
+	#[must_use]
+	pub fn started(command: &Command) -> Self {
+		Self {
+			summary: "Watchexec: change detected".into(),
+			body: format!("Running {command}"),
+		}
+	}
+
+	/// A notification that a command has ended, with a description of how.
+	#[must_use]
+	pub fn ended(body: impl Into<String>) -> Self {
+		Self {
+			summary: "Watchexec: command ended".into(),
+			body: body.into(),
+		}
+	}
+
+	/// A notification that a command has ended with this status.
+	#[must_use]
+	pub fn outcome(status: ProcessEnd) -> Self {
+		Self::ended(match status {
+			ProcessEnd::Success => "Command was successful".into(),
+			ProcessEnd::ExitError(code) => format!("Command exited with {code}"),
+			ProcessEnd::ExitSignal(sig) => format!("Command killed by {sig:?}"),
+			ProcessEnd::ExitStop(sig) => format!("Command stopped by {sig:?}"),
+			ProcessEnd::Continued => "Command continued".into(),
+			ProcessEnd::Exception(ex) => format!("Command ended by exception {ex:#x}"),
+		})
+	}
+}
+
+/// Something which shows notifications.
+///
+/// This is implemented for closures taking a `&Notification`, which is handy for tests.
+pub trait Notifier: Send + Sync {
+	/// Show a notification.
+	fn notify(&self, notification: &Notification) -> Result<(), Box<dyn Error + Send + Sync>>;
+}
+
+impl<F> Notifier for F
+where
+	F: Fn(&Notification) -> Result<(), Box<dyn Error + Send + Sync>> + Send + Sync,
+{
+	fn notify(&self, notification: &Notification) -> Result<(), Box<dyn Error + Send + Sync>> {
+		self(notification)
+	}
+}
+
+/// Shows notifications as desktop notifications, with [`notify_rust`].
+///
+/// This needs the `desktop-notifications` feature.
+#[cfg(feature = "desktop-notifications")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DesktopNotifier;
+
+#[cfg(feature = "desktop-notifications")]
+impl Notifier for DesktopNotifier {
+	fn notify(&self, notification: &Notification) -> Result<(), Box<dyn Error + Send + Sync>> {
+		notify_rust::Notification::new()
+			.summary(&notification.summary)
+			.body(&notification.body)
+			.show()?;
+		Ok(())
+	}
+}
+
+/// Notify when the job's command ends, with how it ended.
+///
+/// This waits for the command which is running or about to start, as of when it's called, to end.
+/// If the job is stopped or deleted without the command having run, nothing is notified. If the
+/// notifier fails, a warning is logged.
+pub async fn notify_on_outcome(job: Job, notifier: impl Notifier) {
+	job.to_wait().await;
+
+	let (status_s, status_r) = oneshot::channel();
+	job.run(move |context| {
+		if let CommandState::Finished { status, .. } = context.current {
+			status_s.send(*status).ok();
+		}
+	})
+	.await;
+	let Ok(status) = status_r.await else {
+		debug!("command didn't end, not notifying");
+		return;
+	};
+
+	if let Err(err) = notifier.notify(&Notification::outcome(status)) {
+		warn!(%err, "failed to send notification");
+	}
+}
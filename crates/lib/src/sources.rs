@@ -2,4 +2,5 @@
 
 pub mod fs;
 pub mod keyboard;
+pub mod origins;
 pub mod signal;
@@ -1,20 +1,35 @@
 //! Event source for changes to files and directories.
 
 use std::{
+	cmp::Reverse,
 	collections::{HashMap, HashSet},
-	fs::metadata,
+	env::current_dir,
+	fs::{metadata, symlink_metadata},
+	future::pending,
 	mem::take,
+	path::{Path, PathBuf},
 	sync::Arc,
-	time::Duration,
+	time::{Duration, Instant},
 };
 
 use async_priority_channel as priority;
+use futures::future::{select, Either};
 use normalize_path::NormalizePath;
-use tokio::sync::mpsc;
-use tracing::{debug, error, trace};
-use watchexec_events::{Event, Priority, Source, Tag};
+use tokio::{
+	fs,
+	sync::{mpsc, Notify},
+	task::spawn_blocking,
+	time::{sleep, sleep_until},
+};
+use tracing::{debug, error, trace, warn};
+use watchexec_events::{
+	filekind::{CreateKind, FileEventKind, ModifyKind, RemoveKind},
+	metadata::{FILE_EVENT_INFO, NOTIFY_BACKEND, UID, WATCH},
+	Event, Priority, Source, Tag,
+};
 
 use crate::{
+	changeable::Changeable,
 	error::{CriticalError, FsWatcherError, RuntimeError},
 	Config,
 };
@@ -22,6 +37,10 @@ use crate::{
 // re-export for compatibility, until next major version
 pub use crate::WatchedPath;
 
+mod ancestors;
+mod origins;
+mod renames;
+
 /// What kind of filesystem watcher to use.
 ///
 /// For now only native and poll watchers are supported. In the future there may be additional
@@ -40,11 +59,274 @@ pub enum Watcher {
 	Poll(Duration),
 }
 
+/// Whether watched paths and event paths have their symlinks resolved.
+///
+/// See [`Config::path_canonicalization()`](crate::Config::path_canonicalization()).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PathCanonicalization {
+	/// Resolve symlinks where Watchexec compares or lists paths.
+	///
+	/// Symlinked watched paths are resolved when [following symlinks](crate::Config::follow_symlinks),
+	/// and [`Config::watched_paths()`](crate::Config::watched_paths()) and
+	/// [trigger paths](crate::Config::trigger_paths) are canonicalised. Event paths are reported as
+	/// the watcher gives them: some (like FSEvents on macOS) give resolved paths, while others
+	/// (like inotify on Linux) give paths under what they were asked to watch.
+	///
+	/// This is the default, and how Watchexec has always handled paths.
+	#[default]
+	Canonical,
+
+	/// Keep paths in the form they were given, made absolute.
+	///
+	/// Event paths under the resolved form of a watched path are reported under the watched path
+	/// as given instead, whether the watcher gave them resolved or the watched path was
+	/// [followed](crate::Config::follow_symlinks). `Config::watched_paths()` and trigger paths are
+	/// made absolute and normalised, but their symlinks are kept.
+	///
+	/// Paths are only kept symbolic down to the watched paths: symlinks found _within_ watched
+	/// directories are reported however the watcher reports them.
+	Symbolic,
+}
+
+impl Watcher {
+	/// The name of the backend this watcher uses on this platform.
+	///
+	/// This is what's set in the [`NOTIFY_BACKEND`] metadata of events from the watcher, see
+	/// there for the possible values.
+	#[must_use]
+	pub const fn backend(self) -> &'static str {
+		match self {
+			Self::Native if cfg!(any(target_os = "linux", target_os = "android")) => "inotify",
+			Self::Native if cfg!(target_os = "macos") => "fsevent",
+			Self::Native if cfg!(windows) => "windows",
+			Self::Native
+				if cfg!(any(
+					target_os = "freebsd",
+					target_os = "openbsd",
+					target_os = "netbsd",
+					target_os = "dragonfly",
+					target_os = "ios"
+				)) =>
+			{
+				"kqueue"
+			}
+			Self::Native | Self::Poll(_) => "poll",
+		}
+	}
+}
+
+bitflags::bitflags! {
+	/// A set of kinds of filesystem events.
+	///
+	/// This is used to drop filesystem events at the source, see
+	/// [`Config::event_kinds()`](crate::Config::event_kinds()).
+	///
+	/// The default is all kinds.
+	#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+	pub struct FsEventKinds: u8 {
+		/// Access events, like a file being opened, read, or closed.
+		const ACCESS = 1 << 0;
+
+		/// Creation events.
+		const CREATE = 1 << 1;
+
+		/// Removal events.
+		const REMOVE = 1 << 2;
+
+		/// Rename events.
+		const RENAME = 1 << 3;
+
+		/// Modification events, of the data or of an unknown kind.
+		const MODIFY = 1 << 4;
+
+		/// Modification events of metadata only, like permissions or timestamps.
+		const METADATA = 1 << 5;
+	}
+}
+
+impl Default for FsEventKinds {
+	fn default() -> Self {
+		Self::all()
+	}
+}
+
+impl FsEventKinds {
+	/// The kind a filesystem event is classified as.
+	///
+	/// Returns `None` for events which don't have a known kind (`Any` and `Other`): these are not
+	/// dropped by kind.
+	#[must_use]
+	pub fn of(kind: &FileEventKind) -> Option<Self> {
+		match kind {
+			FileEventKind::Access(_) => Some(Self::ACCESS),
+			FileEventKind::Create(_) => Some(Self::CREATE),
+			FileEventKind::Remove(_) => Some(Self::REMOVE),
+			FileEventKind::Modify(ModifyKind::Name(_)) => Some(Self::RENAME),
+			FileEventKind::Modify(ModifyKind::Metadata(_)) => Some(Self::METADATA),
+			FileEventKind::Modify(_) => Some(Self::MODIFY),
+			FileEventKind::Any | FileEventKind::Other => None,
+		}
+	}
+}
+
+/// What to do when the filesystem watcher runs out of file descriptors.
+///
+/// Some watchers, like kqueue (the native watcher on BSDs), open a file descriptor for every
+/// watched file and directory, and watching large trees can run into the process's limit on open
+/// files (`EMFILE`, "too many open files"). This is what to do when adding a path to the watcher
+/// fails that way, see [`Config::fd_exhaustion()`](crate::Config::fd_exhaustion()).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FdExhaustion {
+	/// Report the error, and leave the path unwatched.
+	#[default]
+	Error,
+
+	/// Raise the soft limit on open files as far as allowed, and try again.
+	///
+	/// The soft limit is raised to the hard limit (on macOS, to at most `OPEN_MAX`), once: if it's
+	/// already there, or on platforms without such limits, the error is reported as with `Error`.
+	RaiseLimit,
+
+	/// Watch the path with a poll watcher at this interval instead.
+	///
+	/// This is reported to the error hook as a [`FsWatcherError::PollFallback`], which can be
+	/// treated as a warning. Paths are polled until they're removed from the pathset, or the
+	/// watcher is recreated.
+	Poll(Duration),
+}
+
+/// What to do when the watched paths limit is reached.
+///
+/// See [`Config::max_watched_paths`](crate::Config::max_watched_paths).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WatchLimit {
+	/// Report a [`FsWatcherError::PathLimit`] to the error hook, and keep going with what's watched.
+	///
+	/// This can be treated as a warning.
+	#[default]
+	Warn,
+
+	/// Stop Watchexec with a critical error.
+	Error,
+}
+
+/// How to follow watched paths which are deleted, to watch them again when they're recreated.
+///
+/// See [`Config::watch_ancestors`](crate::Config::watch_ancestors).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AncestorWatch {
+	/// How many directories up from a deleted path to look for one which still exists.
+	///
+	/// The parent is one level up. Default is 3.
+	pub max_depth: usize,
+
+	/// How long to wait before looking again, when no ancestor within reach exists.
+	///
+	/// This doubles at every attempt which still finds nothing, up to
+	/// [`max_backoff`](Self::max_backoff), and starts over once an ancestor is watched. Default is
+	/// 100 milliseconds.
+	pub backoff: Duration,
+
+	/// The longest to wait between attempts. Default is 5 seconds.
+	pub max_backoff: Duration,
+}
+
+impl Default for AncestorWatch {
+	fn default() -> Self {
+		Self {
+			max_depth: 3,
+			backoff: Duration::from_millis(100),
+			max_backoff: Duration::from_secs(5),
+		}
+	}
+}
+
+impl AncestorWatch {
+	/// Look for an existing directory at most this many levels up from a deleted path.
+	#[must_use]
+	pub const fn max_depth(mut self, depth: usize) -> Self {
+		self.max_depth = depth;
+		self
+	}
+
+	/// Wait this long at first, and at most that long, between attempts to find an ancestor.
+	#[must_use]
+	pub const fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+		self.backoff = initial;
+		self.max_backoff = max;
+		self
+	}
+}
+
+/// Whether a notify error is the process running out of file descriptors.
+#[cfg(unix)]
+fn is_fd_exhaustion(err: &notify::Error) -> bool {
+	use nix::libc::EMFILE;
+
+	matches!(err.kind, notify::ErrorKind::Io(ref ioerr) if ioerr.raw_os_error() == Some(EMFILE))
+}
+
+#[cfg(not(unix))]
+fn is_fd_exhaustion(_err: &notify::Error) -> bool {
+	false
+}
+
+/// Raise the soft limit on open files to the hard limit.
+///
+/// Returns whether the limit was raised.
+#[cfg(unix)]
+fn raise_open_files_limit() -> bool {
+	use nix::sys::resource::{getrlimit, setrlimit, Resource};
+
+	let Ok((soft, hard)) = getrlimit(Resource::RLIMIT_NOFILE) else {
+		return false;
+	};
+
+	// macOS refuses limits above OPEN_MAX, even if the hard limit is higher
+	#[cfg(target_os = "macos")]
+	let hard = hard.min(10240);
+
+	if soft >= hard {
+		return false;
+	}
+
+	match setrlimit(Resource::RLIMIT_NOFILE, hard, hard) {
+		Ok(()) => {
+			warn!(from=?soft, to=?hard, "raised the limit on open files for the fs watcher");
+			true
+		}
+		Err(err) => {
+			debug!(%err, "could not raise the limit on open files");
+			false
+		}
+	}
+}
+
+#[cfg(not(unix))]
+fn raise_open_files_limit() -> bool {
+	false
+}
+
+/// The callback given to notify watchers, shared so it can be given to more than one.
+type Handler = Arc<dyn Fn(Result<notify::Event, notify::Error>) + Send + Sync>;
+
 impl Watcher {
+	fn create_with(
+		self,
+		handler: &Handler,
+	) -> Result<Box<dyn notify::Watcher + Send>, FsWatcherError> {
+		let handler = handler.clone();
+		self.create(move |nev: Result<notify::Event, notify::Error>| handler(nev))
+	}
+
 	fn create(
 		self,
 		f: impl notify::EventHandler,
-	) -> Result<Box<dyn notify::Watcher + Send>, CriticalError> {
+	) -> Result<Box<dyn notify::Watcher + Send>, FsWatcherError> {
 		use notify::{Config, Watcher as _};
 
 		match self {
@@ -56,9 +338,8 @@ impl Watcher {
 					.map(|w| Box::new(w) as _)
 			}
 		}
-		.map_err(|err| CriticalError::FsWatcherInit {
-			kind: self,
-			err: if cfg!(target_os = "linux")
+		.map_err(|err| {
+			if cfg!(target_os = "linux")
 				&& (matches!(err.kind, notify::ErrorKind::MaxFilesWatch)
 					|| matches!(err.kind, notify::ErrorKind::Io(ref ioerr) if ioerr.raw_os_error() == Some(28)))
 			{
@@ -69,7 +350,7 @@ impl Watcher {
 				FsWatcherError::TooManyHandles(err)
 			} else {
 				FsWatcherError::Create(err)
-			},
+			}
 		})
 	}
 }
@@ -113,13 +394,116 @@ pub async fn worker(
 	debug!("launching filesystem worker");
 
 	let mut watcher_type = Watcher::default();
-	let mut watcher = None;
-	let mut pathset = HashSet::new();
+	let mut follow_symlinks = false;
+	let mut watcher: Option<Box<dyn notify::Watcher + Send>> = None;
+	let mut handler: Option<Handler> = None;
+	// paths which couldn't be watched by the watcher, see FdExhaustion::Poll
+	let mut poll_watcher: Option<Box<dyn notify::Watcher + Send>> = None;
+	let mut polled: HashMap<WatchedPath, WatchedPath> = HashMap::new();
+	// maps watched paths as configured to the paths actually given to the watcher
+	let mut pathset: HashMap<WatchedPath, WatchedPath> = HashMap::new();
+	// how many paths each watched path counts for, see Config::max_watched_paths
+	let mut watch_counts: HashMap<WatchedPath, usize> = HashMap::new();
+	// watched paths as configured which have disappeared, see Config::watch_check_interval
+	let mut lost: HashSet<WatchedPath> = HashSet::new();
+	// maps lost paths to the ancestors watched for them, see Config::watch_ancestors
+	let mut followed: HashMap<WatchedPath, WatchedPath> = HashMap::new();
+	// when to look for ancestors of lost paths again, and the backoff that was waited
+	let mut retry: Option<(Instant, Duration)> = None;
+	// read by the watcher callback to hide ancestor events and notice lost paths
+	let ancestors: Changeable<Arc<ancestors::Ancestors>> = Changeable::default();
+	// woken by the watcher callback when it sees events which may concern lost paths
+	let wake = Arc::new(Notify::new());
+	// read by the watcher callback to drop events for the siblings of watched files
+	let file_watches: Changeable<Arc<FileWatches>> = Changeable::default();
+	// read by the watcher callback to report symbolic paths, see Config::path_canonicalization
+	let symbolic: Changeable<Arc<SymbolicPaths>> = Changeable::default();
+	// read by the watcher callback to attribute events, see Config::process_origins
+	let origins: Changeable<Option<Arc<origins::ProcessOrigins>>> = Changeable::default();
+	// whether the first config pass happened, see Config::ready_event
+	let mut setup_done = false;
 
 	let mut config_watch = config.watch();
 	loop {
-		config_watch.next().await;
-		trace!("filesystem worker got a config change");
+		let generation = {
+			// the change future is kept across checks so a config change isn't missed during one
+			let changed = config_watch.next();
+			tokio::pin!(changed);
+			loop {
+				let interval = config.watch_check_interval.get();
+				let follow = config.watch_ancestors.get();
+				let Some(watcher) = watcher
+					.as_mut()
+					.filter(|_| interval.is_some() || follow.is_some())
+				else {
+					break changed.await;
+				};
+
+				let check = async move {
+					match interval {
+						Some(interval) => sleep(interval).await,
+						None => pending().await,
+					}
+				};
+				let retry_at = async move {
+					match retry {
+						Some((at, _)) => sleep_until(at.into()).await,
+						None => pending().await,
+					}
+				};
+				let woken = async {
+					match follow {
+						Some(_) => wake.notified().await,
+						None => pending().await,
+					}
+				};
+				tokio::pin!(check, retry_at, woken);
+				let tick = select(select(check, retry_at), woken);
+				if let Either::Left((generation, _)) = select(&mut changed, tick).await {
+					break generation;
+				}
+
+				check_watched_paths(
+					watcher.as_mut(),
+					&mut pathset,
+					&mut lost,
+					follow_symlinks,
+					interval.is_some(),
+					&errors,
+					&events,
+				)
+				.await?;
+				if let Some(follow) = follow {
+					retry = follow_lost(
+						watcher.as_mut(),
+						&pathset,
+						&lost,
+						&mut followed,
+						follow,
+						retry,
+					)
+					.await;
+				}
+				file_watches.replace(Arc::new(file_watches_of(
+					pathset.values().chain(polled.values()),
+				)));
+				update_symbolic(&config, &symbolic, pathset.keys().chain(polled.keys()));
+				update_ancestors(&config, &ancestors, &pathset, &followed, &wake);
+			}
+		};
+		trace!(?generation, "filesystem worker got a config change");
+
+		if config.watch_ancestors.get().is_none() {
+			if let Some(watcher) = watcher.as_mut() {
+				unfollow_all(watcher.as_mut(), &pathset, &mut followed);
+			}
+			followed.clear();
+			retry = None;
+		}
+
+		if config.watch_check_interval.get().is_none() && config.watch_ancestors.get().is_none() {
+			lost.clear();
+		}
 
 		if config.pathset.get().is_empty() {
 			trace!(
@@ -131,47 +515,116 @@ pub async fn worker(
 				}
 			);
 			watcher.take();
+			poll_watcher.take();
 			pathset.clear();
+			polled.clear();
+			lost.clear();
+			followed.clear();
+			retry = None;
+			file_watches.replace(Arc::default());
+			symbolic.replace(Arc::default());
+			ancestors.replace(Arc::default());
+			update_process_origins(&config, &origins, std::iter::empty());
+			if !setup_done {
+				setup_done = true;
+				send_ready_event(&config, &errors, &events).await?;
+			}
+			config.mark_applied(generation);
 			continue;
 		}
 
 		// now we know the watcher should be alive, so let's start it if it's not already:
 
 		let config_watcher = config.file_watcher.get();
-		if watcher.is_none() || watcher_type != config_watcher {
-			debug!(kind=?config_watcher, "creating new watcher");
+		let config_follow_symlinks = config.follow_symlinks.get();
+		if watcher.is_none()
+			|| watcher_type != config_watcher
+			|| follow_symlinks != config_follow_symlinks
+		{
+			debug!(kind=?config_watcher, follow_symlinks=?config_follow_symlinks, "creating new watcher");
+			let n_config = config.clone();
 			let n_errors = errors.clone();
 			let n_events = events.clone();
 			watcher_type = config_watcher;
-			watcher = config_watcher
-				.create(move |nev: Result<notify::Event, notify::Error>| {
-					trace!(event = ?nev, "receiving possible event from watcher");
-					if let Err(e) = process_event(nev, config_watcher, &n_events) {
+			follow_symlinks = config_follow_symlinks;
+			// a new watcher starts with nothing watched
+			pathset.clear();
+			followed.clear();
+			poll_watcher.take();
+			polled.clear();
+			let emit: renames::Emit = {
+				let n_config = n_config.clone();
+				let file_watches = file_watches.clone();
+				let symbolic = symbolic.clone();
+				let origins = origins.clone();
+				let ancestors = ancestors.clone();
+				Arc::new(move |nev: Result<notify::Event, notify::Error>| {
+					let watched = WatchedState {
+						file_watches: file_watches.get(),
+						symbolic: symbolic.get(),
+						ancestors: ancestors.get(),
+						origins: origins.get(),
+					};
+					if let Err(e) = process_event(
+						nev,
+						config_watcher,
+						n_config.event_kinds.get(),
+						&watched,
+						&n_events,
+					) {
 						n_errors.try_send(e).ok();
 					}
 				})
-				.map(Some)?;
+			};
+			let renames = renames::RenameCoalescer::new(emit.clone());
+			let new_handler: Handler =
+				Arc::new(move |nev: Result<notify::Event, notify::Error>| {
+					trace!(event = ?nev, "receiving possible event from watcher");
+					match nev {
+						Ok(nev) if n_config.coalesce_renames.get() => renames.push(nev),
+						nev => emit(nev),
+					}
+				});
+			watcher = config_watcher
+				.create_with(&new_handler)
+				.map(Some)
+				.map_err(|err| CriticalError::FsWatcherInit {
+					kind: config_watcher,
+					err,
+				})?;
+			handler = Some(new_handler);
 		}
 
 		// now let's calculate which paths we should add to the watch, and which we should drop:
 
 		let config_pathset = config.pathset.get();
 		tracing::info!(?config_pathset, "obtaining pathset");
-		let (to_watch, to_drop) = if pathset.is_empty() {
+		lost.retain(|path| config_pathset.contains(path));
+		// lost paths are re-watched by the check when they reappear
+		let config_pathset: Vec<WatchedPath> = config_pathset
+			.into_iter()
+			.filter(|path| !lost.contains(path))
+			.collect();
+		let config_pathset = if config.dedupe_pathset.get() {
+			dedupe_pathset(config_pathset)
+		} else {
+			config_pathset
+		};
+		let (to_watch, to_drop) = if pathset.is_empty() && polled.is_empty() {
 			// if the current pathset is empty, we can take a shortcut
 			(config_pathset, Vec::new())
 		} else {
 			let mut to_watch = Vec::with_capacity(config_pathset.len());
 			let mut to_drop = Vec::with_capacity(pathset.len());
 
-			for path in &pathset {
+			for path in pathset.keys().chain(polled.keys()) {
 				if !config_pathset.contains(path) {
 					to_drop.push(path.clone()); // try dropping the clone?
 				}
 			}
 
 			for path in config_pathset {
-				if !pathset.contains(&path) {
+				if !pathset.contains_key(&path) && !polled.contains_key(&path) {
 					to_watch.push(path);
 				}
 			}
@@ -187,39 +640,688 @@ pub async fn worker(
 
 		debug!(?to_watch, ?to_drop, "applying changes to the watcher");
 
+		let dropped_any = !to_drop.is_empty();
 		for path in to_drop {
-			trace!(?path, "removing path from the watcher");
-			if let Err(err) = watcher.unwatch(path.path.as_ref()) {
+			if let (Some(watched), Some(poller)) = (polled.remove(&path), poll_watcher.as_mut()) {
+				trace!(?path, ?watched, "removing path from the poll watcher");
+				unwatch_path(poller.as_mut(), &polled, &watched).ok();
+				continue;
+			}
+
+			let Some(watched) = pathset.remove(&path) else {
+				continue;
+			};
+
+			trace!(?path, ?watched, "removing path from the watcher");
+			if let Err(err) = unwatch_path(watcher.as_mut(), &pathset, &watched) {
 				error!(?err, "notify unwatch() error");
-				for e in notify_multi_path_errors(watcher_type, path, err, true) {
+				pathset.insert(path, watched.clone());
+				for e in notify_multi_path_errors(watcher_type, watched, err, true) {
 					errors.send(e).await?;
 				}
-			} else {
-				pathset.remove(&path);
 			}
 		}
 
+		if dropped_any {
+			// dropped paths may have taken the watches of ancestors along
+			for ancestor in followed.values().collect::<HashSet<_>>() {
+				watch_path(watcher.as_mut(), &pathset, ancestor).ok();
+			}
+		}
+
+		let max_watched = config.max_watched_paths.get();
+		watch_counts.retain(|path, _| pathset.contains_key(path) || polled.contains_key(path));
+		let mut watched_count: usize = watch_counts.values().sum();
+
 		for path in to_watch {
-			trace!(?path, "adding path to the watcher");
-			if let Err(err) = watcher.watch(
-				path.path.as_ref(),
-				if path.recursive {
-					notify::RecursiveMode::Recursive
-				} else {
-					notify::RecursiveMode::NonRecursive
-				},
-			) {
-				error!(?err, "notify watch() error");
-				for e in notify_multi_path_errors(watcher_type, path, err, false) {
-					errors.send(e).await?;
+			let Some(watched) = resolve_watched_path(&path, follow_symlinks) else {
+				continue;
+			};
+
+			if let Some(limit) = max_watched {
+				let budget = limit.saturating_sub(watched_count);
+				let counting = watched.clone();
+				let counted = spawn_blocking(move || count_watches(&counting, budget))
+					.await
+					.unwrap_or_else(|err| {
+						debug!(?path, %err, "could not count paths, counting the path alone");
+						Some(1)
+					});
+				let Some(count) = counted else {
+					warn!(
+						?path,
+						?limit,
+						"watched paths limit reached, not watching further paths"
+					);
+					let err = RuntimeError::FsWatcher {
+						kind: watcher_type,
+						err: FsWatcherError::PathLimit {
+							path: watched.path,
+							limit,
+						},
+					};
+					match config.watch_limit.get() {
+						WatchLimit::Warn => errors.send(err).await?,
+						WatchLimit::Error => {
+							return Err(CriticalError::Elevated { err, help: None });
+						}
+					}
+					break;
+				};
+
+				trace!(?path, ?count, "counted paths for the watch limit");
+				watch_counts.insert(path.clone(), count);
+				watched_count += count;
+			}
+
+			trace!(?path, ?watched, "adding path to the watcher");
+			let Err(mut err) = watch_path(watcher.as_mut(), &pathset, &watched) else {
+				pathset.insert(path, watched);
+				continue;
+			};
+
+			if is_fd_exhaustion(&err) {
+				match config.fd_exhaustion.get() {
+					FdExhaustion::Error => {}
+					FdExhaustion::RaiseLimit => {
+						if raise_open_files_limit() {
+							match watch_path(watcher.as_mut(), &pathset, &watched) {
+								Ok(()) => {
+									pathset.insert(path, watched);
+									continue;
+								}
+								Err(retry_err) => err = retry_err,
+							}
+						}
+					}
+					FdExhaustion::Poll(interval) => {
+						// release whatever the watcher opened for the path before running out
+						unwatch_path(watcher.as_mut(), &pathset, &watched).ok();
+
+						if poll_watcher.is_none() {
+							if let Some(handler) = handler.as_ref() {
+								poll_watcher = Watcher::Poll(interval).create_with(handler).ok();
+							}
+						}
+
+						if let Some(poller) = poll_watcher.as_mut() {
+							if watch_path(poller.as_mut(), &polled, &watched).is_ok() {
+								warn!(
+									?path,
+									?interval,
+									"out of file descriptors, polling path instead"
+								);
+								polled.insert(path, watched.clone());
+								errors
+									.send(RuntimeError::FsWatcher {
+										kind: watcher_type,
+										err: FsWatcherError::PollFallback {
+											path: watched.path,
+											err,
+										},
+									})
+									.await?;
+								continue;
+							}
+						}
+					}
 				}
+			}
+
+			error!(?err, "notify watch() error");
+			if let Some(count) = watch_counts.remove(&path) {
+				watched_count -= count;
+			}
+			for e in notify_multi_path_errors(watcher_type, watched, err, false) {
+				errors.send(e).await?;
+			}
+		}
+
+		file_watches.replace(Arc::new(file_watches_of(
+			pathset.values().chain(polled.values()),
+		)));
+		update_symbolic(&config, &symbolic, pathset.keys().chain(polled.keys()));
+		update_process_origins(&config, &origins, pathset.values().chain(polled.values()));
+		if let Some(follow) = config.watch_ancestors.get() {
+			retry = follow_lost(
+				watcher.as_mut(),
+				&pathset,
+				&lost,
+				&mut followed,
+				follow,
+				retry,
+			)
+			.await;
+		}
+		update_ancestors(&config, &ancestors, &pathset, &followed, &wake);
+		if !setup_done {
+			setup_done = true;
+			send_ready_event(&config, &errors, &events).await?;
+		}
+		config.mark_applied(generation);
+	}
+}
+
+/// Watched files, by their parent directory, for directories which are watched only for them.
+type FileWatches = HashMap<PathBuf, HashSet<PathBuf>>;
+
+/// What to give the watcher for a watched path.
+///
+/// Files are watched through their parent directory, see [`WatchedPath::file()`].
+fn watch_target(watched: &WatchedPath) -> (&Path, notify::RecursiveMode) {
+	if watched.file {
+		let parent = watched
+			.path
+			.parent()
+			.filter(|parent| !parent.as_os_str().is_empty())
+			.unwrap_or_else(|| Path::new("."));
+		(parent, notify::RecursiveMode::NonRecursive)
+	} else if watched.recursive {
+		(&watched.path, notify::RecursiveMode::Recursive)
+	} else {
+		(&watched.path, notify::RecursiveMode::NonRecursive)
+	}
+}
+
+/// How many paths the watcher uses for a watched path, or `None` if that's more than `budget`.
+///
+/// A recursive watch counts its directory and every directory within it, without following
+/// symlinks; anything else counts as one. Counting stops as soon as it's over the budget, so this
+/// doesn't walk all of a huge tree.
+fn count_watches(watched: &WatchedPath, budget: usize) -> Option<usize> {
+	let mut count = 1;
+	if count > budget {
+		return None;
+	}
+
+	if watched.file || !watched.recursive {
+		return Some(count);
+	}
+
+	let mut dirs = vec![watched.path.clone()];
+	while let Some(dir) = dirs.pop() {
+		let Ok(entries) = std::fs::read_dir(&dir) else {
+			continue;
+		};
+
+		for entry in entries.flatten() {
+			if entry.file_type().map_or(false, |kind| kind.is_dir()) {
+				count += 1;
+				if count > budget {
+					return None;
+				}
+				dirs.push(entry.path());
+			}
+		}
+	}
+
+	Some(count)
+}
+
+/// The widest mode the target is already watched with by the (resolved) watched paths.
+fn existing_watch(
+	pathset: &HashMap<WatchedPath, WatchedPath>,
+	target: &Path,
+) -> Option<notify::RecursiveMode> {
+	let mut existing = None;
+	for (other, mode) in pathset.values().map(watch_target) {
+		if other == target {
+			if mode == notify::RecursiveMode::Recursive {
+				return Some(mode);
+			}
+			existing = Some(mode);
+		}
+	}
+	existing
+}
+
+/// Give a resolved watched path to the watcher.
+///
+/// Several watched paths may need the same directory to be watched, like two files in the same
+/// directory: the watcher is only called if the directory isn't already watched widely enough.
+fn watch_path(
+	watcher: &mut (dyn notify::Watcher + Send),
+	pathset: &HashMap<WatchedPath, WatchedPath>,
+	watched: &WatchedPath,
+) -> Result<(), notify::Error> {
+	let (target, mode) = watch_target(watched);
+	match existing_watch(pathset, target) {
+		Some(notify::RecursiveMode::Recursive) => Ok(()),
+		Some(_) if mode == notify::RecursiveMode::NonRecursive => Ok(()),
+		_ => watcher.watch(target, mode),
+	}
+}
+
+/// Remove a resolved watched path from the watcher.
+///
+/// The `pathset` must not contain it anymore. If other watched paths still need its directory to
+/// be watched, that watch is kept, or narrowed to what they need.
+fn unwatch_path(
+	watcher: &mut (dyn notify::Watcher + Send),
+	pathset: &HashMap<WatchedPath, WatchedPath>,
+	watched: &WatchedPath,
+) -> Result<(), notify::Error> {
+	let (target, mode) = watch_target(watched);
+	match existing_watch(pathset, target) {
+		None => watcher.unwatch(target),
+		Some(remaining) if remaining == mode || remaining == notify::RecursiveMode::Recursive => {
+			Ok(())
+		}
+		Some(remaining) => {
+			watcher.unwatch(target)?;
+			watcher.watch(target, remaining)
+		}
+	}
+}
+
+/// Collect the watched files whose directories are watched only for them.
+fn file_watches_of<'p>(watched: impl Iterator<Item = &'p WatchedPath> + Clone) -> FileWatches {
+	let dirs: Vec<&WatchedPath> = watched.clone().filter(|watched| !watched.file).collect();
+	let mut files = FileWatches::new();
+	for watched in watched.filter(|watched| watched.file) {
+		let dir = watch_target(watched).0.normalize();
+		if dirs.iter().any(|other| {
+			let other_dir = other.path.normalize();
+			dir == other_dir || (other.recursive && dir.starts_with(other_dir))
+		}) {
+			continue;
+		}
+
+		files
+			.entry(dir)
+			.or_default()
+			.insert(watched.path.normalize());
+	}
+	files
+}
+
+/// Resolved forms of watched paths, paired with the watched paths as given and made absolute.
+///
+/// Only paths which differ once resolved are listed, most specific first.
+type SymbolicPaths = Vec<(PathBuf, PathBuf)>;
+
+/// Make a path absolute and normalised, without resolving symlinks.
+pub(crate) fn symbolic_path(path: &Path) -> PathBuf {
+	if path.is_absolute() {
+		path.normalize()
+	} else {
+		current_dir()
+			.map_or_else(|_| path.to_owned(), |dir| dir.join(path))
+			.normalize()
+	}
+}
+
+/// Update the symbolic forms of watched paths, see [`PathCanonicalization::Symbolic`].
+fn update_symbolic<'p>(
+	config: &Config,
+	symbolic: &Changeable<Arc<SymbolicPaths>>,
+	watched: impl Iterator<Item = &'p WatchedPath>,
+) {
+	if config.path_canonicalization.get() != PathCanonicalization::Symbolic {
+		symbolic.replace(Arc::default());
+		return;
+	}
+
+	let mut paths: SymbolicPaths = watched
+		.filter_map(|watched| {
+			let given = symbolic_path(&watched.path);
+			let resolved = dunce::canonicalize(&watched.path).ok()?;
+			(resolved != given).then(|| (resolved, given))
+		})
+		.collect();
+	paths.sort_by_key(|(resolved, _)| Reverse(resolved.components().count()));
+	paths.dedup();
+	trace!(?paths, "symbolic forms of watched paths");
+	symbolic.replace(Arc::new(paths));
+}
+
+/// Report a (normalised) event path under the watched path as given, if it's under one resolved.
+fn restore_symbolic(symbolic: &SymbolicPaths, path: PathBuf) -> PathBuf {
+	for (resolved, given) in symbolic {
+		if let Ok(rest) = path.strip_prefix(resolved) {
+			return if rest.as_os_str().is_empty() {
+				given.clone()
 			} else {
-				pathset.insert(path);
+				given.join(rest)
+			};
+		}
+	}
+	path
+}
+
+/// Whether a (normalised) path is in a directory watched only for some files, but isn't one.
+fn is_file_sibling(file_watches: &FileWatches, path: &Path) -> bool {
+	if file_watches.contains_key(path) {
+		// the directory itself
+		return true;
+	}
+
+	match path.parent().and_then(|dir| file_watches.get(dir)) {
+		Some(files) => !files.contains(path),
+		None => false,
+	}
+}
+
+/// Check that watched paths still exist, see [`Config::watch_check_interval`].
+///
+/// Events for lost and restored paths are only sent if `report` is true.
+async fn check_watched_paths(
+	watcher: &mut (dyn notify::Watcher + Send),
+	pathset: &mut HashMap<WatchedPath, WatchedPath>,
+	lost: &mut HashSet<WatchedPath>,
+	follow_symlinks: bool,
+	report: bool,
+	errors: &mpsc::Sender<RuntimeError>,
+	events: &priority::Sender<Event, Priority>,
+) -> Result<(), CriticalError> {
+	let mut gone = Vec::new();
+	for (path, watched) in pathset.iter() {
+		if fs::metadata(watch_target(watched).0).await.is_err() {
+			gone.push(path.clone());
+		}
+	}
+	for path in gone {
+		let Some(watched) = pathset.remove(&path) else {
+			continue;
+		};
+
+		debug!(?path, ?watched, "watched path has disappeared");
+		// the watcher may have already dropped it on its own
+		unwatch_path(watcher, pathset, &watched).ok();
+		let event = watch_state_event(&path, FileEventKind::Remove(RemoveKind::Any), "lost");
+		lost.insert(path);
+		if report {
+			send_watch_state_event(event, errors, events).await?;
+		}
+	}
+
+	let mut back = Vec::new();
+	for path in lost.iter() {
+		if fs::metadata(watch_target(path).0).await.is_ok() {
+			back.push(path.clone());
+		}
+	}
+	for path in back {
+		let Some(watched) = resolve_watched_path(&path, follow_symlinks) else {
+			continue;
+		};
+
+		if let Err(err) = watch_path(watcher, pathset, &watched) {
+			// it may have disappeared again, try at the next check
+			trace!(?path, ?err, "could not re-watch reappeared path");
+			continue;
+		}
+
+		debug!(?path, ?watched, "watched path has reappeared");
+		lost.remove(&path);
+		let event = watch_state_event(&path, FileEventKind::Create(CreateKind::Any), "restored");
+		pathset.insert(path, watched);
+		if report {
+			send_watch_state_event(event, errors, events).await?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Watch the nearest existing ancestors of lost paths, see [`Config::watch_ancestors`].
+///
+/// Ancestors of paths which aren't lost anymore are dropped. Returns when to look again for lost
+/// paths which couldn't be followed, backing off from the `retry` of the last attempt, if any.
+async fn follow_lost(
+	watcher: &mut (dyn notify::Watcher + Send),
+	pathset: &HashMap<WatchedPath, WatchedPath>,
+	lost: &HashSet<WatchedPath>,
+	followed: &mut HashMap<WatchedPath, WatchedPath>,
+	follow: AncestorWatch,
+	retry: Option<(Instant, Duration)>,
+) -> Option<(Instant, Duration)> {
+	let found: Vec<WatchedPath> = followed
+		.keys()
+		.filter(|path| !lost.contains(*path))
+		.cloned()
+		.collect();
+	for path in found {
+		if let Some(ancestor) = followed.remove(&path) {
+			trace!(?path, ?ancestor, "lost path is back, dropping its ancestor");
+			unfollow(watcher, pathset, followed, &ancestor);
+		}
+	}
+
+	let mut stuck = false;
+	for path in lost {
+		let nearest = ancestors::nearest(watch_target(path).0, follow.max_depth)
+			.await
+			.map(WatchedPath::non_recursive);
+		if followed.get(path) == nearest.as_ref() {
+			stuck |= nearest.is_none();
+			continue;
+		}
+
+		if let Some(ancestor) = followed.remove(path) {
+			unfollow(watcher, pathset, followed, &ancestor);
+		}
+
+		let Some(ancestor) = nearest else {
+			trace!(?path, depth=?follow.max_depth, "no existing ancestor for lost path");
+			stuck = true;
+			continue;
+		};
+
+		if existing_watch(followed, &ancestor.path).is_none() {
+			if let Err(err) = watch_path(watcher, pathset, &ancestor) {
+				trace!(
+					?path,
+					?ancestor,
+					?err,
+					"could not watch ancestor of lost path"
+				);
+				stuck = true;
+				continue;
 			}
 		}
+
+		debug!(?path, ?ancestor, "watching ancestor of lost path");
+		followed.insert(path.clone(), ancestor);
+	}
+
+	if !stuck {
+		return None;
+	}
+
+	let now = Instant::now();
+	Some(match retry {
+		None => (now + follow.backoff, follow.backoff),
+		Some((at, backoff)) if at <= now => {
+			let backoff = (backoff * 2).min(follow.max_backoff);
+			(now + backoff, backoff)
+		}
+		Some(retry) => retry,
+	})
+}
+
+/// Points the watcher callback at the watched paths and followed ancestors, if following.
+fn update_ancestors(
+	config: &Config,
+	ancestors: &Changeable<Arc<ancestors::Ancestors>>,
+	pathset: &HashMap<WatchedPath, WatchedPath>,
+	followed: &HashMap<WatchedPath, WatchedPath>,
+	wake: &Arc<Notify>,
+) {
+	ancestors.replace(if config.watch_ancestors.get().is_some() {
+		Arc::new(ancestors::Ancestors::new(pathset, followed, wake.clone()))
+	} else {
+		Arc::default()
+	});
+}
+
+/// Drop the watch of an ancestor, if it's not needed for other lost paths.
+fn unfollow(
+	watcher: &mut (dyn notify::Watcher + Send),
+	pathset: &HashMap<WatchedPath, WatchedPath>,
+	followed: &HashMap<WatchedPath, WatchedPath>,
+	ancestor: &WatchedPath,
+) {
+	if existing_watch(followed, &ancestor.path).is_none() {
+		unwatch_path(watcher, pathset, ancestor).ok();
+	}
+}
+
+/// Drop the watches of all ancestors of lost paths.
+fn unfollow_all(
+	watcher: &mut (dyn notify::Watcher + Send),
+	pathset: &HashMap<WatchedPath, WatchedPath>,
+	followed: &mut HashMap<WatchedPath, WatchedPath>,
+) {
+	for ancestor in take(followed).into_values().collect::<HashSet<_>>() {
+		unwatch_path(watcher, pathset, &ancestor).ok();
+	}
+}
+
+/// Starts or stops attributing events to processes, and points it at the watched paths.
+fn update_process_origins<'p>(
+	config: &Config,
+	origins: &Changeable<Option<Arc<origins::ProcessOrigins>>>,
+	watched: impl Iterator<Item = &'p WatchedPath>,
+) {
+	if !config.process_origins.get() {
+		origins.replace(None);
+		return;
+	}
+
+	let current = match origins.get() {
+		Some(current) => current,
+		None => {
+			let Some(new) = origins::ProcessOrigins::new().map(Arc::new) else {
+				warn!("process origins are not available here, file events won't carry them");
+				config.process_origins.replace(false);
+				return;
+			};
+			origins.replace(Some(new.clone()));
+			new
+		}
+	};
+
+	current.watch(watched.map(|w| w.path.as_path()));
+}
+
+fn watch_state_event(path: &WatchedPath, kind: FileEventKind, state: &str) -> Event {
+	Event {
+		tags: vec![
+			Tag::Source(Source::Filesystem),
+			Tag::FileEventKind(kind),
+			Tag::Path {
+				path: path.path.clone(),
+				file_type: None,
+			},
+		],
+		metadata: HashMap::from([(WATCH.into(), vec![state.into()])]),
 	}
 }
 
+async fn send_watch_state_event(
+	event: Event,
+	errors: &mpsc::Sender<RuntimeError>,
+	events: &priority::Sender<Event, Priority>,
+) -> Result<(), CriticalError> {
+	trace!(?event, "sending watch state event");
+	send_internal_event(event, "fs watch check", errors, events).await
+}
+
+async fn send_ready_event(
+	config: &Config,
+	errors: &mpsc::Sender<RuntimeError>,
+	events: &priority::Sender<Event, Priority>,
+) -> Result<(), CriticalError> {
+	if !config.ready_event.get() {
+		return Ok(());
+	}
+
+	let event = Event {
+		tags: vec![Tag::Source(Source::Internal)],
+		metadata: HashMap::from([(WATCH.into(), vec!["ready".into()])]),
+	};
+	debug!(?event, "filesystem watcher is set up, sending ready event");
+	send_internal_event(event, "fs watch ready", errors, events).await
+}
+
+async fn send_internal_event(
+	event: Event,
+	ctx: &'static str,
+	errors: &mpsc::Sender<RuntimeError>,
+	events: &priority::Sender<Event, Priority>,
+) -> Result<(), CriticalError> {
+	if let Err(err) = events.try_send(event, Priority::Normal) {
+		errors
+			.send(RuntimeError::EventChannelTrySend { ctx, err })
+			.await?;
+	}
+	Ok(())
+}
+
+/// Resolve a watched path to what should be given to the watcher.
+///
+/// If following symlinks and the path is a symlink, that's its fully-resolved target. Returns
+/// `None` if the link is broken or loops.
+fn resolve_watched_path(path: &WatchedPath, follow_symlinks: bool) -> Option<WatchedPath> {
+	if !follow_symlinks
+		|| !symlink_metadata(&path.path).map_or(false, |meta| meta.file_type().is_symlink())
+	{
+		return Some(path.clone());
+	}
+
+	match dunce::canonicalize(&path.path) {
+		Ok(target) => {
+			debug!(?path, ?target, "following symlinked watched path");
+			Some(WatchedPath {
+				path: target,
+				..path.clone()
+			})
+		}
+		Err(err) => {
+			warn!(?path, %err, "watched path is a broken or looping symlink, skipping");
+			None
+		}
+	}
+}
+
+/// Drop watched paths which overlap with others, see `Config::dedupe_pathset`.
+pub(crate) fn dedupe_pathset(pathset: Vec<WatchedPath>) -> Vec<WatchedPath> {
+	let resolved: Vec<PathBuf> = pathset
+		.iter()
+		.map(|watched| {
+			dunce::canonicalize(&watched.path).unwrap_or_else(|_| watched.path.normalize())
+		})
+		.collect();
+	let covers = |outer: usize, inner: usize| {
+		let (outer_path, inner_path) = (&pathset[outer], &pathset[inner]);
+		if resolved[outer] == resolved[inner] {
+			// the same watch is kept the first time it appears
+			outer < inner
+				&& outer_path.recursive == inner_path.recursive
+				&& outer_path.file == inner_path.file
+		} else {
+			outer_path.recursive
+				&& !outer_path.file
+				&& inner_path.recursive
+				&& !inner_path.file
+				&& resolved[inner].starts_with(&resolved[outer])
+		}
+	};
+
+	let mut kept = Vec::with_capacity(pathset.len());
+	for inner in 0..pathset.len() {
+		if let Some(outer) =
+			(0..pathset.len()).find(|&outer| outer != inner && covers(outer, inner))
+		{
+			debug!(path=?pathset[inner], within=?pathset[outer], "collapsing overlapping watched path");
+		} else {
+			kept.push(pathset[inner].clone());
+		}
+	}
+	kept
+}
+
 fn notify_multi_path_errors(
 	kind: Watcher,
 	watched_path: WatchedPath,
@@ -254,25 +1356,70 @@ fn notify_multi_path_errors(
 	errs
 }
 
+/// The state of the watched paths, as the watcher callback needs it to process an event.
+struct WatchedState {
+	file_watches: Arc<FileWatches>,
+	symbolic: Arc<SymbolicPaths>,
+	ancestors: Arc<ancestors::Ancestors>,
+	origins: Option<Arc<origins::ProcessOrigins>>,
+}
+
 fn process_event(
 	nev: Result<notify::Event, notify::Error>,
 	kind: Watcher,
+	allowed_kinds: FsEventKinds,
+	watched: &WatchedState,
 	n_events: &priority::Sender<Event, Priority>,
 ) -> Result<(), RuntimeError> {
+	let WatchedState {
+		file_watches,
+		symbolic,
+		ancestors,
+		origins,
+	} = watched;
+
 	let nev = nev.map_err(|err| RuntimeError::FsWatcher {
 		kind,
 		err: FsWatcherError::Event(err),
 	})?;
 
+	let paths: Vec<PathBuf> = nev.paths.iter().map(|path| path.normalize()).collect();
+	ancestors.notice(&paths);
+
+	if let Some(event_kind) = FsEventKinds::of(&nev.kind) {
+		if !allowed_kinds.contains(event_kind) {
+			trace!(kind=?nev.kind, "dropping event of disallowed kind");
+			return Ok(());
+		}
+	}
+
+	let had_paths = !nev.paths.is_empty();
+	let paths: Vec<PathBuf> = paths
+		.into_iter()
+		.filter(|path| !is_file_sibling(file_watches, path) && !ancestors.hides(path))
+		.collect();
+	if had_paths && paths.is_empty() {
+		trace!(paths=?nev.paths, "dropping event for siblings of watched files or ancestors of lost paths");
+		return Ok(());
+	}
+
+	let origin = match (origins.as_deref(), nev.attrs.process_id()) {
+		(Some(origins), None) if origins::is_content_change(&nev.kind) => {
+			paths.iter().find_map(|path| origins.of(path))
+		}
+		_ => None,
+	};
+
 	let mut tags = Vec::with_capacity(4);
 	tags.push(Tag::Source(Source::Filesystem));
 	tags.push(Tag::FileEventKind(nev.kind));
 
-	for path in nev.paths {
+	for path in paths {
 		// possibly pull file_type from whatever notify (or the native driver) returns?
+		let file_type = metadata(&path).ok().map(|m| m.file_type().into());
 		tags.push(Tag::Path {
-			file_type: metadata(&path).ok().map(|m| m.file_type().into()),
-			path: path.normalize(),
+			path: restore_symbolic(symbolic, path),
+			file_type,
 		});
 	}
 
@@ -280,14 +1427,18 @@ fn process_event(
 		tags.push(Tag::Process(pid));
 	}
 
-	let mut metadata = HashMap::new();
+	let mut metadata =
+		HashMap::from([(NOTIFY_BACKEND.to_string(), vec![kind.backend().to_string()])]);
 
-	if let Some(uid) = nev.attrs.info() {
-		metadata.insert("file-event-info".to_string(), vec![uid.to_string()]);
+	if let Some(origin) = origin {
+		tags.push(Tag::Process(origin.pid));
+		if let Some(uid) = origin.uid {
+			metadata.insert(UID.to_string(), vec![uid.to_string()]);
+		}
 	}
 
-	if let Some(src) = nev.attrs.source() {
-		metadata.insert("notify-backend".to_string(), vec![src.to_string()]);
+	if let Some(uid) = nev.attrs.info() {
+		metadata.insert(FILE_EVENT_INFO.to_string(), vec![uid.to_string()]);
 	}
 
 	let ev = Event { tags, metadata };
@@ -0,0 +1,105 @@
+//! Following deleted watched paths through their ancestors, see `Config::watch_ancestors`.
+
+use std::{
+	collections::{HashMap, HashSet},
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+use normalize_path::NormalizePath;
+use tokio::{fs::metadata, sync::Notify};
+
+use super::{watch_target, WatchedPath};
+
+/// What the watcher callback needs to know to follow deleted watched paths.
+#[derive(Debug, Default)]
+pub(super) struct Ancestors {
+	/// What's given to the watcher for watched paths, whose removal wakes the worker.
+	roots: HashSet<PathBuf>,
+
+	/// Ancestor directories watched only to follow lost paths.
+	dirs: HashSet<PathBuf>,
+
+	/// Paths in those directories whose events are let through: watched and lost paths.
+	keep: HashSet<PathBuf>,
+
+	/// Wakes the worker to look at lost paths.
+	wake: Arc<Notify>,
+}
+
+impl Ancestors {
+	/// Collect the state for the current watches.
+	///
+	/// `followed` maps lost paths to the ancestors watched for them. Ancestors which are also
+	/// within a watched directory aren't only watched for lost paths, so their events go through.
+	pub fn new(
+		pathset: &HashMap<WatchedPath, WatchedPath>,
+		followed: &HashMap<WatchedPath, WatchedPath>,
+		wake: Arc<Notify>,
+	) -> Self {
+		let watched_dirs: Vec<&WatchedPath> =
+			pathset.values().filter(|watched| !watched.file).collect();
+		let dirs = followed
+			.values()
+			.map(|ancestor| ancestor.path.normalize())
+			.filter(|dir| {
+				!watched_dirs.iter().any(|watched| {
+					let target = watched.path.normalize();
+					*dir == target || (watched.recursive && dir.starts_with(target))
+				})
+			})
+			.collect();
+
+		Self {
+			roots: pathset
+				.values()
+				.map(|watched| watch_target(watched).0.normalize())
+				.collect(),
+			dirs,
+			keep: pathset
+				.values()
+				.chain(followed.keys())
+				.map(|watched| watched.path.normalize())
+				.collect(),
+			wake,
+		}
+	}
+
+	/// Wake the worker if an event for these (normalised) paths may concern lost paths.
+	///
+	/// That's when a watched path may have disappeared, or something changed in an ancestor.
+	pub fn notice(&self, paths: &[PathBuf]) {
+		if paths
+			.iter()
+			.any(|path| self.roots.contains(path) || self.in_dirs(path))
+		{
+			self.wake.notify_one();
+		}
+	}
+
+	/// Whether events for this (normalised) path are only seen through an ancestor watch.
+	pub fn hides(&self, path: &Path) -> bool {
+		self.in_dirs(path) && !self.keep.contains(path)
+	}
+
+	fn in_dirs(&self, path: &Path) -> bool {
+		self.dirs.contains(path) || path.parent().map_or(false, |dir| self.dirs.contains(dir))
+	}
+}
+
+/// The nearest existing directory above a path, at most `max_depth` levels up.
+pub(super) async fn nearest(path: &Path, max_depth: usize) -> Option<PathBuf> {
+	for dir in path.ancestors().skip(1).take(max_depth) {
+		let dir = if dir.as_os_str().is_empty() {
+			Path::new(".")
+		} else {
+			dir
+		};
+
+		if metadata(dir).await.map_or(false, |meta| meta.is_dir()) {
+			return Some(dir.to_path_buf());
+		}
+	}
+
+	None
+}
@@ -0,0 +1,278 @@
+//! Attribution of file changes to the processes which made them, see `Config::process_origins`.
+
+/// The process which last changed a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct Origin {
+	pub pid: u32,
+	pub uid: Option<u32>,
+}
+
+#[cfg(target_os = "linux")]
+pub(super) use linux::ProcessOrigins;
+
+#[cfg(not(target_os = "linux"))]
+pub(super) use unsupported::ProcessOrigins;
+
+#[cfg(target_os = "linux")]
+mod linux {
+	use std::{
+		collections::VecDeque,
+		fs::{read_link, read_to_string},
+		os::unix::io::AsRawFd,
+		path::{Path, PathBuf},
+		sync::{
+			atomic::{AtomicBool, Ordering},
+			Arc, Mutex,
+		},
+		thread,
+		time::{Duration, Instant},
+	};
+
+	use nix::{
+		errno::Errno,
+		fcntl::OFlag,
+		sys::fanotify::{EventFFlags, Fanotify, FanotifyEvent, InitFlags, MarkFlags, MaskFlags},
+	};
+	use tracing::{debug, trace};
+
+	use super::Origin;
+
+	/// How many changes to remember.
+	const MEMORY: usize = 1024;
+
+	/// How long a change is remembered for.
+	const MAX_AGE: Duration = Duration::from_secs(1);
+
+	/// How often the reader drains the queue and checks whether it should stop.
+	const READ_INTERVAL: Duration = Duration::from_millis(100);
+
+	/// Listens to fanotify for file changes, and remembers which process made them.
+	///
+	/// The marks are on whole mounts, so changes are only remembered for paths within the roots
+	/// given to [`watch()`](Self::watch).
+	///
+	/// A background thread drains the fanotify queue every so often. Lookups also drain it, without
+	/// blocking, so they see changes the thread hasn't gotten to yet and never wait on it.
+	pub struct ProcessOrigins {
+		fanotify: Arc<Fanotify>,
+		shared: Arc<Shared>,
+	}
+
+	#[derive(Debug, Default)]
+	struct Shared {
+		roots: Mutex<Vec<PathBuf>>,
+		recent: Mutex<VecDeque<(PathBuf, Origin, Instant)>>,
+		// held while reading from fanotify, so changes are remembered in the order they're read
+		reading: Mutex<()>,
+		stop: AtomicBool,
+	}
+
+	impl ProcessOrigins {
+		/// Start listening, if fanotify is available.
+		pub fn new() -> Option<Self> {
+			let fanotify = Fanotify::init(
+				InitFlags::FAN_CLASS_NOTIF | InitFlags::FAN_CLOEXEC | InitFlags::FAN_NONBLOCK,
+				EventFFlags::from_bits_truncate(
+					(OFlag::O_RDONLY | OFlag::O_CLOEXEC | OFlag::O_LARGEFILE).bits() as _,
+				),
+			)
+			.map_err(|err| debug!(?err, "fanotify is not available"))
+			.ok()?;
+
+			let fanotify = Arc::new(fanotify);
+			let shared = Arc::new(Shared::default());
+
+			let t_fanotify = fanotify.clone();
+			let t_shared = shared.clone();
+			thread::Builder::new()
+				.name("watchexec-fanotify".into())
+				.spawn(move || read_changes(&t_fanotify, &t_shared))
+				.map_err(|err| debug!(?err, "could not start fanotify reader"))
+				.ok()?;
+
+			Some(Self { fanotify, shared })
+		}
+
+		/// Set the paths to remember changes for, and mark their mounts.
+		///
+		/// If paths were dropped since the last call, the marks of their mounts are removed, and
+		/// those of the remaining paths are put back, as several paths can share a mount.
+		pub fn watch<'p>(&self, roots: impl Iterator<Item = &'p Path>) {
+			let roots: Vec<PathBuf> = roots.map(Path::to_path_buf).collect();
+			let mut current = self
+				.shared
+				.roots
+				.lock()
+				.expect("fanotify roots lock poisoned");
+
+			for root in current.iter().filter(|root| !roots.contains(root)) {
+				self.mark(MarkFlags::FAN_MARK_REMOVE, root);
+			}
+
+			for root in &roots {
+				self.mark(MarkFlags::FAN_MARK_ADD, root);
+			}
+
+			*current = roots;
+		}
+
+		fn mark(&self, action: MarkFlags, root: &Path) {
+			if let Err(err) = self.fanotify.mark(
+				action | MarkFlags::FAN_MARK_MOUNT,
+				MaskFlags::FAN_MODIFY | MaskFlags::FAN_CLOSE_WRITE,
+				None,
+				Some(root),
+			) {
+				debug!(?root, ?action, ?err, "could not change fanotify mark");
+			}
+		}
+
+		/// Find the last recent change to a path.
+		///
+		/// This reads whatever fanotify has queued first, but doesn't wait for more. Changes are
+		/// kept until they age out, so the several events a single write can cause (such as a
+		/// modify then a close) are all attributed.
+		pub fn of(&self, path: &Path) -> Option<Origin> {
+			if let Err(err) = drain(&self.fanotify, &self.shared) {
+				debug!(?err, "fanotify read error");
+			}
+
+			let mut recent = self
+				.shared
+				.recent
+				.lock()
+				.expect("fanotify changes lock poisoned");
+			recent.retain(|(_, _, at)| at.elapsed() < MAX_AGE);
+			recent
+				.iter()
+				.rfind(|(changed, _, _)| changed == path)
+				.map(|(_, origin, _)| *origin)
+		}
+	}
+
+	impl Drop for ProcessOrigins {
+		fn drop(&mut self) {
+			self.shared.stop.store(true, Ordering::Relaxed);
+		}
+	}
+
+	impl std::fmt::Debug for ProcessOrigins {
+		fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+			f.debug_struct("ProcessOrigins")
+				.field("fanotify", &self.fanotify)
+				.finish_non_exhaustive()
+		}
+	}
+
+	fn read_changes(fanotify: &Fanotify, shared: &Shared) {
+		while !shared.stop.load(Ordering::Relaxed) {
+			if let Err(err) = drain(fanotify, shared) {
+				debug!(?err, "fanotify read error, stopping");
+				return;
+			}
+
+			thread::sleep(READ_INTERVAL);
+		}
+	}
+
+	/// Read and remember all the changes fanotify has queued, without blocking.
+	fn drain(fanotify: &Fanotify, shared: &Shared) -> Result<(), Errno> {
+		let _reading = shared.reading.lock().expect("fanotify read lock poisoned");
+		loop {
+			let events = match fanotify.read_events() {
+				Ok(events) => events,
+				Err(Errno::EINTR) => continue,
+				Err(Errno::EAGAIN) => return Ok(()),
+				Err(err) => return Err(err),
+			};
+			if events.is_empty() {
+				return Ok(());
+			}
+
+			let changes: Vec<(PathBuf, Origin)> = events
+				.iter()
+				.filter_map(|event| change(event, shared))
+				.collect();
+
+			let mut recent = shared
+				.recent
+				.lock()
+				.expect("fanotify changes lock poisoned");
+			for (path, origin) in changes {
+				trace!(?path, ?origin, "file changed by process");
+				if recent.len() >= MEMORY {
+					recent.pop_front();
+				}
+				recent.push_back((path, origin, Instant::now()));
+			}
+		}
+	}
+
+	fn change(event: &FanotifyEvent, shared: &Shared) -> Option<(PathBuf, Origin)> {
+		let fd = event.fd()?;
+		let path = read_link(format!("/proc/self/fd/{}", fd.as_raw_fd())).ok()?;
+		if !shared
+			.roots
+			.lock()
+			.expect("fanotify roots lock poisoned")
+			.iter()
+			.any(|root| path.starts_with(root))
+		{
+			return None;
+		}
+
+		let pid = u32::try_from(event.pid()).ok()?;
+		Some((
+			path,
+			Origin {
+				pid,
+				uid: uid_of(pid),
+			},
+		))
+	}
+
+	fn uid_of(pid: u32) -> Option<u32> {
+		read_to_string(format!("/proc/{pid}/status"))
+			.ok()?
+			.lines()
+			.find_map(|line| line.strip_prefix("Uid:"))?
+			.split_whitespace()
+			.next()?
+			.parse()
+			.ok()
+	}
+}
+
+#[cfg(not(target_os = "linux"))]
+mod unsupported {
+	use std::path::Path;
+
+	use super::Origin;
+
+	/// Process origins are only available on Linux.
+	#[derive(Debug)]
+	pub struct ProcessOrigins(());
+
+	impl ProcessOrigins {
+		pub fn new() -> Option<Self> {
+			None
+		}
+
+		pub fn watch<'p>(&self, _roots: impl Iterator<Item = &'p Path>) {}
+
+		pub fn of(&self, _path: &Path) -> Option<Origin> {
+			None
+		}
+	}
+}
+
+/// Whether an event is for a kind of change which fanotify sees.
+pub(super) fn is_content_change(kind: &notify::EventKind) -> bool {
+	use notify::event::{AccessKind, AccessMode, EventKind, ModifyKind};
+	matches!(
+		kind,
+		EventKind::Any
+			| EventKind::Modify(ModifyKind::Any | ModifyKind::Data(_))
+			| EventKind::Access(AccessKind::Close(AccessMode::Write))
+	)
+}
@@ -0,0 +1,150 @@
+//! Pairing of rename events into single events.
+
+use std::{
+	collections::VecDeque,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+
+use notify::event::{EventKind, ModifyKind, RenameMode};
+use tokio::{runtime::Handle, time::sleep};
+use tracing::trace;
+
+/// How long a rename "from" event is held back while waiting for its "to" pair.
+pub(super) const RENAME_PAIR_WINDOW: Duration = Duration::from_millis(100);
+
+/// How many paired trackers to remember, to drop the watcher's own paired events.
+const PAIRED_MEMORY: usize = 16;
+
+pub(super) type Emit = Arc<dyn Fn(Result<notify::Event, notify::Error>) + Send + Sync>;
+
+/// Pairs rename "from" and "to" events into a single rename "both" event.
+///
+/// Events are matched by tracker (the inotify cookie) where available, or by adjacency otherwise.
+/// A "from" event is held for [`RENAME_PAIR_WINDOW`] and emitted alone if no "to" arrives; a
+/// "to" event without a "from" is emitted alone immediately. Some watchers emit their own "both"
+/// event after the pair: these are dropped if we've already paired the same tracker.
+pub(super) struct RenameCoalescer {
+	state: Arc<Mutex<State>>,
+	emit: Emit,
+	runtime: Handle,
+}
+
+#[derive(Debug, Default)]
+struct State {
+	pending: Option<(u64, notify::Event)>,
+	next_id: u64,
+	paired: VecDeque<usize>,
+}
+
+impl RenameCoalescer {
+	/// Create a new coalescer which emits events with the given function.
+	///
+	/// This must be called from within a Tokio runtime.
+	pub fn new(emit: Emit) -> Self {
+		Self {
+			state: Default::default(),
+			emit,
+			runtime: Handle::current(),
+		}
+	}
+
+	/// Process an event, emitting it and/or previously held events as appropriate.
+	pub fn push(&self, nev: notify::Event) {
+		let mut out = Vec::with_capacity(2);
+
+		{
+			let mut state = self.state.lock().expect("rename state lock poisoned");
+			match nev.kind {
+				EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+					out.extend(state.pending.take().map(|(_, from)| from));
+					state.next_id += 1;
+					let id = state.next_id;
+					trace!(?nev, "holding rename source while waiting for its pair");
+					state.pending = Some((id, nev));
+					self.flush_later(id);
+				}
+				EventKind::Modify(ModifyKind::Name(RenameMode::To)) => match state.pending.take() {
+					Some((_, from)) if trackers_match(&from, &nev) => {
+						if let Some(tracker) = nev.tracker() {
+							state.paired.push_back(tracker);
+							if state.paired.len() > PAIRED_MEMORY {
+								state.paired.pop_front();
+							}
+						}
+
+						trace!(?from, to=?nev, "pairing rename events");
+						out.push(pair(from, nev));
+					}
+					pending => {
+						out.extend(pending.map(|(_, from)| from));
+						out.push(nev);
+					}
+				},
+				EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+					let tracker = nev.tracker();
+					if let Some(pos) =
+						tracker.and_then(|t| state.paired.iter().position(|p| *p == t))
+					{
+						trace!(?nev, "dropping rename event which was already paired");
+						state.paired.remove(pos);
+					} else {
+						if tracker.is_some()
+							&& state
+								.pending
+								.as_ref()
+								.map_or(false, |(_, from)| from.tracker() == tracker)
+						{
+							trace!("dropping held rename source, the watcher paired it");
+							state.pending.take();
+						}
+						out.push(nev);
+					}
+				}
+				_ => out.push(nev),
+			}
+		}
+
+		for nev in out {
+			(self.emit)(Ok(nev));
+		}
+	}
+
+	fn flush_later(&self, id: u64) {
+		let state = self.state.clone();
+		let emit = self.emit.clone();
+		self.runtime.spawn(async move {
+			sleep(RENAME_PAIR_WINDOW).await;
+			let pending = {
+				let mut state = state.lock().expect("rename state lock poisoned");
+				if matches!(state.pending, Some((pending_id, _)) if pending_id == id) {
+					state.pending.take()
+				} else {
+					None
+				}
+			};
+
+			if let Some((_, from)) = pending {
+				trace!(?from, "no pair arrived for rename source, emitting alone");
+				emit(Ok(from));
+			}
+		});
+	}
+}
+
+fn trackers_match(from: &notify::Event, to: &notify::Event) -> bool {
+	match (from.tracker(), to.tracker()) {
+		(Some(from), Some(to)) => from == to,
+		_ => true,
+	}
+}
+
+fn pair(from: notify::Event, to: notify::Event) -> notify::Event {
+	let mut both = notify::Event {
+		kind: EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+		paths: from.paths,
+		attrs: from.attrs,
+	};
+	both.paths.extend(to.paths);
+	both
+}
@@ -0,0 +1,152 @@
+//! Source of watched paths from project origins found under a base path.
+
+use std::{
+	collections::{HashSet, VecDeque},
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::Duration,
+};
+
+use async_priority_channel as priority;
+use tokio::{fs::read_dir, sync::mpsc, time::timeout};
+use tracing::{debug, trace};
+use watchexec_events::{Event, Priority};
+
+use crate::{
+	error::{CriticalError, RuntimeError},
+	Config, WatchedPath,
+};
+
+/// Where and how often to look for project origins to watch.
+///
+/// See [`Config::origin_scan`](crate::Config::origin_scan).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct OriginScan {
+	/// The directory to look for project origins in, including itself.
+	pub base: PathBuf,
+
+	/// How often to look again.
+	pub interval: Duration,
+
+	/// How many directories deep to look under the base.
+	pub max_depth: usize,
+}
+
+impl OriginScan {
+	/// Look for project origins under this base, every 5 seconds, up to 4 directories deep.
+	#[must_use]
+	pub fn new(base: impl Into<PathBuf>) -> Self {
+		Self {
+			base: base.into(),
+			interval: Duration::from_secs(5),
+			max_depth: 4,
+		}
+	}
+
+	/// Set how often to look again.
+	#[must_use]
+	pub const fn interval(mut self, interval: Duration) -> Self {
+		self.interval = interval;
+		self
+	}
+
+	/// Set how many directories deep to look under the base.
+	#[must_use]
+	pub const fn max_depth(mut self, max_depth: usize) -> Self {
+		self.max_depth = max_depth;
+		self
+	}
+}
+
+/// Launch the project origins worker.
+///
+/// While you can run several, you should only have one.
+///
+/// This doesn't emit events: it finds origins and sets the pathset, which the filesystem worker
+/// then applies. The `errors` and `events` channels are taken for consistency with other workers.
+pub async fn worker(
+	config: Arc<Config>,
+	_errors: mpsc::Sender<RuntimeError>,
+	_events: priority::Sender<Event, Priority>,
+) -> Result<(), CriticalError> {
+	// origins we've added to the pathset, so we only ever remove those
+	let mut added: HashSet<PathBuf> = HashSet::new();
+
+	let mut config_watch = config.watch();
+	config_watch.next().await;
+	loop {
+		let scan = config.origin_scan.get();
+		let found = match &scan {
+			Some(scan) => find_origins(scan).await,
+			None => HashSet::new(),
+		};
+		apply_origins(&config, &mut added, found);
+
+		if let Some(scan) = scan {
+			timeout(scan.interval, config_watch.next()).await.ok();
+		} else {
+			config_watch.next().await;
+		}
+	}
+}
+
+/// Walks the base breadth-first for project origins, skipping hidden directories.
+async fn find_origins(scan: &OriginScan) -> HashSet<PathBuf> {
+	let mut found = HashSet::new();
+	let mut queue = VecDeque::from([(scan.base.clone(), 0)]);
+	while let Some((dir, depth)) = queue.pop_front() {
+		if project_origins::is_origin(&dir).await {
+			found.insert(dir.clone());
+		}
+
+		if depth >= scan.max_depth {
+			continue;
+		}
+
+		let Ok(mut entries) = read_dir(&dir).await else {
+			continue;
+		};
+		while let Ok(Some(entry)) = entries.next_entry().await {
+			if is_hidden(&entry.path()) {
+				continue;
+			}
+			if entry.file_type().await.map_or(false, |t| t.is_dir()) {
+				queue.push_back((entry.path(), depth + 1));
+			}
+		}
+	}
+
+	trace!(base=?scan.base, ?found, "found project origins");
+	found
+}
+
+fn is_hidden(path: &Path) -> bool {
+	path.file_name()
+		.and_then(|name| name.to_str())
+		.map_or(false, |name| name.starts_with('.'))
+}
+
+/// Watches new origins and unwatches origins which have gone, leaving other paths alone.
+fn apply_origins(config: &Config, added: &mut HashSet<PathBuf>, found: HashSet<PathBuf>) {
+	let mut pathset = config.pathset.get();
+	let gone: Vec<PathBuf> = added.difference(&found).cloned().collect();
+	let new: Vec<PathBuf> = found
+		.into_iter()
+		.filter(|origin| !added.contains(origin) && !pathset.iter().any(|p| &p.path == origin))
+		.collect();
+	if gone.is_empty() && new.is_empty() {
+		return;
+	}
+
+	debug!(?new, ?gone, "project origins changed, updating pathset");
+	pathset.retain(|path| !gone.contains(&path.path));
+	for origin in &gone {
+		added.remove(origin);
+	}
+	for origin in new {
+		pathset.push(WatchedPath::from(origin.clone()));
+		added.insert(origin);
+	}
+	config.pathset(pathset);
+}
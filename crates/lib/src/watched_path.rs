@@ -2,11 +2,12 @@ use std::path::{Path, PathBuf};
 
 /// A path to watch.
 ///
-/// Can be a recursive or non-recursive watch.
+/// Can be a recursive or non-recursive watch of a directory, or a watch of a single file.
 #[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct WatchedPath {
 	pub(crate) path: PathBuf,
 	pub(crate) recursive: bool,
+	pub(crate) file: bool,
 }
 
 impl From<PathBuf> for WatchedPath {
@@ -14,6 +15,7 @@ impl From<PathBuf> for WatchedPath {
 		Self {
 			path,
 			recursive: true,
+			file: false,
 		}
 	}
 }
@@ -23,6 +25,7 @@ impl From<&str> for WatchedPath {
 		Self {
 			path: path.into(),
 			recursive: true,
+			file: false,
 		}
 	}
 }
@@ -32,6 +35,7 @@ impl From<String> for WatchedPath {
 		Self {
 			path: path.into(),
 			recursive: true,
+			file: false,
 		}
 	}
 }
@@ -41,6 +45,7 @@ impl From<&Path> for WatchedPath {
 		Self {
 			path: path.into(),
 			recursive: true,
+			file: false,
 		}
 	}
 }
@@ -69,14 +74,57 @@ impl WatchedPath {
 		Self {
 			path: path.into(),
 			recursive: true,
+			file: false,
 		}
 	}
 
 	/// Create a new watched path, not descending into subdirectories.
+	///
+	/// Changes to the immediate contents of the directory are reported, including subdirectories
+	/// being created, removed, or renamed, but not changes within those subdirectories. This also
+	/// applies to subdirectories created after the watch starts: they are not watched.
 	pub fn non_recursive(path: impl Into<PathBuf>) -> Self {
 		Self {
 			path: path.into(),
 			recursive: false,
+			file: false,
 		}
 	}
+
+	/// Create a new watched path for a single file.
+	///
+	/// Filesystem watchers generally follow the file itself (its inode), not its path, so replacing
+	/// the file (as editors do when saving atomically, by writing to a temporary file and renaming
+	/// it over the original) would silently end the watch. Instead, the parent directory of the
+	/// file is watched non-recursively, and events for its other contents are dropped at the
+	/// source, so that only events for this file are seen. Events in that directory are still
+	/// seen if it's also watched on its own, or as part of a recursive watch.
+	///
+	/// Changes to the file are seen across it being removed, replaced, or recreated, as long as
+	/// its parent directory stays.
+	pub fn file(path: impl Into<PathBuf>) -> Self {
+		Self {
+			path: path.into(),
+			recursive: false,
+			file: true,
+		}
+	}
+
+	/// The path to watch.
+	#[must_use]
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Whether subdirectories are watched too.
+	#[must_use]
+	pub const fn is_recursive(&self) -> bool {
+		self.recursive
+	}
+
+	/// Whether this is a watch of a single file, see [`file()`](Self::file).
+	#[must_use]
+	pub const fn is_file(&self) -> bool {
+		self.file
+	}
 }
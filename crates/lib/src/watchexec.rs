@@ -1,23 +1,30 @@
-use std::{fmt, future::Future, sync::Arc};
+use std::{collections::HashMap, fmt, future::Future, sync::Arc, time::Duration};
 
 use async_priority_channel as priority;
 use atomic_take::AtomicTake;
-use futures::TryFutureExt;
+use futures::{future::join_all, stream, Stream, TryFutureExt};
 use miette::Diagnostic;
 use once_cell::sync::OnceCell;
 use tokio::{
 	spawn,
-	sync::{mpsc, Notify},
+	sync::{
+		broadcast::{self, error::RecvError},
+		mpsc, oneshot, watch, Notify,
+	},
 	task::{JoinHandle, JoinSet},
+	time::{error::Elapsed, timeout, timeout_at, Instant},
 };
 use tracing::{debug, error, trace};
 use watchexec_events::{Event, Priority};
+use watchexec_supervisor::job::Job;
 
 use crate::{
 	action::{self, ActionHandler},
-	changeable::ChangeableFn,
+	changeable::{Changeable, ChangeableFn},
 	error::{CriticalError, RuntimeError},
-	sources::{fs, keyboard, signal},
+	error_rate_limit::{ErrorLimiter, ErrorRateLimit},
+	id::Id,
+	sources::{fs, keyboard, origins, signal},
 	Config,
 };
 
@@ -74,6 +81,8 @@ pub struct Watchexec {
 	pub config: Arc<Config>,
 	start_lock: Arc<Notify>,
 	event_input: priority::Sender<Event, Priority>,
+	jobs: watch::Receiver<HashMap<Id, Job>>,
+	raw_events: broadcast::Sender<Event>,
 	handle: Arc<AtomicTake<JoinHandle<Result<(), CriticalError>>>>,
 }
 
@@ -95,6 +104,9 @@ impl fmt::Debug for Watchexec {
 }
 
 impl Watchexec {
+	/// How long [`reconfigure_async()`](Self::reconfigure_async) waits for changes to be applied.
+	pub const RECONFIGURE_TIMEOUT: Duration = Duration::from_secs(10);
+
 	/// Instantiates a new `Watchexec` runtime given an initial action handler.
 	///
 	/// Returns an [`Arc`] for convenience; use [`try_unwrap`][Arc::try_unwrap()] to get the value
@@ -143,6 +155,9 @@ impl Watchexec {
 		let (ev_s, ev_r) =
 			priority::bounded(config.event_channel_size.try_into().unwrap_or(u64::MAX));
 		let event_input = ev_s.clone();
+		let (jobs_s, jobs) = watch::channel(HashMap::new());
+		let (raw_events, _) = broadcast::channel(RAW_EVENTS_CAPACITY);
+		let raw_events_s = raw_events.clone();
 
 		trace!("creating main task");
 		let handle = spawn(async move {
@@ -154,7 +169,16 @@ impl Watchexec {
 
 			let mut tasks = JoinSet::new();
 
-			tasks.spawn(action::worker(config.clone(), er_s.clone(), ev_r).map_ok(|()| "action"));
+			tasks.spawn(
+				action::worker_publishing_jobs(
+					config.clone(),
+					er_s.clone(),
+					ev_r,
+					jobs_s,
+					raw_events_s,
+				)
+				.map_ok(|()| "action"),
+			);
 			tasks.spawn(fs::worker(config.clone(), er_s.clone(), ev_s.clone()).map_ok(|()| "fs"));
 			tasks.spawn(
 				signal::worker(config.clone(), er_s.clone(), ev_s.clone()).map_ok(|()| "signal"),
@@ -163,7 +187,17 @@ impl Watchexec {
 				keyboard::worker(config.clone(), er_s.clone(), ev_s.clone())
 					.map_ok(|()| "keyboard"),
 			);
-			tasks.spawn(error_hook(er_r, config.error_handler.clone()).map_ok(|()| "error"));
+			tasks.spawn(
+				origins::worker(config.clone(), er_s.clone(), ev_s.clone()).map_ok(|()| "origins"),
+			);
+			tasks.spawn(
+				error_hook(
+					er_r,
+					config.error_handler.clone(),
+					config.error_rate_limit.clone(),
+				)
+				.map_ok(|()| "error"),
+			);
 
 			while let Some(Ok(res)) = tasks.join_next().await {
 				match res {
@@ -195,6 +229,8 @@ impl Watchexec {
 			config: outer_config,
 			start_lock,
 			event_input,
+			jobs,
+			raw_events,
 			handle: Arc::new(AtomicTake::new(handle)),
 		})
 	}
@@ -210,6 +246,119 @@ impl Watchexec {
 		Ok(())
 	}
 
+	/// List all jobs currently supervised by Watchexec.
+	///
+	/// This is the same as [`ActionHandler::list_jobs()`], but from outside the action handler. The
+	/// list is updated after each action handler returns, so jobs created by an action handler show
+	/// up once it's done, and jobs which have finished and been deleted are removed after the next
+	/// action. It's empty before Watchexec is started, and after it has quit.
+	///
+	/// The jobs are in no particular order.
+	pub fn jobs(&self) -> Vec<(Id, Job)> {
+		self.jobs
+			.borrow()
+			.iter()
+			.map(|(id, job)| (*id, job.clone()))
+			.collect()
+	}
+
+	/// Obtain a stream of every event as it comes in, before filtering and throttling.
+	///
+	/// Events are delivered here when the action worker receives them, before they're checked by
+	/// the filterer (and by [`ignore_command_outputs`](Config::ignore_command_outputs)) and
+	/// collected by the throttle, so this sees events which will be dropped later, and sees them
+	/// before any action does. This includes events sent with [`send_event()`](Self::send_event).
+	/// Events dropped at their source, such as by [`event_kinds`](Config::event_kinds), aren't
+	/// seen.
+	///
+	/// This doesn't hold up the main pipeline: if a stream falls behind by more than a thousand
+	/// events or so, the oldest events it hasn't read yet are dropped for it. Only events received
+	/// after this is called are delivered, and the stream ends once Watchexec has quit and this
+	/// `Watchexec` has been dropped.
+	pub fn raw_events(&self) -> impl Stream<Item = Event> + Send + 'static {
+		stream::unfold(self.raw_events.subscribe(), |mut events| async move {
+			loop {
+				match events.recv().await {
+					Ok(event) => return Some((event, events)),
+					Err(RecvError::Lagged(missed)) => {
+						debug!(%missed, "raw event stream lagged, events were dropped");
+					}
+					Err(RecvError::Closed) => return None,
+				}
+			}
+		})
+	}
+
+	/// Wait until none of the supervised jobs are running.
+	///
+	/// This waits for the current command of each of the [`jobs()`](Self::jobs) to end. The wait
+	/// is queued behind other controls already sent to each job, so a job which was just started
+	/// is waited on instead of being seen as not running yet. Jobs which never started a command
+	/// aren't waited on.
+	///
+	/// If jobs are added while waiting (by an action handler), they're waited on too; jobs which
+	/// are deleted while waiting stop being waited on. If a job is restarted while waiting, only
+	/// the command that was running when the wait reached it is waited on. This returns
+	/// immediately if there are no jobs, including before Watchexec is started.
+	pub async fn wait_all(&self) {
+		let mut jobs = self.jobs.clone();
+		loop {
+			let waits: Vec<_> = jobs
+				.borrow_and_update()
+				.values()
+				.cloned()
+				.map(wait_for_job)
+				.collect();
+			trace!(jobs=%waits.len(), "waiting for all jobs");
+			join_all(waits).await;
+
+			// if the worker is gone, no jobs will be added anymore
+			if !jobs.has_changed().unwrap_or(false) {
+				break;
+			}
+
+			trace!("jobs changed while waiting, waiting again");
+		}
+	}
+
+	/// Change the configuration, and wait until the changes are applied.
+	///
+	/// The `change` function is given the [`config`](Self::config), to change it with its methods
+	/// as usual. Most of the configuration is read "just in time", when it's needed, and so is in
+	/// effect as soon as it's changed: the handlers, the filterer, the throttle and other settings
+	/// of actions and jobs. The settings of the filesystem watcher aren't: the pathset, the watcher
+	/// kind, whether to follow symlinks, and the watch limits and fallbacks are applied by the
+	/// filesystem worker, which needs to create the watcher and add and remove watches. This
+	/// resolves once the filesystem worker has gone through all changes made up to the end of
+	/// `change`, so from then on events are delivered for newly watched paths and not for removed
+	/// ones. Other sources, like [project origins](Config::origin_scan) and
+	/// [keyboard events](Config::keyboard_events), still pick up changes on their own time.
+	///
+	/// If Watchexec isn't started yet, this returns immediately, as everything is applied when it
+	/// starts. If the changes haven't been applied after
+	/// [`RECONFIGURE_TIMEOUT`](Self::RECONFIGURE_TIMEOUT), for example because Watchexec has quit,
+	/// this returns an error; the changes are made regardless, and are applied whenever the
+	/// filesystem worker gets to them.
+	pub async fn reconfigure_async(&self, change: impl FnOnce(&Config)) -> Result<(), Elapsed> {
+		let mut applied = self.config.applied.subscribe();
+		change(&self.config);
+		let generation = self.config.signal_change().generation();
+
+		if !self.handle.is_taken() {
+			trace!(?generation, "not started, changes will be applied on start");
+			return Ok(());
+		}
+
+		debug!(?generation, "waiting for config changes to be applied");
+		timeout(Self::RECONFIGURE_TIMEOUT, async {
+			applied
+				.wait_for(|applied| *applied >= generation)
+				.await
+				.ok();
+		})
+		.await
+	}
+
 	/// Start watchexec and obtain the handle to its main task.
 	///
 	/// This must only be called once.
@@ -227,21 +376,74 @@ impl Watchexec {
 	}
 }
 
+/// How many events a raw event stream can fall behind by before dropping some.
+const RAW_EVENTS_CAPACITY: usize = 1024;
+
+/// Wait for the job's command to end, if it's running once the controls before this are done.
+async fn wait_for_job(job: Job) {
+	let (running_s, running_r) = oneshot::channel();
+	job.run(move |context| {
+		running_s.send(context.current.is_running()).ok();
+	})
+	.await;
+
+	if running_r.await.unwrap_or(false) {
+		job.to_wait().await;
+	}
+}
+
 async fn error_hook(
 	mut errors: mpsc::Receiver<RuntimeError>,
 	handler: ChangeableFn<ErrorHook, ()>,
+	rate_limit: Changeable<Option<ErrorRateLimit>>,
 ) -> Result<(), CriticalError> {
-	while let Some(err) = errors.recv().await {
+	let mut limiter = ErrorLimiter::default();
+	let call = |err: RuntimeError, suppressed: usize| {
+		error!(%err, %suppressed, "runtime error");
+		let mut payload = ErrorHook::new(err);
+		payload.suppressed = suppressed;
+		let crit = payload.critical.clone();
+		handler.call(payload);
+		crit
+	};
+
+	loop {
+		let err = match limiter.next_end() {
+			Some(next_end) => {
+				if let Ok(err) = timeout_at(next_end, errors.recv()).await {
+					err
+				} else {
+					for (err, suppressed) in limiter.end_intervals(Instant::now(), false) {
+						ErrorHook::handle_crit(call(err, suppressed))?;
+					}
+					continue;
+				}
+			}
+			None => errors.recv().await,
+		};
+
+		let Some(err) = err else {
+			break;
+		};
+
 		if matches!(err, RuntimeError::Exit) {
 			trace!("got graceful exit request via runtime error, upgrading to crit");
 			return Err(CriticalError::Exit);
 		}
 
-		error!(%err, "runtime error");
-		let payload = ErrorHook::new(err);
-		let crit = payload.critical.clone();
-		handler.call(payload);
-		ErrorHook::handle_crit(crit)?;
+		let now = Instant::now();
+		for (err, suppressed) in limiter.end_intervals(now, false) {
+			ErrorHook::handle_crit(call(err, suppressed))?;
+		}
+
+		match limiter.admit(err, rate_limit.get(), now) {
+			Some(err) => ErrorHook::handle_crit(call(err, 0))?,
+			None => trace!("runtime error held back by rate limit"),
+		}
+	}
+
+	for (err, suppressed) in limiter.end_intervals(Instant::now(), true) {
+		ErrorHook::handle_crit(call(err, suppressed))?;
 	}
 
 	Ok(())
@@ -260,6 +462,12 @@ async fn error_hook(
 pub struct ErrorHook {
 	/// The runtime error for which this handler was called.
 	pub error: RuntimeError,
+
+	/// How many errors identical to this one were dropped by the rate limit before it.
+	///
+	/// This is always zero unless [`Config::error_rate_limit()`] is set. See [`ErrorRateLimit`]
+	/// for what makes errors identical.
+	pub suppressed: usize,
 	critical: Arc<OnceCell<CriticalError>>,
 }
 
@@ -267,6 +475,7 @@ impl ErrorHook {
 	fn new(error: RuntimeError) -> Self {
 		Self {
 			error,
+			suppressed: 0,
 			critical: Default::default(),
 		}
 	}
@@ -299,7 +508,9 @@ impl ErrorHook {
 	///
 	/// This is a shorthand method for `ErrorHook::critical(CriticalError::Elevated(error))`.
 	pub fn elevate(self) {
-		let Self { error, critical } = self;
+		let Self {
+			error, critical, ..
+		} = self;
 		critical
 			.set(CriticalError::Elevated {
 				help: error.help().map(|h| h.to_string()),
@@ -0,0 +1,133 @@
+use std::{
+	sync::{
+		atomic::{AtomicBool, AtomicUsize, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+use tokio::{
+	sync::{mpsc, Semaphore},
+	time::{sleep, timeout},
+};
+use watchexec::{error::RuntimeError, ErrorHook, Watchexec};
+use watchexec_events::{Event, Priority};
+use watchexec_signals::Signal;
+
+#[tokio::test]
+async fn task_completes_before_main_returns() {
+	let done = Arc::new(AtomicBool::new(false));
+	let wx = Watchexec::new({
+		let done = done.clone();
+		move |mut action| {
+			let done = done.clone();
+			action.run_task(async move {
+				sleep(Duration::from_millis(100)).await;
+				done.store(true, Ordering::SeqCst);
+			});
+			action.quit_gracefully(Signal::Terminate, Duration::ZERO);
+			action
+		}
+	})
+	.unwrap();
+
+	let main = wx.main();
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+	main.await.unwrap().unwrap();
+
+	assert!(done.load(Ordering::SeqCst), "task should have completed");
+}
+
+#[tokio::test]
+async fn task_panic_goes_to_error_hook() {
+	let (errors_s, mut errors_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(|mut action| {
+		action.run_task(async {
+			panic!("oh no");
+		});
+		action.quit_gracefully(Signal::Terminate, Duration::ZERO);
+		action
+	})
+	.unwrap();
+	wx.config.on_error(move |err: ErrorHook| {
+		if let RuntimeError::Handler { err, .. } = err.error {
+			errors_s.send(err).ok();
+		}
+	});
+
+	let main = wx.main();
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+	main.await.unwrap().unwrap();
+
+	let err = timeout(Duration::from_secs(1), errors_r.recv())
+		.await
+		.expect("panic should be reported");
+	assert_eq!(err.as_deref(), Some("oh no"));
+}
+
+#[tokio::test]
+async fn executor_bounds_concurrent_tasks() {
+	const TASKS: usize = 10;
+	const LIMIT: usize = 2;
+
+	let running = Arc::new(AtomicUsize::new(0));
+	let most = Arc::new(AtomicUsize::new(0));
+	let finished = Arc::new(AtomicUsize::new(0));
+
+	let wx = Watchexec::new({
+		let running = running.clone();
+		let most = most.clone();
+		let finished = finished.clone();
+		move |mut action| {
+			for _ in 0..TASKS {
+				let running = running.clone();
+				let most = most.clone();
+				let finished = finished.clone();
+				action.run_task(async move {
+					let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+					most.fetch_max(now, Ordering::SeqCst);
+					sleep(Duration::from_millis(20)).await;
+					running.fetch_sub(1, Ordering::SeqCst);
+					finished.fetch_add(1, Ordering::SeqCst);
+				});
+			}
+			action.quit_gracefully(Signal::Terminate, Duration::ZERO);
+			action
+		}
+	})
+	.unwrap();
+
+	let limit = Arc::new(Semaphore::new(LIMIT));
+	wx.config.action_executor(move |task| {
+		let limit = limit.clone();
+		tokio::spawn(async move {
+			let _permit = limit.acquire().await.unwrap();
+			task.await;
+		})
+	});
+
+	let main = wx.main();
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+	timeout(Duration::from_secs(5), main)
+		.await
+		.expect("tasks should complete")
+		.unwrap()
+		.unwrap();
+
+	assert_eq!(
+		finished.load(Ordering::SeqCst),
+		TASKS,
+		"all tasks should run"
+	);
+	assert!(
+		most.load(Ordering::SeqCst) <= LIMIT,
+		"at most {LIMIT} tasks should run at once, got {}",
+		most.load(Ordering::SeqCst)
+	);
+}
@@ -0,0 +1,89 @@
+use std::{
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+
+use tokio::time::{sleep, timeout};
+use watchexec::Watchexec;
+use watchexec_events::{Event, Priority};
+
+/// Run one action which takes `runs_for`, with an action timeout of 100ms.
+///
+/// Returns the timeouts the hook was called with.
+async fn run_action(runs_for: Duration) -> Vec<Duration> {
+	let (done_s, done_r) = tokio::sync::mpsc::unbounded_channel();
+	let wx = Watchexec::new_async(move |action| {
+		let done_s = done_s.clone();
+		Box::new(async move {
+			sleep(runs_for).await;
+			done_s.send(()).ok();
+			action
+		})
+	})
+	.unwrap();
+
+	let overdue: Arc<Mutex<Vec<Duration>>> = Arc::default();
+	wx.config.action_timeout(Some(Duration::from_millis(100)));
+	wx.config.on_action_timeout({
+		let overdue = overdue.clone();
+		move |limit| overdue.lock().unwrap().push(limit)
+	});
+
+	let main = wx.main();
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+
+	let mut done_r = done_r;
+	timeout(Duration::from_secs(5), done_r.recv())
+		.await
+		.expect("action should finish");
+	sleep(Duration::from_millis(200)).await;
+	main.abort();
+
+	let overdue = overdue.lock().unwrap().clone();
+	overdue
+}
+
+#[tokio::test]
+async fn slow_handler_fires_hook() {
+	let overdue = run_action(Duration::from_millis(500)).await;
+	assert_eq!(overdue, vec![Duration::from_millis(100)]);
+}
+
+#[tokio::test]
+async fn fast_handler_does_not_fire_hook() {
+	let overdue = run_action(Duration::ZERO).await;
+	assert!(overdue.is_empty(), "hook should not fire: {overdue:?}");
+}
+
+#[tokio::test]
+async fn blocking_sync_handler_fires_hook() {
+	// the test runtime is single-threaded, so this handler blocks it entirely
+	let (done_s, mut done_r) = tokio::sync::mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		std::thread::sleep(Duration::from_millis(500));
+		done_s.send(()).ok();
+		action
+	})
+	.unwrap();
+
+	let overdue: Arc<Mutex<Vec<Duration>>> = Arc::default();
+	wx.config.action_timeout(Some(Duration::from_millis(100)));
+	wx.config.on_action_timeout({
+		let overdue = overdue.clone();
+		move |limit| overdue.lock().unwrap().push(limit)
+	});
+
+	let main = wx.main();
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+
+	timeout(Duration::from_secs(5), done_r.recv())
+		.await
+		.expect("action should finish");
+	main.abort();
+
+	assert_eq!(*overdue.lock().unwrap(), vec![Duration::from_millis(100)]);
+}
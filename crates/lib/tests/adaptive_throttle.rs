@@ -0,0 +1,141 @@
+#![cfg(unix)]
+
+use std::{
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use tokio::{
+	sync::mpsc,
+	time::{sleep, timeout},
+};
+use watchexec::{
+	action::AdaptiveThrottle,
+	command::{Command, Program},
+	Watchexec,
+};
+use watchexec_events::{Event, Priority};
+
+fn shell(script: &str) -> Arc<Command> {
+	Arc::new(Command {
+		program: Program::Exec {
+			prog: "sh".into(),
+			args: vec!["-c".into(), script.into()],
+			argv0: None,
+		},
+		options: Default::default(),
+	})
+}
+
+struct Harness {
+	wx: Arc<Watchexec>,
+	next: Arc<Mutex<Option<Arc<Command>>>>,
+	actions: mpsc::UnboundedReceiver<Instant>,
+}
+
+impl Harness {
+	fn new() -> Self {
+		let next: Arc<Mutex<Option<Arc<Command>>>> = Arc::default();
+		let (actions_s, actions) = mpsc::unbounded_channel();
+		let wx = Watchexec::new({
+			let next = next.clone();
+			move |mut action| {
+				actions_s.send(Instant::now()).ok();
+				if let Some(command) = next.lock().unwrap().take() {
+					let (_, job) = action.create_job(command);
+					job.start();
+				}
+				action
+			}
+		})
+		.unwrap();
+		wx.config.throttle(Duration::from_millis(50));
+		wx.config.adaptive_throttle(Some(AdaptiveThrottle {
+			min: Duration::from_millis(50),
+			max: Duration::from_secs(3),
+		}));
+
+		Self { wx, next, actions }
+	}
+
+	/// Run a command to completion, right away.
+	async fn run(&mut self, script: &str) {
+		let jobs = self.wx.jobs().len();
+		*self.next.lock().unwrap() = Some(shell(script));
+		self.wx
+			.send_event(Event::default(), Priority::Urgent)
+			.await
+			.unwrap();
+		self.actions.recv().await.unwrap();
+
+		timeout(Duration::from_secs(5), async {
+			while self.wx.jobs().len() <= jobs {
+				sleep(Duration::from_millis(10)).await;
+			}
+			self.wx.wait_all().await;
+		})
+		.await
+		.expect("command should finish");
+	}
+
+	/// How long an event takes to become an action, which is the throttle window.
+	async fn window(&mut self) -> Duration {
+		let sent = Instant::now();
+		self.wx
+			.send_event(Event::default(), Priority::Normal)
+			.await
+			.unwrap();
+		let acted = timeout(Duration::from_secs(5), self.actions.recv())
+			.await
+			.expect("action should run")
+			.unwrap();
+		acted - sent
+	}
+}
+
+#[tokio::test]
+async fn window_follows_command_runtime() {
+	let mut harness = Harness::new();
+	let main = harness.wx.main();
+
+	let initial = harness.window().await;
+	assert!(
+		initial < Duration::from_millis(500),
+		"should start from the throttle: {initial:?}"
+	);
+
+	harness.run("sleep 1").await;
+	let grown = harness.window().await;
+	assert!(
+		grown >= Duration::from_millis(800),
+		"should grow after a long command: {grown:?}"
+	);
+
+	for _ in 0..AdaptiveThrottle::RECENT_RUNS {
+		harness.run("true").await;
+	}
+	let shrunk = harness.window().await;
+	assert!(
+		shrunk < Duration::from_millis(500),
+		"should shrink after short commands: {shrunk:?}"
+	);
+
+	main.abort();
+}
+
+#[test]
+fn window_is_bounded() {
+	let bounds = AdaptiveThrottle {
+		min: Duration::from_millis(100),
+		max: Duration::from_secs(2),
+	};
+	assert_eq!(bounds.window(Duration::ZERO), Duration::from_millis(100));
+	assert_eq!(
+		bounds.window(Duration::from_secs(1)),
+		Duration::from_secs(1)
+	);
+	assert_eq!(
+		bounds.window(Duration::from_secs(60)),
+		Duration::from_secs(2)
+	);
+}
@@ -0,0 +1,88 @@
+#![cfg(unix)]
+
+use std::{
+	fs,
+	path::PathBuf,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+use tokio::{
+	sync::mpsc,
+	time::{sleep, timeout},
+};
+use watchexec::{
+	command::{Command, Program},
+	Watchexec,
+};
+use watchexec_events::{Source, Tag};
+
+#[tokio::test]
+async fn command_writing_to_watched_dir_does_not_retrigger() {
+	let dir = tempfile::tempdir().unwrap();
+	let dir_path = dir.path().canonicalize().unwrap();
+	let out = dir_path.join("out");
+
+	let command = Arc::new(Command {
+		program: Program::Exec {
+			prog: "sh".into(),
+			args: vec![
+				"-c".into(),
+				format!(
+					"mkdir -p '{out}' && echo built > '{out}/artifact' && sleep 0.2",
+					out = out.display()
+				),
+			],
+			argv0: None,
+		},
+		options: Default::default(),
+	});
+
+	let (paths_s, mut paths_r) = mpsc::unbounded_channel();
+	let started = Arc::new(AtomicBool::new(false));
+	let wx = Watchexec::new(move |mut action| {
+		let ready = action
+			.events
+			.iter()
+			.any(|event| event.tags.contains(&Tag::Source(Source::Internal)));
+		if ready && !started.swap(true, Ordering::SeqCst) {
+			let (_, job) = action.create_job(command.clone());
+			job.start();
+		}
+
+		let paths: Vec<PathBuf> = action.paths().map(|(path, _)| path.to_owned()).collect();
+		if !paths.is_empty() {
+			paths_s.send(paths).ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(50));
+	wx.config.ready_event(true);
+	wx.config.pathset([dir_path.clone()]);
+	wx.config.ignore_command_outputs([out.clone()]);
+	wx.main();
+
+	// the command runs, writes its output, ends, and the settling window passes
+	sleep(Duration::from_millis(1500)).await;
+	assert!(
+		out.join("artifact").exists(),
+		"the command should have written its output"
+	);
+	assert!(
+		paths_r.try_recv().is_err(),
+		"command outputs should not trigger an action"
+	);
+
+	// outside of runs, changes to the outputs are delivered as usual
+	let late = out.join("edited");
+	fs::write(&late, "by hand").unwrap();
+	let paths = timeout(Duration::from_secs(5), paths_r.recv())
+		.await
+		.expect("change outside of a run should trigger an action")
+		.unwrap();
+	assert!(paths.contains(&late), "{paths:?}");
+}
@@ -0,0 +1,95 @@
+#![cfg(unix)]
+
+use std::{
+	path::Path,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+use tokio::time::{sleep, timeout};
+use watchexec::{
+	action::ConcurrencyPolicy,
+	command::{Command, Program},
+	Watchexec,
+};
+use watchexec_events::{Event, Priority};
+
+fn shell(script: String) -> Arc<Command> {
+	Arc::new(Command {
+		program: Program::Exec {
+			prog: "sh".into(),
+			args: vec!["-c".into(), script],
+			argv0: None,
+		},
+		options: Default::default(),
+	})
+}
+
+/// Start two jobs: the first takes a while then creates `first`, the second records whether
+/// `first` existed when it started by creating `second-after` or `second-during`.
+async fn run_two(dir: &Path, policy: ConcurrencyPolicy) {
+	let first = shell(format!("sleep 0.3 && touch '{}/first'", dir.display()));
+	let second = shell(format!(
+		"if [ -e '{dir}/first' ]; then touch '{dir}/second-after'; else touch '{dir}/second-during'; fi",
+		dir = dir.display()
+	));
+
+	let started = Arc::new(AtomicBool::new(false));
+	let wx = Watchexec::new(move |mut action| {
+		if !started.swap(true, Ordering::SeqCst) {
+			let (_, job) = action.create_job(first.clone());
+			job.start();
+			let (_, job) = action.create_job(second.clone());
+			job.start();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.max_concurrent_jobs(Some(1));
+	wx.config.concurrency_policy(policy);
+
+	let main = wx.main();
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+
+	timeout(Duration::from_secs(5), async {
+		while wx.jobs().len() < 2 {
+			sleep(Duration::from_millis(10)).await;
+		}
+		wx.wait_all().await;
+	})
+	.await
+	.expect("jobs should finish");
+
+	main.abort();
+}
+
+#[tokio::test]
+async fn queue_waits_for_first_to_exit() {
+	let dir = tempfile::tempdir().unwrap();
+	run_two(dir.path(), ConcurrencyPolicy::Queue).await;
+
+	assert!(dir.path().join("first").exists());
+	assert!(
+		dir.path().join("second-after").exists(),
+		"second job should start after the first exits"
+	);
+	assert!(!dir.path().join("second-during").exists());
+}
+
+#[tokio::test]
+async fn skip_does_not_start_over_limit() {
+	let dir = tempfile::tempdir().unwrap();
+	run_two(dir.path(), ConcurrencyPolicy::Skip).await;
+
+	assert!(dir.path().join("first").exists());
+	assert!(!dir.path().join("second-after").exists());
+	assert!(
+		!dir.path().join("second-during").exists(),
+		"second job should not start"
+	);
+}
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use watchexec::{
+	config::{Config, ConfigDiff},
+	WatchedPath,
+};
+
+#[test]
+fn snapshot_is_unchanged() {
+	let config = Config::default();
+	config.pathset(["/a"]);
+	assert!(config.snapshot().diff(&config).is_empty());
+}
+
+#[test]
+fn only_pathset_changed() {
+	let config = Config::default();
+	config.pathset(["/a", "/b"]);
+	let before = config.snapshot();
+
+	config.pathset([WatchedPath::from("/b"), WatchedPath::non_recursive("/c")]);
+	let diff = before.diff(&config);
+
+	assert_eq!(diff.pathset_added, vec![WatchedPath::non_recursive("/c")]);
+	assert_eq!(diff.pathset_removed, vec![WatchedPath::from("/a")]);
+	assert_eq!(diff.fields, Vec::new(), "no other fields should change");
+	assert_eq!(
+		diff.to_string(),
+		"pathset: + /c (non-recursive)\npathset: - /a\n"
+	);
+
+	// the snapshot isn't affected by changes to the original
+	assert_eq!(
+		before.pathset.get(),
+		vec![WatchedPath::from("/a"), WatchedPath::from("/b")]
+	);
+}
+
+#[test]
+fn values_and_replacements() {
+	let config = Config::default();
+	let before = config.snapshot();
+
+	config.throttle(Duration::from_millis(100));
+	config.filterer(());
+	let diff = before.diff(&config);
+
+	assert!(diff.pathset_added.is_empty() && diff.pathset_removed.is_empty());
+	assert_eq!(
+		diff.fields
+			.iter()
+			.map(ToString::to_string)
+			.collect::<Vec<_>>(),
+		["throttle: 50ms -> 100ms", "filterer: replaced"]
+	);
+	assert_ne!(diff, ConfigDiff::default());
+}
@@ -0,0 +1,95 @@
+mod helpers;
+
+use std::{path::PathBuf, time::Duration};
+
+use helpers::event;
+use tokio::{
+	sync::mpsc,
+	time::{sleep, timeout},
+};
+use watchexec::Watchexec;
+use watchexec_events::Priority;
+
+fn watchexec(
+	window: Duration,
+) -> (
+	std::sync::Arc<Watchexec>,
+	mpsc::UnboundedReceiver<Vec<PathBuf>>,
+) {
+	let (actions_s, actions_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		actions_s
+			.send(action.paths().map(|(path, _)| path.to_owned()).collect())
+			.ok();
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(50));
+	wx.config.dedup_runs_by_paths(Some(window));
+	wx.main();
+	(wx, actions_r)
+}
+
+async fn burst(wx: &Watchexec, events: &[&[&str]]) {
+	for paths in events {
+		wx.send_event(event(paths), Priority::Normal).await.unwrap();
+	}
+}
+
+#[tokio::test]
+async fn identical_paths_within_window_run_once() {
+	let (wx, mut actions) = watchexec(Duration::from_secs(5));
+
+	burst(&wx, &[&["/a"], &["/b", "/a"]]).await;
+	timeout(Duration::from_secs(5), actions.recv())
+		.await
+		.expect("first action should run")
+		.unwrap();
+
+	// same set of paths, in another order and with more events
+	burst(&wx, &[&["/b"], &["/a"], &["/b"]]).await;
+	assert!(
+		timeout(Duration::from_millis(500), actions.recv())
+			.await
+			.is_err(),
+		"same paths should be skipped"
+	);
+
+	burst(&wx, &[&["/a"], &["/c"]]).await;
+	let paths = timeout(Duration::from_secs(5), actions.recv())
+		.await
+		.expect("different paths should run")
+		.unwrap();
+	assert_eq!(paths, ["/a", "/c"].map(PathBuf::from));
+}
+
+#[tokio::test]
+async fn identical_paths_after_window_run_again() {
+	let (wx, mut actions) = watchexec(Duration::from_millis(200));
+
+	burst(&wx, &[&["/a"]]).await;
+	timeout(Duration::from_secs(5), actions.recv())
+		.await
+		.expect("first action should run")
+		.unwrap();
+
+	sleep(Duration::from_millis(300)).await;
+	burst(&wx, &[&["/a"]]).await;
+	timeout(Duration::from_secs(5), actions.recv())
+		.await
+		.expect("same paths should run once the window is over")
+		.unwrap();
+}
+
+#[tokio::test]
+async fn pathless_events_always_run() {
+	let (wx, mut actions) = watchexec(Duration::from_secs(5));
+
+	for _ in 0..2 {
+		burst(&wx, &[&[]]).await;
+		timeout(Duration::from_secs(5), actions.recv())
+			.await
+			.expect("action should run")
+			.unwrap();
+	}
+}
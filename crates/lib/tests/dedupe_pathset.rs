@@ -0,0 +1,127 @@
+use std::{fs, time::Duration};
+
+use tokio::{
+	sync::mpsc,
+	time::{sleep, timeout},
+};
+use watchexec::{
+	error::{FsWatcherError, RuntimeError},
+	Config, ErrorHook, WatchedPath, Watchexec,
+};
+use watchexec_events::{filekind::FileEventKind, Event, Source, Tag};
+
+fn is_ready(event: &Event) -> bool {
+	event.tags.contains(&Tag::Source(Source::Internal))
+		&& event.metadata.get("watch") == Some(&vec!["ready".to_string()])
+}
+
+fn is_create(event: &Event) -> bool {
+	event
+		.tags
+		.iter()
+		.any(|tag| matches!(tag, Tag::FileEventKind(FileEventKind::Create(_))))
+}
+
+#[tokio::test]
+async fn nested_recursive_path_is_collapsed() {
+	let root = tempfile::tempdir().unwrap();
+	let root_path = dunce::canonicalize(root.path()).unwrap();
+	let src = root_path.join("src");
+	fs::create_dir(&src).unwrap();
+
+	let (errors_s, mut errors_r) = mpsc::unbounded_channel();
+	let (events_s, mut events_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		for event in action.events.iter() {
+			events_s.send(event.clone()).ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.on_error(move |err: ErrorHook| {
+		if let RuntimeError::FsWatcher {
+			err: FsWatcherError::PathLimit { path, .. },
+			..
+		} = err.error
+		{
+			errors_s.send(path).ok();
+		}
+	});
+	wx.config.throttle(Duration::from_millis(50));
+	wx.config.ready_event(true);
+	// the root and src count as two paths, and watching src again would make three
+	wx.config.max_watched_paths(Some(2));
+	wx.config.dedupe_pathset(true);
+	wx.config
+		.pathset([root_path.clone(), root_path.join(".").join("src")]);
+
+	assert_eq!(
+		wx.config.watched_paths(),
+		vec![WatchedPath::recursive(&root_path)],
+		"only the top-most path should be watched"
+	);
+
+	let main = wx.main();
+	timeout(Duration::from_secs(5), async {
+		while let Some(event) = events_r.recv().await {
+			if is_ready(&event) {
+				break;
+			}
+		}
+	})
+	.await
+	.expect("watcher should be set up");
+	assert!(
+		errors_r.try_recv().is_err(),
+		"the collapsed path should not count against the limit"
+	);
+
+	let file = src.join("file");
+	fs::write(&file, "content").unwrap();
+	let mut creates = 0;
+	timeout(Duration::from_secs(5), async {
+		while let Some(event) = events_r.recv().await {
+			if is_create(&event) && event.paths().any(|(path, _)| path == file) {
+				creates += 1;
+				break;
+			}
+		}
+	})
+	.await
+	.expect("should see the file being created");
+
+	// any duplicate would arrive right after
+	sleep(Duration::from_millis(500)).await;
+	while let Ok(event) = events_r.try_recv() {
+		if is_create(&event) && event.paths().any(|(path, _)| path == file) {
+			creates += 1;
+		}
+	}
+	assert_eq!(creates, 1, "the creation should be seen once");
+
+	main.abort();
+}
+
+#[test]
+fn non_recursive_paths_are_kept() {
+	let root = tempfile::tempdir().unwrap();
+	let root_path = dunce::canonicalize(root.path()).unwrap();
+	let src = root_path.join("src");
+	fs::create_dir(&src).unwrap();
+
+	let config = Config::default();
+	config.dedupe_pathset(true);
+	config.pathset([
+		WatchedPath::recursive(&root_path),
+		WatchedPath::non_recursive(&src),
+		WatchedPath::recursive(&root_path),
+	]);
+
+	assert_eq!(
+		config.watched_paths(),
+		vec![
+			WatchedPath::recursive(&root_path),
+			WatchedPath::non_recursive(&src),
+		]
+	);
+}
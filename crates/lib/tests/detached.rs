@@ -0,0 +1,95 @@
+#![cfg(unix)]
+
+use std::{
+	path::Path,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+use tokio::time::{sleep, timeout};
+use watchexec::{
+	command::{Command, Program, SpawnOptions},
+	Watchexec,
+};
+use watchexec_events::{Event, Priority};
+use watchexec_signals::Signal;
+
+/// Start a command which touches `marker` after a second, then quit gracefully right away.
+async fn start_and_quit(marker: &Path, detached: bool) {
+	let command = Arc::new(Command {
+		program: Program::Exec {
+			prog: "sh".into(),
+			args: vec![
+				"-c".into(),
+				format!("sleep 1; touch '{}'", marker.display()),
+			],
+			argv0: None,
+		},
+		options: SpawnOptions {
+			detached,
+			..Default::default()
+		},
+	});
+
+	let started = Arc::new(AtomicBool::new(false));
+	let wx = Watchexec::new(move |mut action| {
+		if started.swap(true, Ordering::SeqCst) {
+			action.quit_gracefully(Signal::Terminate, Duration::from_secs(1));
+		} else {
+			let (_, job) = action.create_job(command.clone());
+			job.start();
+		}
+		action
+	})
+	.unwrap();
+
+	let main = wx.main();
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+	sleep(Duration::from_millis(200)).await;
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+
+	timeout(Duration::from_secs(5), main)
+		.await
+		.expect("watchexec should quit")
+		.unwrap()
+		.unwrap();
+}
+
+async fn marker_appears(marker: &Path) -> bool {
+	timeout(Duration::from_secs(3), async {
+		while !marker.exists() {
+			sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.is_ok()
+}
+
+#[tokio::test]
+async fn detached_command_survives_quit() {
+	let dir = tempfile::tempdir().unwrap();
+	let marker = dir.path().join("marker");
+	start_and_quit(&marker, true).await;
+	assert!(
+		marker_appears(&marker).await,
+		"detached command should keep running after watchexec quits"
+	);
+}
+
+#[tokio::test]
+async fn attached_command_stops_on_quit() {
+	let dir = tempfile::tempdir().unwrap();
+	let marker = dir.path().join("marker");
+	start_and_quit(&marker, false).await;
+	assert!(
+		!marker_appears(&marker).await,
+		"command should be stopped when watchexec quits"
+	);
+}
@@ -0,0 +1,71 @@
+use std::{
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+
+use tokio::time::sleep;
+use watchexec::{error::RuntimeError, filter::Filterer, ErrorHook, Watchexec};
+use watchexec_events::{Event, Priority, Source, Tag};
+
+/// Fails every check with the same error.
+#[derive(Debug)]
+struct Failing;
+
+impl Filterer for Failing {
+	fn check_event(&self, _event: &Event, _priority: Priority) -> Result<bool, RuntimeError> {
+		Err(RuntimeError::Filterer {
+			kind: "failing",
+			err: "always fails".into(),
+		})
+	}
+}
+
+#[tokio::test]
+async fn identical_errors_are_coalesced() {
+	const SENT: usize = 500;
+
+	let calls = Arc::new(Mutex::new(Vec::new()));
+	let wx = Watchexec::default();
+	wx.config.filterer(Failing);
+	wx.config.error_rate_limit(3, Duration::from_millis(300));
+	wx.config.on_error({
+		let calls = calls.clone();
+		move |err: ErrorHook| {
+			calls.lock().unwrap().push(err.suppressed);
+		}
+	});
+	let main = wx.main();
+
+	for _ in 0..SENT {
+		let event = Event {
+			tags: vec![Tag::Source(Source::Filesystem)],
+			metadata: Default::default(),
+		};
+		wx.send_event(event, Priority::Normal).await.unwrap();
+	}
+
+	// wait for the interval to end and the held back error to come through
+	sleep(Duration::from_secs(1)).await;
+	main.abort();
+
+	let calls = calls.lock().unwrap();
+	assert!(
+		calls.len() < 20,
+		"handler should be called far fewer times than there were errors, got {}",
+		calls.len()
+	);
+	assert_eq!(
+		calls.iter().map(|suppressed| suppressed + 1).sum::<usize>(),
+		SENT,
+		"every error should be accounted for, as given or suppressed: {calls:?}"
+	);
+	assert_eq!(
+		&calls[..3],
+		&[0, 0, 0],
+		"first errors should pass as they come"
+	);
+	assert!(
+		calls[3] > 0,
+		"the held back error should carry a suppressed count"
+	);
+}
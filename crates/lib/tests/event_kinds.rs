@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use tokio::{
+	sync::mpsc,
+	time::{sleep, timeout},
+};
+use watchexec::{sources::fs::FsEventKinds, Watchexec};
+use watchexec_events::{
+	filekind::{AccessKind, DataChange, FileEventKind, ModifyKind},
+	Event, Tag,
+};
+
+#[test]
+fn classifies_event_kinds() {
+	assert_eq!(
+		FsEventKinds::of(&FileEventKind::Access(AccessKind::Any)),
+		Some(FsEventKinds::ACCESS)
+	);
+	assert_eq!(
+		FsEventKinds::of(&FileEventKind::Modify(ModifyKind::Data(DataChange::Any))),
+		Some(FsEventKinds::MODIFY)
+	);
+	assert_eq!(FsEventKinds::of(&FileEventKind::Other), None);
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn drops_access_events_keeps_writes() {
+	let root = tempfile::tempdir().unwrap();
+	let file = dunce::canonicalize(root.path()).unwrap().join("file");
+
+	let (events_s, mut events_r) = mpsc::unbounded_channel::<Event>();
+	let wx = Watchexec::new(move |action| {
+		for event in action.events.iter() {
+			events_s.send(event.clone()).ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.pathset([root.path().to_owned()]);
+	wx.config
+		.event_kinds(FsEventKinds::all().difference(FsEventKinds::ACCESS));
+	wx.config.throttle(Duration::from_millis(10));
+	let main = wx.main();
+
+	// give the watcher time to start
+	sleep(Duration::from_millis(200)).await;
+
+	std::fs::write(&file, "hello").unwrap();
+	std::fs::read(&file).unwrap();
+
+	let mut kinds = Vec::new();
+	let _ = timeout(Duration::from_secs(2), async {
+		while let Some(event) = events_r.recv().await {
+			kinds.extend(event.tags.iter().filter_map(|tag| match tag {
+				Tag::FileEventKind(kind) => Some(*kind),
+				_ => None,
+			}));
+		}
+	})
+	.await;
+	main.abort();
+
+	assert!(
+		kinds
+			.iter()
+			.any(|kind| matches!(kind, FileEventKind::Create(_) | FileEventKind::Modify(_))),
+		"should see write events, got {kinds:?}"
+	);
+	assert!(
+		!kinds
+			.iter()
+			.any(|kind| matches!(kind, FileEventKind::Access(_))),
+		"should not see access events, got {kinds:?}"
+	);
+}
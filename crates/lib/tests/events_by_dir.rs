@@ -0,0 +1,81 @@
+mod helpers;
+
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use helpers::event;
+use tokio::{sync::mpsc, time::timeout};
+use watchexec::Watchexec;
+use watchexec_events::{Event, Priority};
+
+#[tokio::test]
+async fn groups_across_two_directories() {
+	let (actions_s, mut actions_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		let groups: HashMap<PathBuf, Vec<Vec<PathBuf>>> = action
+			.events_by_dir(2)
+			.into_iter()
+			.map(|(dir, events)| {
+				(
+					dir,
+					events
+						.into_iter()
+						.map(|event| event.paths().map(|(p, _)| p.to_owned()).collect())
+						.collect(),
+				)
+			})
+			.collect();
+		actions_s.send(groups).ok();
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(100));
+	wx.main();
+
+	for paths in [
+		&["/project/frontend/src/app.js"][..],
+		&["/project/backend/main.rs"],
+		&["/project/frontend/index.html"],
+		&["/project/backend/lib/db.rs", "/project/frontend/api.js"],
+		&["/project/readme.md"],
+	] {
+		wx.send_event(event(paths), Priority::Normal).await.unwrap();
+	}
+	// no paths, so not in any group
+	wx.send_event(Event::default(), Priority::Normal)
+		.await
+		.unwrap();
+
+	let groups = timeout(Duration::from_secs(5), actions_r.recv())
+		.await
+		.expect("action should run")
+		.unwrap();
+
+	let mut keys: Vec<_> = groups.keys().cloned().collect();
+	keys.sort();
+	assert_eq!(
+		keys,
+		["/project", "/project/backend", "/project/frontend"].map(PathBuf::from)
+	);
+
+	let firsts = |dir: &str| -> Vec<PathBuf> {
+		groups[&PathBuf::from(dir)]
+			.iter()
+			.map(|p| p[0].clone())
+			.collect()
+	};
+	assert_eq!(
+		firsts("/project/frontend"),
+		[
+			"/project/frontend/src/app.js",
+			"/project/frontend/index.html",
+			"/project/backend/lib/db.rs",
+		]
+		.map(PathBuf::from),
+		"events are in order, and the mixed one is in both groups"
+	);
+	assert_eq!(
+		firsts("/project/backend"),
+		["/project/backend/main.rs", "/project/backend/lib/db.rs"].map(PathBuf::from)
+	);
+	assert_eq!(firsts("/project"), [PathBuf::from("/project/readme.md")]);
+}
@@ -0,0 +1,92 @@
+#![cfg(unix)]
+
+use std::{
+	fs,
+	path::PathBuf,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+use tokio::{
+	sync::mpsc,
+	time::{sleep, timeout},
+};
+use watchexec::{
+	command::{Command, Program},
+	Watchexec,
+};
+use watchexec_events::{Source, Tag};
+
+#[tokio::test]
+async fn command_touching_watched_files_does_not_retrigger() {
+	let dir = tempfile::tempdir().unwrap();
+	let dir_path = dir.path().canonicalize().unwrap();
+
+	let command = Arc::new(Command {
+		program: Program::Exec {
+			prog: "sh".into(),
+			args: vec![
+				"-c".into(),
+				format!(
+					"touch '{dir}/source.rs' && mkdir -p '{dir}/target' && echo built > '{dir}/target/bin' && sleep 0.2",
+					dir = dir_path.display()
+				),
+			],
+			argv0: None,
+		},
+		options: Default::default(),
+	});
+
+	let (paths_s, mut paths_r) = mpsc::unbounded_channel();
+	let started = Arc::new(AtomicBool::new(false));
+	let wx = Watchexec::new(move |mut action| {
+		let ready = action
+			.events
+			.iter()
+			.any(|event| event.tags.contains(&Tag::Source(Source::Internal)));
+		if ready && !started.swap(true, Ordering::SeqCst) {
+			let (_, job) = action.create_job(command.clone());
+			job.start();
+		}
+
+		let paths: Vec<PathBuf> = action.paths().map(|(path, _)| path.to_owned()).collect();
+		if !paths.is_empty() {
+			paths_s.send(paths).ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(50));
+	wx.config.ready_event(true);
+	wx.config.pathset([dir_path.clone()]);
+	wx.config.ignore_events_during_run(true);
+	wx.main();
+
+	// the command runs, touches files, ends, and the settling window passes
+	sleep(Duration::from_millis(1500)).await;
+	assert_eq!(
+		wx.jobs().len(),
+		1,
+		"the command should have been started once"
+	);
+	assert!(
+		dir_path.join("source.rs").exists() && dir_path.join("target/bin").exists(),
+		"the command should have touched its files"
+	);
+	assert!(
+		paths_r.try_recv().is_err(),
+		"changes made during the run should not trigger an action"
+	);
+
+	// after the run, changes are delivered as usual
+	let late = dir_path.join("edited");
+	fs::write(&late, "by hand").unwrap();
+	let paths = timeout(Duration::from_secs(5), paths_r.recv())
+		.await
+		.expect("change after the run should trigger an action")
+		.unwrap();
+	assert!(paths.contains(&late), "{paths:?}");
+}
@@ -0,0 +1,124 @@
+// kqueue is the native watcher on these, and it uses a file descriptor per watched path
+#![cfg(any(
+	target_os = "freebsd",
+	target_os = "openbsd",
+	target_os = "netbsd",
+	target_os = "dragonfly"
+))]
+
+use std::{fs, path::Path, sync::Arc, time::Duration};
+
+use nix::sys::resource::{getrlimit, setrlimit, Resource};
+use tokio::{sync::mpsc, time::timeout};
+use watchexec::{
+	error::{FsWatcherError, RuntimeError},
+	sources::fs::FdExhaustion,
+	ErrorHook, Watchexec,
+};
+
+const FILES: usize = 200;
+const LOW_LIMIT: u64 = 64;
+
+fn busy_tree(dir: &Path) {
+	for n in 0..FILES {
+		fs::write(dir.join(format!("file{n}")), "content").unwrap();
+	}
+}
+
+/// Lowers the soft limit on open files, and restores it when dropped.
+struct LowLimit(u64);
+
+impl LowLimit {
+	fn set() -> Self {
+		let (soft, hard) = getrlimit(Resource::RLIMIT_NOFILE).unwrap();
+		setrlimit(Resource::RLIMIT_NOFILE, LOW_LIMIT, hard).unwrap();
+		Self(soft)
+	}
+}
+
+impl Drop for LowLimit {
+	fn drop(&mut self) {
+		let (_, hard) = getrlimit(Resource::RLIMIT_NOFILE).unwrap();
+		setrlimit(Resource::RLIMIT_NOFILE, self.0, hard).ok();
+	}
+}
+
+async fn watch(
+	strategy: FdExhaustion,
+	dir: &Path,
+) -> (
+	Arc<Watchexec>,
+	mpsc::UnboundedReceiver<String>,
+	mpsc::UnboundedReceiver<usize>,
+) {
+	let (errors_s, errors_r) = mpsc::unbounded_channel();
+	let (actions_s, mut actions_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		actions_s.send(action.paths().count()).ok();
+		action
+	})
+	.unwrap();
+	wx.config.on_error(move |err: ErrorHook| {
+		let kind = match &err.error {
+			RuntimeError::FsWatcher {
+				err: FsWatcherError::PollFallback { .. },
+				..
+			} => "fallback".to_string(),
+			other => other.to_string(),
+		};
+		errors_s.send(kind).ok();
+	});
+	wx.config.fd_exhaustion(strategy);
+	wx.config.throttle(Duration::from_millis(100));
+	wx.config.ready_event(true);
+	wx.config.pathset([dir]);
+	wx.main();
+
+	timeout(Duration::from_secs(5), actions_r.recv())
+		.await
+		.expect("watcher should get ready")
+		.unwrap();
+	(wx, errors_r, actions_r)
+}
+
+// the limit is process-wide, so both cases are in the same test
+#[tokio::test]
+async fn degrades_gracefully() {
+	let dir = tempfile::tempdir().unwrap();
+	busy_tree(dir.path());
+	let _limit = LowLimit::set();
+
+	{
+		let (_wx, mut errors, mut actions) = watch(FdExhaustion::RaiseLimit, dir.path()).await;
+
+		fs::write(dir.path().join("file0"), "changed").unwrap();
+		let paths = timeout(Duration::from_secs(5), actions.recv())
+			.await
+			.expect("changes should be seen")
+			.unwrap();
+		assert!(paths > 0);
+		assert!(errors.try_recv().is_err(), "no error should be reported");
+
+		let (raised, _) = getrlimit(Resource::RLIMIT_NOFILE).unwrap();
+		assert!(raised > LOW_LIMIT, "limit should have been raised");
+	}
+
+	let _limit = LowLimit::set();
+	{
+		let (_wx, mut errors, mut actions) =
+			watch(FdExhaustion::Poll(Duration::from_millis(100)), dir.path()).await;
+
+		let error = timeout(Duration::from_secs(5), errors.recv())
+			.await
+			.expect("fallback should be reported")
+			.unwrap();
+		assert_eq!(error, "fallback");
+
+		fs::write(dir.path().join("file1"), "changed").unwrap();
+		let paths = timeout(Duration::from_secs(5), actions.recv())
+			.await
+			.expect("changes should still be seen by polling")
+			.unwrap();
+		assert!(paths > 0);
+	}
+}
@@ -0,0 +1,105 @@
+#![cfg(unix)]
+
+use std::{fs, path::PathBuf, time::Duration};
+
+use tokio::{
+	sync::mpsc,
+	time::{sleep, timeout},
+};
+use watchexec::{WatchedPath, Watchexec};
+
+async fn next_paths(actions: &mut mpsc::UnboundedReceiver<Vec<PathBuf>>) -> Option<Vec<PathBuf>> {
+	timeout(Duration::from_secs(2), actions.recv())
+		.await
+		.ok()
+		.flatten()
+}
+
+#[tokio::test]
+async fn only_the_watched_file_in_a_busy_directory() {
+	let dir = tempfile::tempdir().unwrap();
+	let dir_path = dir.path().canonicalize().unwrap();
+	let watched = dir_path.join("config.toml");
+	fs::write(&watched, "a = 1").unwrap();
+
+	let (actions_s, mut actions_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		let paths: Vec<PathBuf> = action.paths().map(|(path, _)| path.to_owned()).collect();
+		if !paths.is_empty() {
+			actions_s.send(paths).ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(100));
+	wx.config.pathset([WatchedPath::file(&watched)]);
+	wx.main();
+	sleep(Duration::from_millis(500)).await;
+
+	// siblings changing shouldn't be seen at all
+	for n in 0..10 {
+		fs::write(dir_path.join(format!("sibling{n}")), "noise").unwrap();
+	}
+	fs::create_dir(dir_path.join("subdir")).unwrap();
+	fs::write(dir_path.join("subdir").join("nested"), "noise").unwrap();
+	assert_eq!(
+		next_paths(&mut actions_r).await,
+		None,
+		"should not see sibling events"
+	);
+
+	// a plain write to the file
+	fs::write(&watched, "a = 2").unwrap();
+	let paths = next_paths(&mut actions_r)
+		.await
+		.expect("should see a write to the file");
+	assert!(paths.iter().all(|path| path == &watched), "{paths:?}");
+
+	// atomic save: write a sibling then rename it over the file
+	let temp = dir_path.join(".config.toml.tmp");
+	fs::write(&temp, "a = 3").unwrap();
+	fs::rename(&temp, &watched).unwrap();
+	let paths = next_paths(&mut actions_r)
+		.await
+		.expect("should see the file being replaced");
+	assert!(paths.iter().all(|path| path == &watched), "{paths:?}");
+
+	// and the replacement is still watched
+	fs::write(&watched, "a = 4").unwrap();
+	let paths = next_paths(&mut actions_r)
+		.await
+		.expect("should see a write to the replaced file");
+	assert!(paths.iter().all(|path| path == &watched), "{paths:?}");
+}
+
+#[tokio::test]
+async fn directory_watch_alongside_file_watch_sees_everything() {
+	let dir = tempfile::tempdir().unwrap();
+	let dir_path = dir.path().canonicalize().unwrap();
+	let watched = dir_path.join("config.toml");
+	fs::write(&watched, "a = 1").unwrap();
+
+	let (actions_s, mut actions_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		let paths: Vec<PathBuf> = action.paths().map(|(path, _)| path.to_owned()).collect();
+		if !paths.is_empty() {
+			actions_s.send(paths).ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(100));
+	wx.config.pathset([
+		WatchedPath::file(&watched),
+		WatchedPath::non_recursive(&dir_path),
+	]);
+	wx.main();
+	sleep(Duration::from_millis(500)).await;
+
+	let sibling = dir_path.join("sibling");
+	fs::write(&sibling, "seen").unwrap();
+	let paths = next_paths(&mut actions_r)
+		.await
+		.expect("should see siblings when the directory is watched too");
+	assert!(paths.contains(&sibling), "{paths:?}");
+}
@@ -0,0 +1,93 @@
+use std::sync::{
+	atomic::{AtomicUsize, Ordering},
+	Arc,
+};
+
+use watchexec::{
+	error::RuntimeError,
+	filter::{AndFilterer, Filterer, OrFilterer},
+};
+use watchexec_events::{Event, Priority};
+
+#[derive(Debug)]
+struct Fixed {
+	pass: Result<bool, &'static str>,
+	calls: Arc<AtomicUsize>,
+}
+
+impl Filterer for Fixed {
+	fn check_event(&self, _event: &Event, _priority: Priority) -> Result<bool, RuntimeError> {
+		self.calls.fetch_add(1, Ordering::SeqCst);
+		self.pass.map_err(|err| RuntimeError::Filterer {
+			kind: "fixed",
+			err: err.into(),
+		})
+	}
+}
+
+fn filterers(results: &[Result<bool, &'static str>]) -> (Vec<Box<dyn Filterer>>, Arc<AtomicUsize>) {
+	let calls = Arc::new(AtomicUsize::new(0));
+	let filterers = results
+		.iter()
+		.map(|pass| {
+			Box::new(Fixed {
+				pass: *pass,
+				calls: calls.clone(),
+			}) as Box<dyn Filterer>
+		})
+		.collect();
+	(filterers, calls)
+}
+
+fn check(filterer: &impl Filterer) -> Result<bool, RuntimeError> {
+	filterer.check_event(&Event::default(), Priority::Normal)
+}
+
+#[test]
+fn and_requires_all() {
+	let (all, _) = filterers(&[Ok(true), Ok(true), Ok(true)]);
+	assert!(check(&AndFilterer(all)).unwrap());
+
+	let (some, calls) = filterers(&[Ok(true), Ok(false), Ok(true)]);
+	assert!(!check(&AndFilterer(some)).unwrap());
+	assert_eq!(
+		calls.load(Ordering::SeqCst),
+		2,
+		"should stop at first reject"
+	);
+
+	assert!(check(&AndFilterer::default()).unwrap());
+}
+
+#[test]
+fn or_requires_any() {
+	let (none, _) = filterers(&[Ok(false), Ok(false)]);
+	assert!(!check(&OrFilterer(none)).unwrap());
+
+	let (some, calls) = filterers(&[Ok(false), Ok(true), Ok(false)]);
+	assert!(check(&OrFilterer(some)).unwrap());
+	assert_eq!(calls.load(Ordering::SeqCst), 2, "should stop at first pass");
+
+	assert!(!check(&OrFilterer::default()).unwrap());
+}
+
+#[test]
+fn first_error_wins() {
+	let (and, calls) = filterers(&[Ok(true), Err("first"), Err("second")]);
+	let err = check(&AndFilterer(and)).unwrap_err();
+	assert_eq!(err.to_string(), "fixed filterer: first");
+	assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+	let (or, calls) = filterers(&[Ok(false), Err("first"), Ok(true)]);
+	let err = check(&OrFilterer(or)).unwrap_err();
+	assert_eq!(err.to_string(), "fixed filterer: first");
+	assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn combinators_nest() {
+	let (inner, _) = filterers(&[Ok(false), Ok(true)]);
+	let (mut outer, _) = filterers(&[Ok(true)]);
+	outer.push(Box::new(OrFilterer(inner)));
+	assert!(check(&AndFilterer(outer)).unwrap());
+}
@@ -0,0 +1,151 @@
+use std::{
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		mpsc, Arc, Barrier,
+	},
+	thread,
+	time::Duration,
+};
+
+use watchexec::{
+	error::RuntimeError,
+	filter::{ChangeableFilterer, Filterer},
+};
+use watchexec_events::{Event, Priority};
+
+/// Passes events on even generations, built in two halves which must always agree.
+#[derive(Debug)]
+struct Generation {
+	first: Vec<usize>,
+	second: Vec<usize>,
+}
+
+impl Generation {
+	fn new(n: usize) -> Self {
+		Self {
+			first: vec![n; 64],
+			second: vec![n; 64],
+		}
+	}
+}
+
+impl Filterer for Generation {
+	fn check_event(&self, _event: &Event, _priority: Priority) -> Result<bool, RuntimeError> {
+		let n = self.first[0];
+		assert!(self.first.iter().chain(&self.second).all(|m| *m == n));
+		Ok(n % 2 == 0)
+	}
+}
+
+/// Blocks in the middle of a check until released.
+#[derive(Debug)]
+struct Blocking {
+	entered: Arc<Barrier>,
+	release: Arc<Barrier>,
+}
+
+impl Filterer for Blocking {
+	fn check_event(&self, _event: &Event, _priority: Priority) -> Result<bool, RuntimeError> {
+		self.entered.wait();
+		self.release.wait();
+		Ok(true)
+	}
+}
+
+#[test]
+fn checks_survive_replacements() {
+	let filterer = ChangeableFilterer::default();
+	filterer.replace(Generation::new(0));
+	let stop = Arc::new(AtomicBool::new(false));
+
+	let checkers: Vec<_> = (0..4)
+		.map(|_| {
+			let filterer = filterer.clone();
+			let stop = stop.clone();
+			thread::spawn(move || {
+				let mut checks = 0_usize;
+				while !stop.load(Ordering::Relaxed) {
+					filterer
+						.check_event(&Event::default(), Priority::Normal)
+						.unwrap();
+					checks += 1;
+				}
+				checks
+			})
+		})
+		.collect();
+
+	for n in 1..=1000 {
+		filterer.replace(Generation::new(n));
+	}
+	stop.store(true, Ordering::Relaxed);
+
+	for checker in checkers {
+		checker.join().expect("no check should see a torn filterer");
+	}
+
+	assert!(
+		filterer
+			.check_event(&Event::default(), Priority::Normal)
+			.unwrap(),
+		"checks after the last replacement should use it"
+	);
+}
+
+#[test]
+fn in_flight_check_completes_with_old_filterer() {
+	let filterer = ChangeableFilterer::default();
+	let entered = Arc::new(Barrier::new(2));
+	let release = Arc::new(Barrier::new(2));
+	filterer.replace(Blocking {
+		entered: entered.clone(),
+		release: release.clone(),
+	});
+
+	let (done_s, done_r) = mpsc::channel();
+	let checker = {
+		let filterer = filterer.clone();
+		thread::spawn(move || {
+			let pass = filterer
+				.check_event(&Event::default(), Priority::Normal)
+				.unwrap();
+			done_s.send(pass).unwrap();
+		})
+	};
+
+	entered.wait();
+	// the check is now running; replacing mustn't wait for it nor affect it
+	filterer.replace(Generation::new(1));
+	assert!(
+		!filterer
+			.check_event(&Event::default(), Priority::Normal)
+			.unwrap(),
+		"new checks should use the new filterer"
+	);
+	assert!(
+		done_r.recv_timeout(Duration::from_millis(50)).is_err(),
+		"in-flight check should still be running"
+	);
+
+	release.wait();
+	assert!(
+		done_r.recv_timeout(Duration::from_secs(5)).unwrap(),
+		"in-flight check should complete with the old filterer"
+	);
+	checker.join().unwrap();
+}
+
+#[test]
+fn current_is_a_snapshot() {
+	let filterer = ChangeableFilterer::default();
+	filterer.replace(Generation::new(0));
+	let current = filterer.current();
+
+	filterer.replace(Generation::new(1));
+	assert!(current
+		.check_event(&Event::default(), Priority::Normal)
+		.unwrap());
+	assert!(!filterer
+		.check_event(&Event::default(), Priority::Normal)
+		.unwrap());
+}
@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use tokio::{sync::mpsc, time::timeout};
+use watchexec::Watchexec;
+use watchexec_events::{Event, Priority, Source, Tag};
+
+fn event(path: &str) -> Event {
+	Event {
+		tags: vec![
+			Tag::Source(Source::Filesystem),
+			Tag::Path {
+				path: path.into(),
+				file_type: None,
+			},
+		],
+		metadata: Default::default(),
+	}
+}
+
+#[tokio::test]
+async fn only_first_action_is_marked() {
+	let (actions_s, mut actions) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		actions_s.send(action.is_first_run()).ok();
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(50));
+	wx.main();
+
+	// nothing is sent at startup, as with --postpone: the first real change is the first run
+	for (path, first) in [("/a", true), ("/b", false), ("/c", false)] {
+		wx.send_event(event(path), Priority::Normal).await.unwrap();
+		let first_run = timeout(Duration::from_secs(5), actions.recv())
+			.await
+			.expect("action should run")
+			.unwrap();
+		assert_eq!(first_run, first, "action for {path}");
+	}
+}
@@ -0,0 +1,14 @@
+use watchexec_events::{Event, Source, Tag};
+
+/// A filesystem event about these paths, and nothing else.
+pub fn event(paths: &[&str]) -> Event {
+	let mut tags = vec![Tag::Source(Source::Filesystem)];
+	tags.extend(paths.iter().map(|path| Tag::Path {
+		path: path.into(),
+		file_type: None,
+	}));
+	Event {
+		tags,
+		metadata: Default::default(),
+	}
+}
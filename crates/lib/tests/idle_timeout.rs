@@ -0,0 +1,90 @@
+use std::time::{Duration, Instant};
+
+use tokio::time::{sleep, timeout};
+use watchexec::Watchexec;
+use watchexec_events::{Event, Priority};
+
+#[tokio::test]
+async fn quits_when_idle() {
+	let wx = Watchexec::default();
+	wx.config.idle_timeout(Some(Duration::from_millis(300)));
+
+	let started = Instant::now();
+	timeout(Duration::from_secs(5), wx.main())
+		.await
+		.expect("should quit on its own")
+		.unwrap()
+		.unwrap();
+	assert!(
+		started.elapsed() >= Duration::from_millis(300),
+		"should not quit before the idle timeout"
+	);
+}
+
+#[tokio::test]
+async fn stays_alive_while_events_arrive() {
+	let wx = Watchexec::default();
+	wx.config.idle_timeout(Some(Duration::from_millis(300)));
+	wx.config.throttle(Duration::from_millis(10));
+	let main = wx.main();
+
+	for _ in 0..10 {
+		sleep(Duration::from_millis(100)).await;
+		wx.send_event(Event::default(), Priority::Normal)
+			.await
+			.unwrap();
+	}
+	assert!(!main.is_finished(), "should still be running");
+
+	timeout(Duration::from_secs(5), main)
+		.await
+		.expect("should quit once events stop")
+		.unwrap()
+		.unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn running_command_suspends_timeout() {
+	use std::sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	};
+	use watchexec::command::{Command, Program};
+
+	let command = Arc::new(Command {
+		program: Program::Exec {
+			prog: "sleep".into(),
+			args: vec!["1".into()],
+			argv0: None,
+		},
+		options: Default::default(),
+	});
+
+	let started = Arc::new(AtomicBool::new(false));
+	let wx = Watchexec::new(move |mut action| {
+		if !started.swap(true, Ordering::SeqCst) {
+			let (_, job) = action.create_job(command.clone());
+			job.start();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.idle_timeout(Some(Duration::from_millis(300)));
+
+	let started = Instant::now();
+	let main = wx.main();
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+
+	timeout(Duration::from_secs(5), main)
+		.await
+		.expect("should quit once the command has ended")
+		.unwrap()
+		.unwrap();
+	assert!(
+		started.elapsed() >= Duration::from_millis(1300),
+		"should wait for the command, then the idle timeout"
+	);
+}
@@ -0,0 +1,67 @@
+#![cfg(unix)]
+
+use std::{sync::Arc, time::Duration};
+
+use tokio::{
+	sync::mpsc,
+	time::{sleep, timeout},
+};
+use watchexec::{
+	command::{Command, Program},
+	Watchexec,
+};
+use watchexec_events::{Event, Priority, ProcessEnd};
+
+#[tokio::test]
+async fn second_action_sees_how_the_first_run_went() {
+	let command = Arc::new(Command {
+		program: Program::Exec {
+			prog: "sleep".into(),
+			args: vec!["0.3".into()],
+			argv0: None,
+		},
+		options: Default::default(),
+	});
+
+	let (actions_s, mut actions) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |mut action| {
+		actions_s.send(action.last_run()).ok();
+		if action.list_jobs().next().is_none() {
+			let (_, job) = action.create_job(command.clone());
+			job.start();
+		}
+		action
+	})
+	.unwrap();
+	let main = wx.main();
+
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+	let last_run = timeout(Duration::from_secs(5), actions.recv())
+		.await
+		.expect("first action should run")
+		.unwrap();
+	assert_eq!(last_run, None, "nothing has run before the first action");
+
+	// let the command end
+	sleep(Duration::from_millis(800)).await;
+
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+	let last_run = timeout(Duration::from_secs(5), actions.recv())
+		.await
+		.expect("second action should run")
+		.unwrap()
+		.expect("the first run should be seen");
+	assert_eq!(last_run.status, ProcessEnd::Success);
+	assert!(
+		last_run.duration >= Duration::from_millis(300),
+		"should have run for as long as the command: {:?}",
+		last_run.duration
+	);
+	assert!(last_run.duration < Duration::from_secs(5));
+
+	main.abort();
+}
@@ -0,0 +1,74 @@
+mod helpers;
+
+use std::{path::PathBuf, time::Duration};
+
+use helpers::event;
+use tokio::{sync::mpsc, time::timeout};
+use watchexec::Watchexec;
+use watchexec_events::Priority;
+
+#[tokio::test]
+async fn paths_beyond_limit_are_truncated() {
+	let (actions_s, mut actions_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		let paths: Vec<PathBuf> = action.paths().map(|(path, _)| path.to_owned()).collect();
+		actions_s
+			.send((action.events.len(), paths, action.paths_truncated()))
+			.ok();
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(100));
+	wx.config.max_paths_per_action(Some(3));
+	wx.main();
+
+	wx.send_event(event(&["/a", "/b"]), Priority::Normal)
+		.await
+		.unwrap();
+	// a repeated path doesn't count twice
+	wx.send_event(event(&["/a", "/c", "/d"]), Priority::Normal)
+		.await
+		.unwrap();
+	wx.send_event(event(&["/e"]), Priority::Normal)
+		.await
+		.unwrap();
+
+	let (events, paths, truncated) = timeout(Duration::from_secs(5), actions_r.recv())
+		.await
+		.expect("action should run")
+		.unwrap();
+
+	assert_eq!(
+		paths,
+		["/a", "/b", "/a", "/c"].map(PathBuf::from),
+		"should keep the first distinct paths by arrival"
+	);
+	assert_eq!(events, 2, "event left without paths should be dropped");
+	assert_eq!(truncated, Some(5), "should report the distinct path count");
+}
+
+#[tokio::test]
+async fn paths_within_limit_are_untouched() {
+	let (actions_s, mut actions_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		actions_s
+			.send((action.paths().count(), action.paths_truncated()))
+			.ok();
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(100));
+	wx.config.max_paths_per_action(Some(3));
+	wx.main();
+
+	wx.send_event(event(&["/a", "/b", "/c"]), Priority::Normal)
+		.await
+		.unwrap();
+
+	let (paths, truncated) = timeout(Duration::from_secs(5), actions_r.recv())
+		.await
+		.expect("action should run")
+		.unwrap();
+	assert_eq!(paths, 3);
+	assert_eq!(truncated, None);
+}
@@ -0,0 +1,84 @@
+use std::{path::PathBuf, time::Duration};
+
+use tokio::{sync::mpsc, time::timeout};
+use watchexec::Watchexec;
+use watchexec_events::{
+	filekind::{CreateKind, DataChange, FileEventKind, MetadataKind, ModifyKind},
+	Event, Priority, Source, Tag,
+};
+
+const CHMOD: FileEventKind = FileEventKind::Modify(ModifyKind::Metadata(MetadataKind::Permissions));
+const WRITE: FileEventKind = FileEventKind::Modify(ModifyKind::Data(DataChange::Content));
+const CREATE: FileEventKind = FileEventKind::Create(CreateKind::File);
+
+fn event(path: &str, kinds: &[FileEventKind]) -> Event {
+	let mut tags = vec![
+		Tag::Source(Source::Filesystem),
+		Tag::Path {
+			path: path.into(),
+			file_type: None,
+		},
+	];
+	tags.extend(kinds.iter().copied().map(Tag::FileEventKind));
+	Event {
+		tags,
+		metadata: Default::default(),
+	}
+}
+
+/// Send a chmod, a write, and a creation with permissions, and return the paths of the action.
+async fn delivered(ignore_metadata: bool) -> Vec<PathBuf> {
+	let (actions_s, mut actions) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		actions_s
+			.send(
+				action
+					.paths()
+					.map(|(path, _)| path.to_owned())
+					.collect::<Vec<_>>(),
+			)
+			.ok();
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(200));
+	wx.config.sort_events(true);
+	wx.config.ignore_metadata_only_events(ignore_metadata);
+	let main = wx.main();
+
+	for event in [
+		event("/chmod", &[CHMOD]),
+		event("/write", &[WRITE]),
+		event("/create", &[CREATE, CHMOD]),
+	] {
+		wx.send_event(event, Priority::Normal).await.unwrap();
+	}
+
+	let paths = timeout(Duration::from_secs(5), actions.recv())
+		.await
+		.expect("action should run")
+		.unwrap();
+	main.abort();
+	paths
+}
+
+#[tokio::test]
+async fn metadata_only_events_are_dropped() {
+	assert_eq!(
+		delivered(true).await,
+		vec![PathBuf::from("/create"), PathBuf::from("/write")],
+		"the chmod should be dropped, and the creation kept"
+	);
+}
+
+#[tokio::test]
+async fn metadata_only_events_are_kept_by_default() {
+	assert_eq!(
+		delivered(false).await,
+		vec![
+			PathBuf::from("/chmod"),
+			PathBuf::from("/create"),
+			PathBuf::from("/write"),
+		]
+	);
+}
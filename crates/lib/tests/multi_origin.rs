@@ -0,0 +1,147 @@
+use std::{
+	ffi::OsString,
+	path::{Path, PathBuf},
+};
+
+use watchexec::{
+	error::RuntimeError,
+	filter::{Filterer, MultiOriginFilterer},
+};
+use watchexec_events::{Event, FileType, Priority, Tag};
+
+/// Rejects paths with a particular file name, like a one-line ignore file.
+#[derive(Debug)]
+struct Ignore(&'static str);
+
+impl Filterer for Ignore {
+	fn check_event(&self, event: &Event, _priority: Priority) -> Result<bool, RuntimeError> {
+		Ok(event
+			.paths()
+			.any(|(path, _)| path.file_name() != Some(&OsString::from(self.0))))
+	}
+}
+
+fn root() -> PathBuf {
+	if cfg!(windows) {
+		PathBuf::from("C:\\")
+	} else {
+		PathBuf::from("/")
+	}
+}
+
+fn event(paths: &[&Path]) -> Event {
+	Event {
+		tags: paths
+			.iter()
+			.map(|path| Tag::Path {
+				path: path.to_path_buf(),
+				file_type: Some(FileType::File),
+			})
+			.collect(),
+		metadata: Default::default(),
+	}
+}
+
+fn check(filterer: &impl Filterer, paths: &[&Path]) -> bool {
+	filterer
+		.check_event(&event(paths), Priority::Normal)
+		.unwrap()
+}
+
+fn filterer() -> (MultiOriginFilterer, PathBuf, PathBuf) {
+	let one = root().join("code").join("one");
+	let two = root().join("code").join("two");
+	let filterer = MultiOriginFilterer::new(Ignore("default.txt"))
+		.with_origin(&one, Ignore("build.log"))
+		.with_origin(&two, Ignore("notes.md"));
+	(filterer, one, two)
+}
+
+#[test]
+fn ignore_in_one_origin_does_not_affect_the_other() {
+	let (filterer, one, two) = filterer();
+
+	assert!(!check(&filterer, &[&one.join("build.log")]));
+	assert!(check(&filterer, &[&two.join("build.log")]));
+
+	assert!(check(&filterer, &[&one.join("notes.md")]));
+	assert!(!check(&filterer, &[&two.join("notes.md")]));
+}
+
+#[test]
+fn paths_outside_all_origins_use_default() {
+	let (filterer, one, _) = filterer();
+	let elsewhere = root().join("elsewhere");
+
+	assert!(!check(&filterer, &[&elsewhere.join("default.txt")]));
+	assert!(check(&filterer, &[&elsewhere.join("build.log")]));
+	assert!(check(&filterer, &[&one.join("default.txt")]));
+
+	// a sibling with the origin's name as a prefix isn't in the origin
+	assert!(check(
+		&filterer,
+		&[&root().join("code").join("one-more").join("build.log")]
+	));
+}
+
+#[test]
+fn pathless_events_use_default() {
+	#[derive(Debug)]
+	struct Fixed(bool);
+	impl Filterer for Fixed {
+		fn check_event(&self, _event: &Event, _priority: Priority) -> Result<bool, RuntimeError> {
+			Ok(self.0)
+		}
+	}
+
+	let filterer = MultiOriginFilterer::new(Fixed(false)).with_origin(root(), Fixed(true));
+	assert!(!filterer
+		.check_event(&Event::default(), Priority::Normal)
+		.unwrap());
+
+	let filterer = MultiOriginFilterer::new(Fixed(true)).with_origin(root(), Fixed(false));
+	assert!(filterer
+		.check_event(&Event::default(), Priority::Normal)
+		.unwrap());
+}
+
+#[test]
+fn most_specific_origin_wins() {
+	let (filterer, one, _) = filterer();
+	let nested = one.join("vendor");
+	let filterer = filterer.with_origin(&nested, Ignore("vendored.rs"));
+
+	assert!(check(&filterer, &[&nested.join("build.log")]));
+	assert!(!check(
+		&filterer,
+		&[&nested.join("deep").join("vendored.rs")]
+	));
+	assert!(check(&filterer, &[&one.join("vendored.rs")]));
+	assert!(!check(&filterer, &[&one.join("build.log")]));
+}
+
+#[test]
+fn same_origin_replaces() {
+	let (filterer, one, _) = filterer();
+	let filterer = filterer.with_origin(&one, Ignore("other.log"));
+
+	assert!(check(&filterer, &[&one.join("build.log")]));
+	assert!(!check(&filterer, &[&one.join("other.log")]));
+}
+
+#[test]
+fn event_across_origins_is_split() {
+	let (filterer, one, two) = filterer();
+
+	// each filterer only sees its own paths: both are ignored where they are
+	assert!(!check(
+		&filterer,
+		&[&one.join("build.log"), &two.join("notes.md")]
+	));
+
+	// one path passes in its own origin
+	assert!(check(
+		&filterer,
+		&[&one.join("build.log"), &two.join("build.log")]
+	));
+}
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use tokio::{
+	sync::mpsc,
+	time::{sleep, timeout},
+};
+use watchexec::{WatchedPath, Watchexec};
+use watchexec_events::Event;
+
+#[tokio::test]
+async fn subdirectory_changes_do_not_trigger() {
+	let root = tempfile::tempdir().unwrap();
+	let root_path = dunce::canonicalize(root.path()).unwrap();
+	let subdir = root_path.join("subdir");
+	std::fs::create_dir(&subdir).unwrap();
+
+	let (events_s, mut events_r) = mpsc::unbounded_channel::<Event>();
+	let wx = Watchexec::new(move |action| {
+		for event in action.events.iter() {
+			events_s.send(event.clone()).ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config
+		.pathset([WatchedPath::non_recursive(root_path.clone())]);
+	wx.config.throttle(Duration::from_millis(10));
+	let main = wx.main();
+
+	// give the watcher time to start
+	sleep(Duration::from_millis(200)).await;
+
+	let nested = subdir.join("nested");
+	std::fs::write(&nested, "nested").unwrap();
+
+	// then change something at the top level, so we know when events have caught up
+	let top = root_path.join("top");
+	std::fs::write(&top, "top").unwrap();
+
+	let mut paths = Vec::new();
+	timeout(Duration::from_secs(5), async {
+		while let Some(event) = events_r.recv().await {
+			paths.extend(event.paths().map(|(path, _)| path.to_owned()));
+			if paths.contains(&top) {
+				break;
+			}
+		}
+	})
+	.await
+	.expect("should see the top-level change");
+	main.abort();
+
+	assert!(
+		!paths.contains(&nested),
+		"should not see changes in subdirectories: {paths:?}"
+	);
+}
@@ -0,0 +1,52 @@
+#![cfg(unix)]
+
+use std::{error::Error, sync::Arc, time::Duration};
+
+use tokio::{sync::mpsc, time::timeout};
+use watchexec::{
+	command::{Command, Program},
+	notification::{notify_on_outcome, Notification},
+	Watchexec,
+};
+use watchexec_events::{Event, Priority};
+
+#[tokio::test]
+async fn notifies_when_the_command_fails() {
+	let command = Arc::new(Command {
+		program: Program::Exec {
+			prog: "false".into(),
+			args: Vec::new(),
+			argv0: None,
+		},
+		options: Default::default(),
+	});
+
+	let (notes_s, mut notes) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |mut action| {
+		let (_, job) = action.create_job(command.clone());
+		job.start();
+		let notes_s = notes_s.clone();
+		action.run_task(notify_on_outcome(
+			job,
+			move |notification: &Notification| -> Result<(), Box<dyn Error + Send + Sync>> {
+				notes_s.send(notification.clone())?;
+				Ok(())
+			},
+		));
+		action
+	})
+	.unwrap();
+	let main = wx.main();
+
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+	let notification = timeout(Duration::from_secs(5), notes.recv())
+		.await
+		.expect("should notify")
+		.unwrap();
+	assert_eq!(notification.summary, "Watchexec: command ended");
+	assert_eq!(notification.body, "Command exited with 1");
+
+	main.abort();
+}
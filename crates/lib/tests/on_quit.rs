@@ -0,0 +1,135 @@
+use std::{
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+use tokio::time::{sleep, timeout, Instant};
+use watchexec::{action::QuitReturn, Watchexec};
+use watchexec_events::{Event, Priority};
+
+#[tokio::test]
+async fn runs_once_before_main_resolves() {
+	let wx = Watchexec::new(|mut action| {
+		action.quit();
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(10));
+
+	let calls = Arc::new(AtomicUsize::new(0));
+	let finished = Arc::new(AtomicUsize::new(0));
+	let (hook_calls, hook_finished) = (calls.clone(), finished.clone());
+	wx.config.on_quit(move || -> QuitReturn {
+		hook_calls.fetch_add(1, Ordering::SeqCst);
+		let finished = hook_finished.clone();
+		Box::new(async move {
+			sleep(Duration::from_millis(200)).await;
+			finished.fetch_add(1, Ordering::SeqCst);
+		})
+	});
+
+	let main = wx.main();
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+
+	timeout(Duration::from_secs(5), main)
+		.await
+		.expect("main should resolve")
+		.unwrap()
+		.unwrap();
+
+	assert_eq!(calls.load(Ordering::SeqCst), 1, "hook should run once");
+	assert_eq!(
+		finished.load(Ordering::SeqCst),
+		1,
+		"hook should complete before main resolves"
+	);
+}
+
+#[tokio::test]
+async fn slow_hook_is_bounded_by_timeout() {
+	let wx = Watchexec::new(|mut action| {
+		action.quit();
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(10));
+	wx.config.quit_timeout(Duration::from_millis(100));
+	wx.config
+		.on_quit(|| -> QuitReturn { Box::new(sleep(Duration::from_secs(60))) });
+
+	let start = Instant::now();
+	let main = wx.main();
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+
+	timeout(Duration::from_secs(5), main)
+		.await
+		.expect("main should resolve once the hook times out")
+		.unwrap()
+		.unwrap();
+	assert!(start.elapsed() < Duration::from_secs(2));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn exit_from_error_hook_stops_jobs_before_hook() {
+	use std::sync::OnceLock;
+	use watchexec::{
+		command::{Command, Program},
+		error::CriticalError,
+	};
+
+	let wx = Watchexec::new(move |mut action| {
+		let (_, job) = action.create_job(Arc::new(Command {
+			program: Program::Exec {
+				prog: "sleep".into(),
+				args: vec!["10".into()],
+				argv0: None,
+			},
+			options: Default::default(),
+		}));
+		job.start();
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(10));
+
+	// an error which happens before the action runs, turned into an exit by the error hook
+	let dir = tempfile::tempdir().unwrap();
+	wx.config
+		.throttle_state_file(Some(dir.path().join("missing").join("state")));
+	wx.config.on_error(|err| err.critical(CriticalError::Exit));
+
+	let jobs_in_hook = Arc::new(AtomicUsize::new(usize::MAX));
+	let hook_wx: Arc<OnceLock<Arc<Watchexec>>> = Arc::default();
+	let (hook_jobs, hook_wx_ref) = (jobs_in_hook.clone(), hook_wx.clone());
+	wx.config.on_quit(move || -> QuitReturn {
+		let jobs = hook_wx_ref.get().map_or(0, |wx| wx.jobs().len());
+		hook_jobs.store(jobs, Ordering::SeqCst);
+		Box::new(async {})
+	});
+	hook_wx.set(wx.clone()).ok();
+
+	let main = wx.main();
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+
+	timeout(Duration::from_secs(5), main)
+		.await
+		.expect("main should resolve")
+		.unwrap()
+		.unwrap();
+
+	assert_eq!(
+		jobs_in_hook.load(Ordering::SeqCst),
+		0,
+		"jobs should be stopped before the quit hook runs"
+	);
+}
@@ -0,0 +1,97 @@
+use std::{
+	fs,
+	path::{Path, PathBuf},
+	time::Duration,
+};
+
+use tokio::{
+	sync::mpsc,
+	time::{sleep, timeout},
+};
+use watchexec::{sources::origins::OriginScan, Watchexec};
+
+fn watched(wx: &Watchexec) -> Vec<PathBuf> {
+	wx.config
+		.pathset
+		.get()
+		.into_iter()
+		.map(PathBuf::from)
+		.collect()
+}
+
+async fn wait_for(wx: &Watchexec, what: &str, check: impl Fn(&[PathBuf]) -> bool) {
+	timeout(Duration::from_secs(5), async {
+		while !check(&watched(wx)) {
+			sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.unwrap_or_else(|_| panic!("{what}: {:?}", watched(wx)));
+}
+
+fn origin(dir: &Path) {
+	fs::create_dir_all(dir).unwrap();
+	fs::write(dir.join("Cargo.toml"), "").unwrap();
+}
+
+#[tokio::test]
+async fn new_origins_are_watched_after_a_scan() {
+	let base = tempfile::tempdir().unwrap();
+	let base = base.path().canonicalize().unwrap();
+	let elsewhere = tempfile::tempdir().unwrap();
+	origin(&base.join("first"));
+
+	let (actions_s, mut actions_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		actions_s
+			.send(
+				action
+					.paths()
+					.map(|(path, _)| path.to_owned())
+					.collect::<Vec<_>>(),
+			)
+			.ok();
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(50));
+	wx.config.pathset([elsewhere.path()]);
+	wx.config.origin_scan(Some(
+		OriginScan::new(&base).interval(Duration::from_millis(100)),
+	));
+	wx.main();
+
+	wait_for(&wx, "existing origin should be watched", |paths| {
+		paths.contains(&base.join("first"))
+	})
+	.await;
+
+	// hidden directories aren't looked in
+	origin(&base.join(".hidden"));
+	origin(&base.join("second").join("nested"));
+	wait_for(&wx, "new origin should be watched", |paths| {
+		paths.contains(&base.join("second").join("nested"))
+	})
+	.await;
+	assert!(!watched(&wx).contains(&base.join(".hidden")));
+
+	sleep(Duration::from_millis(200)).await;
+	while actions_r.try_recv().is_ok() {}
+	let file = base.join("second").join("nested").join("lib.rs");
+	fs::write(&file, "").unwrap();
+	let paths = timeout(Duration::from_secs(5), actions_r.recv())
+		.await
+		.expect("change in new origin should be seen")
+		.unwrap();
+	assert!(paths.contains(&file), "{paths:?}");
+
+	fs::remove_file(base.join("first").join("Cargo.toml")).unwrap();
+	wait_for(&wx, "gone origin should be unwatched", |paths| {
+		!paths.contains(&base.join("first"))
+	})
+	.await;
+	assert!(
+		watched(&wx).contains(&elsewhere.path().to_owned()),
+		"other paths should be left alone"
+	);
+}
@@ -0,0 +1,69 @@
+// fanotify needs CAP_SYS_ADMIN, so this only checks attribution where that's available
+#![cfg(target_os = "linux")]
+
+use std::{fs, time::Duration};
+
+use tokio::{sync::mpsc, time::timeout};
+use watchexec::Watchexec;
+use watchexec_events::{Event, Tag};
+
+fn origin(event: &Event) -> (Option<u32>, Option<String>) {
+	(
+		event.tags.iter().find_map(|tag| match tag {
+			Tag::Process(pid) => Some(*pid),
+			_ => None,
+		}),
+		event
+			.metadata
+			.get("uid")
+			.and_then(|uids| uids.first().cloned()),
+	)
+}
+
+#[tokio::test]
+async fn attributes_writes_to_their_process() {
+	let dir = tempfile::tempdir().unwrap();
+	let dir_path = dir.path().canonicalize().unwrap();
+	let file = dir_path.join("file");
+
+	let (actions_s, mut actions_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		actions_s
+			.send(action.events.iter().map(origin).collect::<Vec<_>>())
+			.ok();
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(50));
+	wx.config.ready_event(true);
+	wx.config.process_origins(true);
+	wx.config.pathset([dir_path.clone()]);
+	wx.main();
+
+	timeout(Duration::from_secs(5), actions_r.recv())
+		.await
+		.expect("watcher should get ready")
+		.unwrap();
+
+	// set back to false if fanotify isn't available
+	let available = wx.config.process_origins.get();
+
+	fs::write(&file, "content").unwrap();
+	let origins = timeout(Duration::from_secs(5), actions_r.recv())
+		.await
+		.expect("change should be seen")
+		.unwrap();
+
+	let ours = (
+		Some(std::process::id()),
+		Some(nix::unistd::getuid().as_raw().to_string()),
+	);
+	if available {
+		assert!(origins.contains(&ours), "{origins:?}");
+	} else {
+		assert!(
+			origins.iter().all(|origin| *origin == (None, None)),
+			"{origins:?}"
+		);
+	}
+}
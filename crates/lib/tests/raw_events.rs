@@ -0,0 +1,92 @@
+use std::{
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+use futures::StreamExt;
+use tokio::time::{sleep, timeout};
+use watchexec::{error::RuntimeError, filter::Filterer, Watchexec};
+use watchexec_events::{Event, Priority, Tag};
+
+/// Drops every event with a path.
+#[derive(Debug)]
+struct NoPaths;
+
+impl Filterer for NoPaths {
+	fn check_event(&self, event: &Event, _priority: Priority) -> Result<bool, RuntimeError> {
+		Ok(event.paths().next().is_none())
+	}
+}
+
+fn path_event(path: &str) -> Event {
+	Event {
+		tags: vec![Tag::Path {
+			path: path.into(),
+			file_type: None,
+		}],
+		metadata: Default::default(),
+	}
+}
+
+#[tokio::test]
+async fn raw_stream_sees_filtered_events() {
+	let actions = Arc::new(AtomicUsize::new(0));
+	let wx = Watchexec::new({
+		let actions = actions.clone();
+		move |action| {
+			actions.fetch_add(1, Ordering::SeqCst);
+			action
+		}
+	})
+	.unwrap();
+	wx.config.filterer(NoPaths);
+
+	let mut raw = Box::pin(wx.raw_events());
+	let main = wx.main();
+
+	let event = path_event("/tmp/dropped");
+	wx.send_event(event.clone(), Priority::Normal)
+		.await
+		.unwrap();
+
+	let received = timeout(Duration::from_secs(5), raw.next())
+		.await
+		.expect("raw stream should receive the event")
+		.unwrap();
+	assert_eq!(received, event);
+
+	sleep(Duration::from_millis(200)).await;
+	assert_eq!(
+		actions.load(Ordering::SeqCst),
+		0,
+		"filterer should have dropped the event"
+	);
+
+	main.abort();
+}
+
+#[tokio::test]
+async fn raw_stream_in_order() {
+	let wx = Watchexec::new(|action| action).unwrap();
+	let mut raw = Box::pin(wx.raw_events());
+	let main = wx.main();
+
+	for path in ["/a", "/b", "/c"] {
+		wx.send_event(path_event(path), Priority::Normal)
+			.await
+			.unwrap();
+	}
+
+	for path in ["/a", "/b", "/c"] {
+		let received = timeout(Duration::from_secs(5), raw.next())
+			.await
+			.unwrap()
+			.unwrap();
+		assert_eq!(received, path_event(path));
+	}
+
+	main.abort();
+}
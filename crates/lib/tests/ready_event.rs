@@ -0,0 +1,82 @@
+use std::{fs, path::PathBuf, time::Duration};
+
+use tokio::{
+	sync::mpsc,
+	time::{sleep, timeout},
+};
+use watchexec::Watchexec;
+use watchexec_events::{Event, Source, Tag};
+
+fn is_ready(event: &Event) -> bool {
+	event.tags.contains(&Tag::Source(Source::Internal))
+		&& event.metadata.get("watch") == Some(&vec!["ready".to_string()])
+}
+
+#[tokio::test]
+async fn one_ready_event_after_setup() {
+	let dir = tempfile::tempdir().unwrap();
+	let dir_path = dir.path().canonicalize().unwrap();
+
+	let (actions_s, mut actions_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		for event in action.events.iter() {
+			actions_s
+				.send((
+					is_ready(event),
+					event
+						.paths()
+						.map(|(path, _)| path.to_owned())
+						.collect::<Vec<PathBuf>>(),
+				))
+				.ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(50));
+	wx.config.ready_event(true);
+	wx.config.pathset([dir_path.clone()]);
+	wx.main();
+
+	let (ready, paths) = timeout(Duration::from_secs(5), actions_r.recv())
+		.await
+		.expect("ready event should be delivered")
+		.unwrap();
+	assert!(ready, "first event should be the ready event");
+	assert!(paths.is_empty());
+
+	// the watcher is live by the time the ready event is out
+	let file = dir_path.join("file");
+	fs::write(&file, "content").unwrap();
+	let (ready, paths) = timeout(Duration::from_secs(5), actions_r.recv())
+		.await
+		.expect("change should be seen")
+		.unwrap();
+	assert!(!ready);
+	assert!(paths.contains(&file), "{paths:?}");
+
+	// reconfiguring doesn't emit it again
+	wx.config.pathset([dir_path.join("elsewhere"), dir_path]);
+	sleep(Duration::from_millis(500)).await;
+	while let Ok((ready, _)) = actions_r.try_recv() {
+		assert!(!ready, "ready event should only be sent once");
+	}
+}
+
+#[tokio::test]
+async fn no_ready_event_by_default() {
+	let dir = tempfile::tempdir().unwrap();
+
+	let (actions_s, mut actions_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		actions_s.send(action.events.iter().any(is_ready)).ok();
+		action
+	})
+	.unwrap();
+	wx.config.pathset([dir.path()]);
+	wx.main();
+
+	assert!(timeout(Duration::from_millis(500), actions_r.recv())
+		.await
+		.is_err());
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use tokio::{sync::mpsc, time::timeout};
+use watchexec::Watchexec;
+use watchexec_events::Event;
+
+#[tokio::test]
+async fn new_watched_path_delivers_events_once_applied() {
+	let root = tempfile::tempdir().unwrap();
+	let root_path = dunce::canonicalize(root.path()).unwrap();
+	let first = root_path.join("first");
+	let second = root_path.join("second");
+	std::fs::create_dir(&first).unwrap();
+	std::fs::create_dir(&second).unwrap();
+
+	let (events_s, mut events_r) = mpsc::unbounded_channel::<Event>();
+	let wx = Watchexec::new(move |action| {
+		for event in action.events.iter() {
+			events_s.send(event.clone()).ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.pathset([first.clone()]);
+	wx.config.throttle(Duration::from_millis(10));
+	let main = wx.main();
+
+	wx.reconfigure_async(|config| {
+		config.pathset([first.clone(), second.clone()]);
+	})
+	.await
+	.expect("changes should be applied");
+
+	// no waiting for the watcher here: the new path should already be watched
+	let file = second.join("file");
+	std::fs::write(&file, "content").unwrap();
+	timeout(Duration::from_secs(5), async {
+		while let Some(event) = events_r.recv().await {
+			if event.paths().any(|(path, _)| path == file) {
+				return;
+			}
+		}
+	})
+	.await
+	.expect("should see an event in the newly watched path");
+
+	main.abort();
+}
+
+#[tokio::test]
+async fn not_started_returns_immediately() {
+	let wx = Watchexec::default();
+	timeout(
+		Duration::from_secs(1),
+		wx.reconfigure_async(|config| {
+			config.pathset(["."]);
+		}),
+	)
+	.await
+	.expect("should not wait for a worker which isn't running")
+	.unwrap();
+}
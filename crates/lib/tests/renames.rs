@@ -0,0 +1,66 @@
+#![cfg(target_os = "linux")]
+
+use std::time::Duration;
+
+use tokio::{
+	sync::mpsc,
+	time::{sleep, timeout},
+};
+use watchexec::Watchexec;
+use watchexec_events::{
+	filekind::{FileEventKind, ModifyKind, RenameMode},
+	Event, Tag,
+};
+
+#[tokio::test]
+async fn rename_is_paired() {
+	let root = tempfile::tempdir().unwrap();
+	let root_path = dunce::canonicalize(root.path()).unwrap();
+	let from = root_path.join("from");
+	let to = root_path.join("to");
+	std::fs::write(&from, "content").unwrap();
+
+	let (events_s, mut events_r) = mpsc::unbounded_channel::<Event>();
+	let wx = Watchexec::new(move |action| {
+		for event in action.events.iter() {
+			events_s.send(event.clone()).ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.pathset([root_path.clone()]);
+	wx.config.coalesce_renames(true);
+	wx.config.throttle(Duration::from_millis(10));
+	let main = wx.main();
+
+	// give the watcher time to start
+	sleep(Duration::from_millis(200)).await;
+
+	std::fs::rename(&from, &to).unwrap();
+
+	let mut renames = Vec::new();
+	let _ = timeout(Duration::from_secs(1), async {
+		while let Some(event) = events_r.recv().await {
+			if event.tags.iter().any(|tag| {
+				matches!(
+					tag,
+					Tag::FileEventKind(FileEventKind::Modify(ModifyKind::Name(_)))
+				)
+			}) {
+				renames.push(event);
+			}
+		}
+	})
+	.await;
+	main.abort();
+
+	assert_eq!(renames.len(), 1, "expected one rename event: {renames:?}");
+	let event = &renames[0];
+	assert!(event
+		.tags
+		.contains(&Tag::FileEventKind(FileEventKind::Modify(
+			ModifyKind::Name(RenameMode::Both)
+		))));
+	let paths: Vec<_> = event.paths().map(|(path, _)| path.to_owned()).collect();
+	assert_eq!(paths, vec![from, to]);
+}
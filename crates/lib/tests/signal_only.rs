@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use tokio::{sync::mpsc, time::timeout};
+use watchexec::Watchexec;
+use watchexec_events::{Event, Priority, Source, Tag};
+use watchexec_signals::Signal;
+
+fn signal() -> Event {
+	Event {
+		tags: vec![Tag::Source(Source::Os), Tag::Signal(Signal::User1)],
+		metadata: Default::default(),
+	}
+}
+
+fn file() -> Event {
+	Event {
+		tags: vec![
+			Tag::Source(Source::Filesystem),
+			Tag::Path {
+				path: "/project/src/main.rs".into(),
+				file_type: None,
+			},
+		],
+		metadata: Default::default(),
+	}
+}
+
+async fn classify(events: Vec<Event>) -> (bool, bool) {
+	let (actions_s, mut actions_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		actions_s
+			.send((action.is_signal_only(), action.has_filesystem_events()))
+			.ok();
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(100));
+	wx.main();
+
+	for event in events {
+		wx.send_event(event, Priority::Normal).await.unwrap();
+	}
+
+	timeout(Duration::from_secs(5), actions_r.recv())
+		.await
+		.expect("action should run")
+		.unwrap()
+}
+
+#[tokio::test]
+async fn signal_only_batch() {
+	assert_eq!(classify(vec![signal(), signal()]).await, (true, false));
+}
+
+#[tokio::test]
+async fn filesystem_only_batch() {
+	assert_eq!(classify(vec![file(), file()]).await, (false, true));
+}
+
+#[tokio::test]
+async fn mixed_batch() {
+	assert_eq!(classify(vec![file(), signal()]).await, (false, true));
+}
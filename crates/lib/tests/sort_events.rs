@@ -0,0 +1,126 @@
+use std::{path::PathBuf, time::Duration};
+
+use tokio::{sync::mpsc, time::timeout};
+use watchexec::Watchexec;
+use watchexec_events::{
+	filekind::{CreateKind, FileEventKind, ModifyKind},
+	Event, Priority, Source, Tag,
+};
+
+fn event(path: &str, kind: FileEventKind) -> Event {
+	Event {
+		tags: vec![
+			Tag::Source(Source::Filesystem),
+			Tag::Path {
+				path: path.into(),
+				file_type: None,
+			},
+			Tag::FileEventKind(kind),
+		],
+		metadata: Default::default(),
+	}
+}
+
+async fn delivered(
+	sort: bool,
+	sent: &[(&str, FileEventKind, Priority)],
+) -> Vec<(PathBuf, Option<FileEventKind>)> {
+	let (actions_s, mut actions) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		actions_s
+			.send(
+				action
+					.events
+					.iter()
+					.map(|event| {
+						let path = event.paths().next().unwrap().0.to_owned();
+						let kind = event.tags.iter().find_map(|tag| match tag {
+							Tag::FileEventKind(kind) => Some(*kind),
+							_ => None,
+						});
+						(path, kind)
+					})
+					.collect::<Vec<_>>(),
+			)
+			.ok();
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(200));
+	wx.config.sort_events(sort);
+	let main = wx.main();
+
+	for (path, kind, priority) in sent {
+		wx.send_event(event(path, *kind), *priority).await.unwrap();
+	}
+
+	let events = timeout(Duration::from_secs(5), actions.recv())
+		.await
+		.expect("action should run")
+		.unwrap();
+	main.abort();
+	events
+}
+
+#[tokio::test]
+async fn events_are_sorted_by_path_then_kind() {
+	let modify = FileEventKind::Modify(ModifyKind::Any);
+	let create = FileEventKind::Create(CreateKind::File);
+	let sorted = vec![
+		("/a".into(), Some(create)),
+		("/a".into(), Some(modify)),
+		("/b".into(), Some(create)),
+		("/c".into(), Some(modify)),
+	];
+
+	assert_eq!(
+		delivered(
+			true,
+			&[
+				("/c", modify, Priority::Normal),
+				("/a", modify, Priority::Normal),
+				("/b", create, Priority::Normal),
+				("/a", create, Priority::Normal)
+			]
+		)
+		.await,
+		sorted
+	);
+	assert_eq!(
+		delivered(
+			true,
+			&[
+				("/a", create, Priority::Normal),
+				("/b", create, Priority::Normal),
+				("/a", modify, Priority::Normal),
+				("/c", modify, Priority::Normal)
+			]
+		)
+		.await,
+		sorted,
+		"the order events are sent in should not matter"
+	);
+}
+
+#[tokio::test]
+async fn events_keep_their_order_of_arrival_when_not_sorted() {
+	let modify = FileEventKind::Modify(ModifyKind::Any);
+
+	// events of the same priority may be received in any order, so priorities fix it here
+	assert_eq!(
+		delivered(
+			false,
+			&[
+				("/c", modify, Priority::High),
+				("/a", modify, Priority::Normal),
+				("/b", modify, Priority::Low)
+			]
+		)
+		.await,
+		vec![
+			("/c".into(), Some(modify)),
+			("/a".into(), Some(modify)),
+			("/b".into(), Some(modify)),
+		]
+	);
+}
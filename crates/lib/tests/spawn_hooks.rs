@@ -0,0 +1,129 @@
+#![cfg(unix)]
+
+use std::{
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+use tokio::{
+	sync::{mpsc, Notify},
+	time::{sleep, timeout},
+};
+use watchexec::{
+	command::{Command, Program},
+	error::RuntimeError,
+	ErrorHook, Watchexec,
+};
+use watchexec_events::{Event, Priority};
+
+fn true_command() -> Arc<Command> {
+	Arc::new(Command {
+		program: Program::Exec {
+			prog: "true".into(),
+			args: Vec::new(),
+			argv0: None,
+		},
+		options: Default::default(),
+	})
+}
+
+#[tokio::test]
+async fn pre_spawn_blocks_until_released() {
+	let (spawned_s, mut spawned_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(|mut action| {
+		let (_, job) = action.create_job(true_command());
+		job.start();
+		action
+	})
+	.unwrap();
+
+	let release = Arc::new(Notify::new());
+	let released = Arc::new(AtomicBool::new(false));
+	wx.config.on_pre_spawn({
+		let release = release.clone();
+		let released = released.clone();
+		move |_| {
+			let release = release.clone();
+			let released = released.clone();
+			Box::new(async move {
+				release.notified().await;
+				released.store(true, Ordering::SeqCst);
+				Ok(())
+			})
+		}
+	});
+	wx.config.on_post_spawn({
+		let released = released.clone();
+		move |context| {
+			spawned_s
+				.send((released.load(Ordering::SeqCst), context.pid))
+				.ok();
+			Box::new(async {})
+		}
+	});
+
+	let main = wx.main();
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+
+	sleep(Duration::from_millis(100)).await;
+	assert!(
+		spawned_r.try_recv().is_err(),
+		"command should not spawn while the pre-spawn hook is blocked"
+	);
+
+	release.notify_one();
+	let (was_released, pid) = timeout(Duration::from_secs(5), spawned_r.recv())
+		.await
+		.expect("command should spawn once released")
+		.unwrap();
+	assert!(was_released, "spawn should happen after the pre-spawn hook");
+	assert!(pid.is_some(), "post-spawn hook should get the pid");
+
+	main.abort();
+}
+
+#[tokio::test]
+async fn pre_spawn_error_cancels_spawn() {
+	let (errors_s, mut errors_r) = mpsc::unbounded_channel();
+	let spawned = Arc::new(AtomicBool::new(false));
+	let wx = Watchexec::new(|mut action| {
+		let (_, job) = action.create_job(true_command());
+		job.start();
+		action
+	})
+	.unwrap();
+	wx.config.on_error(move |err: ErrorHook| {
+		if let RuntimeError::Handler { err, .. } = err.error {
+			errors_s.send(err).ok();
+		}
+	});
+	wx.config
+		.on_pre_spawn(|_| Box::new(async { Err("not now".into()) }));
+	wx.config.on_post_spawn({
+		let spawned = spawned.clone();
+		move |_| {
+			spawned.store(true, Ordering::SeqCst);
+			Box::new(async {})
+		}
+	});
+
+	let main = wx.main();
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+
+	let err = timeout(Duration::from_secs(5), errors_r.recv())
+		.await
+		.expect("error should be reported")
+		.unwrap();
+	assert!(err.contains("not now"), "unexpected error: {err}");
+	sleep(Duration::from_millis(100)).await;
+	assert!(!spawned.load(Ordering::SeqCst), "command should not spawn");
+
+	main.abort();
+}
@@ -0,0 +1,150 @@
+#![cfg(unix)]
+
+use std::{fs::File, time::Duration};
+
+use tokio::{
+	sync::mpsc,
+	time::{sleep, timeout},
+};
+use watchexec::{sources::fs::PathCanonicalization, ErrorHook, Watchexec};
+use watchexec_events::Event;
+
+#[tokio::test]
+async fn follows_symlinked_directory() {
+	let root = tempfile::tempdir().unwrap();
+	let target = root.path().join("target");
+	std::fs::create_dir(&target).unwrap();
+	let link = root.path().join("link");
+	std::os::unix::fs::symlink(&target, &link).unwrap();
+
+	let (events_s, mut events_r) = mpsc::unbounded_channel::<Event>();
+	let wx = Watchexec::new(move |action| {
+		for event in action.events.iter() {
+			events_s.send(event.clone()).ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.pathset([link.clone()]);
+	wx.config.follow_symlinks(true);
+	wx.config.throttle(Duration::from_millis(10));
+	let main = wx.main();
+
+	// give the watcher time to start
+	sleep(Duration::from_millis(200)).await;
+
+	let created = dunce::canonicalize(&target).unwrap().join("created");
+	File::create(&created).unwrap();
+
+	timeout(Duration::from_secs(5), async {
+		while let Some(event) = events_r.recv().await {
+			if event.paths().any(|(path, _)| path == created) {
+				return;
+			}
+		}
+	})
+	.await
+	.expect("should see the create event from the symlink target");
+
+	main.abort();
+}
+
+#[tokio::test]
+async fn reports_symbolic_paths() {
+	let root = tempfile::tempdir().unwrap();
+	let target = root.path().join("target");
+	std::fs::create_dir(&target).unwrap();
+	let link = root.path().join("link");
+	std::os::unix::fs::symlink(&target, &link).unwrap();
+
+	let (events_s, mut events_r) = mpsc::unbounded_channel::<Event>();
+	let wx = Watchexec::new(move |action| {
+		for event in action.events.iter() {
+			events_s.send(event.clone()).ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.pathset([link.clone()]);
+	wx.config.follow_symlinks(true);
+	wx.config
+		.path_canonicalization(PathCanonicalization::Symbolic);
+	wx.config.throttle(Duration::from_millis(10));
+	let main = wx.main();
+
+	assert_eq!(wx.config.watched_paths()[0].path(), link.as_path());
+
+	// give the watcher time to start
+	sleep(Duration::from_millis(200)).await;
+
+	File::create(target.join("created")).unwrap();
+
+	let symbolic = link.join("created");
+	let paths = timeout(Duration::from_secs(5), async {
+		while let Some(event) = events_r.recv().await {
+			let paths: Vec<_> = event.paths().map(|(path, _)| path.to_owned()).collect();
+			if paths.iter().any(|path| path.ends_with("created")) {
+				return paths;
+			}
+		}
+		Vec::new()
+	})
+	.await
+	.expect("should see the create event");
+
+	assert!(
+		paths.contains(&symbolic),
+		"event paths should be under the symlink, got {paths:?}"
+	);
+
+	main.abort();
+}
+
+#[tokio::test]
+async fn skips_broken_symlink() {
+	let root = tempfile::tempdir().unwrap();
+	let link = root.path().join("link");
+	std::os::unix::fs::symlink(root.path().join("nowhere"), &link).unwrap();
+
+	let (events_s, mut events_r) = mpsc::unbounded_channel::<Event>();
+	let wx = Watchexec::new(move |action| {
+		for event in action.events.iter() {
+			events_s.send(event.clone()).ok();
+		}
+		action
+	})
+	.unwrap();
+	let (errors_s, mut errors_r) = mpsc::unbounded_channel();
+	wx.config.on_error(move |err: ErrorHook| {
+		errors_s.send(err.error.to_string()).ok();
+	});
+	wx.config.pathset([link, root.path().to_owned()]);
+	wx.config.follow_symlinks(true);
+	wx.config.throttle(Duration::from_millis(10));
+	let main = wx.main();
+
+	// give the watcher time to start
+	sleep(Duration::from_millis(200)).await;
+	assert!(
+		!main.is_finished(),
+		"broken symlink should not stop watchexec"
+	);
+
+	File::create(root.path().join("sibling")).unwrap();
+	timeout(Duration::from_secs(5), async {
+		while let Some(event) = events_r.recv().await {
+			if event.paths().any(|(path, _)| path.ends_with("sibling")) {
+				return;
+			}
+		}
+	})
+	.await
+	.expect("should see events for files next to the broken symlink");
+
+	assert!(
+		errors_r.try_recv().is_err(),
+		"broken symlink should not be reported as an error"
+	);
+
+	main.abort();
+}
@@ -0,0 +1,103 @@
+use std::time::{Duration, Instant};
+
+use tokio::{
+	sync::mpsc,
+	time::{sleep, timeout},
+};
+use watchexec::{action::ThrottleMode, Watchexec};
+use watchexec_events::{Event, Priority, Source, Tag};
+
+fn event() -> Event {
+	Event {
+		tags: vec![Tag::Source(Source::Internal)],
+		metadata: Default::default(),
+	}
+}
+
+async fn start(mode: ThrottleMode) -> (std::sync::Arc<Watchexec>, mpsc::UnboundedReceiver<usize>) {
+	let (actions_s, actions_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		actions_s.send(action.events.len()).ok();
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_secs(1));
+	wx.config.throttle_mode(mode);
+	wx.main();
+	(wx, actions_r)
+}
+
+#[tokio::test]
+async fn leading_runs_immediately_then_drops() {
+	let (wx, mut actions) = start(ThrottleMode::Leading).await;
+
+	let sent = Instant::now();
+	wx.send_event(event(), Priority::Normal).await.unwrap();
+	let n = timeout(Duration::from_millis(500), actions.recv())
+		.await
+		.expect("first event should run without waiting for the throttle");
+	assert_eq!(n, Some(1));
+	assert!(sent.elapsed() < Duration::from_millis(500));
+
+	wx.send_event(event(), Priority::Normal).await.unwrap();
+	assert!(
+		timeout(Duration::from_millis(1500), actions.recv())
+			.await
+			.is_err(),
+		"events within the window should be dropped"
+	);
+}
+
+#[tokio::test]
+async fn both_coalesces_trailing_events() {
+	let (wx, mut actions) = start(ThrottleMode::Both).await;
+
+	wx.send_event(event(), Priority::Normal).await.unwrap();
+	let n = timeout(Duration::from_millis(500), actions.recv())
+		.await
+		.expect("first event should run without waiting for the throttle");
+	assert_eq!(n, Some(1));
+
+	sleep(Duration::from_millis(100)).await;
+	wx.send_event(event(), Priority::Normal).await.unwrap();
+	wx.send_event(event(), Priority::Normal).await.unwrap();
+	let n = timeout(Duration::from_millis(1500), actions.recv())
+		.await
+		.expect("events within the window should run at the end of it");
+	assert_eq!(n, Some(2));
+}
+
+#[tokio::test]
+async fn initial_throttle_applies_until_first_action() {
+	let (actions_s, mut actions) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		actions_s.send(action.events.len()).ok();
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(100));
+	wx.config.initial_throttle(Some(Duration::from_secs(1)));
+	wx.main();
+
+	let sent = Instant::now();
+	wx.send_event(event(), Priority::Normal).await.unwrap();
+	sleep(Duration::from_millis(300)).await;
+	wx.send_event(event(), Priority::Normal).await.unwrap();
+	let n = timeout(Duration::from_secs(3), actions.recv())
+		.await
+		.expect("first batch should run at the end of the initial window");
+	assert_eq!(n, Some(2));
+	assert!(
+		sent.elapsed() >= Duration::from_secs(1),
+		"first batch should wait the initial window, ran after {:?}",
+		sent.elapsed()
+	);
+
+	let sent = Instant::now();
+	wx.send_event(event(), Priority::Normal).await.unwrap();
+	let n = timeout(Duration::from_millis(700), actions.recv())
+		.await
+		.expect("later batches should use the normal throttle");
+	assert_eq!(n, Some(1));
+	assert!(sent.elapsed() >= Duration::from_millis(100));
+}
@@ -0,0 +1,82 @@
+use std::{
+	path::Path,
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use tokio::{sync::mpsc, time::timeout};
+use watchexec::Watchexec;
+use watchexec_events::{Event, Priority};
+
+fn write_state(path: &Path, at: SystemTime) {
+	let millis = at.duration_since(UNIX_EPOCH).unwrap().as_millis();
+	std::fs::write(path, format!("{millis}\n")).unwrap();
+}
+
+/// Start an instance with a throttle of a second and the state file, and kick it off.
+///
+/// Returns how long it took for the first action to run.
+async fn first_action_after(state: &Path) -> Duration {
+	let (ran_s, mut ran_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		ran_s.send(Instant::now()).ok();
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_secs(1));
+	wx.config.throttle_state_file(Some(state.to_owned()));
+
+	let started = Instant::now();
+	let main = wx.main();
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+
+	let ran = timeout(Duration::from_secs(5), ran_r.recv())
+		.await
+		.expect("first action should run")
+		.unwrap();
+	main.abort();
+	ran - started
+}
+
+#[tokio::test]
+async fn recent_state_delays_first_action() {
+	let dir = tempfile::tempdir().unwrap();
+	let state = dir.path().join("state");
+	write_state(&state, SystemTime::now());
+
+	let waited = first_action_after(&state).await;
+	assert!(
+		waited >= Duration::from_millis(800),
+		"first action should wait out the window: {waited:?}"
+	);
+}
+
+#[tokio::test]
+async fn stale_state_is_ignored() {
+	let dir = tempfile::tempdir().unwrap();
+	let state = dir.path().join("state");
+	write_state(&state, SystemTime::now() - Duration::from_secs(10));
+
+	let waited = first_action_after(&state).await;
+	assert!(
+		waited < Duration::from_millis(800),
+		"first action should not wait: {waited:?}"
+	);
+
+	let written: u64 = std::fs::read_to_string(&state)
+		.unwrap()
+		.trim()
+		.parse()
+		.unwrap();
+	let recorded = UNIX_EPOCH + Duration::from_millis(written);
+	assert!(
+		recorded.elapsed().unwrap() < Duration::from_secs(5),
+		"the action time should be recorded"
+	);
+	assert_eq!(
+		std::fs::read_dir(dir.path()).unwrap().count(),
+		1,
+		"no temporary file should be left behind"
+	);
+}
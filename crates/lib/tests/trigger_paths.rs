@@ -0,0 +1,210 @@
+#![cfg(unix)]
+
+use std::{
+	fs,
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::Duration,
+};
+
+use tokio::{
+	sync::mpsc,
+	time::{sleep, timeout},
+};
+use watchexec::{
+	command::{Command, Program},
+	Watchexec,
+};
+use watchexec_events::{Source, Tag};
+
+#[tokio::test]
+async fn changes_outside_trigger_paths_are_held_until_triggered() {
+	let root = tempfile::tempdir().unwrap();
+	let root_path = dunce::canonicalize(root.path()).unwrap();
+	let src = root_path.join("src");
+	let docs = root_path.join("docs");
+	fs::create_dir(&src).unwrap();
+	fs::create_dir(&docs).unwrap();
+
+	let output = tempfile::tempdir().unwrap();
+	let marker = output.path().join("ran");
+	let command = Arc::new(Command {
+		program: Program::Exec {
+			prog: "touch".into(),
+			args: vec![marker.display().to_string()],
+			argv0: None,
+		},
+		options: Default::default(),
+	});
+
+	let (ready_s, mut ready_r) = mpsc::unbounded_channel();
+	let (paths_s, mut paths_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |mut action| {
+		if action
+			.events
+			.iter()
+			.any(|event| event.tags.contains(&Tag::Source(Source::Internal)))
+		{
+			ready_s.send(()).ok();
+		}
+
+		let paths: Vec<PathBuf> = action.paths().map(|(path, _)| path.to_owned()).collect();
+		if !paths.is_empty() {
+			let (_, job) = action.create_job(command.clone());
+			job.start();
+			paths_s.send(paths).ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(50));
+	wx.config.ready_event(true);
+	wx.config.pathset([root_path.clone()]);
+	wx.config.trigger_paths([src.clone()]);
+	let main = wx.main();
+
+	timeout(Duration::from_secs(5), ready_r.recv())
+		.await
+		.expect("watcher should be set up")
+		.unwrap();
+
+	let doc = docs.join("guide.md");
+	fs::write(&doc, "context").unwrap();
+	sleep(Duration::from_millis(500)).await;
+	assert!(
+		paths_r.try_recv().is_err(),
+		"a change outside the trigger paths should not call the handler with it"
+	);
+	assert!(!marker.exists(), "the command should not have started");
+
+	let file = src.join("main.rs");
+	fs::write(&file, "trigger").unwrap();
+	let paths = timeout(Duration::from_secs(5), paths_r.recv())
+		.await
+		.expect("a change under the trigger paths should call the handler")
+		.unwrap();
+	assert!(paths.contains(&file), "should see the trigger: {paths:?}");
+	assert!(
+		paths.contains(&doc),
+		"should see the held change too: {paths:?}"
+	);
+
+	timeout(Duration::from_secs(5), async {
+		while !marker.exists() {
+			sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.expect("the command should have started");
+
+	main.abort();
+}
+
+#[tokio::test]
+async fn held_events_are_bounded() {
+	use watchexec_events::{Event, Priority};
+
+	let event = |path: String| Event {
+		tags: vec![
+			Tag::Source(Source::Filesystem),
+			Tag::Path {
+				path: path.into(),
+				file_type: None,
+			},
+		],
+		metadata: Default::default(),
+	};
+
+	let (events_s, mut events_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		if !action.events.is_empty() {
+			events_s.send(action.events.to_vec()).ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(50));
+	wx.config.trigger_paths(["/project/src"]);
+	wx.main();
+
+	for n in 0..1500 {
+		wx.send_event(event(format!("/project/docs/{n}.md")), Priority::Normal)
+			.await
+			.unwrap();
+	}
+	sleep(Duration::from_millis(200)).await;
+	wx.send_event(event("/project/src/main.rs".into()), Priority::Normal)
+		.await
+		.unwrap();
+
+	let events = timeout(Duration::from_secs(5), events_r.recv())
+		.await
+		.expect("the trigger should call the handler")
+		.unwrap();
+	let paths: Vec<PathBuf> = events
+		.iter()
+		.flat_map(|event| event.paths().map(|(path, _)| path.to_owned()))
+		.collect();
+
+	assert_eq!(paths.len(), 1024 + 1, "held events should be capped");
+	assert!(paths.contains(&PathBuf::from("/project/src/main.rs")));
+}
+
+#[tokio::test]
+async fn held_events_keep_the_latest_for_each_path() {
+	use watchexec_events::{Event, Priority};
+
+	let event = |path: &str, n: usize| Event {
+		tags: vec![
+			Tag::Source(Source::Filesystem),
+			Tag::Path {
+				path: path.into(),
+				file_type: None,
+			},
+		],
+		metadata: [("n".to_string(), vec![n.to_string()])].into(),
+	};
+
+	let (events_s, mut events_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		if !action.events.is_empty() {
+			events_s.send(action.events.to_vec()).ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.throttle(Duration::from_millis(50));
+	wx.config.trigger_paths(["/project/src"]);
+	wx.main();
+
+	for n in 0..10 {
+		wx.send_event(event("/project/docs/same.md", n), Priority::Normal)
+			.await
+			.unwrap();
+		// in separate batches, so they're held in order
+		sleep(Duration::from_millis(100)).await;
+	}
+	sleep(Duration::from_millis(200)).await;
+	wx.send_event(event("/project/src/main.rs", 0), Priority::Normal)
+		.await
+		.unwrap();
+
+	let events = timeout(Duration::from_secs(5), events_r.recv())
+		.await
+		.expect("the trigger should call the handler")
+		.unwrap();
+	let held: Vec<&Event> = events
+		.iter()
+		.filter(|event| {
+			event
+				.paths()
+				.any(|(path, _)| path == Path::new("/project/docs/same.md"))
+		})
+		.collect();
+	assert_eq!(held.len(), 1, "the same path should only be held once");
+	assert_eq!(
+		held[0].metadata_str("n"),
+		Some("9"),
+		"the latest should be kept"
+	);
+}
@@ -0,0 +1,82 @@
+use watchexec::{
+	config::{Config, Validation},
+	error::ConfigError,
+};
+
+fn with_handler() -> Config {
+	let config = Config::default();
+	config.on_action(|action| action);
+	config
+}
+
+#[test]
+fn default_config_is_invalid() {
+	let errors = Config::default().validate().unwrap_err();
+	assert!(
+		matches!(
+			errors.as_slice(),
+			[ConfigError::NoActionHandler, ConfigError::EmptyPathset]
+		),
+		"all problems should be returned at once: {errors:?}"
+	);
+}
+
+#[test]
+fn empty_pathset() {
+	let config = with_handler();
+	let errors = config.validate().unwrap_err();
+	assert!(
+		matches!(errors.as_slice(), [ConfigError::EmptyPathset]),
+		"{errors:?}"
+	);
+
+	config
+		.validate_with(Validation {
+			allow_empty_pathset: true,
+			..Default::default()
+		})
+		.expect("empty pathset should be allowed");
+}
+
+#[test]
+fn nonexistent_watch_directory() {
+	let missing = std::env::temp_dir().join("watchexec-validate-does-not-exist");
+	let config = with_handler();
+	config.pathset([missing.clone()]);
+
+	config
+		.validate()
+		.expect("missing paths should only be warned about by default");
+
+	let errors = config
+		.validate_with(Validation {
+			missing_paths_are_errors: true,
+			..Default::default()
+		})
+		.unwrap_err();
+	assert!(
+		matches!(errors.as_slice(), [ConfigError::MissingPath { path }] if *path == missing),
+		"{errors:?}"
+	);
+}
+
+#[test]
+fn filterer_can_be_required() {
+	let config = with_handler();
+	config.pathset(["."]);
+	let strict = Validation {
+		require_filterer: true,
+		..Default::default()
+	};
+
+	let errors = config.validate_with(strict).unwrap_err();
+	assert!(
+		matches!(errors.as_slice(), [ConfigError::NoFilterer]),
+		"{errors:?}"
+	);
+
+	config.filterer(());
+	config
+		.validate_with(strict)
+		.expect("filterer should now be set");
+}
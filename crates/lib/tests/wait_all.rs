@@ -0,0 +1,118 @@
+#![cfg(unix)]
+
+use std::{
+	path::Path,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+use tokio::time::{sleep, timeout};
+use watchexec::{
+	command::{Command, Program},
+	Watchexec,
+};
+use watchexec_events::{Event, Priority};
+
+fn sleep_then_touch(secs: &str, file: &Path) -> Arc<Command> {
+	Arc::new(Command {
+		program: Program::Exec {
+			prog: "sh".into(),
+			args: vec![
+				"-c".into(),
+				format!("sleep {secs} && touch '{}'", file.display()),
+			],
+			argv0: None,
+		},
+		options: Default::default(),
+	})
+}
+
+#[tokio::test]
+async fn wait_all_resolves_after_all_jobs_exit() {
+	let dir = tempfile::tempdir().unwrap();
+	let one = dir.path().join("one");
+	let two = dir.path().join("two");
+
+	let started = Arc::new(AtomicBool::new(false));
+	let wx = Watchexec::new({
+		let one = one.clone();
+		let two = two.clone();
+		move |mut action| {
+			if !started.swap(true, Ordering::SeqCst) {
+				let (_, job) = action.create_job(sleep_then_touch("0.2", &one));
+				job.start();
+				let (_, job) = action.create_job(sleep_then_touch("0.5", &two));
+				job.start();
+			}
+			action
+		}
+	})
+	.unwrap();
+
+	let main = wx.main();
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+
+	timeout(Duration::from_secs(5), async {
+		while wx.jobs().len() < 2 {
+			sleep(Duration::from_millis(10)).await;
+		}
+	})
+	.await
+	.expect("jobs should be listed");
+
+	timeout(Duration::from_secs(5), wx.wait_all())
+		.await
+		.expect("wait_all should resolve");
+	assert!(one.exists(), "first job should have finished");
+	assert!(two.exists(), "second job should have finished");
+
+	main.abort();
+}
+
+#[tokio::test]
+async fn wait_all_without_jobs_resolves_immediately() {
+	let wx = Watchexec::new(|action| action).unwrap();
+	assert!(wx.jobs().is_empty());
+	timeout(Duration::from_secs(1), wx.wait_all())
+		.await
+		.expect("wait_all should resolve");
+}
+
+#[tokio::test]
+async fn wait_all_skips_jobs_which_never_started() {
+	let dir = tempfile::tempdir().unwrap();
+	let file = dir.path().join("file");
+
+	let created = Arc::new(AtomicBool::new(false));
+	let wx = Watchexec::new(move |mut action| {
+		if !created.swap(true, Ordering::SeqCst) {
+			let (_, _job) = action.create_job(sleep_then_touch("0", &file));
+		}
+		action
+	})
+	.unwrap();
+
+	let main = wx.main();
+	wx.send_event(Event::default(), Priority::Urgent)
+		.await
+		.unwrap();
+
+	timeout(Duration::from_secs(5), async {
+		while wx.jobs().is_empty() {
+			sleep(Duration::from_millis(10)).await;
+		}
+	})
+	.await
+	.expect("job should be listed");
+
+	timeout(Duration::from_secs(1), wx.wait_all())
+		.await
+		.expect("wait_all should not wait on a job which never started");
+
+	main.abort();
+}
@@ -0,0 +1,102 @@
+#![cfg(unix)]
+
+use std::{path::Path, time::Duration};
+
+use tokio::{
+	sync::mpsc,
+	time::{sleep, timeout},
+};
+use watchexec::{sources::fs::AncestorWatch, Watchexec};
+use watchexec_events::Event;
+
+/// Wait for an event matching `f`, and return the paths of all events seen until then.
+async fn wait_for(
+	events_r: &mut mpsc::UnboundedReceiver<Event>,
+	what: &str,
+	mut f: impl FnMut(&Event) -> bool,
+) -> Vec<std::path::PathBuf> {
+	let mut seen = Vec::new();
+	timeout(Duration::from_secs(5), async {
+		while let Some(event) = events_r.recv().await {
+			assert!(
+				!event.metadata.contains_key("watch"),
+				"no watch state events without the check: {event:?}"
+			);
+			if f(&event) {
+				return;
+			}
+			seen.extend(event.paths().map(|(path, _)| path.to_owned()));
+		}
+	})
+	.await
+	.unwrap_or_else(|_| panic!("should see {what}"));
+	seen
+}
+
+fn has_path(event: &Event, path: &Path) -> bool {
+	event.paths().any(|(p, _)| p == path)
+}
+
+#[tokio::test]
+async fn deleted_watched_dir_is_watched_again_when_recreated() {
+	let root = tempfile::tempdir().unwrap();
+	let root_path = dunce::canonicalize(root.path()).unwrap();
+	let parent = root_path.join("parent");
+	let watched = parent.join("watched");
+	std::fs::create_dir_all(&watched).unwrap();
+
+	let (events_s, mut events_r) = mpsc::unbounded_channel::<Event>();
+	let wx = Watchexec::new(move |action| {
+		for event in action.events.iter() {
+			events_s.send(event.clone()).ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.pathset([watched.clone()]);
+	wx.config.throttle(Duration::from_millis(10));
+	wx.config.watch_ancestors(Some(AncestorWatch::default()));
+	let main = wx.main();
+
+	// give the watcher time to start
+	sleep(Duration::from_millis(200)).await;
+
+	std::fs::remove_dir_all(&watched).unwrap();
+	sleep(Duration::from_millis(200)).await;
+
+	// the parent is watched for the deleted dir, but that's not visible
+	let sibling = parent.join("sibling");
+	std::fs::write(&sibling, "content").unwrap();
+
+	std::fs::create_dir(&watched).unwrap();
+	sleep(Duration::from_millis(200)).await;
+
+	let file = watched.join("file");
+	std::fs::write(&file, "content").unwrap();
+	let seen = wait_for(&mut events_r, "changes in the recreated dir", |event| {
+		has_path(event, &file)
+	})
+	.await;
+	assert!(
+		!seen.contains(&sibling),
+		"events in the ancestor should be hidden: {seen:?}"
+	);
+
+	// deleting the parent too, the nearest ancestor is further up
+	std::fs::remove_dir_all(&parent).unwrap();
+	sleep(Duration::from_millis(200)).await;
+
+	std::fs::create_dir_all(&watched).unwrap();
+	sleep(Duration::from_millis(200)).await;
+
+	let file = watched.join("again");
+	std::fs::write(&file, "content").unwrap();
+	wait_for(
+		&mut events_r,
+		"changes in the dir recreated with its parent",
+		|event| has_path(event, &file),
+	)
+	.await;
+
+	main.abort();
+}
@@ -0,0 +1,121 @@
+use std::{fs, path::PathBuf, time::Duration};
+
+use tokio::{sync::mpsc, time::timeout};
+use watchexec::{
+	error::{CriticalError, FsWatcherError, RuntimeError},
+	sources::fs::WatchLimit,
+	ErrorHook, Watchexec,
+};
+
+/// Makes `small` (2 directories), `big` (6), and `after` (1) in a temporary directory.
+fn trees() -> (tempfile::TempDir, [PathBuf; 3]) {
+	let root = tempfile::tempdir().unwrap();
+	let root_path = dunce::canonicalize(root.path()).unwrap();
+
+	let small = root_path.join("small");
+	fs::create_dir_all(small.join("sub")).unwrap();
+
+	let big = root_path.join("big");
+	for n in 0..5 {
+		fs::create_dir_all(big.join(format!("sub{n}"))).unwrap();
+	}
+
+	let after = root_path.join("after");
+	fs::create_dir(&after).unwrap();
+
+	(root, [small, big, after])
+}
+
+#[tokio::test]
+async fn stops_watching_at_the_limit() {
+	let (_root, [small, big, after]) = trees();
+
+	let (errors_s, mut errors_r) = mpsc::unbounded_channel();
+	let (paths_s, mut paths_r) = mpsc::unbounded_channel();
+	let wx = Watchexec::new(move |action| {
+		for (path, _) in action.paths() {
+			paths_s.send(path.to_owned()).ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.on_error(move |err: ErrorHook| {
+		if let RuntimeError::FsWatcher {
+			err: FsWatcherError::PathLimit { path, limit },
+			..
+		} = err.error
+		{
+			errors_s.send((path, limit)).ok();
+		}
+	});
+	wx.config.max_watched_paths(Some(4));
+	wx.config.throttle(Duration::from_millis(50));
+	wx.config
+		.pathset([small.clone(), big.clone(), after.clone()]);
+	let main = wx.main();
+
+	let (path, limit) = timeout(Duration::from_secs(5), errors_r.recv())
+		.await
+		.expect("should warn about the limit")
+		.unwrap();
+	assert_eq!(path, big, "the first path over the limit is reported");
+	assert_eq!(limit, 4);
+
+	// the watcher is set up right after the limit is reached, give it time to settle
+	tokio::time::sleep(Duration::from_millis(200)).await;
+	while paths_r.try_recv().is_ok() {}
+
+	fs::write(big.join("file"), "big").unwrap();
+	fs::write(after.join("file"), "after").unwrap();
+	fs::write(small.join("file"), "small").unwrap();
+
+	let mut seen = Vec::new();
+	timeout(Duration::from_secs(5), async {
+		while let Some(path) = paths_r.recv().await {
+			let done = path.starts_with(&small);
+			seen.push(path);
+			if done {
+				break;
+			}
+		}
+	})
+	.await
+	.expect("paths within the limit should still be watched");
+	main.abort();
+
+	assert!(
+		!seen
+			.iter()
+			.any(|path| path.starts_with(&big) || path.starts_with(&after)),
+		"paths from the limit on shouldn't be watched: {seen:?}"
+	);
+}
+
+#[tokio::test]
+async fn errors_at_the_limit() {
+	let (_root, [small, big, after]) = trees();
+
+	let wx = Watchexec::new(|action| action).unwrap();
+	wx.config.max_watched_paths(Some(4));
+	wx.config.watch_limit(WatchLimit::Error);
+	wx.config
+		.pathset([small.clone(), big.clone(), after.clone()]);
+
+	let result = timeout(Duration::from_secs(5), wx.main())
+		.await
+		.expect("watchexec should stop")
+		.unwrap();
+	assert!(
+		matches!(
+			result,
+			Err(CriticalError::Elevated {
+				err: RuntimeError::FsWatcher {
+					err: FsWatcherError::PathLimit { .. },
+					..
+				},
+				..
+			})
+		),
+		"{result:?}"
+	);
+}
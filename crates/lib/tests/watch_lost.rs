@@ -0,0 +1,80 @@
+#![cfg(unix)]
+
+use std::time::Duration;
+
+use tokio::{
+	sync::mpsc,
+	time::{sleep, timeout},
+};
+use watchexec::Watchexec;
+use watchexec_events::Event;
+
+async fn wait_for(
+	events_r: &mut mpsc::UnboundedReceiver<Event>,
+	what: &str,
+	mut f: impl FnMut(&Event) -> bool,
+) {
+	timeout(Duration::from_secs(5), async {
+		while let Some(event) = events_r.recv().await {
+			if f(&event) {
+				return;
+			}
+		}
+	})
+	.await
+	.unwrap_or_else(|_| panic!("should see {what}"));
+}
+
+fn watch_state(event: &Event) -> Option<&str> {
+	event
+		.metadata
+		.get("watch")
+		.and_then(|states| states.first())
+		.map(String::as_str)
+}
+
+#[tokio::test]
+async fn deleted_watched_dir_is_lost_then_restored() {
+	let root = tempfile::tempdir().unwrap();
+	let root_path = dunce::canonicalize(root.path()).unwrap();
+	let watched = root_path.join("watched");
+	std::fs::create_dir(&watched).unwrap();
+
+	let (events_s, mut events_r) = mpsc::unbounded_channel::<Event>();
+	let wx = Watchexec::new(move |action| {
+		for event in action.events.iter() {
+			events_s.send(event.clone()).ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.pathset([watched.clone()]);
+	wx.config.throttle(Duration::from_millis(10));
+	wx.config
+		.watch_check_interval(Some(Duration::from_millis(50)));
+	let main = wx.main();
+
+	// give the watcher time to start
+	sleep(Duration::from_millis(200)).await;
+
+	std::fs::remove_dir_all(&watched).unwrap();
+	wait_for(&mut events_r, "a watch-lost event", |event| {
+		watch_state(event) == Some("lost") && event.paths().any(|(path, _)| path == watched)
+	})
+	.await;
+
+	std::fs::create_dir(&watched).unwrap();
+	wait_for(&mut events_r, "a watch-restored event", |event| {
+		watch_state(event) == Some("restored") && event.paths().any(|(path, _)| path == watched)
+	})
+	.await;
+
+	let file = watched.join("file");
+	std::fs::write(&file, "content").unwrap();
+	wait_for(&mut events_r, "changes in the restored dir", |event| {
+		event.paths().any(|(path, _)| path == file)
+	})
+	.await;
+
+	main.abort();
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use tokio::{
+	sync::mpsc,
+	time::{sleep, timeout},
+};
+use watchexec::{sources::fs::Watcher, Watchexec};
+use watchexec_events::{Event, Priority, Source, Tag};
+
+#[tokio::test]
+async fn poll_watcher_events_carry_backend() {
+	let root = tempfile::tempdir().unwrap();
+	let root_path = dunce::canonicalize(root.path()).unwrap();
+	let file = root_path.join("file");
+
+	let (events_s, mut events_r) = mpsc::unbounded_channel::<Event>();
+	let wx = Watchexec::new(move |action| {
+		for event in action.events.iter() {
+			events_s.send(event.clone()).ok();
+		}
+		action
+	})
+	.unwrap();
+	wx.config.pathset([root_path.clone()]);
+	wx.config
+		.file_watcher(Watcher::Poll(Duration::from_millis(50)));
+	wx.config.throttle(Duration::from_millis(10));
+	let main = wx.main();
+
+	// give the watcher time to start
+	sleep(Duration::from_millis(200)).await;
+
+	std::fs::write(&file, "content").unwrap();
+	let event = timeout(Duration::from_secs(5), async {
+		while let Some(event) = events_r.recv().await {
+			if event.paths().any(|(path, _)| path == file) {
+				return event;
+			}
+		}
+		panic!("event channel closed");
+	})
+	.await
+	.expect("should see an event for the file");
+	assert_eq!(event.watcher_backend(), Some("poll"));
+
+	wx.send_event(
+		Event {
+			tags: vec![Tag::Source(Source::Internal)],
+			metadata: Default::default(),
+		},
+		Priority::Normal,
+	)
+	.await
+	.unwrap();
+	let event = timeout(Duration::from_secs(5), async {
+		loop {
+			let event = events_r.recv().await.expect("event channel closed");
+			if event.is_internal() {
+				return event;
+			}
+		}
+	})
+	.await
+	.expect("should see the internal event");
+	assert_eq!(event.watcher_backend(), None);
+
+	main.abort();
+}
@@ -194,70 +194,6 @@ impl ProjectType {
 /// This looks at a wider variety of files than the [`types`] function does: something can be
 /// detected as an origin but not be able to match to any particular [`ProjectType`].
 pub async fn origins(path: impl AsRef<Path> + Send) -> HashSet<PathBuf> {
-	fn check_list(list: &DirList) -> bool {
-		if list.is_empty() {
-			return false;
-		}
-
-		[
-			list.has_dir("_darcs"),
-			list.has_dir(".bzr"),
-			list.has_dir(".fossil-settings"),
-			list.has_dir(".git"),
-			list.has_dir(".github"),
-			list.has_dir(".hg"),
-			list.has_dir(".svn"),
-			list.has_file(".asf.yaml"),
-			list.has_file(".bzrignore"),
-			list.has_file(".codecov.yml"),
-			list.has_file(".ctags"),
-			list.has_file(".editorconfig"),
-			list.has_file(".git"),
-			list.has_file(".gitattributes"),
-			list.has_file(".gitmodules"),
-			list.has_file(".hgignore"),
-			list.has_file(".hgtags"),
-			list.has_file(".perltidyrc"),
-			list.has_file(".travis.yml"),
-			list.has_file("appveyor.yml"),
-			list.has_file("build.gradle"),
-			list.has_file("build.properties"),
-			list.has_file("build.xml"),
-			list.has_file("Cargo.toml"),
-			list.has_file("Cargo.lock"),
-			list.has_file("cgmanifest.json"),
-			list.has_file("CMakeLists.txt"),
-			list.has_file("composer.json"),
-			list.has_file("COPYING"),
-			list.has_file("docker-compose.yml"),
-			list.has_file("Dockerfile"),
-			list.has_file("Gemfile"),
-			list.has_file("LICENSE.txt"),
-			list.has_file("LICENSE"),
-			list.has_file("Makefile.am"),
-			list.has_file("Makefile.pl"),
-			list.has_file("Makefile.PL"),
-			list.has_file("Makefile"),
-			list.has_file("mix.exs"),
-			list.has_file("moonshine-dependencies.xml"),
-			list.has_file("package.json"),
-			list.has_file("package-lock.json"),
-			list.has_file("pnpm-lock.yaml"),
-			list.has_file("yarn.lock"),
-			list.has_file("pom.xml"),
-			list.has_file("project.clj"),
-			list.has_file("requirements.txt"),
-			list.has_file("v.mod"),
-			list.has_file("CONTRIBUTING.md"),
-			list.has_file("go.mod"),
-			list.has_file("go.sum"),
-			list.has_file("Pipfile"),
-			list.has_file("build.zig"),
-		]
-		.into_iter()
-		.any(|f| f)
-	}
-
 	let mut origins = HashSet::new();
 
 	let path = path.as_ref();
@@ -277,6 +213,90 @@ pub async fn origins(path: impl AsRef<Path> + Send) -> HashSet<PathBuf> {
 	origins
 }
 
+/// Returns true if the given path is itself a project origin.
+///
+/// This is the check that [`origins`] does on each of the parents of its path, for this one path
+/// only. It's useful to find origins below a path, for example while walking a directory tree.
+pub async fn is_origin(path: impl AsRef<Path> + Send) -> bool {
+	check_list(&DirList::obtain(path.as_ref()).await)
+}
+
+/// Returns whether the given path is itself a project origin, and all project types detected there.
+///
+/// This is [`is_origin`] and [`types`] together, reading the directory only once, so it's cheaper
+/// than calling both when classifying many candidate directories, for example while walking a
+/// directory tree.
+///
+/// As with [`types`], the set of types may be empty even if the path is an origin.
+pub async fn classify(path: impl AsRef<Path> + Send) -> (bool, HashSet<ProjectType>) {
+	let list = DirList::obtain(path.as_ref()).await;
+	(check_list(&list), list_types(&list))
+}
+
+fn check_list(list: &DirList) -> bool {
+	if list.is_empty() {
+		return false;
+	}
+
+	[
+		list.has_dir("_darcs"),
+		list.has_dir(".bzr"),
+		list.has_dir(".fossil-settings"),
+		list.has_dir(".git"),
+		list.has_dir(".github"),
+		list.has_dir(".hg"),
+		list.has_dir(".svn"),
+		list.has_file(".asf.yaml"),
+		list.has_file(".bzrignore"),
+		list.has_file(".codecov.yml"),
+		list.has_file(".ctags"),
+		list.has_file(".editorconfig"),
+		list.has_file(".git"),
+		list.has_file(".gitattributes"),
+		list.has_file(".gitmodules"),
+		list.has_file(".hgignore"),
+		list.has_file(".hgtags"),
+		list.has_file(".perltidyrc"),
+		list.has_file(".travis.yml"),
+		list.has_file("appveyor.yml"),
+		list.has_file("build.gradle"),
+		list.has_file("build.properties"),
+		list.has_file("build.xml"),
+		list.has_file("Cargo.toml"),
+		list.has_file("Cargo.lock"),
+		list.has_file("cgmanifest.json"),
+		list.has_file("CMakeLists.txt"),
+		list.has_file("composer.json"),
+		list.has_file("COPYING"),
+		list.has_file("docker-compose.yml"),
+		list.has_file("Dockerfile"),
+		list.has_file("Gemfile"),
+		list.has_file("LICENSE.txt"),
+		list.has_file("LICENSE"),
+		list.has_file("Makefile.am"),
+		list.has_file("Makefile.pl"),
+		list.has_file("Makefile.PL"),
+		list.has_file("Makefile"),
+		list.has_file("mix.exs"),
+		list.has_file("moonshine-dependencies.xml"),
+		list.has_file("package.json"),
+		list.has_file("package-lock.json"),
+		list.has_file("pnpm-lock.yaml"),
+		list.has_file("yarn.lock"),
+		list.has_file("pom.xml"),
+		list.has_file("project.clj"),
+		list.has_file("requirements.txt"),
+		list.has_file("v.mod"),
+		list.has_file("CONTRIBUTING.md"),
+		list.has_file("go.mod"),
+		list.has_file("go.sum"),
+		list.has_file("Pipfile"),
+		list.has_file("build.zig"),
+	]
+	.into_iter()
+	.any(|f| f)
+}
+
 /// Returns all project types detected at this given origin.
 ///
 /// This should be called with a result of [`origins()`], or a project origin if already known; it
@@ -287,8 +307,10 @@ pub async fn origins(path: impl AsRef<Path> + Send) -> HashSet<PathBuf> {
 /// Note that this only detects project types listed in the [`ProjectType`] enum, and may not detect
 /// anything for some paths returned by [`origins()`].
 pub async fn types(path: impl AsRef<Path> + Send) -> HashSet<ProjectType> {
-	let path = path.as_ref();
-	let list = DirList::obtain(path).await;
+	list_types(&DirList::obtain(path.as_ref()).await)
+}
+
+fn list_types(list: &DirList) -> HashSet<ProjectType> {
 	[
 		list.if_has_dir("_darcs", ProjectType::Darcs),
 		list.if_has_dir(".bzr", ProjectType::Bazaar),
@@ -0,0 +1,15 @@
+use std::path::Path;
+
+use project_origins::{classify, origins, types, ProjectType};
+
+#[tokio::test]
+async fn classify_cargo_crate() {
+	let path = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+	let (is_origin, classified) = classify(path).await;
+	assert!(is_origin);
+	assert!(classified.contains(&ProjectType::Cargo));
+
+	assert_eq!(is_origin, origins(path).await.contains(path));
+	assert_eq!(classified, types(path).await);
+}
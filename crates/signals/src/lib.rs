@@ -159,6 +159,60 @@ impl Signal {
 	}
 }
 
+impl Signal {
+	/// The action the OS takes by default when a process receives this signal.
+	///
+	/// This is what happens if the process hasn't set up a handler for the signal, or ignored it.
+	/// On Unix, this is the default disposition of the signal. On Windows, control events which
+	/// are supported terminate the process by default, and the others are ignored (as they're
+	/// never delivered).
+	///
+	/// This returns [`Disposition::Unknown`] for [`Custom`][Signal::Custom] signals.
+	///
+	/// ```
+	/// # use watchexec_signals::{Disposition, Signal};
+	/// assert_eq!(Signal::Terminate.default_disposition(), Disposition::Terminate);
+	/// assert_eq!(Signal::Custom(6).default_disposition(), Disposition::Unknown);
+	/// ```
+	#[must_use]
+	pub const fn default_disposition(self) -> Disposition {
+		match self {
+			Self::Hangup | Self::ForceStop | Self::Interrupt | Self::Terminate => {
+				Disposition::Terminate
+			}
+			Self::Quit | Self::User1 | Self::User2 if cfg!(windows) => Disposition::Ignore,
+			Self::Quit => Disposition::CoreDump,
+			Self::User1 | Self::User2 => Disposition::Terminate,
+			Self::Custom(_) => Disposition::Unknown,
+		}
+	}
+}
+
+/// What the OS does with a process when it receives a signal it doesn't handle.
+///
+/// See [`Signal::default_disposition`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Disposition {
+	/// The process is terminated.
+	Terminate,
+
+	/// The process is terminated, and the kernel dumps its core.
+	CoreDump,
+
+	/// The process is stopped (suspended), and can be continued later.
+	Stop,
+
+	/// The process is continued if it was stopped.
+	Continue,
+
+	/// The signal is discarded, and the process keeps running.
+	Ignore,
+
+	/// The disposition isn't known for this signal.
+	Unknown,
+}
+
 impl From<i32> for Signal {
 	/// Converts from a raw signal number.
 	///
@@ -0,0 +1,37 @@
+use watchexec_signals::{Disposition, Signal};
+
+#[test]
+#[cfg(unix)]
+fn unix_defaults() {
+	assert_eq!(Signal::Quit.default_disposition(), Disposition::CoreDump);
+	assert_eq!(Signal::User1.default_disposition(), Disposition::Terminate);
+	assert_eq!(Signal::User2.default_disposition(), Disposition::Terminate);
+	assert_eq!(Signal::Hangup.default_disposition(), Disposition::Terminate);
+	assert_eq!(
+		Signal::ForceStop.default_disposition(),
+		Disposition::Terminate
+	);
+}
+
+#[test]
+#[cfg(windows)]
+fn windows_defaults() {
+	assert_eq!(
+		Signal::Interrupt.default_disposition(),
+		Disposition::Terminate
+	);
+	assert_eq!(Signal::Quit.default_disposition(), Disposition::Ignore);
+	assert_eq!(Signal::User1.default_disposition(), Disposition::Ignore);
+}
+
+#[test]
+fn custom_is_unknown() {
+	assert_eq!(
+		Signal::Custom(0).default_disposition(),
+		Disposition::Unknown
+	);
+	assert_eq!(
+		Signal::Custom(19).default_disposition(),
+		Disposition::Unknown
+	);
+}
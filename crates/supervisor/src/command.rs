@@ -1,11 +1,26 @@
 //! Command construction and configuration.
 
 #[doc(inline)]
-pub use self::{program::Program, shell::Shell};
+pub use self::{
+	info::CommandInfo,
+	program::Program,
+	resolve::PathResolution,
+	shell::{Shell, ShellInput},
+	sink::OutputSink,
+};
+
+pub(crate) use self::set_priority::SetPriority;
 
 mod conversions;
+mod info;
 mod program;
+mod pty;
+mod resolve;
+mod script;
+mod set_priority;
+mod set_user;
 mod shell;
+mod sink;
 
 /// A command to execute.
 ///
@@ -17,6 +32,7 @@ mod shell;
 ///     program: Program::Exec {
 ///         prog: "make".into(),
 ///         args: vec!["check".into()],
+///         argv0: None,
 ///     },
 ///     options: Default::default(),
 /// };
@@ -43,6 +59,7 @@ pub struct Command {
 ///     program: Program::Exec {
 ///         prog: "make".into(),
 ///         args: vec!["check".into()],
+///         argv0: None,
 ///     },
 ///     options: SpawnOptions {
 ///         grouped: true,
@@ -50,7 +67,7 @@ pub struct Command {
 ///     },
 /// };
 /// ```
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct SpawnOptions {
 	/// Run the program in a new process group.
 	///
@@ -69,6 +86,22 @@ pub struct SpawnOptions {
 	/// [sessions]: https://pubs.opengroup.org/onlinepubs/9699919799/functions/setsid.html
 	pub session: bool,
 
+	/// Run the program detached, so that it can outlive the supervisor.
+	///
+	/// The program isn't killed when its job's task is dropped, as happens when Watchexec aborts,
+	/// nor when Watchexec quits gracefully: detached jobs are [detached](crate::job::Job::detach)
+	/// then instead of stopped, and the program is left running. Explicitly stopping, restarting,
+	/// or deleting the job still stops it, and while the job is around, it's supervised as usual.
+	///
+	/// On Unix, the program is also started in a new session (and so in a new process group), so
+	/// it doesn't get the signals sent to the supervisor's process group or terminal. This implies
+	/// `session: true` and `grouped: true`.
+	///
+	/// On Windows, the program is only not killed on drop: it stays in the supervisor's console.
+	/// The equivalent of a new session there would be the `DETACHED_PROCESS` creation flag, which
+	/// isn't set yet.
+	pub detached: bool,
+
 	/// Reset the signal mask of the process before we spawn it.
 	///
 	/// By default, the signal mask of the process is inherited from the parent process. This means
@@ -77,4 +110,110 @@ pub struct SpawnOptions {
 	///
 	/// This is only supported on Unix systems.
 	pub reset_sigmask: bool,
+
+	/// Run the program as this user ID.
+	///
+	/// The program's supplementary groups are set from the user's entry in the group database, and
+	/// if `gid` isn't set, the user's primary group is used. On macOS, AIX, illumos, and Solaris,
+	/// supplementary groups can't be looked up, so the program only gets its primary group; on
+	/// Haiku and Redox they can't be set, so spawning fails. Either way, the program never keeps
+	/// the supervisor's supplementary groups. If the user ID has no entry in the user database,
+	/// `gid` must be set, or spawning fails with an
+	/// [`InvalidInput`](std::io::ErrorKind::InvalidInput) error. This generally requires
+	/// privileges: if the change fails, spawning fails.
+	///
+	/// This is only supported on Unix systems. On other platforms, setting this makes spawning
+	/// fail with an [`Unsupported`](std::io::ErrorKind::Unsupported) error.
+	pub uid: Option<u32>,
+
+	/// Run the program as this group ID.
+	///
+	/// This generally requires privileges: if the change fails, spawning fails.
+	///
+	/// This is only supported on Unix systems. On other platforms, setting this makes spawning
+	/// fail with an [`Unsupported`](std::io::ErrorKind::Unsupported) error.
+	pub gid: Option<u32>,
+
+	/// Where to send the program's standard output.
+	///
+	/// Defaults to [`OutputSink::Inherit`], which leaves it as set on the command, or inherited.
+	pub stdout: OutputSink,
+
+	/// Where to send the program's standard error.
+	///
+	/// Defaults to [`OutputSink::Inherit`], which leaves it as set on the command, or inherited.
+	/// This is ignored when [`merge_stderr_into_stdout`](Self::merge_stderr_into_stdout) is set.
+	pub stderr: OutputSink,
+
+	/// Send the program's standard error to the same place as its standard output.
+	///
+	/// The program's stderr is made a copy of its stdout, once that's set up, so both streams go
+	/// to wherever the [`stdout`](Self::stdout) sink (or a spawn hook, or inheritance) sends
+	/// stdout, as one stream in the order the program wrote them. When stdout is captured to a
+	/// file or to rotated files, they get the merged stream, and the [`stderr`](Self::stderr) sink
+	/// is ignored. With [`pty`](Self::pty), both streams already go to the terminal, so this has
+	/// no effect.
+	///
+	/// This is only supported on Unix systems. On other platforms, setting this makes spawning
+	/// fail with an [`Unsupported`](std::io::ErrorKind::Unsupported) error.
+	pub merge_stderr_into_stdout: bool,
+
+	/// How to locate the program to run.
+	///
+	/// Defaults to [`PathResolution::Inherit`], which leaves it to the OS.
+	pub path_resolution: PathResolution,
+
+	/// Check that the program exists before spawning it.
+	///
+	/// When this is set, spawning first checks with [`Command::preflight()`], so a missing program
+	/// fails with a [`CommandError::NotFound`](crate::errors::CommandError::NotFound) saying where
+	/// it was looked for, instead of a bare OS error. The check uses the supervisor's own `PATH`,
+	/// so don't set this when the program is found otherwise, for example when a spawn hook
+	/// changes the `PATH` or environment.
+	///
+	/// Defaults to false.
+	pub preflight: bool,
+
+	/// Run the program with a pseudo-terminal as its stdout and stderr.
+	///
+	/// Many programs change how they output (colours, progress bars) depending on whether they
+	/// think they're running in a terminal; this makes them believe they are. The terminal's output
+	/// (both streams, together) is copied to the supervisor's stdout. Stdin is left as is.
+	///
+	/// The terminal gets the window size of the supervisor's own terminal, if there is one, and
+	/// follows it when it changes: the program (or its process group, if it's in one) gets a
+	/// `SIGWINCH` then. When the process exits, waiting on it also waits for its output to be
+	/// written out, for up to one second. The terminal is closed once no process holds it anymore.
+	///
+	/// This takes precedence over the [`stdout`](Self::stdout) and [`stderr`](Self::stderr)
+	/// sinks, and over output settings made in spawn hooks.
+	///
+	/// This is only supported on Unix systems. On other platforms (where a ConPTY would be needed
+	/// on Windows), setting this makes spawning fail with an
+	/// [`Unsupported`](std::io::ErrorKind::Unsupported) error.
+	pub pty: bool,
+
+	/// Run the program at this scheduling priority, as a Unix niceness value.
+	///
+	/// This goes from -20 (highest priority) to 19 (lowest priority), and the default for a new
+	/// process is 0. Values outside of that range make spawning fail with an
+	/// [`InvalidInput`](std::io::ErrorKind::InvalidInput) error. Raising the priority (a negative
+	/// niceness, or lower than the supervisor's own) generally requires privileges: if the change
+	/// fails, spawning fails with the OS error, such as a permission denied.
+	///
+	/// On Windows, the niceness is mapped to a [priority class]:
+	///
+	/// | Niceness    | Priority class                |
+	/// |-------------|-------------------------------|
+	/// | 15 to 19    | `IDLE_PRIORITY_CLASS`         |
+	/// | 1 to 14     | `BELOW_NORMAL_PRIORITY_CLASS` |
+	/// | 0           | `NORMAL_PRIORITY_CLASS`       |
+	/// | -1 to -10   | `ABOVE_NORMAL_PRIORITY_CLASS` |
+	/// | -11 to -20  | `HIGH_PRIORITY_CLASS`         |
+	///
+	/// The realtime class is never used. On other platforms, setting this makes spawning fail with
+	/// an [`Unsupported`](std::io::ErrorKind::Unsupported) error.
+	///
+	/// [priority class]: https://learn.microsoft.com/en-us/windows/win32/procthread/scheduling-priorities
+	pub priority: Option<i32>,
 }
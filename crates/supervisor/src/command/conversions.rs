@@ -1,20 +1,45 @@
-use std::fmt;
+use std::{fmt, path::PathBuf};
 
 use process_wrap::tokio::{KillOnDrop, TokioCommandWrap};
 use tokio::process::Command as TokioCommand;
 use tracing::trace;
 
-use super::{Command, Program, SpawnOptions};
+use super::{
+	pty::Pty, resolve::Unresolved, script::ScriptFile, set_priority::SetPriority,
+	set_user::SetUser, sink::Sinks, Command, Program, ShellInput, SpawnOptions,
+};
 
 impl Command {
 	/// Obtain a [`process_wrap::tokio::TokioCommandWrap`].
 	pub fn to_spawnable(&self) -> TokioCommandWrap {
 		trace!(program=?self.program, "constructing command");
 
+		let mut unresolved = if self.options.preflight {
+			self.preflight().err().map(Unresolved::from)
+		} else {
+			None
+		};
+		let mut resolve = |prog: &PathBuf| {
+			self.options
+				.path_resolution
+				.resolve(prog)
+				.unwrap_or_else(|err| {
+					unresolved.get_or_insert_with(|| Unresolved::from(err));
+					prog.clone()
+				})
+		};
+
+		let mut script = None;
 		let cmd = match &self.program {
-			Program::Exec { prog, args, .. } => {
-				let mut c = TokioCommand::new(prog);
+			Program::Exec { prog, args, argv0 } => {
+				let mut c = TokioCommand::new(resolve(prog));
 				c.args(args);
+				#[cfg(unix)]
+				if let Some(argv0) = argv0 {
+					c.arg0(argv0);
+				}
+				#[cfg(not(unix))]
+				let _ = argv0;
 				c
 			}
 
@@ -22,8 +47,34 @@ impl Command {
 				shell,
 				args,
 				command,
+				via: ShellInput::ScriptFile,
 			} => {
-				let mut c = TokioCommand::new(shell.prog.clone());
+				let style = shell.script_style();
+				let mut c = TokioCommand::new(resolve(&shell.prog));
+				c.args(shell.options.clone());
+				if let (true, Some(progopt)) = (style.program_option, &shell.program_option) {
+					c.arg(progopt);
+				}
+				match ScriptFile::write(command, style.extension) {
+					Ok(file) => {
+						c.arg(file.path());
+						script = Some(file);
+					}
+					Err(err) => {
+						unresolved.get_or_insert_with(|| Unresolved::from(err));
+					}
+				}
+				c.args(args);
+				c
+			}
+
+			Program::Shell {
+				shell,
+				args,
+				command,
+				via: ShellInput::Arg,
+			} => {
+				let mut c = TokioCommand::new(resolve(&shell.prog));
 
 				// Avoid quoting issues on Windows by using raw_arg everywhere
 				#[cfg(windows)]
@@ -57,11 +108,29 @@ impl Command {
 		};
 
 		let mut cmd = TokioCommandWrap::from(cmd);
-		cmd.wrap(KillOnDrop);
+		if !self.options.detached {
+			cmd.wrap(KillOnDrop);
+		}
+
+		if let Some(unresolved) = unresolved {
+			cmd.wrap(unresolved);
+		}
+
+		if let Some(script) = script {
+			cmd.wrap(script);
+		}
+
+		// before the job object, which needs to know the creation flags
+		#[cfg(windows)]
+		if let Some(priority) = self.options.priority {
+			cmd.wrap(process_wrap::tokio::CreationFlags(
+				super::set_priority::priority_class(priority),
+			));
+		}
 
 		match self.options {
 			#[cfg(unix)]
-			SpawnOptions { session: true, .. } => {
+			SpawnOptions { session: true, .. } | SpawnOptions { detached: true, .. } => {
 				cmd.wrap(process_wrap::tokio::ProcessSession);
 			}
 			#[cfg(unix)]
@@ -80,6 +149,31 @@ impl Command {
 			cmd.wrap(process_wrap::tokio::ResetSigmask);
 		}
 
+		if self.options.uid.is_some() || self.options.gid.is_some() {
+			cmd.wrap(SetUser {
+				uid: self.options.uid,
+				gid: self.options.gid,
+			});
+		}
+
+		if let Some(priority) = self.options.priority {
+			cmd.wrap(SetPriority(priority));
+		}
+
+		let sinks = Sinks {
+			stdout: self.options.stdout.clone(),
+			stderr: self.options.stderr.clone(),
+			merge: self.options.merge_stderr_into_stdout,
+		};
+		// last, so their child wrappers see the process exit through all the others
+		if self.options.pty {
+			cmd.wrap(Pty::new(
+				self.options.grouped || self.options.session || self.options.detached,
+			));
+		} else if !sinks.is_inherit() {
+			cmd.wrap(sinks);
+		}
+
 		cmd
 	}
 }
@@ -0,0 +1,109 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use super::{Command, Program, ShellInput};
+
+/// A structured description of what a command will run, for logging and status output.
+///
+/// This is obtained with [`Command::to_structured()`] or [`Program::to_structured()`].
+///
+/// For shelled programs, [`program`](Self::program) and [`args`](Self::args) describe the shell
+/// invocation exactly as it will be spawned, and [`shell_command`](Self::shell_command) holds the
+/// command line which is given to the shell, unchanged. For raw programs, `shell_command` is
+/// `None`. For shelled programs given [through a script file](super::ShellInput::ScriptFile),
+/// the path of the file isn't known until spawning, so it's left out of `args`.
+///
+/// The working directory and environment aren't part of a [`Command`], as they're set by spawn
+/// hooks; these fields are left empty here, for callers to fill in if they know them.
+///
+/// With the `serde` feature, this can be serialised and deserialised, with kebab-case field names.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+#[non_exhaustive]
+pub struct CommandInfo {
+	/// The program which is spawned: the shell, for shelled programs.
+	pub program: PathBuf,
+
+	/// The arguments passed to the spawned program.
+	///
+	/// For shelled programs, this is the shell options, the program option, the command line, and
+	/// then the extra shell arguments, in that order.
+	pub args: Vec<String>,
+
+	/// The `argv[0]` given to the program, when it's set to something else than the program.
+	///
+	/// This is the [`argv0`](super::Program::Exec::argv0) of raw programs, which only applies on
+	/// Unix. It's always `None` for shelled programs.
+	pub argv0: Option<String>,
+
+	/// The command line given to the shell, for shelled programs.
+	pub shell_command: Option<String>,
+
+	/// Whether the program runs in a new process group.
+	pub grouped: bool,
+
+	/// Whether the program runs in a new session.
+	pub session: bool,
+
+	/// The working directory the program runs in, if it's known and set.
+	pub cwd: Option<PathBuf>,
+
+	/// Environment variables set or overridden for the program, if known.
+	pub env: BTreeMap<String, String>,
+}
+
+impl Program {
+	/// Describe this program as a [`CommandInfo`], with default options.
+	#[must_use]
+	pub fn to_structured(&self) -> CommandInfo {
+		match self {
+			Self::Exec { prog, args, argv0 } => CommandInfo {
+				program: prog.clone(),
+				args: args.clone(),
+				argv0: argv0
+					.as_ref()
+					.map(|argv0| argv0.to_string_lossy().into_owned()),
+				..Default::default()
+			},
+			Self::Shell {
+				shell,
+				command,
+				args,
+				via,
+			} => {
+				let mut spawned = shell.options.clone();
+				if *via == ShellInput::Arg || shell.script_style().program_option {
+					spawned.extend(
+						shell
+							.program_option
+							.as_ref()
+							.map(|opt| opt.to_string_lossy().into_owned()),
+					);
+				}
+				if *via == ShellInput::Arg {
+					spawned.push(command.clone());
+				}
+				spawned.extend(args.iter().cloned());
+
+				CommandInfo {
+					program: shell.prog.clone(),
+					args: spawned,
+					shell_command: Some(command.clone()),
+					..Default::default()
+				}
+			}
+		}
+	}
+}
+
+impl Command {
+	/// Describe this command as a [`CommandInfo`].
+	#[must_use]
+	pub fn to_structured(&self) -> CommandInfo {
+		CommandInfo {
+			grouped: self.options.grouped || self.options.session || self.options.detached,
+			session: self.options.session || (cfg!(unix) && self.options.detached),
+			..self.program.to_structured()
+		}
+	}
+}
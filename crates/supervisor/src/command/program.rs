@@ -1,6 +1,10 @@
-use std::path::PathBuf;
+use std::{
+	ffi::OsString,
+	io::{Error, ErrorKind, Result},
+	path::PathBuf,
+};
 
-use super::Shell;
+use super::{Shell, ShellInput};
 
 /// A single program call.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -12,6 +16,16 @@ pub enum Program {
 
 		/// The arguments to pass.
 		args: Vec<String>,
+
+		/// What to give the program as its own name (`argv[0]`), instead of `prog`.
+		///
+		/// Some programs behave differently depending on the name they're called by, such as
+		/// multi-call binaries, or shells which act as login shells when their name starts with
+		/// `-`. This only changes what the program sees, not which program is run.
+		///
+		/// This is only supported on Unix: on other platforms it's ignored, as there's no separate
+		/// `argv[0]` to give to a new process.
+		argv0: Option<OsString>,
 	},
 
 	/// A shell program: a string which is to be executed by a shell.
@@ -33,5 +47,48 @@ pub enum Program {
 		///
 		/// This appends the values within to the shell process invocation.
 		args: Vec<String>,
+
+		/// How the command line is given to the shell.
+		via: ShellInput,
 	},
 }
+
+impl Program {
+	/// Parse a command line into a [`Program::Exec`], without involving a shell.
+	///
+	/// The string is split into words following POSIX shell quoting rules: single and double
+	/// quotes group words, and backslashes escape the next character (except within single
+	/// quotes). No other shell syntax is interpreted: there's no variable expansion, globbing,
+	/// pipes, or redirections. The first word is the program, and the rest are its arguments.
+	///
+	/// Returns an error of kind [`InvalidInput`](ErrorKind::InvalidInput) if quotes are
+	/// unbalanced, if the string ends with a lone backslash, or if there are no words at all.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use watchexec_supervisor::command::Program;
+	/// assert_eq!(
+	///     Program::exec_from_str("git commit -m 'hello world'").unwrap(),
+	///     Program::Exec {
+	///         prog: "git".into(),
+	///         args: vec!["commit".into(), "-m".into(), "hello world".into()],
+	///         argv0: None,
+	///     },
+	/// );
+	/// ```
+	pub fn exec_from_str(line: &str) -> Result<Self> {
+		let mut words = shell_words::split(line)
+			.map_err(|err| Error::new(ErrorKind::InvalidInput, err))?
+			.into_iter();
+		let prog = words
+			.next()
+			.ok_or_else(|| Error::new(ErrorKind::InvalidInput, "empty command line"))?;
+
+		Ok(Self::Exec {
+			prog: prog.into(),
+			args: words.collect(),
+			argv0: None,
+		})
+	}
+}
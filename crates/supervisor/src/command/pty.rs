@@ -0,0 +1,275 @@
+use std::io::Result;
+
+use process_wrap::tokio::{TokioCommandWrap, TokioCommandWrapper};
+use tokio::process::Command;
+use tracing::trace;
+
+#[cfg(unix)]
+pub(crate) use unix::Pty;
+
+#[cfg(not(unix))]
+pub(crate) use unsupported::Pty;
+
+#[cfg(unix)]
+mod unix {
+	use std::{
+		fs::File,
+		future::Future,
+		io::{ErrorKind, Read, Write},
+		os::unix::io::AsRawFd,
+		pin::Pin,
+		process::{ExitStatus, Stdio},
+		thread,
+	};
+
+	use nix::{
+		libc,
+		pty::{openpty, Winsize},
+		sys::{
+			signal::{kill, killpg, Signal},
+			termios::{tcgetattr, tcsetattr, OutputFlags, SetArg},
+		},
+		unistd::Pid,
+	};
+	use process_wrap::tokio::TokioChildWrapper;
+	use tokio::{
+		process::Child,
+		signal::unix::{signal, SignalKind},
+		sync::oneshot,
+		task::JoinHandle,
+		time::timeout,
+	};
+
+	use super::{trace, Command, Result, TokioCommandWrap, TokioCommandWrapper};
+	use crate::command::sink::FLUSH_TIMEOUT;
+
+	nix::ioctl_read_bad!(get_window_size, libc::TIOCGWINSZ, Winsize);
+	nix::ioctl_write_ptr_bad!(set_window_size, libc::TIOCSWINSZ, Winsize);
+
+	/// Wrapper which runs the program with a pseudo-terminal as its stdout and stderr.
+	///
+	/// The terminal gets the window size of the supervisor's own terminal, if it has one, and
+	/// follows it when it changes (on `SIGWINCH`). Output is copied from the terminal to the
+	/// supervisor's stdout by a thread, until every process holding the terminal has exited.
+	#[derive(Debug)]
+	pub(crate) struct Pty {
+		/// Whether the program is a process group leader, to resize the whole group.
+		group: bool,
+		master: Option<File>,
+	}
+
+	impl Pty {
+		pub fn new(group: bool) -> Self {
+			Self {
+				group,
+				master: None,
+			}
+		}
+	}
+
+	impl TokioCommandWrapper for Pty {
+		fn pre_spawn(&mut self, command: &mut Command, _core: &TokioCommandWrap) -> Result<()> {
+			let size = window_size();
+			trace!(?size, "opening pseudo-terminal");
+			let pty = openpty(size.as_ref(), None)?;
+			let slave = File::from(pty.slave);
+
+			// leave line endings alone: the supervisor's own terminal, if any, converts them
+			let mut termios = tcgetattr(&slave)?;
+			termios.output_flags.remove(OutputFlags::ONLCR);
+			tcsetattr(&slave, SetArg::TCSANOW, &termios)?;
+
+			command.stdout(Stdio::from(slave.try_clone()?));
+			command.stderr(Stdio::from(slave));
+			self.master = Some(File::from(pty.master));
+			Ok(())
+		}
+
+		fn wrap_child(
+			&mut self,
+			child: Box<dyn TokioChildWrapper>,
+			_core: &TokioCommandWrap,
+		) -> Result<Box<dyn TokioChildWrapper>> {
+			let Some(master) = self.master.take() else {
+				return Ok(child);
+			};
+
+			let resizes = match child.id() {
+				Some(pid) => Some(AbortOnDrop(tokio::spawn(follow_resizes(
+					master.try_clone()?,
+					pid,
+					self.group,
+				)))),
+				None => None,
+			};
+
+			let (done, copy) = oneshot::channel();
+			thread::Builder::new()
+				.name("watchexec-pty".into())
+				.spawn(move || {
+					forward(master);
+					done.send(()).ok();
+				})?;
+
+			Ok(Box::new(PtyChild {
+				inner: child,
+				copy: Some(copy),
+				resizes,
+			}))
+		}
+	}
+
+	/// The window size of the supervisor's terminal, if it has one.
+	fn window_size() -> Option<Winsize> {
+		[libc::STDOUT_FILENO, libc::STDERR_FILENO, libc::STDIN_FILENO]
+			.into_iter()
+			.find_map(|fd| {
+				let mut size = Winsize {
+					ws_row: 0,
+					ws_col: 0,
+					ws_xpixel: 0,
+					ws_ypixel: 0,
+				};
+				unsafe { get_window_size(fd, &mut size) }.ok()?;
+				(size.ws_col > 0).then(|| size)
+			})
+	}
+
+	async fn follow_resizes(master: File, pid: u32, group: bool) {
+		let Ok(mut changes) = signal(SignalKind::window_change()) else {
+			trace!("cannot listen for window size changes");
+			return;
+		};
+
+		#[allow(clippy::cast_possible_wrap)]
+		let pid = Pid::from_raw(pid as i32);
+		while changes.recv().await.is_some() {
+			let Some(size) = window_size() else {
+				continue;
+			};
+
+			trace!(?size, "window size changed, resizing pseudo-terminal");
+			unsafe { set_window_size(master.as_raw_fd(), &size) }.ok();
+			if group {
+				killpg(pid, Signal::SIGWINCH).ok();
+			} else {
+				kill(pid, Signal::SIGWINCH).ok();
+			}
+		}
+	}
+
+	fn forward(mut master: File) {
+		let mut buf = [0; 8192];
+		loop {
+			match master.read(&mut buf) {
+				Ok(0) => break,
+				Ok(n) => {
+					let mut stdout = std::io::stdout();
+					if stdout
+						.write_all(&buf[..n])
+						.and_then(|()| stdout.flush())
+						.is_err()
+					{
+						break;
+					}
+				}
+				Err(err) if err.kind() == ErrorKind::Interrupted => {}
+				// EIO once no process holds the terminal anymore
+				Err(_) => break,
+			}
+		}
+		trace!("pseudo-terminal closed");
+	}
+
+	/// Child wrapper which waits for the terminal output to be written out after the process
+	/// exits, and stops following window size changes.
+	///
+	/// Everything else is passed through to the inner wrapper.
+	#[derive(Debug)]
+	struct PtyChild {
+		inner: Box<dyn TokioChildWrapper>,
+		copy: Option<oneshot::Receiver<()>>,
+		resizes: Option<AbortOnDrop>,
+	}
+
+	#[derive(Debug)]
+	struct AbortOnDrop(JoinHandle<()>);
+
+	impl Drop for AbortOnDrop {
+		fn drop(&mut self) {
+			self.0.abort();
+		}
+	}
+
+	impl TokioChildWrapper for PtyChild {
+		fn inner(&self) -> &Child {
+			self.inner.inner()
+		}
+
+		fn inner_mut(&mut self) -> &mut Child {
+			self.inner.inner_mut()
+		}
+
+		fn into_inner(self: Box<Self>) -> Child {
+			self.inner.into_inner()
+		}
+
+		fn id(&self) -> Option<u32> {
+			self.inner.id()
+		}
+
+		fn start_kill(&mut self) -> Result<()> {
+			self.inner.start_kill()
+		}
+
+		fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+			self.inner.try_wait()
+		}
+
+		fn wait(&mut self) -> Box<dyn Future<Output = Result<ExitStatus>> + Send + '_> {
+			Box::new(async move {
+				let status = Pin::from(self.inner.wait()).await?;
+				self.resizes.take();
+				if let Some(copy) = self.copy.take() {
+					if timeout(FLUSH_TIMEOUT, copy).await.is_err() {
+						trace!(
+							"terminal still open after exit, leaving it to copy in the background"
+						);
+					}
+				}
+				Ok(status)
+			})
+		}
+
+		fn signal(&self, sig: i32) -> Result<()> {
+			self.inner.signal(sig)
+		}
+	}
+}
+
+#[cfg(not(unix))]
+mod unsupported {
+	use super::{trace, Command, Result, TokioCommandWrap, TokioCommandWrapper};
+
+	/// Wrapper which fails spawning, as pseudo-terminals are only supported on Unix.
+	///
+	/// Windows would need a ConPTY, which isn't implemented yet.
+	#[derive(Debug)]
+	pub(crate) struct Pty;
+
+	impl Pty {
+		pub fn new(_group: bool) -> Self {
+			Self
+		}
+	}
+
+	impl TokioCommandWrapper for Pty {
+		fn pre_spawn(&mut self, _command: &mut Command, _core: &TokioCommandWrap) -> Result<()> {
+			trace!("cannot use a pseudo-terminal on this platform");
+			Err(std::io::Error::new(
+				std::io::ErrorKind::Unsupported,
+				"pseudo-terminals are not supported on this platform",
+			))
+		}
+	}
+}
@@ -0,0 +1,196 @@
+use std::{
+	io::{Error, ErrorKind, Result},
+	path::{Path, PathBuf},
+};
+
+use process_wrap::tokio::{TokioCommandWrap, TokioCommandWrapper};
+use tokio::process::Command as TokioCommand;
+use tracing::trace;
+
+use super::{Command, Program};
+use crate::errors::CommandError;
+
+/// How the program to run is located.
+///
+/// This applies to the `prog` of a [`Program::Exec`](super::Program::Exec), and to the shell
+/// program of a [`Program::Shell`](super::Program::Shell).
+///
+/// When the program can't be located as required, spawning fails with:
+/// - a [`NotFound`](ErrorKind::NotFound) error for [`Explicit`](PathResolution::Explicit), if
+///   the program isn't in any of the directories;
+/// - an [`InvalidInput`](ErrorKind::InvalidInput) error for
+///   [`RequireAbsolute`](PathResolution::RequireAbsolute), if the program isn't an absolute path.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PathResolution {
+	/// Leave it to the OS, which searches the `PATH` of the supervisor.
+	///
+	/// This is the default.
+	#[default]
+	Inherit,
+
+	/// Search these directories, in order, and run the program by its full path.
+	///
+	/// Programs given with a directory part (like `./script` or `bin/tool`) aren't searched for,
+	/// as with the OS search. On Unix, only files with an executable bit are found. On Windows,
+	/// a program without an extension is also looked for with the extensions in `PATHEXT`.
+	Explicit(Vec<PathBuf>),
+
+	/// Only run programs given as absolute paths.
+	RequireAbsolute,
+}
+
+impl PathResolution {
+	/// Locate a program.
+	///
+	/// Returns the path to run it by, which is `prog` unchanged if it isn't searched for.
+	pub fn resolve(&self, prog: &Path) -> Result<PathBuf> {
+		match self {
+			Self::Inherit => Ok(prog.to_owned()),
+			Self::RequireAbsolute if prog.is_absolute() => Ok(prog.to_owned()),
+			Self::RequireAbsolute => Err(Error::new(
+				ErrorKind::InvalidInput,
+				format!("program {prog:?} is not an absolute path"),
+			)),
+			Self::Explicit(_) if prog.components().count() > 1 => Ok(prog.to_owned()),
+			Self::Explicit(dirs) => dirs
+				.iter()
+				.flat_map(|dir| candidates(dir, prog))
+				.find(|path| is_executable(path))
+				.ok_or_else(|| {
+					Error::new(
+						ErrorKind::NotFound,
+						format!("program {prog:?} not found in {dirs:?}"),
+					)
+				}),
+		}
+	}
+
+	/// Check that a program exists, without running it.
+	///
+	/// Only programs which are searched for, and absolute paths, are checked. Programs given with a
+	/// relative directory part depend on the working directory, which may only be set when
+	/// spawning, so they're left for the spawn to fail. With
+	/// [`RequireAbsolute`](PathResolution::RequireAbsolute), relative paths aren't checked either:
+	/// spawning fails them regardless. On Windows, [`Inherit`](PathResolution::Inherit) searches
+	/// places other than the `PATH`, so it isn't checked at all.
+	pub fn preflight(&self, prog: &Path) -> std::result::Result<(), CommandError> {
+		let not_found = |searched_paths: Vec<PathBuf>| CommandError::NotFound {
+			program: prog.to_owned(),
+			searched_paths,
+		};
+
+		if prog.is_absolute() {
+			return if prog.exists() {
+				Ok(())
+			} else {
+				Err(not_found(Vec::new()))
+			};
+		}
+
+		let dirs = match self {
+			_ if prog.components().count() > 1 => return Ok(()),
+			Self::RequireAbsolute => return Ok(()),
+			Self::Inherit if cfg!(windows) => return Ok(()),
+			Self::Inherit => std::env::var_os("PATH")
+				.map(|path| std::env::split_paths(&path).collect())
+				.unwrap_or_default(),
+			Self::Explicit(dirs) => dirs.clone(),
+		};
+
+		if dirs
+			.iter()
+			.flat_map(|dir| candidates(dir, prog))
+			.any(|path| is_executable(&path))
+		{
+			Ok(())
+		} else {
+			Err(not_found(dirs))
+		}
+	}
+}
+
+#[cfg(not(windows))]
+fn candidates(dir: &Path, prog: &Path) -> Vec<PathBuf> {
+	vec![dir.join(prog)]
+}
+
+#[cfg(windows)]
+fn candidates(dir: &Path, prog: &Path) -> Vec<PathBuf> {
+	let mut paths = vec![dir.join(prog)];
+	if prog.extension().is_none() {
+		let exts = std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".into());
+		for ext in exts.split(';').filter(|ext| !ext.is_empty()) {
+			let mut name = prog.as_os_str().to_owned();
+			name.push(ext);
+			paths.push(dir.join(name));
+		}
+	}
+	paths
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+	use std::os::unix::fs::PermissionsExt;
+	path.metadata().map_or(false, |meta| {
+		meta.is_file() && meta.permissions().mode() & 0o111 != 0
+	})
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+	path.is_file()
+}
+
+impl Command {
+	/// Check that the program to run exists, without running it.
+	///
+	/// For a shell command, this checks the shell program. This is done when spawning if
+	/// [`preflight`](super::SpawnOptions::preflight) is set; call it directly to find out early,
+	/// e.g. before the first run. See [`PathResolution::preflight()`] for what's checked.
+	pub fn preflight(&self) -> std::result::Result<(), CommandError> {
+		let prog = match &self.program {
+			Program::Exec { prog, .. } => prog,
+			Program::Shell { shell, .. } => &shell.prog,
+		};
+
+		self.options.path_resolution.preflight(prog)
+	}
+}
+
+/// Wrapper which fails spawning because the program couldn't be located, or set up to run.
+#[derive(Debug)]
+pub(crate) struct Unresolved {
+	pub kind: ErrorKind,
+	pub message: String,
+	pub source: Option<CommandError>,
+}
+
+impl From<Error> for Unresolved {
+	fn from(err: Error) -> Self {
+		Self {
+			kind: err.kind(),
+			message: err.to_string(),
+			source: None,
+		}
+	}
+}
+
+impl From<CommandError> for Unresolved {
+	fn from(err: CommandError) -> Self {
+		Self {
+			kind: ErrorKind::NotFound,
+			message: err.to_string(),
+			source: Some(err),
+		}
+	}
+}
+
+impl TokioCommandWrapper for Unresolved {
+	fn pre_spawn(&mut self, _command: &mut TokioCommand, _core: &TokioCommandWrap) -> Result<()> {
+		trace!(message=?self.message, "program couldn't be located, not spawning");
+		Err(match &self.source {
+			Some(err) => Error::new(self.kind, err.clone()),
+			None => Error::new(self.kind, self.message.clone()),
+		})
+	}
+}
@@ -0,0 +1,114 @@
+use std::{
+	future::Future,
+	io::{Result, Write},
+	path::Path,
+	pin::Pin,
+	process::ExitStatus,
+};
+
+use process_wrap::tokio::{TokioChildWrapper, TokioCommandWrap, TokioCommandWrapper};
+use tempfile::TempPath;
+use tokio::process::Child;
+use tracing::{trace, warn};
+
+/// Wrapper which keeps the script file of a shelled program until it exits.
+///
+/// See [`ShellInput::ScriptFile`](super::ShellInput::ScriptFile). The file is removed when this
+/// is dropped without having spawned, or once the child has been waited on.
+#[derive(Debug)]
+pub(crate) struct ScriptFile(Option<TempPath>);
+
+impl ScriptFile {
+	/// Write the command line to a new temporary file, with an extension if given.
+	pub fn write(command: &str, extension: Option<&str>) -> Result<Self> {
+		let suffix = extension.map(|ext| format!(".{ext}")).unwrap_or_default();
+		let mut file = tempfile::Builder::new()
+			.prefix("watchexec-script-")
+			.suffix(&suffix)
+			.tempfile()?;
+		file.write_all(command.as_bytes())?;
+		file.flush()?;
+
+		// closes the file, so it can be opened by the shell on all platforms
+		let path = file.into_temp_path();
+		trace!(?path, "wrote command to script file");
+		Ok(Self(Some(path)))
+	}
+
+	pub fn path(&self) -> &Path {
+		self.0.as_deref().unwrap_or_else(|| Path::new(""))
+	}
+}
+
+impl TokioCommandWrapper for ScriptFile {
+	fn wrap_child(
+		&mut self,
+		child: Box<dyn TokioChildWrapper>,
+		_core: &TokioCommandWrap,
+	) -> Result<Box<dyn TokioChildWrapper>> {
+		Ok(Box::new(ScriptChild {
+			inner: child,
+			script: self.0.take(),
+		}))
+	}
+}
+
+#[derive(Debug)]
+struct ScriptChild {
+	inner: Box<dyn TokioChildWrapper>,
+	script: Option<TempPath>,
+}
+
+impl ScriptChild {
+	fn remove_script(&mut self) {
+		if let Some(path) = self.script.take() {
+			trace!(?path, "removing script file");
+			if let Err(err) = path.close() {
+				warn!(?err, "could not remove script file");
+			}
+		}
+	}
+}
+
+impl TokioChildWrapper for ScriptChild {
+	fn inner(&self) -> &Child {
+		self.inner.inner()
+	}
+
+	fn inner_mut(&mut self) -> &mut Child {
+		self.inner.inner_mut()
+	}
+
+	fn into_inner(self: Box<Self>) -> Child {
+		self.inner.into_inner()
+	}
+
+	fn id(&self) -> Option<u32> {
+		self.inner.id()
+	}
+
+	fn start_kill(&mut self) -> Result<()> {
+		self.inner.start_kill()
+	}
+
+	fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+		let status = self.inner.try_wait()?;
+		if status.is_some() {
+			self.remove_script();
+		}
+		Ok(status)
+	}
+
+	fn wait(&mut self) -> Box<dyn Future<Output = Result<ExitStatus>> + Send + '_> {
+		Box::new(async move {
+			let status = Pin::from(self.inner.wait()).await?;
+			self.remove_script();
+			Ok(status)
+		})
+	}
+
+	#[cfg(unix)]
+	fn signal(&self, sig: i32) -> Result<()> {
+		self.inner.signal(sig)
+	}
+}
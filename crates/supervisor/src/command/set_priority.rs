@@ -0,0 +1,112 @@
+use std::io::{Error, ErrorKind, Result};
+
+use process_wrap::tokio::{TokioCommandWrap, TokioCommandWrapper};
+use tokio::process::Command;
+use tracing::trace;
+
+/// Wrapper which runs the program at a different scheduling priority.
+///
+/// On Unix, this sets the niceness of the child before it executes the program. On Windows, this
+/// is done with a priority class creation flag, see
+/// [`SpawnOptions::priority`](super::SpawnOptions::priority) for the mapping.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SetPriority(pub i32);
+
+impl SetPriority {
+	/// The range of valid niceness values.
+	pub const RANGE: std::ops::RangeInclusive<i32> = -20..=19;
+
+	/// Describe an error from spawning a program at this priority.
+	///
+	/// Setting the niceness fails in the child, where the error can't be given any context, so it
+	/// comes back to the parent as a bare OS error from the spawn.
+	#[must_use]
+	pub fn spawn_error(self, err: Error) -> Error {
+		if cfg!(unix) && err.raw_os_error().is_some() {
+			Error::new(
+				err.kind(),
+				format!("spawning at niceness {}: {err}", self.0),
+			)
+		} else {
+			err
+		}
+	}
+
+	fn check(self) -> Result<()> {
+		if Self::RANGE.contains(&self.0) {
+			Ok(())
+		} else {
+			Err(Error::new(
+				ErrorKind::InvalidInput,
+				format!(
+					"priority {} is out of range: niceness goes from {} to {}",
+					self.0,
+					Self::RANGE.start(),
+					Self::RANGE.end()
+				),
+			))
+		}
+	}
+}
+
+impl TokioCommandWrapper for SetPriority {
+	#[cfg(unix)]
+	fn pre_spawn(&mut self, command: &mut Command, _core: &TokioCommandWrap) -> Result<()> {
+		use nix::libc::{id_t, setpriority, PRIO_PROCESS};
+
+		self.check()?;
+		let niceness = self.0;
+		trace!(?niceness, "running program at niceness");
+		unsafe {
+			// this runs between fork and exec, so it mustn't allocate: see `spawn_error()`
+			command.pre_exec(move || {
+				if setpriority(PRIO_PROCESS, 0 as id_t, niceness) == 0 {
+					Ok(())
+				} else {
+					Err(Error::last_os_error())
+				}
+			});
+		}
+
+		Ok(())
+	}
+
+	/// The priority class itself is given with a [`CreationFlags`] wrapper, added alongside this
+	/// one so that a job object wrapper sees it; this only checks the value.
+	///
+	/// [`CreationFlags`]: process_wrap::tokio::CreationFlags
+	#[cfg(windows)]
+	fn pre_spawn(&mut self, _command: &mut Command, _core: &TokioCommandWrap) -> Result<()> {
+		self.check()?;
+		trace!(niceness=?self.0, class=?priority_class(self.0), "running program with priority class");
+		Ok(())
+	}
+
+	#[cfg(not(any(unix, windows)))]
+	fn pre_spawn(&mut self, _command: &mut Command, _core: &TokioCommandWrap) -> Result<()> {
+		trace!(niceness=?self.0, "cannot set priority on this platform");
+		Err(Error::new(
+			ErrorKind::Unsupported,
+			"running a program at a different priority is only supported on Unix and Windows",
+		))
+	}
+}
+
+/// The Windows priority class for a niceness value.
+#[cfg(windows)]
+pub(crate) fn priority_class(
+	niceness: i32,
+) -> windows::Win32::System::Threading::PROCESS_CREATION_FLAGS {
+	use windows::Win32::System::Threading::{
+		ABOVE_NORMAL_PRIORITY_CLASS, BELOW_NORMAL_PRIORITY_CLASS, HIGH_PRIORITY_CLASS,
+		IDLE_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS,
+	};
+
+	match niceness {
+		15.. => IDLE_PRIORITY_CLASS,
+		1..=14 => BELOW_NORMAL_PRIORITY_CLASS,
+		0 => NORMAL_PRIORITY_CLASS,
+		-10..=-1 => ABOVE_NORMAL_PRIORITY_CLASS,
+		_ => HIGH_PRIORITY_CLASS,
+	}
+}
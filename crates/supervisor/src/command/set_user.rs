@@ -0,0 +1,142 @@
+use std::io::{Error, ErrorKind, Result};
+
+use process_wrap::tokio::{TokioCommandWrap, TokioCommandWrapper};
+use tokio::process::Command;
+use tracing::trace;
+
+/// Wrapper which runs the program as a different user and/or group.
+///
+/// On Unix, this looks up the user (if any) before spawning, then in the child sets the
+/// supplementary groups, the group ID, and finally the user ID, in that order so privileges are
+/// only dropped once everything that needs them is done. On other platforms, spawning fails.
+///
+/// When changing user, the supplementary groups are always replaced, so the program never keeps
+/// the supervisor's. Where the user's groups can't be listed, they're replaced with only the
+/// primary group; where they can't be set at all, changing user fails.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SetUser {
+	pub uid: Option<u32>,
+	pub gid: Option<u32>,
+}
+
+impl TokioCommandWrapper for SetUser {
+	#[cfg(unix)]
+	fn pre_spawn(&mut self, command: &mut Command, _core: &TokioCommandWrap) -> Result<()> {
+		use nix::unistd::{setgid, setuid, Gid, Uid, User};
+
+		let uid = self.uid.map(Uid::from_raw);
+		let mut gid = self.gid.map(Gid::from_raw);
+		let mut groups = None;
+
+		if let Some(uid) = uid {
+			if let Some(user) = User::from_uid(uid)? {
+				let primary = *gid.get_or_insert(user.gid);
+				groups = supplementary_groups(&user.name, primary)?;
+			} else {
+				let Some(gid) = gid else {
+					return Err(Error::new(
+						ErrorKind::InvalidInput,
+						format!("no user with uid {uid}: a gid must be given to run as it"),
+					));
+				};
+				trace!(?uid, "user not found, only setting given group");
+				groups = Some(vec![gid]);
+			}
+		}
+
+		trace!(?uid, ?gid, ?groups, "running program as user/group");
+		unsafe {
+			command.pre_exec(move || {
+				if let Some(groups) = &groups {
+					set_groups(groups)?;
+				}
+				if let Some(gid) = gid {
+					setgid(gid)?;
+				}
+				if let Some(uid) = uid {
+					setuid(uid)?;
+				}
+				Ok(())
+			});
+		}
+
+		Ok(())
+	}
+
+	#[cfg(not(unix))]
+	fn pre_spawn(&mut self, _command: &mut Command, _core: &TokioCommandWrap) -> Result<()> {
+		trace!(uid=?self.uid, gid=?self.gid, "cannot set user/group on this platform");
+		Err(Error::new(
+			ErrorKind::Unsupported,
+			"running a program as a different user or group is only supported on Unix",
+		))
+	}
+}
+
+#[cfg(all(
+	unix,
+	not(any(
+		target_vendor = "apple",
+		target_os = "aix",
+		target_os = "haiku",
+		target_os = "illumos",
+		target_os = "redox",
+		target_os = "solaris",
+	))
+))]
+fn supplementary_groups(
+	name: &str,
+	primary: nix::unistd::Gid,
+) -> Result<Option<Vec<nix::unistd::Gid>>> {
+	let name = std::ffi::CString::new(name)?;
+	Ok(Some(nix::unistd::getgrouplist(&name, primary)?))
+}
+
+#[cfg(any(
+	target_vendor = "apple",
+	target_os = "aix",
+	target_os = "illumos",
+	target_os = "solaris",
+))]
+fn supplementary_groups(
+	_name: &str,
+	primary: nix::unistd::Gid,
+) -> Result<Option<Vec<nix::unistd::Gid>>> {
+	trace!("cannot list supplementary groups on this platform, only keeping the primary group");
+	Ok(Some(vec![primary]))
+}
+
+#[cfg(any(target_os = "haiku", target_os = "redox"))]
+fn supplementary_groups(
+	_name: &str,
+	_primary: nix::unistd::Gid,
+) -> Result<Option<Vec<nix::unistd::Gid>>> {
+	Err(Error::new(
+		ErrorKind::Unsupported,
+		"changing user needs supplementary groups to be set, which isn't supported on this platform",
+	))
+}
+
+#[cfg(all(
+	unix,
+	not(any(target_vendor = "apple", target_os = "haiku", target_os = "redox"))
+))]
+fn set_groups(groups: &[nix::unistd::Gid]) -> nix::Result<()> {
+	nix::unistd::setgroups(groups)
+}
+
+#[cfg(target_vendor = "apple")]
+fn set_groups(groups: &[nix::unistd::Gid]) -> nix::Result<()> {
+	use nix::libc::{c_int, gid_t, setgroups};
+
+	// Gid is a transparent wrapper around gid_t, and this can't allocate to convert them
+	#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+	let res = unsafe { setgroups(groups.len() as c_int, groups.as_ptr().cast::<gid_t>()) };
+	nix::errno::Errno::result(res).map(drop)
+}
+
+#[cfg(any(target_os = "haiku", target_os = "redox"))]
+fn set_groups(_groups: &[nix::unistd::Gid]) -> nix::Result<()> {
+	// unreachable: supplementary_groups() fails first on these platforms
+	Err(nix::errno::Errno::ENOSYS)
+}
@@ -39,3 +39,64 @@ impl Shell {
 		}
 	}
 }
+
+/// How the command line of a [`Program::Shell`](super::Program::Shell) is given to the shell.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ShellInput {
+	/// As an argument, after the shell's `program_option`.
+	///
+	/// This is the default.
+	#[default]
+	Arg,
+
+	/// Through a script file, whose path is given to the shell instead.
+	///
+	/// This avoids platform limits on the length of arguments, and quoting issues with multi-line
+	/// commands. The command line is written to a new temporary file when spawning, which is
+	/// removed once the process has exited and been waited on, or if spawning fails. The shell is
+	/// called with its `options`, the path of the file, and the extra arguments, but without its
+	/// `program_option`: the shell needs to run scripts given by path, as `sh` and `bash` do.
+	///
+	/// Some shells only run scripts with the right extension, so the file gets one depending on
+	/// the name of the shell: `.ps1` for `powershell` and `pwsh`, and `.bat` for `cmd`. As CMD.EXE
+	/// doesn't run a script given only by its path, it's also given its `program_option` (`/C`)
+	/// before the path. Other shells get a file without extension.
+	///
+	/// On Unix, the file is only readable and writable by the current user, so a program run as
+	/// another user with [`SpawnOptions::uid`](super::SpawnOptions::uid) can't read it.
+	ScriptFile,
+}
+
+/// How a shell wants the script file of [`ShellInput::ScriptFile`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ScriptStyle {
+	/// The extension the file needs, without the dot.
+	pub extension: Option<&'static str>,
+
+	/// Whether the `program_option` goes before the path of the file.
+	pub program_option: bool,
+}
+
+impl Shell {
+	/// How this shell wants a script file, guessed from its name.
+	pub(crate) fn script_style(&self) -> ScriptStyle {
+		let name = self
+			.prog
+			.file_stem()
+			.map(|stem| stem.to_string_lossy().to_ascii_lowercase());
+		match name.as_deref() {
+			Some("cmd") => ScriptStyle {
+				extension: Some("bat"),
+				program_option: true,
+			},
+			Some("powershell" | "pwsh") => ScriptStyle {
+				extension: Some("ps1"),
+				program_option: false,
+			},
+			_ => ScriptStyle {
+				extension: None,
+				program_option: false,
+			},
+		}
+	}
+}
@@ -0,0 +1,356 @@
+use std::{
+	fs::{self, File, OpenOptions},
+	future::Future,
+	io::{Result, Write},
+	path::{Path, PathBuf},
+	pin::Pin,
+	process::{ExitStatus, Stdio},
+	time::Duration,
+};
+
+use process_wrap::tokio::{TokioChildWrapper, TokioCommandWrap, TokioCommandWrapper};
+use tokio::{
+	io::{AsyncRead, AsyncReadExt},
+	process::{Child, Command},
+	task::{spawn_blocking, JoinHandle},
+	time::timeout,
+};
+use tracing::{trace, warn};
+
+/// How long to wait for output to be written out once the process has exited.
+///
+/// Output is copied until the pipe closes, which can be after the process exits if descendants of
+/// the process hold it open. Past this, the copy carries on in the background.
+pub(super) const FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Where to send the output of a program.
+///
+/// # Examples
+///
+/// ```
+/// # use watchexec_supervisor::command::{OutputSink, SpawnOptions};
+/// SpawnOptions {
+///     stdout: OutputSink::Rotating {
+///         dir: "logs".into(),
+///         max_size: 10 * 1024 * 1024,
+///         keep: 5,
+///     },
+///     stderr: OutputSink::Null,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum OutputSink {
+	/// Inherit the output of the supervisor.
+	///
+	/// This is the default. The stream isn't touched, so it can also be set from a spawn hook.
+	#[default]
+	Inherit,
+
+	/// Discard the output.
+	Null,
+
+	/// Append the output to a file, creating it if needed.
+	File(PathBuf),
+
+	/// Append the output to a file in a directory, rotating it by size.
+	///
+	/// The file is named after the stream (`stdout.log` or `stderr.log`). Before a write would make
+	/// it larger than `max_size` bytes, it's renamed to `stdout.log.1`, previously rotated files
+	/// are shifted up (`.1` to `.2`, etc), and those past `keep` are deleted. A single write larger
+	/// than `max_size` isn't split, so files can exceed it in that case.
+	///
+	/// The output goes through a pipe and is copied to the file by the supervisor. When the process
+	/// exits, waiting on it also waits for its output to be written out, for up to one second.
+	Rotating {
+		/// The directory to write the files in. It's created if needed.
+		dir: PathBuf,
+
+		/// The size in bytes past which the file is rotated.
+		max_size: u64,
+
+		/// How many rotated files to keep, besides the current one.
+		keep: usize,
+	},
+}
+
+/// Wrapper which sends the program's output to [`OutputSink`]s.
+///
+/// When `merge` is set, the stderr sink is ignored, and the program's stderr is made a copy of its
+/// stdout in the child, once both are set up, so the two streams go to the same place in order.
+#[derive(Clone, Debug)]
+pub(crate) struct Sinks {
+	pub stdout: OutputSink,
+	pub stderr: OutputSink,
+	pub merge: bool,
+}
+
+impl Sinks {
+	pub fn is_inherit(&self) -> bool {
+		self.stdout == OutputSink::Inherit && self.stderr == OutputSink::Inherit && !self.merge
+	}
+}
+
+impl OutputSink {
+	fn stdio(&self) -> Result<Option<Stdio>> {
+		Ok(match self {
+			Self::Inherit => None,
+			Self::Null => Some(Stdio::null()),
+			Self::File(path) => Some(append(path)?.into()),
+			Self::Rotating { dir, .. } => {
+				fs::create_dir_all(dir)?;
+				Some(Stdio::piped())
+			}
+		})
+	}
+}
+
+impl TokioCommandWrapper for Sinks {
+	fn pre_spawn(&mut self, command: &mut Command, _core: &TokioCommandWrap) -> Result<()> {
+		trace!(stdout=?self.stdout, stderr=?self.stderr, merge=?self.merge, "setting output sinks");
+		if let Some(stdio) = self.stdout.stdio()? {
+			command.stdout(stdio);
+		}
+		if self.merge {
+			merge_stderr(command)?;
+		} else if let Some(stdio) = self.stderr.stdio()? {
+			command.stderr(stdio);
+		}
+		Ok(())
+	}
+
+	fn wrap_child(
+		&mut self,
+		mut child: Box<dyn TokioChildWrapper>,
+		_core: &TokioCommandWrap,
+	) -> Result<Box<dyn TokioChildWrapper>> {
+		let mut copies = Vec::new();
+		if let OutputSink::Rotating {
+			dir,
+			max_size,
+			keep,
+		} = &self.stdout
+		{
+			if let Some(stdout) = child.stdout().take() {
+				let file = RotatingFile::open(dir, "stdout.log", *max_size, *keep)?;
+				copies.push(tokio::spawn(copy(stdout, file)));
+			}
+		}
+		if let (
+			OutputSink::Rotating {
+				dir,
+				max_size,
+				keep,
+			},
+			false,
+		) = (&self.stderr, self.merge)
+		{
+			if let Some(stderr) = child.stderr().take() {
+				let file = RotatingFile::open(dir, "stderr.log", *max_size, *keep)?;
+				copies.push(tokio::spawn(copy(stderr, file)));
+			}
+		}
+
+		Ok(Box::new(SinksChild {
+			inner: child,
+			copies,
+		}))
+	}
+}
+
+/// Make the program's stderr a copy of its stdout.
+///
+/// This runs in the child after its stdio is set up, so it follows wherever stdout was sent.
+#[cfg(unix)]
+fn merge_stderr(command: &mut Command) -> Result<()> {
+	use nix::libc::dup2;
+
+	unsafe {
+		command.pre_exec(|| {
+			if dup2(1, 2) == -1 {
+				Err(std::io::Error::last_os_error())
+			} else {
+				Ok(())
+			}
+		});
+	}
+	Ok(())
+}
+
+#[cfg(not(unix))]
+fn merge_stderr(_command: &mut Command) -> Result<()> {
+	trace!("cannot merge stderr into stdout on this platform");
+	Err(std::io::Error::new(
+		std::io::ErrorKind::Unsupported,
+		"merging stderr into stdout is only supported on Unix",
+	))
+}
+
+fn append(path: &Path) -> Result<File> {
+	OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Copy output to a rotating file.
+///
+/// Writing and rotating the file is blocking I/O, so it's done on the blocking thread pool.
+async fn copy(mut from: impl AsyncRead + Unpin, mut to: RotatingFile) {
+	let mut buf = vec![0; 8192];
+	loop {
+		match from.read(&mut buf).await {
+			Ok(0) => break,
+			Ok(n) => {
+				let written = spawn_blocking(move || {
+					let res = to.write(&buf[..n]);
+					(to, buf, res)
+				});
+				match written.await {
+					Ok((file, data, Ok(()))) => {
+						to = file;
+						buf = data;
+					}
+					Ok((file, _, Err(err))) => {
+						warn!(?err, path=?file.path(), "error writing output, stopping");
+						return;
+					}
+					Err(err) => {
+						warn!(?err, "output writer failed, stopping");
+						return;
+					}
+				}
+			}
+			Err(err) => {
+				warn!(?err, "error reading output, stopping");
+				break;
+			}
+		}
+	}
+
+	match spawn_blocking(move || to.file.flush().map_err(|err| (err, to.path()))).await {
+		Ok(Ok(())) => {}
+		Ok(Err((err, path))) => warn!(?err, ?path, "error flushing output"),
+		Err(err) => warn!(?err, "output writer failed"),
+	}
+}
+
+/// A file which is rotated by size, see [`OutputSink::Rotating`].
+#[derive(Debug)]
+pub(crate) struct RotatingFile {
+	dir: PathBuf,
+	name: &'static str,
+	max_size: u64,
+	keep: usize,
+	file: File,
+	size: u64,
+}
+
+impl RotatingFile {
+	pub fn open(dir: &Path, name: &'static str, max_size: u64, keep: usize) -> Result<Self> {
+		let file = append(&dir.join(name))?;
+		let size = file.metadata()?.len();
+		Ok(Self {
+			dir: dir.to_owned(),
+			name,
+			max_size,
+			keep,
+			file,
+			size,
+		})
+	}
+
+	fn path(&self) -> PathBuf {
+		self.dir.join(self.name)
+	}
+
+	fn rotated(&self, n: usize) -> PathBuf {
+		self.dir.join(format!("{}.{n}", self.name))
+	}
+
+	pub fn write(&mut self, data: &[u8]) -> Result<()> {
+		if self.size > 0 && self.size + data.len() as u64 > self.max_size {
+			self.rotate()?;
+		}
+
+		self.file.write_all(data)?;
+		self.size += data.len() as u64;
+		Ok(())
+	}
+
+	fn rotate(&mut self) -> Result<()> {
+		trace!(path=?self.path(), size=%self.size, "rotating output file");
+		self.file.flush()?;
+
+		if self.keep == 0 {
+			fs::remove_file(self.path())?;
+		} else {
+			for n in (1..self.keep).rev() {
+				let from = self.rotated(n);
+				if from.exists() {
+					fs::rename(from, self.rotated(n + 1))?;
+				}
+			}
+			fs::rename(self.path(), self.rotated(1))?;
+		}
+
+		// files past `keep` from a previous run with a higher `keep`
+		let mut n = self.keep + 1;
+		while fs::remove_file(self.rotated(n)).is_ok() {
+			n += 1;
+		}
+
+		self.file = append(&self.path())?;
+		self.size = 0;
+		Ok(())
+	}
+}
+
+/// Child wrapper which waits for the output to be written out after the process exits.
+///
+/// Everything else is passed through to the inner wrapper.
+#[derive(Debug)]
+struct SinksChild {
+	inner: Box<dyn TokioChildWrapper>,
+	copies: Vec<JoinHandle<()>>,
+}
+
+impl TokioChildWrapper for SinksChild {
+	fn inner(&self) -> &Child {
+		self.inner.inner()
+	}
+
+	fn inner_mut(&mut self) -> &mut Child {
+		self.inner.inner_mut()
+	}
+
+	fn into_inner(self: Box<Self>) -> Child {
+		self.inner.into_inner()
+	}
+
+	fn id(&self) -> Option<u32> {
+		self.inner.id()
+	}
+
+	fn start_kill(&mut self) -> Result<()> {
+		self.inner.start_kill()
+	}
+
+	fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+		self.inner.try_wait()
+	}
+
+	fn wait(&mut self) -> Box<dyn Future<Output = Result<ExitStatus>> + Send + '_> {
+		Box::new(async move {
+			let status = Pin::from(self.inner.wait()).await?;
+			for mut copy in self.copies.drain(..) {
+				if timeout(FLUSH_TIMEOUT, &mut copy).await.is_err() {
+					trace!("output still open after exit, leaving it to copy in the background");
+				}
+			}
+			Ok(status)
+		})
+	}
+
+	#[cfg(unix)]
+	fn signal(&self, sig: i32) -> Result<()> {
+		self.inner.signal(sig)
+	}
+}
@@ -1,7 +1,9 @@
 //! Error types.
 
 use std::{
+	fmt,
 	io::Error,
+	path::PathBuf,
 	sync::{Arc, OnceLock},
 };
 
@@ -15,3 +17,52 @@ pub fn sync_io_error(err: Error) -> SyncIoError {
 	lock.set(err).expect("unreachable: lock was just created");
 	Arc::new(lock)
 }
+
+/// A problem with a command, found before spawning it.
+///
+/// When spawning fails because of one of these, the [`std::io::Error`] it fails with wraps it, and
+/// it can be had with [`get_ref()`](Error::get_ref) and `downcast_ref::<CommandError>()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CommandError {
+	/// The program doesn't exist.
+	///
+	/// For a shell command, this is the shell program. `searched_paths` are the directories that
+	/// were searched, in order; it's empty if the program was given with a directory part.
+	NotFound {
+		/// The program, as given.
+		program: PathBuf,
+
+		/// The directories it was looked for in.
+		searched_paths: Vec<PathBuf>,
+	},
+}
+
+impl fmt::Display for CommandError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::NotFound {
+				program,
+				searched_paths,
+			} if searched_paths.is_empty() => {
+				write!(f, "program not found: {}", program.display())
+			}
+			Self::NotFound {
+				program,
+				searched_paths,
+			} => {
+				write!(
+					f,
+					"program not found: {} (searched in: {})",
+					program.display(),
+					searched_paths
+						.iter()
+						.map(|path| path.display().to_string())
+						.collect::<Vec<_>>()
+						.join(", ")
+				)
+			}
+		}
+	}
+}
+
+impl std::error::Error for CommandError {}
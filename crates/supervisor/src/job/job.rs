@@ -1,8 +1,14 @@
 #![allow(clippy::must_use_candidate)] // Ticket-returning methods are supposed to be used without awaiting
 
-use std::{future::Future, sync::Arc, time::Duration};
+use std::{
+	future::Future,
+	sync::{Arc, RwLock},
+	time::Duration,
+};
 
 use process_wrap::tokio::TokioCommandWrap;
+use tokio::sync::oneshot;
+use watchexec_events::ProcessEnd;
 use watchexec_signals::Signal;
 
 use crate::{command::Command, errors::SyncIoError, flag::Flag};
@@ -36,7 +42,7 @@ use super::{
 /// drops, the command will be dropped in turn, and forcefully terminated via `kill_on_drop`.
 #[derive(Debug, Clone)]
 pub struct Job {
-	pub(crate) command: Arc<Command>,
+	pub(crate) command: Arc<RwLock<Arc<Command>>>,
 	pub(crate) control_queue: PrioritySender,
 
 	/// Set to true when the command task has stopped gracefully.
@@ -45,8 +51,11 @@ pub struct Job {
 
 impl Job {
 	/// The [`Command`] this job is managing.
+	///
+	/// After [`set_command()`](Self::set_command()), this is the new command once the control has
+	/// been processed.
 	pub fn command(&self) -> Arc<Command> {
-		self.command.clone()
+		self.command.read().expect("command lock poisoned").clone()
 	}
 
 	/// If this job is dead.
@@ -196,6 +205,62 @@ impl Job {
 		self.send_controls([Control::Stop, Control::Delete], Priority::Urgent)
 	}
 
+	/// Stop the command within a bounded time, then mark it for garbage collection.
+	///
+	/// The command is sent [`Signal::Terminate`] and given `timeout` to exit. If it's still running
+	/// after that, it's sent [`Signal::ForceStop`] and the job is deleted without waiting for the
+	/// process to be reaped. In that case, the final [`CommandState`](super::CommandState) is
+	/// `Finished` with a [`ProcessEnd::ExitSignal(Signal::ForceStop)`](watchexec_events::ProcessEnd)
+	/// status, and `finished` set to the time the timeout was reached.
+	///
+	/// The underlying control message is sent with urgent priority, like `delete_now()`.
+	///
+	/// The returned future resolves when the job is gone or once `timeout` has elapsed, whichever
+	/// comes first. It resolves to `true` if the command exited (or wasn't running) before the
+	/// timeout, and `false` otherwise. The control is sent immediately, so the cleanup will happen
+	/// even if the future is dropped.
+	///
+	/// On Windows, the command is forcefully stopped immediately, as signals aren't supported.
+	pub fn delete_with_timeout(&self, timeout: Duration) -> impl Future<Output = bool> {
+		let ticket = self.send_controls(
+			[Control::GracefulDelete {
+				signal: if cfg!(unix) {
+					Signal::Terminate
+				} else {
+					Signal::ForceStop
+				},
+				grace: timeout,
+			}],
+			Priority::Urgent,
+		);
+
+		async move { tokio::time::timeout(timeout, ticket).await.is_ok() }
+	}
+
+	/// Stop supervising the command, but leave it running.
+	///
+	/// If the command is running, the job lets go of the process without signalling it, stops
+	/// waiting on it, and then stops: controls queued after this one are cancelled, and the job
+	/// becomes dead. This is useful to hand off a started process, like a server, to something
+	/// else. If the process was spawned in a process group or session, the group or session is
+	/// left intact.
+	///
+	/// The returned future resolves to the process ID of the detached process, if available. The
+	/// control is sent immediately, so the detach will happen even if the future is dropped.
+	///
+	/// If the command isn't running, like if it has already finished, this is a no-op: the job
+	/// keeps going, and the future resolves to `None`.
+	///
+	/// The process remains a child of the current process, so on Unix, whatever adopts it should
+	/// reap it once it exits (with `waitpid()`), or it will linger as a zombie until the current
+	/// process exits. On Windows, the process remains in its job object, if it was spawned in one,
+	/// and is terminated when the current process exits.
+	pub fn detach(&self) -> impl Future<Output = Option<u32>> {
+		let (sender, receiver) = oneshot::channel();
+		self.control(Control::Detach(sender));
+		async move { receiver.await.ok().flatten() }
+	}
+
 	/// Get a future which resolves when the command ends.
 	///
 	/// If the command is not running, the future resolves immediately.
@@ -245,7 +310,7 @@ impl Job {
 	/// # use watchexec_supervisor::command::{Command, Program};
 	/// # use watchexec_supervisor::job::{CommandState, start_job};
 	/// #
-	/// # let (job, _task) = start_job(Arc::new(Command { program: Program::Exec { prog: "/bin/date".into(), args: Vec::new() }.into(), options: Default::default() }));
+	/// # let (job, _task) = start_job(Arc::new(Command { program: Program::Exec { prog: "/bin/date".into(), args: Vec::new(), argv0: None }.into(), options: Default::default() }));
 	/// let (channel, receiver) = mpsc::channel(10);
 	/// job.run_async(|context| Box::new(async move {
 	///     if let CommandState::Finished { status, .. } = context.current {
@@ -262,7 +327,7 @@ impl Job {
 	/// # use watchexec_supervisor::command::{Command, Program};
 	/// # use watchexec_supervisor::job::{CommandState, start_job};
 	/// #
-	/// # let (job, _task) = start_job(Arc::new(Command { program: Program::Exec { prog: "/bin/date".into(), args: Vec::new() }.into(), options: Default::default() }));
+	/// # let (job, _task) = start_job(Arc::new(Command { program: Program::Exec { prog: "/bin/date".into(), args: Vec::new(), argv0: None }.into(), options: Default::default() }));
 	/// let (channel, receiver) = mpsc::channel(10);
 	/// job.run_async(|context| {
 	///     let status = if let CommandState::Finished { status, .. } = context.current {
@@ -288,6 +353,16 @@ impl Job {
 		self.control(Control::AsyncFunc(Box::new(fun)))
 	}
 
+	/// Replace the command this job manages.
+	///
+	/// The new command is used the next time the job starts or restarts its program. A process
+	/// that's currently running is not affected: it keeps running until it's stopped or restarted
+	/// as usual. As this goes through the control queue, it's ordered with the other controls, so
+	/// e.g. `set_command()` then `restart()` always restarts with the new command.
+	pub fn set_command(&self, command: Arc<Command>) -> Ticket {
+		self.control(Control::SetCommand(command))
+	}
+
 	/// Set the spawn hook.
 	///
 	/// The hook will be called once per process spawned, before the process is spawned. It's given
@@ -331,6 +406,77 @@ impl Job {
 		self.control(Control::UnsetSpawnHook)
 	}
 
+	/// Set the pre-spawn hook.
+	///
+	/// The hook will be called once per process spawned, before the spawn hook and before the
+	/// process is spawned. It's given some context, and the job waits for the future it returns
+	/// before continuing, so it can be used to wait for some resource to become available.
+	///
+	/// If the future resolves to an error, the process is not spawned, and the error is given to the
+	/// [error handler](Job::set_error_handler).
+	///
+	/// As with [`set_spawn_async_hook`](Job::set_spawn_async_hook), anything needed from the
+	/// context must be cloned or copied outside of the returned future.
+	pub fn set_pre_spawn_hook(
+		&self,
+		fun: impl (Fn(&JobTaskContext<'_>) -> Box<dyn Future<Output = std::io::Result<()>> + Send + Sync>)
+			+ Send
+			+ Sync
+			+ 'static,
+	) -> Ticket {
+		self.control(Control::SetPreSpawnHook(Arc::new(fun)))
+	}
+
+	/// Unset any pre-spawn hook.
+	pub fn unset_pre_spawn_hook(&self) -> Ticket {
+		self.control(Control::UnsetPreSpawnHook)
+	}
+
+	/// Set the post-spawn hook.
+	///
+	/// The hook will be called once per process spawned, right after the process is spawned. It's
+	/// given some context and the process ID of the new process, if available. The job waits for
+	/// the future it returns before continuing.
+	pub fn set_post_spawn_hook(
+		&self,
+		fun: impl (Fn(&JobTaskContext<'_>, Option<u32>) -> Box<dyn Future<Output = ()> + Send + Sync>)
+			+ Send
+			+ Sync
+			+ 'static,
+	) -> Ticket {
+		self.control(Control::SetPostSpawnHook(Arc::new(fun)))
+	}
+
+	/// Unset any post-spawn hook.
+	pub fn unset_post_spawn_hook(&self) -> Ticket {
+		self.control(Control::UnsetPostSpawnHook)
+	}
+
+	/// Set the completion hook.
+	///
+	/// The hook will be called every time the job reaps its process, with how the process ended and
+	/// the command it was spawned from, even if the job's command was changed since with
+	/// [`set_command()`](Job::set_command). It's called from the job task as soon as the wait on
+	/// the process resolves, including when the process is killed by [`stop()`](Job::stop) or a
+	/// restart, and before anything else is done about the ending: before
+	/// [`to_wait()`](Job::to_wait) tickets resolve, and before the command is started again by a
+	/// restart. As it runs in the job task, it should return quickly.
+	///
+	/// It's not called when the job lets go of the process without waiting on it: when it's
+	/// [detached](Job::detach), or when a [graceful delete](Job::delete_with_timeout) runs out of
+	/// time. Setting the hook replaces any previous one.
+	pub fn on_completion(
+		&self,
+		fun: impl Fn(ProcessEnd, &Command) + Send + Sync + 'static,
+	) -> Ticket {
+		self.control(Control::SetCompletionHook(Arc::new(fun)))
+	}
+
+	/// Unset any completion hook.
+	pub fn unset_completion_hook(&self) -> Ticket {
+		self.control(Control::UnsetCompletionHook)
+	}
+
 	/// Set the error handler.
 	pub fn set_error_handler(&self, fun: impl Fn(SyncIoError) + Send + Sync + 'static) -> Ticket {
 		self.control(Control::SetSyncErrorHandler(Arc::new(fun)))
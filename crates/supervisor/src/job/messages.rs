@@ -1,17 +1,20 @@
 use std::{
 	future::Future,
 	pin::Pin,
+	sync::Arc,
 	task::{Context, Poll},
 	time::Duration,
 };
 
 use futures::{future::select, FutureExt};
+use tokio::sync::oneshot;
 use watchexec_signals::Signal;
 
-use crate::flag::Flag;
+use crate::{command::Command, flag::Flag};
 
 use super::task::{
-	AsyncErrorHandler, AsyncFunc, AsyncSpawnHook, SyncErrorHandler, SyncFunc, SyncSpawnHook,
+	AsyncErrorHandler, AsyncFunc, AsyncSpawnHook, CompletionHook, PostSpawnHook, PreSpawnHook,
+	SyncErrorHandler, SyncFunc, SyncSpawnHook,
 };
 
 /// The underlying control message types for [`Job`](super::Job).
@@ -45,6 +48,21 @@ pub enum Control {
 	Signal(Signal),
 	/// For [`Job::delete()`](super::Job::delete()) and [`Job::delete_now()`](super::Job::delete_now()).
 	Delete,
+	/// For [`Job::delete_with_timeout()`](super::Job::delete_with_timeout()).
+	GracefulDelete {
+		/// Signal to send immediately
+		signal: Signal,
+		/// Time to wait before forceful termination and deletion
+		grace: Duration,
+	},
+	/// Internal implementation detail of [`Control::GracefulDelete`].
+	ContinueGracefulDelete,
+	/// For [`Job::detach()`](super::Job::detach()).
+	///
+	/// The sender is given the process ID of the detached process, if any.
+	Detach(oneshot::Sender<Option<u32>>),
+	/// For [`Job::set_command()`](super::Job::set_command()).
+	SetCommand(Arc<Command>),
 
 	/// For [`Job::to_wait()`](super::Job::to_wait()).
 	NextEnding,
@@ -60,6 +78,18 @@ pub enum Control {
 	SetAsyncSpawnHook(AsyncSpawnHook),
 	/// For [`Job::unset_spawn_hook()`](super::Job::unset_spawn_hook()).
 	UnsetSpawnHook,
+	/// For [`Job::set_pre_spawn_hook()`](super::Job::set_pre_spawn_hook()).
+	SetPreSpawnHook(PreSpawnHook),
+	/// For [`Job::unset_pre_spawn_hook()`](super::Job::unset_pre_spawn_hook()).
+	UnsetPreSpawnHook,
+	/// For [`Job::set_post_spawn_hook()`](super::Job::set_post_spawn_hook()).
+	SetPostSpawnHook(PostSpawnHook),
+	/// For [`Job::unset_post_spawn_hook()`](super::Job::unset_post_spawn_hook()).
+	UnsetPostSpawnHook,
+	/// For [`Job::on_completion()`](super::Job::on_completion()).
+	SetCompletionHook(CompletionHook),
+	/// For [`Job::unset_completion_hook()`](super::Job::unset_completion_hook()).
+	UnsetCompletionHook,
 	/// For [`Job::set_error_handler()`](super::Job::set_error_handler()).
 	SetSyncErrorHandler(SyncErrorHandler),
 	/// For [`Job::set_async_error_handler()`](super::Job::set_async_error_handler()).
@@ -89,6 +119,17 @@ impl std::fmt::Debug for Control {
 			}
 			Self::Signal(signal) => f.debug_struct("Signal").field("signal", signal).finish(),
 			Self::Delete => f.debug_struct("Delete").finish(),
+			Self::GracefulDelete { signal, grace } => f
+				.debug_struct("GracefulDelete")
+				.field("signal", signal)
+				.field("grace", grace)
+				.finish(),
+			Self::ContinueGracefulDelete => f.debug_struct("ContinueGracefulDelete").finish(),
+			Self::Detach(_) => f.debug_struct("Detach").finish_non_exhaustive(),
+			Self::SetCommand(command) => f
+				.debug_struct("SetCommand")
+				.field("command", command)
+				.finish(),
 
 			Self::NextEnding => f.debug_struct("NextEnding").finish(),
 
@@ -100,6 +141,14 @@ impl std::fmt::Debug for Control {
 				f.debug_struct("SetSpawnAsyncHook").finish_non_exhaustive()
 			}
 			Self::UnsetSpawnHook => f.debug_struct("UnsetSpawnHook").finish(),
+			Self::SetPreSpawnHook(_) => f.debug_struct("SetPreSpawnHook").finish_non_exhaustive(),
+			Self::UnsetPreSpawnHook => f.debug_struct("UnsetPreSpawnHook").finish(),
+			Self::SetPostSpawnHook(_) => f.debug_struct("SetPostSpawnHook").finish_non_exhaustive(),
+			Self::UnsetPostSpawnHook => f.debug_struct("UnsetPostSpawnHook").finish(),
+			Self::SetCompletionHook(_) => {
+				f.debug_struct("SetCompletionHook").finish_non_exhaustive()
+			}
+			Self::UnsetCompletionHook => f.debug_struct("UnsetCompletionHook").finish(),
 			Self::SetSyncErrorHandler(_) => f
 				.debug_struct("SetSyncErrorHandler")
 				.finish_non_exhaustive(),
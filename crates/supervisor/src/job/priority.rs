@@ -49,7 +49,8 @@ impl PriorityReceiver {
 	/// urgent priority messages are received until the timer expires, and when the timer completes,
 	/// a `Stop` control message is returned and the `stop_timer` is `None`d.
 	///
-	/// This is used to implement stop's, restart's, and try-restart's graceful stopping logic.
+	/// This is used to implement stop's, restart's, try-restart's, and delete's graceful stopping
+	/// logic.
 	pub async fn recv(&mut self, stop_timer: &mut Option<Timer>) -> Option<ControlMessage> {
 		if stop_timer.as_ref().map_or(false, Timer::is_past) {
 			return stop_timer.take().map(|timer| timer.to_control());
@@ -105,7 +106,14 @@ pub fn new() -> (PrioritySender, PriorityReceiver) {
 pub struct Timer {
 	pub until: Instant,
 	pub done: Flag,
-	pub is_restart: bool,
+	pub kind: TimerKind,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimerKind {
+	Stop,
+	Restart,
+	Delete,
 }
 
 impl Timer {
@@ -113,7 +121,7 @@ impl Timer {
 		Self {
 			until: Instant::now() + grace,
 			done,
-			is_restart: false,
+			kind: TimerKind::Stop,
 		}
 	}
 
@@ -121,7 +129,15 @@ impl Timer {
 		Self {
 			until: Instant::now() + grace,
 			done,
-			is_restart: true,
+			kind: TimerKind::Restart,
+		}
+	}
+
+	pub fn delete(grace: Duration, done: Flag) -> Self {
+		Self {
+			until: Instant::now() + grace,
+			done,
+			kind: TimerKind::Delete,
 		}
 	}
 
@@ -135,10 +151,10 @@ impl Timer {
 
 	fn to_control(&self) -> ControlMessage {
 		ControlMessage {
-			control: if self.is_restart {
-				Control::ContinueTryGracefulRestart
-			} else {
-				Control::Stop
+			control: match self.kind {
+				TimerKind::Stop => Control::Stop,
+				TimerKind::Restart => Control::ContinueTryGracefulRestart,
+				TimerKind::Delete => Control::ContinueGracefulDelete,
 			},
 			done: self.done.clone(),
 		}
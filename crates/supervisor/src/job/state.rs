@@ -4,7 +4,7 @@ use process_wrap::tokio::{TokioChildWrapper, TokioCommandWrap};
 use tracing::trace;
 use watchexec_events::ProcessEnd;
 
-use crate::command::Command;
+use crate::command::{Command, SetPriority};
 
 /// The state of the job's command / process.
 ///
@@ -82,11 +82,17 @@ impl CommandState {
 
 		trace!(?command, "spawning command");
 
+		let priority = command.options.priority;
+		let spawn_error = |err| match priority {
+			Some(priority) => SetPriority(priority).spawn_error(err),
+			None => err,
+		};
+
 		#[cfg(test)]
-		let child = super::TestChild::new(command)?;
+		let child = super::TestChild::new(command).map_err(spawn_error)?;
 
 		#[cfg(not(test))]
-		let child = spawnable.spawn()?;
+		let child = spawnable.spawn().map_err(spawn_error)?;
 
 		*self = Self::Running {
 			child,
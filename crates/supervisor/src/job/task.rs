@@ -1,15 +1,22 @@
-use std::{future::Future, mem::take, sync::Arc, time::Instant};
+use std::{
+	future::Future,
+	mem::{forget, replace, take},
+	pin::Pin,
+	sync::{Arc, RwLock},
+	time::Instant,
+};
 
 use process_wrap::tokio::TokioCommandWrap;
 use tokio::{select, task::JoinHandle};
 use tracing::{instrument, trace, trace_span, Instrument};
+use watchexec_events::ProcessEnd;
 use watchexec_signals::Signal;
 
 use crate::{
 	command::Command,
 	errors::{sync_io_error, SyncIoError},
 	flag::Flag,
-	job::priority::Timer,
+	job::priority::{Timer, TimerKind},
 };
 
 use super::{
@@ -34,16 +41,23 @@ pub fn start_job(command: Arc<Command>) -> (Job, JoinHandle<()>) {
 	let (sender, mut receiver) = priority::new();
 	let gone = Flag::default();
 	let done = gone.clone();
+	let shared_command = Arc::new(RwLock::new(command.clone()));
 
 	(
 		Job {
-			command: command.clone(),
+			command: shared_command.clone(),
 			control_queue: sender,
 			gone,
 		},
 		tokio::spawn(async move {
+			let mut command = command;
+			// the command the current or last process was spawned from, for the completion hook
+			let mut spawned_command = command.clone();
 			let mut error_handler = ErrorHandler::None;
 			let mut spawn_hook = SpawnHook::None;
+			let mut pre_spawn_hook: Option<PreSpawnHook> = None;
+			let mut post_spawn_hook: Option<PostSpawnHook> = None;
+			let mut completion_hook: Option<CompletionHook> = None;
 			let mut command_state = CommandState::Pending;
 			let mut previous_run = None;
 			let mut stop_timer = None;
@@ -64,29 +78,34 @@ pub fn start_job(command: Arc<Command>) -> (Job, JoinHandle<()>) {
 									return Loop::Skip;
 								}
 								Ok(true) => {
+									call_completion_hook(completion_hook.as_ref(), &command_state, &spawned_command);
+
 									trace!(existing=?stop_timer, "erasing stop timer");
-									stop_timer = None;
+									let timer = stop_timer.take();
 									trace!(count=%on_end.len(), "raising all pending end flags");
 									for done in take(&mut on_end) {
 										done.raise();
 									}
 
+									if let Some(Timer { done, kind: TimerKind::Delete, .. }) = timer {
+										trace!("child ended within graceful delete, raising done flag");
+										done.raise();
+										return Loop::Break;
+									}
+
 									if let Some(flag) = on_end_restart.take() {
 										trace!("continuing a graceful restart");
 
-										let mut spawnable = command.to_spawnable();
 										previous_run = Some(command_state.reset());
-										spawn_hook
-											.call(
-												&mut spawnable,
-												&JobTaskContext {
-													command: command.clone(),
-													current: &command_state,
-													previous: previous_run.as_ref(),
-												},
-											)
-											.await;
-										if let Err(err) = command_state.spawn(command.clone(), spawnable) {
+										if let Err(err) = spawn_with_hooks(
+											&command,
+											&mut command_state,
+											&mut spawned_command,
+											previous_run.as_ref(),
+											&spawn_hook,
+											pre_spawn_hook.as_ref(),
+											post_spawn_hook.as_ref(),
+										).await {
 											let fut = error_handler.call(sync_io_error(err));
 											fut.await;
 											return Loop::Skip;
@@ -139,19 +158,16 @@ pub fn start_job(command: Arc<Command>) -> (Job, JoinHandle<()>) {
 									if command_state.is_running() {
 										trace!("child is running, skip");
 									} else {
-										let mut spawnable = command.to_spawnable();
 										previous_run = Some(command_state.reset());
-										spawn_hook
-											.call(
-												&mut spawnable,
-												&JobTaskContext {
-													command: command.clone(),
-													current: &command_state,
-													previous: previous_run.as_ref(),
-												},
-											)
-											.await;
-										try_with_handler!(command_state.spawn(command.clone(), spawnable));
+										try_with_handler!(spawn_with_hooks(
+											&command,
+											&mut command_state,
+											&mut spawned_command,
+											previous_run.as_ref(),
+											&spawn_hook,
+											pre_spawn_hook.as_ref(),
+											post_spawn_hook.as_ref(),
+										).await);
 									}
 								}
 								Control::Stop => {
@@ -167,6 +183,7 @@ pub fn start_job(command: Arc<Command>) -> (Job, JoinHandle<()>) {
 											started: *started,
 											finished: Instant::now(),
 										};
+										call_completion_hook(completion_hook.as_ref(), &command_state, &spawned_command);
 
 										trace!(count=%on_end.len(), "raising all pending end flags");
 										for done in take(&mut on_end) {
@@ -199,6 +216,7 @@ pub fn start_job(command: Arc<Command>) -> (Job, JoinHandle<()>) {
 											started: *started,
 											finished: Instant::now(),
 										};
+										call_completion_hook(completion_hook.as_ref(), &command_state, &spawned_command);
 										previous_run = Some(command_state.reset());
 
 										trace!(count=%on_end.len(), "raising all pending end flags");
@@ -206,18 +224,15 @@ pub fn start_job(command: Arc<Command>) -> (Job, JoinHandle<()>) {
 											done.raise();
 										}
 
-										let mut spawnable = command.to_spawnable();
-										spawn_hook
-											.call(
-												&mut spawnable,
-												&JobTaskContext {
-													command: command.clone(),
-													current: &command_state,
-													previous: previous_run.as_ref(),
-												},
-											)
-											.await;
-										try_with_handler!(command_state.spawn(command.clone(), spawnable));
+										try_with_handler!(spawn_with_hooks(
+											&command,
+											&mut command_state,
+											&mut spawned_command,
+											previous_run.as_ref(),
+											&spawn_hook,
+											pre_spawn_hook.as_ref(),
+											post_spawn_hook.as_ref(),
+										).await);
 									} else {
 										trace!("child isn't running, skip");
 									}
@@ -249,6 +264,7 @@ pub fn start_job(command: Arc<Command>) -> (Job, JoinHandle<()>) {
 											started: *started,
 											finished: Instant::now(),
 										};
+										call_completion_hook(completion_hook.as_ref(), &command_state, &spawned_command);
 
 										trace!(count=%on_end.len(), "raising all pending end flags");
 										for done in take(&mut on_end) {
@@ -256,19 +272,16 @@ pub fn start_job(command: Arc<Command>) -> (Job, JoinHandle<()>) {
 										}
 									}
 
-									let mut spawnable = command.to_spawnable();
 									previous_run = Some(command_state.reset());
-									spawn_hook
-										.call(
-											&mut spawnable,
-											&JobTaskContext {
-												command: command.clone(),
-												current: &command_state,
-												previous: previous_run.as_ref(),
-											},
-										)
-										.await;
-									try_with_handler!(command_state.spawn(command.clone(), spawnable));
+									try_with_handler!(spawn_with_hooks(
+										&command,
+										&mut command_state,
+										&mut spawned_command,
+										previous_run.as_ref(),
+										&spawn_hook,
+										pre_spawn_hook.as_ref(),
+										post_spawn_hook.as_ref(),
+									).await);
 								}
 								Control::Signal(signal) => {
 									if let CommandState::Running { child, .. } = &mut command_state {
@@ -282,6 +295,71 @@ pub fn start_job(command: Arc<Command>) -> (Job, JoinHandle<()>) {
 									done.raise();
 									return Loop::Break;
 								}
+								Control::GracefulDelete { signal, grace } => {
+									if let CommandState::Running { child, .. } = &mut command_state {
+										try_with_handler!(signal_child(signal, child).await);
+
+										trace!(?grace, "setting up graceful delete timer");
+										stop_timer.replace(Timer::delete(grace, done));
+										return Loop::Skip;
+									}
+
+									trace!("child isn't running, raising done flag immediately");
+									done.raise();
+									return Loop::Break;
+								}
+								Control::ContinueGracefulDelete => {
+									if let CommandState::Running { child, started } = &mut command_state {
+										trace!("grace period elapsed, stopping child without waiting");
+										if let Err(err) = signal_child(Signal::ForceStop, child).await {
+											let fut = error_handler.call(sync_io_error(err));
+											fut.await;
+										}
+
+										command_state = CommandState::Finished {
+											status: ProcessEnd::ExitSignal(Signal::ForceStop),
+											started: *started,
+											finished: Instant::now(),
+										};
+
+										trace!(count=%on_end.len(), "raising all pending end flags");
+										for done in take(&mut on_end) {
+											done.raise();
+										}
+									}
+
+									trace!("raising done flag");
+									done.raise();
+									return Loop::Break;
+								}
+								Control::Detach(pid) => {
+									let CommandState::Running { child, .. } = &mut command_state else {
+										trace!("child isn't running, skip");
+										pid.send(None).ok();
+										done.raise();
+										return Loop::Normally;
+									};
+
+									let id = child.id();
+									trace!(?id, "detaching child");
+									// dropping the child would kill it (kill_on_drop)
+									forget(replace(&mut command_state, CommandState::Pending));
+									pid.send(id).ok();
+
+									trace!(count=%on_end.len(), "raising all pending end flags");
+									for done in take(&mut on_end) {
+										done.raise();
+									}
+
+									trace!("raising done flag");
+									done.raise();
+									return Loop::Break;
+								}
+								Control::SetCommand(new) => {
+									trace!(command=?new, "replacing command for the next start");
+									command = new;
+									*shared_command.write().expect("command lock poisoned") = command.clone();
+								}
 
 								Control::NextEnding => {
 									if matches!(command_state, CommandState::Finished { .. }) {
@@ -334,6 +412,30 @@ pub fn start_job(command: Arc<Command>) -> (Job, JoinHandle<()>) {
 									trace!("unsetting spawn hook");
 									spawn_hook = SpawnHook::None;
 								}
+								Control::SetPreSpawnHook(f) => {
+									trace!("setting pre-spawn hook");
+									pre_spawn_hook = Some(f);
+								}
+								Control::UnsetPreSpawnHook => {
+									trace!("unsetting pre-spawn hook");
+									pre_spawn_hook = None;
+								}
+								Control::SetPostSpawnHook(f) => {
+									trace!("setting post-spawn hook");
+									post_spawn_hook = Some(f);
+								}
+								Control::UnsetPostSpawnHook => {
+									trace!("unsetting post-spawn hook");
+									post_spawn_hook = None;
+								}
+								Control::SetCompletionHook(f) => {
+									trace!("setting completion hook");
+									completion_hook = Some(f);
+								}
+								Control::UnsetCompletionHook => {
+									trace!("unsetting completion hook");
+									completion_hook = None;
+								}
 							}
 
 							trace!("raising control done flag");
@@ -422,6 +524,21 @@ pub type AsyncSpawnHook = Arc<
 
 sync_async_callbox!(SpawnHook, SyncSpawnHook, AsyncSpawnHook, (command: &mut TokioCommandWrap, context: &JobTaskContext<'_>));
 
+pub type PreSpawnHook = Arc<
+	dyn (Fn(&JobTaskContext<'_>) -> Box<dyn Future<Output = std::io::Result<()>> + Send + Sync>)
+		+ Send
+		+ Sync
+		+ 'static,
+>;
+pub type PostSpawnHook = Arc<
+	dyn (Fn(&JobTaskContext<'_>, Option<u32>) -> Box<dyn Future<Output = ()> + Send + Sync>)
+		+ Send
+		+ Sync
+		+ 'static,
+>;
+
+pub type CompletionHook = Arc<dyn Fn(ProcessEnd, &Command) + Send + Sync + 'static>;
+
 pub type SyncErrorHandler = Arc<dyn Fn(SyncIoError) + Send + Sync + 'static>;
 pub type AsyncErrorHandler = Arc<
 	dyn (Fn(SyncIoError) -> Box<dyn Future<Output = ()> + Send + Sync>) + Send + Sync + 'static,
@@ -429,6 +546,85 @@ pub type AsyncErrorHandler = Arc<
 
 sync_async_callbox!(ErrorHandler, SyncErrorHandler, AsyncErrorHandler, (error: SyncIoError));
 
+/// Spawn the command, calling the hooks around it.
+///
+/// The pre-spawn hook is called first: if it returns an error, the command is not spawned and the
+/// error is returned. Then the spawn hook is called, the command is spawned and recorded as the
+/// `spawned_command`, and finally the post-spawn hook is called with the process ID of the new
+/// child, if available.
+#[instrument(
+	level = "trace",
+	skip(spawned_command, spawn_hook, pre_spawn_hook, post_spawn_hook)
+)]
+async fn spawn_with_hooks(
+	command: &Arc<Command>,
+	command_state: &mut CommandState,
+	spawned_command: &mut Arc<Command>,
+	previous: Option<&CommandState>,
+	spawn_hook: &SpawnHook,
+	pre_spawn_hook: Option<&PreSpawnHook>,
+	post_spawn_hook: Option<&PostSpawnHook>,
+) -> std::io::Result<bool> {
+	if let Some(hook) = pre_spawn_hook {
+		trace!("calling pre-spawn hook");
+		Pin::from(hook(&JobTaskContext {
+			command: command.clone(),
+			current: command_state,
+			previous,
+		}))
+		.await?;
+	}
+
+	let mut spawnable = command.to_spawnable();
+	spawn_hook
+		.call(
+			&mut spawnable,
+			&JobTaskContext {
+				command: command.clone(),
+				current: command_state,
+				previous,
+			},
+		)
+		.await;
+	if !command_state.spawn(command.clone(), spawnable)? {
+		return Ok(false);
+	}
+	*spawned_command = command.clone();
+
+	if let Some(hook) = post_spawn_hook {
+		let pid = if let CommandState::Running { child, .. } = command_state {
+			child.id()
+		} else {
+			None
+		};
+
+		trace!(?pid, "calling post-spawn hook");
+		Pin::from(hook(
+			&JobTaskContext {
+				command: command.clone(),
+				current: command_state,
+				previous,
+			},
+			pid,
+		))
+		.await;
+	}
+
+	Ok(true)
+}
+
+/// Call the completion hook, if any, with the status of a process which was just reaped.
+fn call_completion_hook(
+	hook: Option<&CompletionHook>,
+	command_state: &CommandState,
+	command: &Command,
+) {
+	if let (Some(hook), CommandState::Finished { status, .. }) = (hook, command_state) {
+		trace!(?status, "calling completion hook");
+		hook(*status, command);
+	}
+}
+
 #[cfg_attr(not(windows), allow(clippy::needless_pass_by_ref_mut))] // needed for start_kill()
 #[instrument(level = "trace")]
 async fn signal_child(
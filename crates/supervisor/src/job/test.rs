@@ -29,6 +29,7 @@ fn erroring_command() -> Arc<Command> {
 		program: Program::Exec {
 			prog: "/does/not/exist".into(),
 			args: Vec::new(),
+			argv0: None,
 		},
 		options: Default::default(),
 	})
@@ -39,6 +40,7 @@ fn working_command() -> Arc<Command> {
 		program: Program::Exec {
 			prog: "/does/not/run".into(),
 			args: Vec::new(),
+			argv0: None,
 		},
 		options: Default::default(),
 	})
@@ -49,6 +51,7 @@ fn ungraceful_command() -> Arc<Command> {
 		program: Program::Exec {
 			prog: "sleep".into(),
 			args: vec![(GRACE * 2).to_string()],
+			argv0: None,
 		},
 		options: Default::default(),
 	})
@@ -59,6 +62,7 @@ fn graceful_command() -> Arc<Command> {
 		program: Program::Exec {
 			prog: "sleep".into(),
 			args: vec![(2 * GRACE / 3).to_string()],
+			argv0: None,
 		},
 		options: Default::default(),
 	})
@@ -458,6 +462,45 @@ async fn restart() {
 	task.abort();
 }
 
+#[tokio::test]
+async fn set_command_applies_on_restart() {
+	let (job, task) = start_job(working_command());
+
+	job.start().await;
+	expect_state!(job, CommandState::Running { .. });
+
+	let new_command = Arc::new(Command {
+		program: Program::Exec {
+			prog: "/does/not/run/either".into(),
+			args: vec!["--new".into()],
+			argv0: None,
+		},
+		options: Default::default(),
+	});
+	job.set_command(new_command.clone()).await;
+	assert_eq!(
+		job.command(),
+		new_command,
+		"job should report the new command"
+	);
+	assert_eq!(
+		get_child(&job).await.command,
+		working_command(),
+		"running process shouldn't be affected"
+	);
+
+	set_running_child_status(&job, ProcessEnd::Success.into_exitstatus()).await;
+	job.restart().await;
+	expect_state!(job, CommandState::Running { .. });
+	assert_eq!(
+		get_child(&job).await.command,
+		new_command,
+		"restarted process should run the new command"
+	);
+
+	task.abort();
+}
+
 #[tokio::test]
 async fn graceful_stop() {
 	let (job, task) = start_job(working_command());
@@ -850,3 +893,161 @@ async fn try_graceful_restart_beyond_grace() {
 
 	task.abort();
 }
+
+#[tokio::test]
+async fn delete_with_timeout_within_grace() {
+	let (job, task) = start_job(graceful_command());
+
+	job.start().await;
+
+	expect_state!(job, CommandState::Running { .. });
+
+	assert!(
+		job.delete_with_timeout(Duration::from_millis(GRACE)).await,
+		"should have completed cleanly"
+	);
+
+	task.await.unwrap();
+	assert!(job.is_dead());
+}
+
+#[tokio::test]
+async fn delete_with_timeout_beyond_grace() {
+	let (job, task) = start_job(working_command());
+
+	job.start().await;
+
+	expect_state!(job, CommandState::Running { .. });
+
+	let calls = get_child(&job).await.calls;
+	let started = Instant::now();
+
+	assert!(
+		!job.delete_with_timeout(Duration::from_millis(GRACE)).await,
+		"should have timed out"
+	);
+
+	tokio::time::timeout(Duration::from_millis(GRACE * 5), task)
+		.await
+		.expect("job should be deleted shortly after the timeout")
+		.unwrap();
+	assert!(job.is_dead());
+	assert!(started.elapsed() < Duration::from_millis(GRACE * 5));
+
+	#[cfg(unix)]
+	{
+		use nix::sys::signal::Signal;
+		assert!(calls.iter().any(|(_, call)| matches!(
+			call,
+			TestChildCall::Signal(sig) if *sig == Signal::SIGTERM as i32
+		)));
+		assert!(calls.iter().any(|(_, call)| matches!(
+			call,
+			TestChildCall::Signal(sig) if *sig == Signal::SIGKILL as i32
+		)));
+	}
+	#[cfg(windows)]
+	assert!(calls
+		.iter()
+		.any(|(_, call)| matches!(call, TestChildCall::StartKill)));
+}
+
+#[tokio::test]
+async fn pre_spawn_hook_error_aborts_spawn() {
+	let (job, task) = start_job(working_command());
+	let error_handler_called = Arc::new(AtomicBool::new(false));
+
+	job.set_error_handler({
+		let error_handler_called = error_handler_called.clone();
+		move |_| {
+			error_handler_called.store(true, Ordering::Relaxed);
+		}
+	});
+	job.set_pre_spawn_hook(|context| {
+		assert!(context.current.is_pending());
+		Box::new(async { Err(std::io::Error::new(std::io::ErrorKind::Other, "nope")) })
+	});
+
+	job.start().await;
+
+	expect_state!(job, CommandState::Pending);
+	assert!(
+		error_handler_called.load(Ordering::Relaxed),
+		"error should be handled"
+	);
+
+	job.unset_pre_spawn_hook();
+	job.start().await;
+
+	expect_state!(job, CommandState::Running { .. });
+
+	task.abort();
+}
+
+#[tokio::test]
+async fn post_spawn_hook() {
+	let (job, task) = start_job(working_command());
+	let post_spawn_called = Arc::new(AtomicBool::new(false));
+
+	job.set_post_spawn_hook({
+		let post_spawn_called = post_spawn_called.clone();
+		move |context, _pid| {
+			assert!(context.current.is_running());
+			let post_spawn_called = post_spawn_called.clone();
+			Box::new(async move {
+				post_spawn_called.store(true, Ordering::Relaxed);
+			})
+		}
+	});
+
+	job.start().await;
+
+	assert!(post_spawn_called.load(Ordering::Relaxed), "called on start");
+
+	task.abort();
+}
+
+#[tokio::test]
+async fn detach_running() {
+	let (job, task) = start_job(working_command());
+
+	job.start().await;
+
+	expect_state!(job, CommandState::Running { .. });
+
+	#[cfg(unix)]
+	let calls = get_child(&job).await.calls;
+	let wait = job.to_wait();
+	job.detach().await;
+
+	task.await.unwrap();
+	assert!(job.is_dead());
+	wait.await;
+
+	#[cfg(unix)]
+	assert!(
+		!calls.iter().any(|(_, call)| matches!(
+			call,
+			TestChildCall::Kill | TestChildCall::StartKill | TestChildCall::Signal(_)
+		)),
+		"should not have stopped or signalled the child"
+	);
+}
+
+#[tokio::test]
+async fn detach_finished_is_noop() {
+	let (job, task) = start_job(graceful_command());
+
+	job.start().await;
+	sleep(Duration::from_millis(GRACE * 2)).await;
+
+	expect_state!(job, CommandState::Finished { .. });
+
+	assert_eq!(job.detach().await, None);
+	assert!(!job.is_dead());
+
+	job.start().await;
+	expect_state!(job, CommandState::Running { .. });
+
+	task.abort();
+}
@@ -34,7 +34,7 @@ impl TestChild {
 		}
 
 		Ok(Self {
-			grouped: command.options.grouped || command.options.session,
+			grouped: command.options.grouped || command.options.session || command.options.detached,
 			command,
 			calls: Arc::new(boxcar::Vec::new()),
 			output: Arc::new(Mutex::new(None)),
@@ -74,7 +74,7 @@ impl TestChild {
 	pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
 		self.calls.push(TestChildCall::TryWait);
 
-		if let Program::Exec { prog, args } = &self.command.program {
+		if let Program::Exec { prog, args, .. } = &self.command.program {
 			if prog == Path::new("sleep") {
 				if let Some(time) = args
 					.get(0)
@@ -98,7 +98,7 @@ impl TestChild {
 	pub fn wait(&mut self) -> Box<dyn Future<Output = Result<ExitStatus>> + Send + '_> {
 		self.calls.push(TestChildCall::Wait);
 		Box::new(async {
-			if let Program::Exec { prog, args } = &self.command.program {
+			if let Program::Exec { prog, args, .. } = &self.command.program {
 				if prog == Path::new("sleep") {
 					if let Some(time) = args
 						.get(0)
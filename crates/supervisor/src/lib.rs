@@ -48,7 +48,7 @@
 //! # use watchexec_supervisor::command::{Command, Program};
 //! # use watchexec_supervisor::job::{CommandState, start_job};
 //! #
-//! # let (job, task) = start_job(Arc::new(Command { program: Program::Exec { prog: "/bin/date".into(), args: Vec::new() }.into(), options: Default::default() }));
+//! # let (job, task) = start_job(Arc::new(Command { program: Program::Exec { prog: "/bin/date".into(), args: Vec::new(), argv0: None }.into(), options: Default::default() }));
 //! #
 //! job.start().await;
 //! job.signal(Signal::User1).await;
@@ -66,7 +66,7 @@
 //! # use watchexec_supervisor::command::{Command, Program};
 //! # use watchexec_supervisor::job::{CommandState, start_job};
 //! #
-//! # let (job, task) = start_job(Arc::new(Command { program: Program::Exec { prog: "/bin/date".into(), args: Vec::new() }.into(), options: Default::default() }));
+//! # let (job, task) = start_job(Arc::new(Command { program: Program::Exec { prog: "/bin/date".into(), args: Vec::new(), argv0: None }.into(), options: Default::default() }));
 //! #
 //! job.start();
 //! job.signal(Signal::User1);
@@ -86,7 +86,7 @@
 //! # use watchexec_supervisor::command::{Command, Program};
 //! # use watchexec_supervisor::job::{CommandState, start_job};
 //! #
-//! # let (job, task) = start_job(Arc::new(Command { program: Program::Exec { prog: "/bin/date".into(), args: Vec::new() }.into(), options: Default::default() }));
+//! # let (job, task) = start_job(Arc::new(Command { program: Program::Exec { prog: "/bin/date".into(), args: Vec::new(), argv0: None }.into(), options: Default::default() }));
 //! #
 //! job.start().await;
 //! println!("program started!");
@@ -116,6 +116,7 @@
 //!     program: Program::Exec {
 //!         prog: "/bin/date".into(),
 //!         args: Vec::new(),
+//!         argv0: None,
 //!     }.into(),
 //!     options: Default::default(),
 //! }));
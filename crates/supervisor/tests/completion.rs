@@ -0,0 +1,106 @@
+#[cfg(unix)]
+use std::{num::NonZeroI64, sync::Arc};
+
+#[cfg(unix)]
+use tokio::sync::mpsc;
+#[cfg(unix)]
+use watchexec_events::ProcessEnd;
+#[cfg(unix)]
+use watchexec_signals::Signal;
+#[cfg(unix)]
+use watchexec_supervisor::{
+	command::{Command, Program, Shell, ShellInput},
+	job::start_job,
+};
+
+#[cfg(unix)]
+fn shell(command: &str) -> Arc<Command> {
+	Arc::new(Command {
+		program: Program::Shell {
+			shell: Shell::new("sh"),
+			command: command.into(),
+			args: Vec::new(),
+			via: ShellInput::Arg,
+		},
+		options: Default::default(),
+	})
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn completion_hook_gets_exit_status() {
+	let (ends_s, mut ends) = mpsc::unbounded_channel();
+	let (job, task) = start_job(shell("exit 3"));
+	job.on_completion(move |end, command| {
+		ends_s.send((end, command.to_string())).ok();
+	});
+
+	job.start().await;
+	job.to_wait().await;
+	assert_eq!(
+		ends.try_recv()
+			.expect("hook should have run before the wait ended"),
+		(
+			ProcessEnd::ExitError(NonZeroI64::new(3).unwrap()),
+			"exit 3".to_string()
+		)
+	);
+
+	job.start().await;
+	job.to_wait().await;
+	assert!(
+		ends.try_recv().is_ok(),
+		"hook should run again for the next run"
+	);
+
+	job.delete_now().await;
+	task.await.unwrap();
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn completion_hook_runs_on_stop() {
+	let (ends_s, mut ends) = mpsc::unbounded_channel();
+	let (job, task) = start_job(shell("sleep 10"));
+	job.on_completion(move |end, _| {
+		ends_s.send(end).ok();
+	});
+
+	job.start().await;
+	job.stop().await;
+	assert_eq!(
+		ends.try_recv()
+			.expect("hook should have run before the stop ended"),
+		ProcessEnd::ExitSignal(Signal::ForceStop)
+	);
+
+	job.delete_now().await;
+	task.await.unwrap();
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn completion_hook_gets_the_spawned_command() {
+	let (ends_s, mut ends) = mpsc::unbounded_channel();
+	let (job, task) = start_job(shell("sleep 10"));
+	job.on_completion(move |_, command| {
+		ends_s.send(command.to_string()).ok();
+	});
+
+	job.start().await;
+	job.set_command(shell("exit 0")).await;
+	job.stop().await;
+	assert_eq!(
+		ends.try_recv()
+			.expect("hook should have run before the stop ended"),
+		"sleep 10",
+		"hook should get the command the process was spawned from"
+	);
+
+	job.start().await;
+	job.to_wait().await;
+	assert_eq!(ends.try_recv().unwrap(), "exit 0");
+
+	job.delete_now().await;
+	task.await.unwrap();
+}
@@ -0,0 +1,80 @@
+#[cfg(unix)]
+use std::{sync::Arc, time::Duration};
+
+#[cfg(unix)]
+use watchexec_supervisor::{
+	command::{Command, Program, Shell, ShellInput},
+	job::start_job,
+};
+
+#[tokio::test]
+#[cfg(unix)]
+async fn detached_process_outlives_job() {
+	let dir = tempfile::tempdir().unwrap();
+	let marker = dir.path().join("marker");
+
+	let (job, task) = start_job(Arc::new(Command {
+		program: Program::Shell {
+			shell: Shell::new("sh"),
+			command: format!("sleep 1; touch '{}'", marker.display()),
+			args: Vec::new(),
+			via: ShellInput::Arg,
+		},
+		options: Default::default(),
+	}));
+
+	job.start().await;
+	let pid = job.detach().await;
+	assert!(
+		pid.is_some(),
+		"should return the pid of the running process"
+	);
+
+	drop(job);
+	task.await.unwrap();
+
+	tokio::time::timeout(Duration::from_secs(5), async {
+		while !marker.exists() {
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.expect("detached process should keep running and finish its work");
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn detached_option_survives_dropped_task() {
+	use watchexec_supervisor::command::SpawnOptions;
+
+	let dir = tempfile::tempdir().unwrap();
+	let marker = dir.path().join("marker");
+
+	let (job, task) = start_job(Arc::new(Command {
+		program: Program::Shell {
+			shell: Shell::new("sh"),
+			command: format!("sleep 1; touch '{}'", marker.display()),
+			args: Vec::new(),
+			via: ShellInput::Arg,
+		},
+		options: SpawnOptions {
+			detached: true,
+			..Default::default()
+		},
+	}));
+
+	job.start().await;
+
+	// dropping the task drops the child, which would kill it if it wasn't detached
+	task.abort();
+	assert!(task.await.unwrap_err().is_cancelled());
+	drop(job);
+
+	tokio::time::timeout(Duration::from_secs(5), async {
+		while !marker.exists() {
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.expect("detached process should not be killed when its job task is dropped");
+}
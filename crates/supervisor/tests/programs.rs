@@ -1,4 +1,4 @@
-use watchexec_supervisor::command::{Command, Program, Shell};
+use watchexec_supervisor::command::{Command, Program, Shell, ShellInput};
 
 #[tokio::test]
 #[cfg(unix)]
@@ -8,6 +8,7 @@ async fn unix_shell_none() -> Result<(), std::io::Error> {
 			program: Program::Exec {
 				prog: "echo".into(),
 				args: vec!["hi".into()],
+				argv0: None,
 			},
 			options: Default::default()
 		}
@@ -29,6 +30,7 @@ async fn unix_shell_sh() -> Result<(), std::io::Error> {
 				shell: Shell::new("sh"),
 				command: "echo hi".into(),
 				args: Vec::new(),
+				via: ShellInput::Arg,
 			},
 			options: Default::default()
 		}
@@ -50,6 +52,7 @@ async fn unix_shell_alternate() -> Result<(), std::io::Error> {
 				shell: Shell::new("bash"),
 				command: "echo".into(),
 				args: vec!["--".into(), "hi".into()],
+				via: ShellInput::Arg,
 			},
 			options: Default::default()
 		}
@@ -74,6 +77,7 @@ async fn unix_shell_alternate_shopts() -> Result<(), std::io::Error> {
 				},
 				command: "echo hi".into(),
 				args: Vec::new(),
+				via: ShellInput::Arg,
 			},
 			options: Default::default()
 		}
@@ -94,6 +98,7 @@ async fn windows_shell_none() -> Result<(), std::io::Error> {
 			program: Program::Exec {
 				prog: "echo".into(),
 				args: vec!["hi".into()],
+				argv0: None,
 			},
 			options: Default::default()
 		}
@@ -114,7 +119,8 @@ async fn windows_shell_cmd() -> Result<(), std::io::Error> {
 			program: Program::Shell {
 				shell: Shell::cmd(),
 				args: Vec::new(),
-				command: r#""echo" hi"#.into()
+				command: r#""echo" hi"#.into(),
+				via: ShellInput::Arg,
 			},
 			options: Default::default()
 		}
@@ -135,7 +141,8 @@ async fn windows_shell_powershell() -> Result<(), std::io::Error> {
 			program: Program::Shell {
 				shell: Shell::new("pwsh.exe"),
 				args: Vec::new(),
-				command: "echo hi".into()
+				command: "echo hi".into(),
+				via: ShellInput::Arg,
 			},
 			options: Default::default()
 		}
@@ -147,3 +154,622 @@ async fn windows_shell_powershell() -> Result<(), std::io::Error> {
 	.success());
 	Ok(())
 }
+
+#[test]
+fn exec_from_str_quoted_args() {
+	assert_eq!(
+		Program::exec_from_str(r#"git commit -m 'hello world' --author "A. N. Other" it\'s"#)
+			.unwrap(),
+		Program::Exec {
+			prog: "git".into(),
+			args: vec![
+				"commit".into(),
+				"-m".into(),
+				"hello world".into(),
+				"--author".into(),
+				"A. N. Other".into(),
+				"it's".into(),
+			],
+			argv0: None,
+		}
+	);
+}
+
+#[test]
+fn exec_from_str_unterminated_quote() {
+	let err = Program::exec_from_str("echo 'hello world").unwrap_err();
+	assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn exec_from_str_empty() {
+	let err = Program::exec_from_str("  ").unwrap_err();
+	assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn unix_exec_from_str() -> Result<(), std::io::Error> {
+	assert!(Box::into_pin(
+		Command {
+			program: Program::exec_from_str("sh -c 'exit 0'")?,
+			options: Default::default()
+		}
+		.to_spawnable()
+		.spawn()?
+		.wait()
+	)
+	.await?
+	.success());
+	Ok(())
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn unix_set_user() -> Result<(), std::io::Error> {
+	use watchexec_supervisor::command::SpawnOptions;
+
+	if !nix::unistd::geteuid().is_root() {
+		eprintln!("skipping: needs to run as root");
+		return Ok(());
+	}
+
+	assert!(Box::into_pin(
+		Command {
+			program: Program::Exec {
+				prog: "sh".into(),
+				args: vec![
+					"-c".into(),
+					// and without the supervisor's supplementary groups
+					r#"test "$(id -u)" = 65534 && test "$(id -g)" = 65533 &&
+						! id -G | tr ' ' '\n' | grep -qx 0"#
+						.into()
+				],
+				argv0: None,
+			},
+			options: SpawnOptions {
+				uid: Some(65534),
+				gid: Some(65533),
+				..Default::default()
+			}
+		}
+		.to_spawnable()
+		.spawn()?
+		.wait()
+	)
+	.await?
+	.success());
+	Ok(())
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn unix_set_unknown_user_without_group() {
+	use std::io::ErrorKind;
+	use watchexec_supervisor::command::SpawnOptions;
+
+	let err = Command {
+		program: Program::Exec {
+			prog: "true".into(),
+			args: Vec::new(),
+			argv0: None,
+		},
+		options: SpawnOptions {
+			uid: Some(0x7fff_fff0),
+			..Default::default()
+		},
+	}
+	.to_spawnable()
+	.spawn()
+	.expect_err("should not spawn as a user without a passwd entry and no gid");
+	assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn unix_priority() -> Result<(), std::io::Error> {
+	use std::fs::read_to_string;
+	use watchexec_supervisor::command::{OutputSink, SpawnOptions};
+
+	let dir = tempfile::tempdir()?;
+	let out = dir.path().join("niceness");
+
+	assert!(Box::into_pin(
+		Command {
+			program: Program::Exec {
+				prog: "sh".into(),
+				args: vec!["-c".into(), "ps -o ni= -p $$".into()],
+				argv0: None,
+			},
+			options: SpawnOptions {
+				priority: Some(19),
+				stdout: OutputSink::File(out.clone()),
+				..Default::default()
+			}
+		}
+		.to_spawnable()
+		.spawn()?
+		.wait()
+	)
+	.await?
+	.success());
+
+	assert_eq!(read_to_string(out)?.trim(), "19");
+	Ok(())
+}
+
+#[test]
+fn priority_out_of_range() {
+	use std::io::ErrorKind;
+	use watchexec_supervisor::command::SpawnOptions;
+
+	let err = Command {
+		program: Program::Exec {
+			prog: "true".into(),
+			args: Vec::new(),
+			argv0: None,
+		},
+		options: SpawnOptions {
+			priority: Some(20),
+			..Default::default()
+		},
+	}
+	.to_spawnable()
+	.spawn()
+	.unwrap_err();
+	assert_eq!(err.kind(), ErrorKind::InvalidInput);
+	assert!(err.to_string().contains("out of range"), "{err}");
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn unix_rotating_output() -> Result<(), std::io::Error> {
+	use std::fs::{read_dir, read_to_string};
+	use watchexec_supervisor::command::{OutputSink, SpawnOptions};
+
+	let dir = tempfile::tempdir()?;
+	let logs = dir.path().join("logs");
+
+	// separate writes of 51 bytes each, so each goes past max_size when added to the last
+	assert!(Box::into_pin(
+		Command {
+			program: Program::Exec {
+				prog: "sh".into(),
+				args: vec![
+					"-c".into(),
+					r#"for i in 1 2 3 4 5; do printf 'line %045d\n' $i; sleep 0.1; done; echo oops >&2"#
+						.into()
+				],
+				argv0: None,
+			},
+			options: SpawnOptions {
+				stdout: OutputSink::Rotating {
+					dir: logs.clone(),
+					max_size: 100,
+					keep: 2,
+				},
+				stderr: OutputSink::File(dir.path().join("errors")),
+				..Default::default()
+			}
+		}
+		.to_spawnable()
+		.spawn()?
+		.wait()
+	)
+	.await?
+	.success());
+
+	let line = |i: u8| format!("line {i:045}\n");
+	assert_eq!(read_to_string(logs.join("stdout.log"))?, line(5));
+	assert_eq!(read_to_string(logs.join("stdout.log.1"))?, line(4));
+	assert_eq!(read_to_string(logs.join("stdout.log.2"))?, line(3));
+	assert_eq!(read_dir(&logs)?.count(), 3, "older files should be removed");
+	assert_eq!(read_to_string(dir.path().join("errors"))?, "oops\n");
+	Ok(())
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn unix_merge_stderr_into_stdout() -> Result<(), std::io::Error> {
+	use std::fs::read_to_string;
+	use watchexec_supervisor::command::{OutputSink, SpawnOptions};
+
+	let dir = tempfile::tempdir()?;
+	let out = dir.path().join("output");
+	let errors = dir.path().join("errors");
+
+	assert!(Box::into_pin(
+		Command {
+			program: Program::Exec {
+				prog: "sh".into(),
+				args: vec![
+					"-c".into(),
+					"echo one; echo two >&2; echo three; echo four >&2".into()
+				],
+				argv0: None,
+			},
+			options: SpawnOptions {
+				stdout: OutputSink::File(out.clone()),
+				stderr: OutputSink::File(errors.clone()),
+				merge_stderr_into_stdout: true,
+				..Default::default()
+			}
+		}
+		.to_spawnable()
+		.spawn()?
+		.wait()
+	)
+	.await?
+	.success());
+
+	assert_eq!(read_to_string(out)?, "one\ntwo\nthree\nfour\n");
+	assert!(!errors.exists(), "stderr sink should be ignored");
+	Ok(())
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn unix_exec_argv0() -> Result<(), std::io::Error> {
+	use std::fs::read_to_string;
+	use watchexec_supervisor::command::{OutputSink, SpawnOptions};
+
+	let dir = tempfile::tempdir()?;
+	let out = dir.path().join("output");
+
+	// `sh -c` sets $0 to the argument after the command line, so look at the shell's own argv
+	// instead; the trailing `true` stops the shell from exec'ing into ps
+	assert!(Box::into_pin(
+		Command {
+			program: Program::Exec {
+				prog: "sh".into(),
+				args: vec!["-c".into(), "ps -o args= -p $$; true".into()],
+				argv0: Some("custom-name".into()),
+			},
+			options: SpawnOptions {
+				stdout: OutputSink::File(out.clone()),
+				..Default::default()
+			}
+		}
+		.to_spawnable()
+		.spawn()?
+		.wait()
+	)
+	.await?
+	.success());
+
+	let output = read_to_string(out)?;
+	assert!(
+		output.starts_with("custom-name -c "),
+		"argv0 should be overridden, got {output:?}"
+	);
+	Ok(())
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn unix_explicit_path_resolution() -> Result<(), std::io::Error> {
+	use std::{fs, io::ErrorKind, os::unix::fs::PermissionsExt};
+	use watchexec_supervisor::command::{PathResolution, SpawnOptions};
+
+	let dir = tempfile::tempdir()?;
+	let (first, second) = (dir.path().join("first"), dir.path().join("second"));
+	fs::create_dir(&first)?;
+	fs::create_dir(&second)?;
+
+	// not executable, so skipped over
+	fs::write(first.join("tool"), "#!/bin/sh\nexit 1\n")?;
+	fs::write(second.join("tool"), "#!/bin/sh\nexit 0\n")?;
+	fs::set_permissions(second.join("tool"), fs::Permissions::from_mode(0o755))?;
+
+	let command = |prog: &str| Command {
+		program: Program::Exec {
+			prog: prog.into(),
+			args: Vec::new(),
+			argv0: None,
+		},
+		options: SpawnOptions {
+			path_resolution: PathResolution::Explicit(vec![first.clone(), second.clone()]),
+			..Default::default()
+		},
+	};
+
+	assert!(
+		Box::into_pin(command("tool").to_spawnable().spawn()?.wait())
+			.await?
+			.success()
+	);
+
+	let err = command("echo").to_spawnable().spawn().unwrap_err();
+	assert_eq!(err.kind(), ErrorKind::NotFound, "{err}");
+	Ok(())
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn unix_require_absolute_path() -> Result<(), std::io::Error> {
+	use std::io::ErrorKind;
+	use watchexec_supervisor::command::{PathResolution, SpawnOptions};
+
+	let command = |prog: &str| Command {
+		program: Program::Exec {
+			prog: prog.into(),
+			args: vec!["-c".into(), "true".into()],
+			argv0: None,
+		},
+		options: SpawnOptions {
+			path_resolution: PathResolution::RequireAbsolute,
+			..Default::default()
+		},
+	};
+
+	let err = command("sh").to_spawnable().spawn().unwrap_err();
+	assert_eq!(err.kind(), ErrorKind::InvalidInput, "{err}");
+
+	assert!(
+		Box::into_pin(command("/bin/sh").to_spawnable().spawn()?.wait())
+			.await?
+			.success()
+	);
+	Ok(())
+}
+
+#[test]
+fn missing_program_is_not_found() {
+	use std::io::ErrorKind;
+	use watchexec_supervisor::{
+		command::{PathResolution, SpawnOptions},
+		errors::CommandError,
+	};
+
+	let dir = tempfile::tempdir().unwrap();
+	let command = |preflight| Command {
+		program: Program::Exec {
+			prog: "watchexec-does-not-exist".into(),
+			args: Vec::new(),
+			argv0: None,
+		},
+		options: SpawnOptions {
+			path_resolution: PathResolution::Explicit(vec![dir.path().to_owned()]),
+			preflight,
+			..Default::default()
+		},
+	};
+	let expected = CommandError::NotFound {
+		program: "watchexec-does-not-exist".into(),
+		searched_paths: vec![dir.path().to_owned()],
+	};
+
+	assert_eq!(command(true).preflight(), Err(expected.clone()));
+
+	let err = command(true).to_spawnable().spawn().unwrap_err();
+	assert_eq!(err.kind(), ErrorKind::NotFound, "{err}");
+	assert_eq!(
+		err.get_ref()
+			.and_then(|err| err.downcast_ref::<CommandError>()),
+		Some(&expected),
+		"spawn error should carry the structured error"
+	);
+
+	let err = command(false).to_spawnable().spawn().unwrap_err();
+	assert!(
+		err.get_ref()
+			.and_then(|err| err.downcast_ref::<CommandError>())
+			.is_none(),
+		"spawn error shouldn't be from the preflight when it's not enabled: {err}"
+	);
+}
+
+#[test]
+#[cfg(unix)]
+fn missing_shell_is_not_found() {
+	use watchexec_supervisor::errors::CommandError;
+
+	let command = Command {
+		program: Program::Shell {
+			shell: Shell::new("/watchexec/does/not/exist/sh"),
+			command: "true".into(),
+			args: Vec::new(),
+			via: ShellInput::Arg,
+		},
+		options: Default::default(),
+	};
+
+	assert_eq!(
+		command.preflight(),
+		Err(CommandError::NotFound {
+			program: "/watchexec/does/not/exist/sh".into(),
+			searched_paths: Vec::new(),
+		})
+	);
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn unix_pty() -> Result<(), std::io::Error> {
+	use watchexec_supervisor::command::SpawnOptions;
+
+	let mut child = Command {
+		program: Program::Exec {
+			prog: "sh".into(),
+			args: vec!["-c".into(), "test -t 1 && test -t 2".into()],
+			argv0: None,
+		},
+		options: SpawnOptions {
+			pty: true,
+			..Default::default()
+		},
+	}
+	.to_spawnable()
+	.spawn()?;
+
+	assert!(
+		Box::into_pin(child.wait()).await?.success(),
+		"stdout and stderr should be terminals"
+	);
+	Ok(())
+}
+
+#[test]
+fn structured_exec() {
+	let info = Command {
+		program: Program::Exec {
+			prog: "make".into(),
+			args: vec!["check".into(), "--jobs=2".into()],
+			argv0: None,
+		},
+		options: watchexec_supervisor::command::SpawnOptions {
+			grouped: true,
+			..Default::default()
+		},
+	}
+	.to_structured();
+
+	assert_eq!(info.program, std::path::PathBuf::from("make"));
+	assert_eq!(info.args, vec!["check".to_string(), "--jobs=2".into()]);
+	assert_eq!(info.argv0, None);
+	assert_eq!(info.shell_command, None);
+	assert!(info.grouped);
+	assert!(!info.session);
+	assert_eq!(info.cwd, None);
+	assert!(info.env.is_empty());
+}
+
+#[test]
+fn structured_exec_argv0() {
+	let info = Program::Exec {
+		prog: "/usr/bin/busybox".into(),
+		args: vec!["-l".into()],
+		argv0: Some("sh".into()),
+	}
+	.to_structured();
+
+	assert_eq!(info.program, std::path::PathBuf::from("/usr/bin/busybox"));
+	assert_eq!(info.argv0.as_deref(), Some("sh"));
+}
+
+#[test]
+fn structured_shell() {
+	let mut shell = Shell::new("bash");
+	shell.options = vec!["-e".into()];
+	let info = Command {
+		program: Program::Shell {
+			shell,
+			command: "echo $1 && make".into(),
+			args: vec!["--".into(), "hi".into()],
+			via: ShellInput::Arg,
+		},
+		options: Default::default(),
+	}
+	.to_structured();
+
+	assert_eq!(info.program, std::path::PathBuf::from("bash"));
+	assert_eq!(
+		info.args,
+		vec![
+			"-e".to_string(),
+			"-c".into(),
+			"echo $1 && make".into(),
+			"--".into(),
+			"hi".into()
+		]
+	);
+	assert_eq!(info.shell_command.as_deref(), Some("echo $1 && make"));
+	assert!(!info.grouped);
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn unix_shell_script_file() -> Result<(), std::io::Error> {
+	use std::{fs::read_to_string, path::Path};
+	use watchexec_supervisor::command::{OutputSink, SpawnOptions};
+
+	let dir = tempfile::tempdir()?;
+	let output = dir.path().join("output");
+
+	assert!(Box::into_pin(
+		Command {
+			program: Program::Shell {
+				shell: Shell::new("sh"),
+				command: "cat <<EOF\nhello '$1'\n\"quoted\"\nEOF\necho \"$0\"\n".into(),
+				args: vec!["world".into()],
+				via: ShellInput::ScriptFile,
+			},
+			options: SpawnOptions {
+				stdout: OutputSink::File(output.clone()),
+				..Default::default()
+			}
+		}
+		.to_spawnable()
+		.spawn()?
+		.wait()
+	)
+	.await?
+	.success());
+
+	let output = read_to_string(output)?;
+	let (text, script) = output
+		.strip_suffix('\n')
+		.and_then(|output| output.rsplit_once('\n'))
+		.expect("script path should be printed last");
+	assert_eq!(text, "hello 'world'\n\"quoted\"");
+	assert!(
+		!Path::new(script).exists(),
+		"script file should be removed: {script}"
+	);
+	Ok(())
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn unix_shell_script_file_extension() -> Result<(), std::io::Error> {
+	use std::fs::read_to_string;
+	use watchexec_supervisor::command::{OutputSink, SpawnOptions};
+
+	// a shell named like PowerShell, which only runs .ps1 scripts
+	let dir = tempfile::tempdir()?;
+	let pwsh = dir.path().join("pwsh");
+	std::os::unix::fs::symlink("/bin/sh", &pwsh)?;
+	let output = dir.path().join("output");
+
+	assert!(Box::into_pin(
+		Command {
+			program: Program::Shell {
+				shell: Shell::new(&pwsh),
+				command: "echo \"$0\"".into(),
+				args: Vec::new(),
+				via: ShellInput::ScriptFile,
+			},
+			options: SpawnOptions {
+				stdout: OutputSink::File(output.clone()),
+				..Default::default()
+			}
+		}
+		.to_spawnable()
+		.spawn()?
+		.wait()
+	)
+	.await?
+	.success());
+
+	let script = read_to_string(output)?;
+	assert!(script.trim_end().ends_with(".ps1"), "{script}");
+	Ok(())
+}
+
+#[test]
+fn structured_cmd_script_file() {
+	let info = Program::Shell {
+		shell: Shell {
+			prog: "CMD.EXE".into(),
+			options: Vec::new(),
+			program_option: Some(std::borrow::Cow::Borrowed(std::ffi::OsStr::new("/C"))),
+		},
+		command: "echo hi".into(),
+		args: vec!["there".into()],
+		via: ShellInput::ScriptFile,
+	}
+	.to_structured();
+
+	// CMD.EXE needs /C to run a script, which is given by path once spawning
+	assert_eq!(info.args, vec!["/C".to_string(), "there".into()]);
+}